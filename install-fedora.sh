@@ -42,6 +42,7 @@ install_dependencies() {
         webkit2gtk4.1 \
         gtk3 \
         libusb1 \
+        alsa-lib \
         openssl \
         ydotool \
         playerctl \
@@ -128,6 +129,7 @@ install_from_source() {
         webkit2gtk4.1-devel \
         openssl-devel \
         libusb1-devel \
+        alsa-lib-devel \
         gtk3-devel
     
     print_step "Instalando Rust..."
@@ -47,7 +47,7 @@ detect_distro() {
 # Instalar en Arch
 install_arch() {
     print_step "Instalando dependencias para Arch..."
-    sudo pacman -S --needed --noconfirm webkit2gtk gtk3 libusb openssl glib2 base-devel ydotool playerctl
+    sudo pacman -S --needed --noconfirm webkit2gtk gtk3 libusb alsa-lib openssl glib2 base-devel ydotool playerctl
     
     setup_common
     build_from_source
@@ -56,7 +56,7 @@ install_arch() {
 # Instalar en Fedora
 install_fedora() {
     print_step "Instalando dependencias para Fedora..."
-    sudo dnf install -y webkit2gtk4.1 gtk3 libusb1 openssl ydotool playerctl curl wget
+    sudo dnf install -y webkit2gtk4.1 gtk3 libusb1 alsa-lib openssl ydotool playerctl curl wget
     
     setup_common
     
@@ -73,7 +73,7 @@ install_fedora() {
 install_debian() {
     print_step "Instalando dependencias para Debian/Ubuntu..."
     sudo apt update
-    sudo apt install -y libwebkit2gtk-4.1-0 libgtk-3-0 libusb-1.0-0 libssl3 ydotool playerctl curl wget
+    sudo apt install -y libwebkit2gtk-4.1-0 libgtk-3-0 libusb-1.0-0 libasound2 libssl3 ydotool playerctl curl wget
     
     setup_common
     
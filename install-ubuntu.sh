@@ -43,6 +43,7 @@ install_dependencies() {
         libwebkit2gtk-4.1-0 \
         libgtk-3-0 \
         libusb-1.0-0 \
+        libasound2 \
         libssl3 \
         ydotool \
         playerctl \
@@ -58,7 +58,7 @@ check_arch() {
 install_dependencies() {
     print_step "Instalando dependencias del sistema..."
 
-    DEPS="webkit2gtk gtk3 libusb openssl glib2 base-devel ydotool playerctl"
+    DEPS="webkit2gtk gtk3 libusb alsa-lib openssl glib2 base-devel ydotool playerctl"
 
     # Verificar cuáles ya están instaladas
     MISSING=""
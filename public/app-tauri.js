@@ -11,7 +11,11 @@ let config = null;
 let currentButtonId = null;
 let editingPageIndex = null;
 let selectedIconPath = null;
+// Dropped or pasted image (data URL), uploaded with save_icon_data
+let selectedIconData = null;
 let presetCommands = [];
+// External tools that are missing here (check_dependencies)
+let missingDependencies = [];
 
 // ============================================================================
 // Initialization
@@ -35,10 +39,12 @@ document.addEventListener('DOMContentLoaded', async () => {
 
   try {
     await loadConfig();
+    await checkConfigRecovery();
     await loadPresetCommands();
     // Auto-connect on startup
     await autoConnect();
     startButtonListener();
+    await startSimulator();
   } catch (e) {
     console.error('Initialization error:', e);
   }
@@ -51,6 +57,8 @@ document.addEventListener('DOMContentLoaded', async () => {
 async function loadPresetCommands() {
   try {
     presetCommands = await invoke('get_preset_commands');
+    const dependencies = await invoke('check_dependencies').catch(() => []);
+    missingDependencies = dependencies.filter(d => !d.available);
     populatePresetDropdown();
   } catch (e) {
     console.error('Error loading preset commands:', e);
@@ -68,11 +76,12 @@ function populatePresetDropdown() {
     'Multimedia': presetCommands.filter(p => ['Vol +', 'Vol -', 'Mute', 'Play/Pause', 'Next', 'Prev'].includes(p[0])),
     'Aplicaciones': presetCommands.filter(p => ['Firefox', 'Chrome', 'Terminal', 'Files', 'VS Code', 'Discord', 'Spotify', 'Steam', 'OBS'].includes(p[0])),
     'URLs': presetCommands.filter(p => ['YouTube', 'Twitch', 'GitHub', 'Twitter/X', 'ChatGPT', 'Claude'].includes(p[0])),
-    'Hotkeys': presetCommands.filter(p => ['Copiar', 'Pegar', 'Cortar', 'Deshacer', 'Rehacer', 'Guardar', 'Buscar', 'Seleccionar todo', 'Cerrar ventana', 'Cambiar ventana', 'Pantalla completa', 'Emoji picker'].includes(p[0])),
-    'Texto': presetCommands.filter(p => ['Email', 'Saludo', 'Firma'].includes(p[0])),
-    'Multi-acción': presetCommands.filter(p => ['Abrir+Escribir', 'Copy+Paste'].includes(p[0])),
-    'Fecha/Hora': presetCommands.filter(p => ['Reloj', 'Reloj+Seg', 'Fecha', 'Fecha completa', 'Día semana'].includes(p[0])),
-    'Info Sistema': presetCommands.filter(p => ['CPU %', 'RAM %', 'Temp CPU'].includes(p[0])),
+    // Labels below may be translated (see the language setting), so these go by command
+    'Hotkeys': presetCommands.filter(p => p[1].startsWith('__KEY_')),
+    'Texto': presetCommands.filter(p => p[1].startsWith('__TYPE_')),
+    'Multi-acción': presetCommands.filter(p => p[1].startsWith('__MULTI_')),
+    'Fecha/Hora': presetCommands.filter(p => ['__CLOCK', '__DATE', '__WEEKDAY'].some(prefix => p[1].startsWith(prefix))),
+    'Info Sistema': presetCommands.filter(p => ['CPU %', 'RAM %', 'Temp CPU'].includes(p[0]) || ['__UPTIME', '__LOAD', '__UPDATES', '__JOURNAL', '__PROC_'].some(prefix => p[1].startsWith(prefix))),
     'Timers': presetCommands.filter(p => p[0].startsWith('Timer ')),
     'Workspaces': presetCommands.filter(p => p[0].startsWith('WS ')),
     'Sistema': presetCommands.filter(p => ['Screenshot', 'Lock', 'Suspend'].includes(p[0])),
@@ -87,12 +96,23 @@ function populatePresetDropdown() {
       const option = document.createElement('option');
       option.value = JSON.stringify({ label, command });
       option.textContent = `${label} - ${description}`;
+      const missing = missingCommandTools(command);
+      if (missing.length > 0) {
+        option.textContent = `⚠ ${option.textContent}`;
+        option.title = `No funcionará aquí: ${missing.map(d => d.detail).join('; ')}`;
+        option.style.color = '#888';
+      }
       optgroup.appendChild(option);
     }
     select.appendChild(optgroup);
   }
 }
 
+// Missing tools a command needs, from check_dependencies
+function missingCommandTools(command) {
+  return missingDependencies.filter(d => d.usedBy.some(fragment => command.includes(fragment)));
+}
+
 function applyPreset(selectElement) {
   if (!selectElement.value) return;
 
@@ -176,6 +196,14 @@ async function loadConfig() {
     renderButtons();
     document.getElementById('brightness').value = config.brightness;
     document.getElementById('brightness-value').textContent = config.brightness;
+    document.getElementById('jpeg-quality').value = config.jpegQuality;
+    document.getElementById('orientation-rotation').value = config.orientation?.rotation ?? 180;
+    document.getElementById('orientation-mirror').checked = config.orientation?.mirror ?? false;
+    document.getElementById('language').value = config.language || 'auto';
+    document.getElementById('usage-stats').checked = config.usageStats;
+    document.getElementById('notifications').checked = config.notifications?.enabled ?? true;
+    document.getElementById('safe-mode').checked = config.safeMode ?? false;
+    measureJpegQuality();
   } catch (e) {
     console.error('Error loading config:', e);
   }
@@ -218,6 +246,7 @@ function renderButtons() {
       el.textContent = btn.label || '';
     }
   }
+  renderUsage();
 }
 
 // Helper function to load button icon as base64
@@ -448,6 +477,12 @@ async function reconnect() {
 // Brightness
 // ============================================================================
 
+// Mientras se arrastra: solo el deck, sin guardar
+function previewBrightness(value) {
+  document.getElementById('brightness-value').textContent = value;
+  invoke('preview_brightness', { brightness: parseInt(value) }).catch(e => console.error('Error previewing brightness:', e));
+}
+
 async function setBrightness(value) {
   document.getElementById('brightness-value').textContent = value;
   config.brightness = parseInt(value);
@@ -459,6 +494,267 @@ async function setBrightness(value) {
   }
 }
 
+// ============================================================================
+// Logs
+// ============================================================================
+
+async function copyRecentLogs() {
+  try {
+    const logs = await invoke('get_recent_logs', { lines: 500 });
+    await navigator.clipboard.writeText(logs);
+    showToast('Registros copiados al portapapeles');
+  } catch (e) {
+    console.error('Error copying logs:', e);
+    showToast('No se pudieron copiar los registros');
+  }
+}
+
+// ============================================================================
+// Profile Import
+// ============================================================================
+
+// Shared profiles can run commands: show every shell command and URL first
+async function importProfile() {
+  if (!dialogOpen) {
+    showToast('Selector de archivos no disponible');
+    return;
+  }
+  try {
+    const path = await dialogOpen({
+      multiple: false,
+      filters: [{ name: 'Perfil', extensions: ['zip'] }]
+    });
+    if (!path) return;
+
+    const actions = await invoke('review_profile', { path });
+    if (actions.length > 0) {
+      const list = actions
+        .map((a) => `${a.page} / tecla ${a.key} (${a.kind === 'url' ? 'URL' : 'comando'}): ${a.command}`)
+        .join('\n');
+      const message = `Este perfil ejecutará lo siguiente al pulsar sus teclas:\n\n${list}\n\n¿Importarlo igualmente?`;
+      if (!confirm(message)) return;
+    }
+
+    const count = await invoke('import_profile', { path, approved: actions.length > 0 });
+    await loadConfig();
+    showToast(`${count} página(s) importada(s)`);
+  } catch (e) {
+    console.error('Error importing profile:', e);
+    showToast('No se pudo importar el perfil');
+  }
+}
+
+// ============================================================================
+// Diagnostics
+// ============================================================================
+
+async function showDiagnostics() {
+  const info = document.getElementById('device-info');
+  const list = document.getElementById('diagnostics-list');
+  info.textContent = 'Comprobando...';
+  list.innerHTML = '';
+  document.getElementById('diagnostics-modal').classList.add('active');
+
+  try {
+    const device = await invoke('get_device_info');
+    info.textContent = device.detected
+      ? [
+          `USB ${device.vendorId}:${device.productId} (bus ${device.bus}, dirección ${device.address})`,
+          `${device.manufacturer || '?'} ${device.product || '?'}, serie ${device.serial || '?'}, firmware ${device.firmware || '?'}`,
+          `Endpoints: ${device.endpoints.map(e => `${e.address} ${e.direction} ${e.transferType}`).join(', ')}`,
+        ].join('\n')
+      : 'Deck no detectado en USB';
+
+    const checks = await invoke('get_diagnostics');
+    checks.forEach(check => {
+      const item = document.createElement('li');
+      item.className = check.ok ? 'ok' : 'fail';
+      item.textContent = `${check.name}: ${check.detail}`;
+      list.appendChild(item);
+    });
+
+    const env = await invoke('get_environment');
+    const session = document.createElement('li');
+    session.className = 'ok';
+    session.textContent = `sesión: ${env.sessionType} (${env.desktop || env.compositor})` +
+      `${env.flatpak ? ', Flatpak' : ''}, portal ${env.portal ? 'sí' : 'no'}; ` +
+      `entrada ${env.backends.input}, capturas ${env.backends.screenshot}, ventana activa ${env.backends.windowWatcher}`;
+    list.appendChild(session);
+
+    const dependencies = await invoke('check_dependencies');
+    dependencies.forEach(dependency => {
+      const item = document.createElement('li');
+      item.className = dependency.available ? 'ok' : 'fail';
+      item.textContent = `${dependency.name}: ${dependency.detail}`;
+      list.appendChild(item);
+    });
+  } catch (e) {
+    info.textContent = `Error: ${e}`;
+  }
+}
+
+function closeDiagnostics() {
+  invoke('cancel_calibration').catch(() => {});
+  document.getElementById('diagnostics-modal').classList.remove('active');
+}
+
+// Key calibration: the deck lights one key at a time until all 15 are pressed
+async function startCalibration() {
+  const status = document.getElementById('calibration-status');
+  try {
+    await invoke('start_calibration');
+  } catch (e) {
+    status.textContent = `Error: ${e}`;
+    return;
+  }
+  const poll = setInterval(async () => {
+    const calibration = await invoke('get_calibration_status');
+    if (calibration.active) {
+      status.textContent = `Pulsa la tecla iluminada en el deck (${calibration.key}/15)`;
+      return;
+    }
+    clearInterval(poll);
+    status.textContent = calibration.mapped === 15
+      ? 'Calibración guardada'
+      : 'Calibración cancelada';
+    await loadConfig();
+  }, 500);
+}
+
+async function resetKeyMap() {
+  try {
+    await invoke('reset_key_map');
+    document.getElementById('calibration-status').textContent = 'Mapa de teclas por defecto';
+    await loadConfig();
+  } catch (e) {
+    console.error('Error resetting key map:', e);
+  }
+}
+
+// ============================================================================
+// JPEG Quality
+// ============================================================================
+
+async function setJpegQuality(value) {
+  config.jpegQuality = parseInt(value);
+  try {
+    await invoke('set_jpeg_quality', { quality: parseInt(value) });
+  } catch (e) {
+    console.error('Error setting JPEG quality:', e);
+  }
+}
+
+// Tamaño medio por tecla de la página actual en cada opción
+async function measureJpegQuality() {
+  try {
+    const samples = await invoke('measure_jpeg_quality');
+    const select = document.getElementById('jpeg-quality');
+    samples.forEach(({ quality, bytes }) => {
+      const option = select.querySelector(`option[value="${quality}"]`);
+      if (option) option.textContent = `${quality} (~${(bytes / 1024).toFixed(1)} KB/tecla)`;
+    });
+  } catch (e) {
+    console.error('Error measuring JPEG quality:', e);
+  }
+}
+
+// ============================================================================
+// Orientation
+// ============================================================================
+
+async function setOrientation() {
+  const rotation = parseInt(document.getElementById('orientation-rotation').value);
+  const mirror = document.getElementById('orientation-mirror').checked;
+  config.orientation = { rotation, mirror };
+  try {
+    await invoke('set_orientation', { rotation, mirror });
+  } catch (e) {
+    console.error('Error setting orientation:', e);
+  }
+}
+
+// ============================================================================
+// Language
+// ============================================================================
+
+async function setLanguage(language) {
+  config.language = language;
+  try {
+    await invoke('set_language', { language });
+    await loadPresetCommands();
+  } catch (e) {
+    console.error('Error setting language:', e);
+  }
+}
+
+// ============================================================================
+// Usage Heatmap
+// ============================================================================
+
+let usageVisible = false;
+const USAGE_DAYS = 30;
+
+function toggleUsage() {
+  usageVisible = !usageVisible;
+  document.getElementById('usage-btn').classList.toggle('active', usageVisible);
+  renderUsage();
+}
+
+// Número de pulsaciones en cada tecla de la página actual, con un borde más intenso cuanto más se usa
+async function renderUsage() {
+  document.querySelectorAll('.usage-badge').forEach(badge => badge.remove());
+  document.querySelectorAll('.button').forEach(el => el.style.boxShadow = '');
+  if (!usageVisible) return;
+
+  try {
+    const stats = await invoke('get_usage_stats', { days: USAGE_DAYS });
+    const keys = stats.keys.filter(k => k.page === config.currentPage);
+    const max = Math.max(1, ...keys.map(k => k.count));
+    document.querySelectorAll('.button').forEach(el => {
+      const usage = keys.find(k => String(k.key) === el.dataset.id);
+      const count = usage ? usage.count : 0;
+      el.style.boxShadow = `inset 0 0 0 3px rgba(233, 69, 96, ${0.1 + 0.9 * count / max})`;
+      const badge = document.createElement('span');
+      badge.className = 'usage-badge';
+      badge.textContent = count;
+      el.appendChild(badge);
+    });
+  } catch (e) {
+    console.error('Error loading usage stats:', e);
+  }
+}
+
+async function setNotifications(enabled) {
+  config.notifications = { ...config.notifications, enabled };
+  try {
+    await invoke('set_notifications', { enabled });
+  } catch (e) {
+    console.error('Error setting notifications:', e);
+  }
+}
+
+async function setSafeMode(enabled) {
+  config.safeMode = enabled;
+  try {
+    await invoke('set_safe_mode', { enabled });
+  } catch (e) {
+    console.error('Error setting safe mode:', e);
+  }
+}
+
+async function setUsageStats(enabled) {
+  config.usageStats = enabled;
+  try {
+    await invoke('set_usage_stats', { enabled });
+    if (!enabled && confirm('¿Borrar también el historial de uso registrado?')) {
+      await invoke('clear_usage_stats');
+      renderUsage();
+    }
+  } catch (e) {
+    console.error('Error setting usage stats:', e);
+  }
+}
+
 // ============================================================================
 // Button Editing
 // ============================================================================
@@ -466,6 +762,7 @@ async function setBrightness(value) {
 function editButton(id) {
   currentButtonId = id;
   selectedIconPath = null;
+  selectedIconData = null;
   const page = config.pages[config.currentPage];
   const btn = page.buttons[id] || { label: '', command: '', color: '#1a1a2e', icon: '' };
 
@@ -473,6 +770,7 @@ function editButton(id) {
   document.getElementById('edit-label').value = btn.label || '';
   document.getElementById('edit-command').value = btn.command || '';
   document.getElementById('edit-color').value = btn.color || '#1a1a2e';
+  document.getElementById('edit-sound').value = btn.sound || '';
   document.getElementById('edit-icon-path').value = '';
 
   // Show existing hotkey if present
@@ -516,9 +814,37 @@ function editButton(id) {
   const presetSelect = document.getElementById('preset-commands');
   if (presetSelect) presetSelect.value = '';
 
+  updateKeyPreview();
   document.getElementById('modal').classList.add('active');
 }
 
+// Key image rendered by the backend, exactly as the deck shows it
+let keyPreviewTimer = null;
+function updateKeyPreview() {
+  clearTimeout(keyPreviewTimer);
+  keyPreviewTimer = setTimeout(async () => {
+    if (!currentButtonId) return;
+    const buttonConfig = {
+      label: document.getElementById('edit-label').value,
+      command: document.getElementById('edit-command').value,
+      color: document.getElementById('edit-color').value,
+      icon: config.pages[config.currentPage].buttons[currentButtonId]?.icon || ''
+    };
+    try {
+      document.getElementById('key-preview').src = await invoke('preview_button', {
+        buttonConfig,
+        pageIndex: config.currentPage
+      });
+    } catch (e) {
+      console.error('Error rendering preview:', e);
+    }
+  }, 200);
+}
+
+['edit-label', 'edit-command', 'edit-color'].forEach((id) => {
+  document.getElementById(id)?.addEventListener('input', updateKeyPreview);
+});
+
 // ============================================================================
 // Icon Management
 // ============================================================================
@@ -554,6 +880,104 @@ async function browseIcon() {
   }
 }
 
+// Render the typed emoji into an icon on the button color
+async function useEmojiIcon() {
+  if (!currentButtonId) return;
+  const emoji = document.getElementById('edit-emoji').value.trim();
+  if (!emoji) return;
+  try {
+    const icon = await invoke('save_emoji_icon', {
+      emoji,
+      background: document.getElementById('edit-color').value
+    });
+    config.pages[config.currentPage].buttons[currentButtonId].icon = icon;
+    selectedIconPath = null;
+    selectedIconData = null;
+    await loadButtonIcon(document.getElementById('icon-preview'), icon);
+    updateKeyPreview();
+  } catch (e) {
+    console.error('Error rendering emoji:', e);
+    showToast(`Error: ${e}`);
+  }
+}
+
+// Use a dropped or pasted image file as the icon
+function useIconFile(file) {
+  if (!file || !file.type.startsWith('image/')) return;
+  const reader = new FileReader();
+  reader.onload = (e) => {
+    selectedIconData = e.target.result;
+    selectedIconPath = null;
+    const preview = document.getElementById('icon-preview');
+    preview.style.backgroundImage = `url(${selectedIconData})`;
+    preview.classList.add('has-icon');
+    showToast('Imagen seleccionada');
+  };
+  reader.readAsDataURL(file);
+}
+
+document.getElementById('icon-preview').addEventListener('dragover', (e) => e.preventDefault());
+document.getElementById('icon-preview').addEventListener('drop', (e) => {
+  e.preventDefault();
+  useIconFile(e.dataTransfer.files[0]);
+});
+document.addEventListener('paste', (e) => {
+  if (!document.getElementById('modal').classList.contains('active')) return;
+  const item = [...e.clipboardData.items].find((i) => i.type.startsWith('image/'));
+  if (item) useIconFile(item.getAsFile());
+});
+
+// ============================================================================
+// Config Recovery (config.json was unreadable at startup)
+// ============================================================================
+
+let recoveryBackup = null;
+
+async function checkConfigRecovery() {
+  try {
+    const recovery = await invoke('get_config_recovery');
+    if (!recovery || !recovery.backup) return;
+
+    recoveryBackup = recovery.backup.name;
+    const date = new Date(recovery.backup.modified * 1000).toLocaleString();
+    document.getElementById('recovery-message').textContent =
+      `No se pudo leer config.json (${recovery.error}). ¿Restaurar la copia de seguridad del ${date}?`;
+    document.getElementById('recovery-modal').classList.add('active');
+  } catch (e) {
+    console.error('Error checking config recovery:', e);
+  }
+}
+
+function closeRecoveryModal() {
+  document.getElementById('recovery-modal').classList.remove('active');
+}
+
+async function restoreConfigBackup() {
+  try {
+    await invoke('restore_config_backup', { name: recoveryBackup });
+    await loadConfig();
+    closeRecoveryModal();
+    showToast('Configuración restaurada');
+  } catch (e) {
+    console.error('Error restoring backup:', e);
+    showToast('Error al restaurar la copia');
+  }
+}
+
+// ============================================================================
+// Undo / Redo
+// ============================================================================
+
+async function undoConfig(redo = false) {
+  try {
+    await invoke(redo ? 'redo_config' : 'undo_config');
+    await loadConfig();
+    showToast(redo ? 'Cambio rehecho' : 'Cambio deshecho');
+  } catch (e) {
+    showToast(redo ? 'Nada que rehacer' : 'Nada que deshacer');
+  }
+}
+
 // ============================================================================
 // Reset Configuration
 // ============================================================================
@@ -605,6 +1029,7 @@ async function removeIcon() {
 
   config.pages[pageIndex].buttons[currentButtonId].icon = '';
   renderButtons();
+  updateKeyPreview();
 }
 
 async function saveButton() {
@@ -618,8 +1043,20 @@ async function saveButton() {
 
   let icon = config.pages[pageIndex].buttons[currentButtonId]?.icon || '';
 
-  // Handle icon from file picker
-  if (selectedIconPath && iconPath) {
+  // Handle icon dropped or pasted into the editor
+  if (selectedIconData) {
+    try {
+      icon = await invoke('save_icon_data', {
+        name: `btn_p${pageIndex}_b${currentButtonId}_${Date.now()}`,
+        data: selectedIconData
+      });
+      showToast('Icono guardado');
+    } catch (e) {
+      console.error('Error saving icon:', e);
+      showToast('Error al guardar icono');
+    }
+  } else if (selectedIconPath && iconPath) {
+    // Icon from file picker
     try {
       // Generate a unique name for the icon
       const iconName = `btn_p${pageIndex}_b${currentButtonId}_${Date.now()}.png`;
@@ -641,6 +1078,8 @@ async function saveButton() {
     color,
     icon
   };
+  const sound = document.getElementById('edit-sound').value.trim();
+  if (sound) buttonConfig.sound = sound;
 
   try {
     await invoke('update_button', {
@@ -653,13 +1092,7 @@ async function saveButton() {
     renderButtons();
     closeModal();
     showToast('Botón guardado');
-
-    // Reload the current page on the device to show the new button
-    try {
-      await invoke('load_current_page');
-    } catch (e) {
-      console.log('Could not update device:', e);
-    }
+    // The backend redraws just this key on the device
 
     // Reload hotkeys if the command contains a hotkey
     if (command.startsWith('__HOTKEY_')) {
@@ -688,6 +1121,69 @@ async function startButtonListener() {
   }, 5000);
 }
 
+// ============================================================================
+// Simulator (app started with --simulator)
+// ============================================================================
+
+// The panel mirrors what the deck shows ("deck-mirror" events). In simulator
+// mode it is always visible and its keys can be pressed.
+async function startSimulator() {
+  const status = await invoke('get_status');
+  const panel = document.getElementById('simulator');
+  const keys = {};
+  panel.querySelectorAll('.button').forEach(el => { keys[el.dataset.simKey] = el; });
+
+  const show = () => panel.classList.remove('hidden');
+  const setKey = (key, dataUrl) => {
+    keys[key].style.backgroundImage = dataUrl ? `url('${dataUrl}')` : 'none';
+  };
+  const setBrightness = (brightness) => {
+    Object.values(keys).forEach(el => { el.style.filter = `brightness(${Math.max(brightness, 10)}%)`; });
+  };
+
+  if (status.simulator) {
+    show();
+    // Presses go to the same listener as the hardware keys
+    Object.entries(keys).forEach(([key, el]) => {
+      const keyId = parseInt(key);
+      const send = async (pressed) => {
+        el.classList.toggle('pressed', pressed);
+        try {
+          await invoke('simulate_key', { keyId, pressed });
+        } catch (e) {
+          console.error('Error simulating key:', e);
+        }
+      };
+      el.addEventListener('mousedown', () => send(true));
+      el.addEventListener('mouseup', () => send(false));
+      el.addEventListener('mouseleave', () => {
+        if (el.classList.contains('pressed')) send(false);
+      });
+    });
+  }
+
+  try {
+    const state = await invoke('get_deck_mirror');
+    Object.entries(state.keys).forEach(([key, dataUrl]) => setKey(key, dataUrl));
+    setBrightness(state.brightness);
+    if (Object.keys(state.keys).length > 0) show();
+  } catch (e) {
+    console.error('Error loading deck mirror:', e);
+  }
+
+  if (!window.__TAURI__?.event) return;
+  await window.__TAURI__.event.listen('deck-mirror', ({ payload }) => {
+    show();
+    if (payload.type === 'key') {
+      setKey(payload.key, payload.image);
+    } else if (payload.type === 'clear') {
+      Object.keys(keys).forEach(key => setKey(key, null));
+    } else if (payload.type === 'brightness') {
+      setBrightness(payload.brightness);
+    }
+  });
+}
+
 // ============================================================================
 // Modal Event Handlers
 // ============================================================================
@@ -702,6 +1198,15 @@ document.addEventListener('keydown', (e) => {
   if (e.key === 'Enter' && document.getElementById('new-page-modal').classList.contains('active')) {
     createNewPage();
   }
+  // Ctrl+Z / Ctrl+Shift+Z (Ctrl+Y) outside of text fields
+  const editing = ['INPUT', 'TEXTAREA', 'SELECT'].includes(document.activeElement?.tagName);
+  if (e.ctrlKey && !editing && e.key.toLowerCase() === 'z') {
+    e.preventDefault();
+    undoConfig(e.shiftKey);
+  } else if (e.ctrlKey && !editing && e.key.toLowerCase() === 'y') {
+    e.preventDefault();
+    undoConfig(true);
+  }
 });
 
 document.getElementById('modal').addEventListener('click', (e) => {
@@ -0,0 +1,407 @@
+// ============================================================================
+// Button Actions
+// ============================================================================
+//
+// Structured form of a button command. Buttons are stored with both the
+// legacy `command` string (still what the editor shows and what widgets are
+// rendered from) and an `action`:
+//
+//   { "label": "Docs", "command": "__URL_https://docs.rs",
+//     "action": { "type": "url", "url": "https://docs.rs" }, ... }
+//
+// `command` wins when the two disagree, so configs edited by hand or by
+// older versions keep working; a button with only an `action` gets its
+// `command` filled in on load. Integration commands (systemd, docker, ssh, plugins, ...) are
+// kept as their command string and handled by their own modules.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::input::{self, MouseAction};
+use crate::{apps, audio, ci, clipboard, color_picker, command_widget, display, docker, elgato, github, journal, key_name_to_code, kubernetes, midi, mpris, mpv, network, notify, numpad, OBS_CHAPTER_COMMAND, OBS_LAUNCH_COMMAND, OBS_SCENES_COMMAND, plugins, power, privacy, process_monitor, profiles, scheduler, screen, snippets, sound, ssh, systemd, updates, variables, virtual_page, vlc, window_switcher, workspaces};
+
+// Display-only widgets; pressing them just refreshes the deck
+const DISPLAY_WIDGETS: [&str; 13] = [
+    "__CLOCK__",
+    "__CLOCK_S__",
+    "__DATE__",
+    "__DATE_FULL__",
+    "__WEEKDAY__",
+    "__CPU__",
+    "__RAM__",
+    "__TEMP__",
+    "__UPTIME__",
+    "__LOAD__",
+    "__OBS_STATUS__",
+    "__TWITCH_VIEWERS__",
+    "__TWITCH_FOLLOWERS__",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PageTarget {
+    Next,
+    Prev,
+    Index(usize),
+    // Leave a generated page (SSH hosts, clipboard, ...)
+    Close,
+}
+
+// How a shell action is run; only settable through the structured action
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ShellOptions {
+    // Working directory, `~/` is expanded
+    pub cwd: Option<String>,
+    // Extra environment variables (values may use ${var})
+    pub env: HashMap<String, String>,
+    // Run through `$SHELL -l -c` so PATH and friends match a terminal
+    pub login_shell: bool,
+}
+
+impl ShellOptions {
+    pub fn is_default(&self) -> bool {
+        *self == ShellOptions::default()
+    }
+
+    pub fn working_dir(&self) -> Option<PathBuf> {
+        let cwd = self.cwd.as_deref()?.trim();
+        if cwd.is_empty() {
+            return None;
+        }
+        match (cwd.strip_prefix("~/"), std::env::var("HOME")) {
+            (Some(rest), Ok(home)) => Some(PathBuf::from(home).join(rest)),
+            _ if cwd == "~" => std::env::var("HOME").ok().map(PathBuf::from),
+            _ => Some(PathBuf::from(cwd)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Action {
+    None,
+    Shell {
+        command: String,
+        #[serde(default, skip_serializing_if = "ShellOptions::is_default")]
+        options: ShellOptions,
+    },
+    Url { url: String },
+    Hotkey { keys: String },
+    Type { text: String },
+    Mouse { action: MouseAction },
+    Delay { ms: u64 },
+    Multi { steps: Vec<Action> },
+    PageNav { target: PageTarget },
+    Timer { minutes: u64 },
+    ObsStream,
+    ObsRecord,
+    ObsMute,
+    ObsScene { scene: String },
+    TwitchChat { message: String },
+    TwitchClip,
+    TwitchAd { length: u32 },
+    // <app data>/scripts/<name>.rhai
+    Script { name: String },
+    SetVar { name: String, value: String },
+    Widget { command: String },
+    Integration { command: String },
+}
+
+fn is_integration_command(cmd: &str) -> bool {
+    systemd::is_systemd_command(cmd)
+        || apps::is_app_command(cmd)
+        || cmd == color_picker::COMMAND
+        || display::is_display_command(cmd)
+        || docker::is_docker_command(cmd)
+        || ci::is_ci_command(cmd)
+        || github::is_github_command(cmd)
+        || snippets::is_snippet_command(cmd)
+        || clipboard::is_clipboard_command(cmd)
+        || ssh::is_ssh_command(cmd)
+        || cmd == window_switcher::COMMAND
+        || workspaces::is_workspace_widget(cmd)
+        || process_monitor::is_process_command(cmd)
+        || cmd == updates::COMMAND
+        || cmd == journal::COMMAND
+        || kubernetes::is_kube_command(cmd)
+        || midi::is_midi_command(cmd)
+        || mpv::is_mpv_command(cmd)
+        || mpris::is_mpris_command(cmd)
+        || network::is_network_command(cmd)
+        || audio::is_audio_command(cmd)
+        || notify::is_notify_command(cmd)
+        || numpad::is_numpad_command(cmd)
+        || plugins::is_plugin_command(cmd)
+        || elgato::is_sd_command(cmd)
+        || power::is_power_command(cmd)
+        || cmd == privacy::COMMAND
+        || cmd == privacy::SCREEN_COMMAND
+        || profiles::is_profile_command(cmd)
+        || scheduler::is_schedule_command(cmd)
+        || screen::is_screen_command(cmd)
+        || sound::is_sound_command(cmd)
+        || command_widget::is_command_widget(cmd)
+        || vlc::is_vlc_command(cmd)
+        || virtual_page::is_list_command(cmd)
+        || cmd == OBS_SCENES_COMMAND
+        || cmd == OBS_CHAPTER_COMMAND
+        || cmd == OBS_LAUNCH_COMMAND
+}
+
+// "__<prefix><n>__" -> n
+fn parse_number<T: std::str::FromStr>(cmd: &str, prefix: &str) -> Option<T> {
+    cmd.strip_prefix(prefix)?.strip_suffix("__")?.parse().ok()
+}
+
+impl Action {
+    // Migrate a legacy command string
+    pub fn parse(cmd: &str) -> Action {
+        // __HOTKEY_<combo>__<command>: the combo is a binding, not an action
+        if let Some(rest) = cmd.strip_prefix("__HOTKEY_") {
+            return match rest.find("__") {
+                Some(idx) => Action::parse(&rest[idx + 2..]),
+                None => Action::None,
+            };
+        }
+
+        if cmd.trim().is_empty() {
+            return Action::None;
+        }
+        if cmd == virtual_page::CLOSE_COMMAND {
+            return Action::PageNav { target: PageTarget::Close };
+        }
+        if cmd == "__NEXT_PAGE__" {
+            return Action::PageNav { target: PageTarget::Next };
+        }
+        if cmd == "__PREV_PAGE__" {
+            return Action::PageNav { target: PageTarget::Prev };
+        }
+        if cmd.starts_with("__PAGE_") && cmd.ends_with("__") {
+            return match parse_number(cmd, "__PAGE_") {
+                Some(index) => Action::PageNav { target: PageTarget::Index(index) },
+                None => Action::None,
+            };
+        }
+        if cmd.starts_with("__TIMER_") && cmd.ends_with("__") {
+            return match parse_number(cmd, "__TIMER_") {
+                Some(minutes) => Action::Timer { minutes },
+                None => Action::None,
+            };
+        }
+
+        if is_integration_command(cmd) {
+            return Action::Integration { command: cmd.to_string() };
+        }
+        if DISPLAY_WIDGETS.contains(&cmd) {
+            return Action::Widget { command: cmd.to_string() };
+        }
+
+        match cmd {
+            "__OBS_STREAM__" => return Action::ObsStream,
+            "__OBS_RECORD__" => return Action::ObsRecord,
+            "__OBS_MUTE__" => return Action::ObsMute,
+            "__TWITCH_CLIP__" => return Action::TwitchClip,
+            _ => {}
+        }
+        if let Some(scene) = cmd.strip_prefix("__OBS_SCENE_") {
+            return Action::ObsScene { scene: scene.to_string() };
+        }
+        if let Some(message) = cmd.strip_prefix("__TWITCH_CHAT_") {
+            return Action::TwitchChat { message: message.to_string() };
+        }
+        if cmd.starts_with("__TWITCH_AD_") && cmd.ends_with("__") {
+            return match parse_number(cmd, "__TWITCH_AD_") {
+                Some(length) => Action::TwitchAd { length },
+                None => Action::None,
+            };
+        }
+
+        if let Some(assignment) = cmd.strip_prefix("__SET_VAR_").and_then(|c| c.strip_suffix("__")) {
+            let (name, value) = assignment.split_once('=').unwrap_or((assignment, ""));
+            return Action::SetVar { name: name.to_string(), value: value.to_string() };
+        }
+        if cmd.starts_with("__SCRIPT_") && cmd.ends_with("__") {
+            let name = &cmd["__SCRIPT_".len()..cmd.len() - 2];
+            return Action::Script { name: name.to_string() };
+        }
+
+        if let Some(url) = cmd.strip_prefix("__URL_") {
+            return Action::Url { url: url.to_string() };
+        }
+        if let Some(text) = cmd.strip_prefix("__TYPE_") {
+            return Action::Type { text: text.to_string() };
+        }
+        if let Some(keys) = cmd.strip_prefix("__KEY_") {
+            return Action::Hotkey { keys: keys.to_string() };
+        }
+        if let Some(action) = input::parse_mouse_command(cmd) {
+            return Action::Mouse { action };
+        }
+        if let Some(steps) = cmd.strip_prefix("__MULTI_") {
+            let steps = steps
+                .split(";;")
+                .map(str::trim)
+                .filter(|step| !step.is_empty())
+                .map(Action::parse)
+                .collect();
+            return Action::Multi { steps };
+        }
+        if let Some(ms) = cmd.strip_prefix("__DELAY_") {
+            if let Ok(ms) = ms.parse() {
+                return Action::Delay { ms };
+            }
+        }
+
+        Action::Shell { command: cmd.to_string(), options: ShellOptions::default() }
+    }
+
+    // Legacy command string for this action
+    pub fn to_command(&self) -> String {
+        match self {
+            Action::None => String::new(),
+            Action::Shell { command, .. } => command.clone(),
+            Action::Url { url } => format!("__URL_{}", url),
+            Action::Hotkey { keys } => format!("__KEY_{}", keys),
+            Action::Type { text } => format!("__TYPE_{}", text),
+            Action::Mouse { action } => action.to_command(),
+            Action::Delay { ms } => format!("__DELAY_{}", ms),
+            Action::Multi { steps } => {
+                let steps: Vec<String> = steps.iter().map(Action::to_command).collect();
+                format!("__MULTI_{}", steps.join(";;"))
+            }
+            Action::PageNav { target } => match target {
+                PageTarget::Next => "__NEXT_PAGE__".to_string(),
+                PageTarget::Prev => "__PREV_PAGE__".to_string(),
+                PageTarget::Index(index) => format!("__PAGE_{}__", index),
+                PageTarget::Close => virtual_page::CLOSE_COMMAND.to_string(),
+            },
+            Action::Timer { minutes } => format!("__TIMER_{}__", minutes),
+            Action::ObsStream => "__OBS_STREAM__".to_string(),
+            Action::ObsRecord => "__OBS_RECORD__".to_string(),
+            Action::ObsMute => "__OBS_MUTE__".to_string(),
+            Action::ObsScene { scene } => format!("__OBS_SCENE_{}", scene),
+            Action::TwitchChat { message } => format!("__TWITCH_CHAT_{}", message),
+            Action::TwitchClip => "__TWITCH_CLIP__".to_string(),
+            Action::TwitchAd { length } => format!("__TWITCH_AD_{}__", length),
+            Action::Script { name } => format!("__SCRIPT_{}__", name),
+            Action::SetVar { name, value } => format!("__SET_VAR_{}={}__", name, value),
+            Action::Widget { command } | Action::Integration { command } => command.clone(),
+        }
+    }
+
+    // The action itself followed by every nested __MULTI_ step
+    pub fn flatten(&self) -> Vec<&Action> {
+        let mut actions = vec![self];
+        if let Action::Multi { steps } = self {
+            for step in steps {
+                actions.extend(step.flatten());
+            }
+        }
+        actions
+    }
+
+    // Runs a shell command, script, program (__APP_, __PROC_) or ssh session
+    // (refused in safe mode), here or in a step
+    pub fn runs_code(&self) -> bool {
+        self.flatten().iter().any(|action| match action {
+            Action::Shell { .. } | Action::Script { .. } => true,
+            Action::Widget { command } | Action::Integration { command } => {
+                command_widget::is_command_widget(command)
+                    || process_monitor::is_process_command(command)
+                    || ssh::is_ssh_command(command)
+                    || apps::is_app_command(command)
+            }
+            _ => false,
+        })
+    }
+
+    // Problems that would make the action fail or do nothing
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        match self {
+            Action::None => problems.push("No action".to_string()),
+            Action::Shell { command, .. } if command.starts_with("__") => {
+                problems.push(format!("Unknown or malformed special command: {}", command));
+            }
+            Action::Shell { options, .. } => {
+                if let Some(dir) = options.working_dir() {
+                    if !dir.is_dir() {
+                        problems.push(format!("Working directory does not exist: {}", dir.display()));
+                    }
+                }
+                if let Some(name) = options.env.keys().find(|k| k.is_empty() || k.contains('=')) {
+                    problems.push(format!("Invalid environment variable name '{}'", name));
+                }
+            }
+            Action::Url { url } if url.trim().is_empty() => problems.push("Missing URL".to_string()),
+            Action::Hotkey { keys } => {
+                for key in keys.split('+').map(str::trim) {
+                    if key.is_empty() {
+                        problems.push(format!("Empty key name in '{}'", keys));
+                    } else if key_name_to_code(key).is_none() {
+                        problems.push(format!("Unknown key name '{}'", key));
+                    }
+                }
+            }
+            Action::Type { text } if text.is_empty() => problems.push("Nothing to type".to_string()),
+            Action::Delay { .. } => problems.push("__DELAY_ only works as a __MULTI_ step".to_string()),
+            Action::Multi { steps } => {
+                if steps.is_empty() {
+                    problems.push("__MULTI_ has no steps".to_string());
+                }
+                for (i, step) in steps.iter().enumerate() {
+                    let step_problems = match step {
+                        Action::Delay { .. } => Vec::new(),
+                        step => step.validate(),
+                    };
+                    problems.extend(step_problems.into_iter().map(|p| format!("Step {}: {}", i + 1, p)));
+                }
+            }
+            Action::Timer { minutes: 0 } => problems.push("Timer needs at least 1 minute".to_string()),
+            Action::ObsScene { scene } if scene.trim().is_empty() => {
+                problems.push("Missing OBS scene name".to_string());
+            }
+            Action::TwitchChat { message } if message.trim().is_empty() => {
+                problems.push("Missing chat message".to_string());
+            }
+            Action::SetVar { name, .. } if !variables::is_valid_name(name) => {
+                problems.push(format!("Invalid variable name '{}' (letters, digits, '_', '-', '.')", name));
+            }
+            Action::Script { name } if name.is_empty() => problems.push("Missing script name".to_string()),
+            Action::TwitchAd { length } if !(1..=180).contains(length) => {
+                problems.push(format!("Commercial length must be 1-180 seconds, got {}", length));
+            }
+            _ => {}
+        }
+        problems
+    }
+}
+
+// Validate a legacy command string, including problems the parser hides
+pub fn validate_command(cmd: &str) -> Vec<String> {
+    let action = Action::parse(cmd);
+    let mut problems = Vec::new();
+
+    if action == Action::None && !cmd.trim().is_empty() {
+        if cmd.starts_with("__HOTKEY_") {
+            problems.push("Hotkey binding has no action".to_string());
+        } else {
+            problems.push(format!("Malformed command: {}", cmd));
+        }
+        return problems;
+    }
+    if let Some(steps) = cmd.strip_prefix("__MULTI_") {
+        let segments: Vec<&str> = steps.split(";;").collect();
+        for (i, segment) in segments.iter().enumerate() {
+            if segment.trim().is_empty() && segments.len() > 1 {
+                problems.push(format!("Empty step {} (stray ';;')", i + 1));
+            }
+        }
+    }
+
+    problems.extend(action.validate());
+    problems
+}
@@ -0,0 +1,151 @@
+// ============================================================================
+// Action Log
+// ============================================================================
+//
+// Shell actions are waited on in the background so their exit status and
+// stderr can be kept; integrations can record their own errors. The last MAX_ENTRIES runs are available through the
+// `get_action_log` Tauri command, and a key whose command failed flashes red
+// for a moment.
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tracing::warn;
+
+use crate::{chrono_lite, request_refresh};
+
+const MAX_ENTRIES: usize = 50;
+// Only the end of stderr is kept
+const MAX_STDERR_LINES: usize = 20;
+const FLASH_DURATION: Duration = Duration::from_millis(1500);
+pub const FLASH_COLOR: &str = "#c0392b";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ActionLogEntry {
+    pub timestamp: u64,
+    pub command: String,
+    pub success: bool,
+    pub exit_code: Option<i32>,
+    pub stderr: String,
+    pub duration_ms: u64,
+}
+
+lazy_static::lazy_static! {
+    static ref LOG: Mutex<VecDeque<ActionLogEntry>> = Mutex::new(VecDeque::new());
+    // Commands whose keys are flashing, with when the flash started
+    static ref FLASHING: Mutex<HashMap<String, Instant>> = Mutex::new(HashMap::new());
+}
+
+fn record(entry: ActionLogEntry) {
+    if let Ok(mut log) = LOG.lock() {
+        log.push_front(entry);
+        log.truncate(MAX_ENTRIES);
+    }
+}
+
+// Most recent first
+pub fn entries() -> Vec<ActionLogEntry> {
+    LOG.lock().map(|log| log.iter().cloned().collect()).unwrap_or_default()
+}
+
+pub fn is_flashing(command: &str) -> bool {
+    FLASHING
+        .lock()
+        .map(|flashing| flashing.get(command).map(|t| t.elapsed() < FLASH_DURATION).unwrap_or(false))
+        .unwrap_or(false)
+}
+
+// Show keys running `command` in red, then redraw them normally
+fn flash(command: &str) {
+    if let Ok(mut flashing) = FLASHING.lock() {
+        flashing.retain(|_, started| started.elapsed() < FLASH_DURATION);
+        flashing.insert(command.to_string(), Instant::now());
+    }
+    request_refresh();
+    thread::spawn(|| {
+        thread::sleep(FLASH_DURATION);
+        request_refresh();
+    });
+}
+
+// Record an action that failed without a process (e.g. a D-Bus call) and
+// flash its key
+pub fn record_error(command: &str, error: &str) {
+    warn!("{}", error);
+    record(ActionLogEntry {
+        timestamp: chrono_lite(),
+        command: command.to_string(),
+        success: false,
+        exit_code: None,
+        stderr: error.to_string(),
+        duration_ms: 0,
+    });
+    flash(command);
+}
+
+// Run a shell process to completion (blocking), logging the result.
+// `command` is the button command shown in the log and used for the flash.
+pub fn run_logged(command: &str, mut process: Command) -> Result<(), String> {
+    let started = Instant::now();
+    let timestamp = chrono_lite();
+
+    let result = process.stderr(Stdio::piped()).spawn().and_then(|mut child| {
+        let mut stderr: VecDeque<String> = VecDeque::new();
+        if let Some(pipe) = child.stderr.take() {
+            for line in BufReader::new(pipe).lines().map_while(Result::ok) {
+                stderr.push_back(line);
+                if stderr.len() > MAX_STDERR_LINES {
+                    stderr.pop_front();
+                }
+            }
+        }
+        let status = child.wait()?;
+        Ok((status, Vec::from(stderr).join("\n")))
+    });
+
+    let (entry, error) = match result {
+        Ok((status, stderr)) => {
+            let error = if status.success() {
+                None
+            } else {
+                Some(format!("Command failed ({}): {}", status, stderr))
+            };
+            let entry = ActionLogEntry {
+                timestamp,
+                command: command.to_string(),
+                success: status.success(),
+                exit_code: status.code(),
+                stderr,
+                duration_ms: started.elapsed().as_millis() as u64,
+            };
+            (entry, error)
+        }
+        Err(e) => {
+            let error = format!("Failed to run command: {}", e);
+            let entry = ActionLogEntry {
+                timestamp,
+                command: command.to_string(),
+                success: false,
+                exit_code: None,
+                stderr: error.clone(),
+                duration_ms: 0,
+            };
+            (entry, Some(error))
+        }
+    };
+    record(entry);
+
+    match error {
+        None => Ok(()),
+        Some(e) => {
+            warn!("{}", e);
+            flash(command);
+            Err(e)
+        }
+    }
+}
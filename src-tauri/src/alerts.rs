@@ -0,0 +1,372 @@
+// ============================================================================
+// Deck-wide Alerts (streaming events)
+// ============================================================================
+//
+// Important events take over the whole deck for a few seconds: the middle row
+// shows the event on the alert color and the other keys flash a frame of that
+// color, then the page comes back. Events:
+//   follower          - the Twitch follower count went up (checked every minute)
+//   raid              - an incoming raid (Twitch chat, read anonymously)
+//   recordingStopped  - OBS stopped recording without the deck asking it to
+//
+// Each can be turned off in config.json:
+//
+//   "alerts": { "follower": true, "raid": true, "recordingStopped": true }
+//
+// Alerts wait in a priority queue (raids and a stopped recording before
+// followers). The listener plays them through AlertPlayer, which preempts
+// widget refreshes and toasts while an alert is on; a key press dismisses it.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::path::Path;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use image::{ImageBuffer, Rgb, RgbImage};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::deck::Deck;
+use crate::{
+    config_store, deck_reader, get_twitch_channel, lock_screen, obs_request, parse_hex_color, render_button_image, render_cache,
+    request_refresh, sound, twitch_get_followers, twitch_init, virtual_page, BUTTON_SIZE,
+};
+
+const ALERT_DURATION: Duration = Duration::from_secs(3);
+const FLASH_INTERVAL: Duration = Duration::from_millis(500);
+const FRAME_WIDTH: u32 = 8;
+const MAX_QUEUED: usize = 10;
+const FOLLOWER_POLL: Duration = Duration::from_secs(60);
+const RECORDING_POLL: Duration = Duration::from_secs(5);
+// A recording stop within this long after a deck toggle was asked for
+const EXPECTED_STOP_WINDOW: Duration = Duration::from_secs(10);
+const IRC_HOST: &str = "irc.chat.twitch.tv:6667";
+const IRC_RETRY: Duration = Duration::from_secs(30);
+// Middle row: title, detail, extra
+const TEXT_KEYS: [u8; 3] = [7, 8, 9];
+const ROW_KEYS: [u8; 5] = [6, 7, 8, 9, 10];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertSettings {
+    #[serde(default = "enabled")]
+    pub follower: bool,
+    #[serde(default = "enabled")]
+    pub raid: bool,
+    #[serde(default = "enabled", rename = "recordingStopped")]
+    pub recording_stopped: bool,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+impl Default for AlertSettings {
+    fn default() -> Self {
+        AlertSettings {
+            follower: true,
+            raid: true,
+            recording_stopped: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    Normal,
+    High,
+}
+
+#[derive(Debug, Clone)]
+pub struct Alert {
+    pub priority: Priority,
+    pub color: String,
+    // Shown on the middle row, one per key
+    pub lines: Vec<String>,
+}
+
+lazy_static::lazy_static! {
+    static ref QUEUE: Mutex<Vec<Alert>> = Mutex::new(Vec::new());
+    // When the deck last toggled recording
+    static ref RECORD_TOGGLED: Mutex<Option<Instant>> = Mutex::new(None);
+}
+
+fn settings() -> AlertSettings {
+    config_store::read(|c| c.alerts.clone()).unwrap_or_default()
+}
+
+// Queue an alert; higher priority ones play first
+pub fn raise(alert: Alert) {
+    if lock_screen::locked() {
+        return;
+    }
+    debug!("Alert: {:?}", alert.lines);
+    if let Ok(mut queue) = QUEUE.lock() {
+        if queue.len() >= MAX_QUEUED {
+            return;
+        }
+        // Stable: same priority keeps arrival order
+        let at = queue.iter().position(|a| a.priority < alert.priority).unwrap_or(queue.len());
+        queue.insert(at, alert);
+    }
+    deck_reader::wake();
+}
+
+fn next() -> Option<Alert> {
+    QUEUE.lock().ok().filter(|q| !q.is_empty()).map(|mut q| q.remove(0))
+}
+
+// The deck is about to start or stop recording; that stop is no surprise
+pub fn expect_recording_change() {
+    if let Ok(mut toggled) = RECORD_TOGGLED.lock() {
+        *toggled = Some(Instant::now());
+    }
+}
+
+fn draw_frame(image: &mut RgbImage, color: Rgb<u8>) {
+    for (x, y, pixel) in image.enumerate_pixels_mut() {
+        let edge = x < FRAME_WIDTH || y < FRAME_WIDTH || x >= BUTTON_SIZE - FRAME_WIDTH || y >= BUTTON_SIZE - FRAME_WIDTH;
+        if edge {
+            *pixel = color;
+        }
+    }
+}
+
+struct Playing {
+    since: Instant,
+    last_flash: Instant,
+    frame_on: bool,
+    // Keys outside the middle row: (plain, framed)
+    outer: Vec<(u8, RgbImage, RgbImage)>,
+}
+
+// Plays queued alerts on the deck; owned by the listener
+#[derive(Default)]
+pub struct AlertPlayer {
+    playing: Option<Playing>,
+}
+
+impl AlertPlayer {
+    pub fn active(&self) -> bool {
+        self.playing.is_some()
+    }
+
+    fn start(&mut self, deck: &dyn Deck, alert: &Alert, icons_path: &Path) {
+        let (r, g, b) = parse_hex_color(&alert.color);
+        let color = Rgb([r, g, b]);
+
+        // The page under the alert, as last drawn
+        let page = virtual_page::active().or_else(|| config_store::read(|c| c.pages.get(c.current_page).cloned()).flatten());
+        let images: HashMap<u8, RgbImage> = page
+            .map(|mut page| {
+                page.apply_default_color();
+                render_cache::render_page(&page, icons_path).into_iter().collect()
+            })
+            .unwrap_or_default();
+
+        let mut outer = Vec::new();
+        for key_id in (1..=15u8).filter(|k| !ROW_KEYS.contains(k)) {
+            let plain = images
+                .get(&key_id)
+                .cloned()
+                .unwrap_or_else(|| ImageBuffer::from_pixel(BUTTON_SIZE, BUTTON_SIZE, Rgb([0, 0, 0])));
+            let mut framed = plain.clone();
+            draw_frame(&mut framed, color);
+            outer.push((key_id, plain, framed));
+        }
+
+        sound::play_event(sound::Event::Alert);
+        for key_id in ROW_KEYS {
+            let text = TEXT_KEYS
+                .iter()
+                .position(|k| *k == key_id)
+                .and_then(|i| alert.lines.get(i))
+                .cloned()
+                .unwrap_or_default();
+            let image = render_button_image(&virtual_page::button(&text, "", &alert.color), icons_path);
+            deck.set_key_image(key_id, &image).ok();
+        }
+        for (key_id, _, framed) in &outer {
+            deck.set_key_image(*key_id, framed).ok();
+        }
+        self.playing = Some(Playing {
+            since: Instant::now(),
+            last_flash: Instant::now(),
+            frame_on: true,
+            outer,
+        });
+    }
+
+    // Draw the next frame, or start the next alert; true while one is shown
+    pub fn step(&mut self, deck: &dyn Deck, icons_path: &Path) -> bool {
+        match self.playing.as_mut() {
+            Some(playing) if playing.since.elapsed() >= ALERT_DURATION => {
+                self.dismiss();
+                false
+            }
+            Some(playing) => {
+                if playing.last_flash.elapsed() >= FLASH_INTERVAL {
+                    playing.frame_on = !playing.frame_on;
+                    playing.last_flash = Instant::now();
+                    for (key_id, plain, framed) in &playing.outer {
+                        deck.set_key_image(*key_id, if playing.frame_on { framed } else { plain }).ok();
+                    }
+                }
+                true
+            }
+            None => match next() {
+                Some(alert) => {
+                    self.start(deck, &alert, icons_path);
+                    true
+                }
+                None => false,
+            },
+        }
+    }
+
+    // End the alert and bring the page back
+    pub fn dismiss(&mut self) {
+        if self.playing.take().is_some() {
+            request_refresh();
+        }
+    }
+}
+
+fn watch_followers() {
+    let mut last: Option<u32> = None;
+    loop {
+        thread::sleep(FOLLOWER_POLL);
+        if !settings().follower {
+            last = None;
+            continue;
+        }
+        // Connects on first use; fails quietly without credentials
+        let followers = match twitch_get_followers().or_else(|_| twitch_init().and_then(|_| twitch_get_followers())) {
+            Ok(f) => f,
+            Err(_) => continue,
+        };
+        if let Some(previous) = last.filter(|p| followers > *p) {
+            raise(Alert {
+                priority: Priority::Normal,
+                color: "#9146ff".to_string(),
+                lines: vec!["FOLLOW".to_string(), format!("+{}", followers - previous), followers.to_string()],
+            });
+        }
+        last = Some(followers);
+    }
+}
+
+// "@badge-info=;msg-id=raid;msg-param-displayName=Name;msg-param-viewerCount=12 :tmi.twitch.tv USERNOTICE #chan"
+fn parse_raid(line: &str) -> Option<(String, String)> {
+    let tags = line.strip_prefix('@')?.split_once(' ')?.0;
+    if !line.contains(" USERNOTICE ") {
+        return None;
+    }
+    let tag = |name: &str| {
+        tags.split(';')
+            .find_map(|t| t.strip_prefix(name)?.strip_prefix('='))
+            .map(str::to_string)
+    };
+    if tag("msg-id")? != "raid" {
+        return None;
+    }
+    Some((tag("msg-param-displayName").unwrap_or_default(), tag("msg-param-viewerCount").unwrap_or_default()))
+}
+
+fn read_chat(channel: &str) -> Result<(), String> {
+    let mut stream = TcpStream::connect(IRC_HOST).map_err(|e| e.to_string())?;
+    // Anonymous read-only login
+    write!(
+        stream,
+        "CAP REQ :twitch.tv/tags twitch.tv/commands\r\nNICK justinfan{}\r\nJOIN #{}\r\n",
+        std::process::id(),
+        channel.to_lowercase()
+    )
+    .map_err(|e| e.to_string())?;
+
+    let reader = BufReader::new(stream.try_clone().map_err(|e| e.to_string())?);
+    for line in reader.lines() {
+        let line = line.map_err(|e| e.to_string())?;
+        if line.starts_with("PING") {
+            writeln!(stream, "PONG :tmi.twitch.tv\r").ok();
+            continue;
+        }
+        if let Some((name, viewers)) = parse_raid(&line) {
+            if settings().raid {
+                raise(Alert {
+                    priority: Priority::High,
+                    color: "#e67e22".to_string(),
+                    lines: vec!["RAID".to_string(), name, viewers],
+                });
+            }
+        }
+    }
+    Err("Chat connection closed".to_string())
+}
+
+fn watch_raids() {
+    let channel = get_twitch_channel();
+    if channel.is_empty() {
+        return;
+    }
+    loop {
+        if let Err(e) = read_chat(&channel) {
+            debug!("Twitch chat for raid alerts: {}", e);
+        }
+        thread::sleep(IRC_RETRY);
+    }
+}
+
+fn watch_recording() {
+    let mut recording: Option<bool> = None;
+    loop {
+        thread::sleep(RECORDING_POLL);
+        if !settings().recording_stopped {
+            recording = None;
+            continue;
+        }
+        let now = match obs_request("GetRecordStatus", None) {
+            Ok(data) => data.get("outputActive").and_then(|v| v.as_bool()).unwrap_or(false),
+            Err(_) => {
+                // OBS closed or crashed while recording
+                if recording == Some(true) {
+                    raise_recording_stopped("OBS OFF");
+                }
+                recording = None;
+                continue;
+            }
+        };
+        if recording == Some(true) && !now {
+            let expected = RECORD_TOGGLED
+                .lock()
+                .ok()
+                .and_then(|t| *t)
+                .map(|at| at.elapsed() < EXPECTED_STOP_WINDOW)
+                .unwrap_or(false);
+            if !expected {
+                raise_recording_stopped("STOPPED");
+            }
+        }
+        recording = Some(now);
+    }
+}
+
+fn raise_recording_stopped(reason: &str) {
+    raise(Alert {
+        priority: Priority::High,
+        color: "#c0392b".to_string(),
+        lines: vec!["REC".to_string(), reason.to_string(), "!".to_string()],
+    });
+}
+
+// Start the event watchers; called once at startup
+pub fn start_monitors() {
+    thread::spawn(watch_followers);
+    thread::spawn(watch_raids);
+    thread::spawn(watch_recording);
+    if get_twitch_channel().is_empty() {
+        warn!("TWITCH_CHANNEL is not set; raid alerts are off");
+    }
+}
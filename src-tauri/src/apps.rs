@@ -0,0 +1,336 @@
+// ============================================================================
+// Application Launcher (run-or-focus)
+// ============================================================================
+//
+// __APP_<desktop-id>__ (e.g. __APP_firefox__, __APP_org.gnome.Nautilus__)
+// reads the app's .desktop file. If a window of the app is already open it is
+// focused, otherwise the app is started.
+//
+// Windows are found through Hyprland (hyprctl), Sway (swaymsg) or X11 (wmctrl)
+// by comparing the window class with StartupWMClass, the desktop id and the
+// executable name. On other desktops the app is always started.
+//
+// A key without its own icon shows the app's icon (PNG icons from the icon
+// themes or /usr/share/pixmaps; SVG-only icons are not shown).
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::RwLock;
+
+use tracing::debug;
+
+use crate::{sandbox, ssh};
+
+const ICON_SIZES: [&str; 7] = ["256x256", "192x192", "128x128", "96x96", "72x72", "64x64", "48x48"];
+
+struct DesktopEntry {
+    exec: String,
+    icon: Option<String>,
+    wm_class: Option<String>,
+    terminal: bool,
+}
+
+lazy_static::lazy_static! {
+    // Resolved icon file per desktop id (None when the app has no PNG icon)
+    static ref ICONS: RwLock<HashMap<String, Option<PathBuf>>> = RwLock::new(HashMap::new());
+}
+
+fn parse_command(cmd: &str) -> Option<&str> {
+    let id = cmd.strip_prefix("__APP_")?.strip_suffix("__")?;
+    let id = id.strip_suffix(".desktop").unwrap_or(id);
+    if id.is_empty() || id.contains('/') {
+        return None;
+    }
+    Some(id)
+}
+
+pub fn is_app_command(cmd: &str) -> bool {
+    parse_command(cmd).is_some()
+}
+
+fn home_dir() -> Option<PathBuf> {
+    std::env::var("HOME").ok().map(PathBuf::from)
+}
+
+// $XDG_DATA_HOME followed by $XDG_DATA_DIRS and the Flatpak exports
+fn data_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    match std::env::var("XDG_DATA_HOME") {
+        Ok(dir) if !dir.is_empty() => dirs.push(PathBuf::from(dir)),
+        _ => dirs.extend(home_dir().map(|h| h.join(".local/share"))),
+    }
+    let system = std::env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|d| !d.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+    dirs.extend(system.split(':').filter(|d| !d.is_empty()).map(PathBuf::from));
+    dirs.extend(home_dir().map(|h| h.join(".local/share/flatpak/exports/share")));
+    dirs.push(PathBuf::from("/var/lib/flatpak/exports/share"));
+    dirs
+}
+
+fn find_desktop_file(id: &str) -> Option<PathBuf> {
+    let file_name = format!("{}.desktop", id);
+    data_dirs().into_iter().find_map(|dir| {
+        let applications = dir.join("applications");
+        // Ids with dashes may live in subdirectories (kde-foo -> kde/foo.desktop)
+        std::iter::once(applications.join(&file_name))
+            .chain(file_name.split_once('-').map(|(sub, rest)| applications.join(sub).join(rest)))
+            .find(|path| path.is_file())
+    })
+}
+
+fn parse_desktop_file(content: &str) -> Option<DesktopEntry> {
+    let mut in_entry = false;
+    let mut values: HashMap<&str, &str> = HashMap::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_entry = line == "[Desktop Entry]";
+            continue;
+        }
+        if !in_entry || line.starts_with('#') {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            // Localized keys like Name[es] are not needed
+            values.entry(key.trim()).or_insert(value.trim());
+        }
+    }
+    let non_empty = |key: &str| values.get(key).filter(|v| !v.is_empty()).map(|v| v.to_string());
+    Some(DesktopEntry {
+        exec: strip_field_codes(values.get("Exec")?),
+        icon: non_empty("Icon"),
+        wm_class: non_empty("StartupWMClass"),
+        terminal: values.get("Terminal") == Some(&"true"),
+    })
+}
+
+fn load_entry(id: &str) -> Result<DesktopEntry, String> {
+    let path = find_desktop_file(id).ok_or_else(|| format!("No .desktop file found for {}", id))?;
+    let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    parse_desktop_file(&content).ok_or_else(|| format!("{} has no Exec line", path.display()))
+}
+
+// Drop %f, %U, ... (no files are passed) and unescape %%
+fn strip_field_codes(exec: &str) -> String {
+    let mut result = String::with_capacity(exec.len());
+    let mut chars = exec.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            result.push(c);
+        } else if let Some('%') = chars.next() {
+            result.push('%');
+        }
+    }
+    result.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// Lowercase class names a window of this app may have
+fn window_classes(id: &str, entry: &DesktopEntry) -> Vec<String> {
+    let mut classes = vec![id.to_string()];
+    // org.mozilla.firefox -> firefox
+    if let Some((_, last)) = id.rsplit_once('.') {
+        classes.push(last.to_string());
+    }
+    if let Some(class) = &entry.wm_class {
+        classes.push(class.clone());
+    }
+    let program = entry
+        .exec
+        .split_whitespace()
+        .find(|word| *word != "env" && !word.contains('='))
+        .map(|word| word.trim_matches('"'));
+    if let Some(name) = program.and_then(|p| Path::new(p).file_name()).and_then(|n| n.to_str()) {
+        classes.push(name.to_string());
+    }
+    classes.iter().map(|c| c.to_lowercase()).collect()
+}
+
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = sandbox::host_command(program).args(args).stderr(Stdio::null()).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn is_class(value: Option<&serde_json::Value>, classes: &[String]) -> bool {
+    value
+        .and_then(|v| v.as_str())
+        .map(|v| classes.contains(&v.to_lowercase()))
+        .unwrap_or(false)
+}
+
+fn focus_hyprland(classes: &[String]) -> Option<bool> {
+    std::env::var("HYPRLAND_INSTANCE_SIGNATURE").ok()?;
+    let clients: serde_json::Value = serde_json::from_str(&command_output("hyprctl", &["clients", "-j"])?).ok()?;
+    let address = clients.as_array()?.iter().find_map(|client| {
+        let matches = is_class(client.get("class"), classes) || is_class(client.get("initialClass"), classes);
+        if matches {
+            client.get("address")?.as_str()
+        } else {
+            None
+        }
+    });
+    Some(match address {
+        Some(address) => command_output("hyprctl", &["dispatch", "focuswindow", &format!("address:{}", address)]).is_some(),
+        None => false,
+    })
+}
+
+// Depth-first search of the sway tree for a window with a matching app_id/class
+fn find_sway_window(node: &serde_json::Value, classes: &[String]) -> Option<i64> {
+    let is_window = node.get("pid").is_some();
+    let class = node.get("window_properties").and_then(|p| p.get("class"));
+    if is_window && (is_class(node.get("app_id"), classes) || is_class(class, classes)) {
+        return node.get("id")?.as_i64();
+    }
+    ["nodes", "floating_nodes"]
+        .iter()
+        .filter_map(|key| node.get(*key)?.as_array())
+        .flatten()
+        .find_map(|child| find_sway_window(child, classes))
+}
+
+fn focus_sway(classes: &[String]) -> Option<bool> {
+    std::env::var("SWAYSOCK").ok()?;
+    let tree: serde_json::Value = serde_json::from_str(&command_output("swaymsg", &["-t", "get_tree", "-r"])?).ok()?;
+    Some(match find_sway_window(&tree, classes) {
+        Some(id) => command_output("swaymsg", &[&format!("[con_id={}] focus", id)]).is_some(),
+        None => false,
+    })
+}
+
+fn focus_x11(classes: &[String]) -> Option<bool> {
+    std::env::var("DISPLAY").ok()?;
+    // <id> <desktop> <instance>.<class> <host> <title>
+    let windows = command_output("wmctrl", &["-lx"])?;
+    let window = windows.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let id = fields.next()?;
+        let wm_class = fields.nth(1)?.to_lowercase();
+        let (instance, class) = wm_class.split_once('.').unwrap_or((&wm_class, &wm_class));
+        let matches = classes.iter().any(|c| c == instance || c == class);
+        matches.then(|| id.to_string())
+    });
+    Some(match window {
+        Some(id) => command_output("wmctrl", &["-ia", &id]).is_some(),
+        None => false,
+    })
+}
+
+// true if an open window was focused
+fn focus_existing(classes: &[String]) -> bool {
+    focus_hyprland(classes)
+        .or_else(|| focus_sway(classes))
+        .or_else(|| focus_x11(classes))
+        .unwrap_or(false)
+}
+
+fn launch(entry: &DesktopEntry) -> Result<(), String> {
+    let mut process = if entry.terminal {
+        let terminal = ssh::find_terminal().ok_or("No terminal emulator found (set $TERMINAL)")?;
+        let mut process = sandbox::host_command(&terminal);
+        process.args(ssh::terminal_args(&terminal, &["sh", "-c", &entry.exec]));
+        process
+    } else {
+        let mut process = sandbox::host_command("sh");
+        process.arg("-c").arg(&entry.exec);
+        process
+    };
+    process
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to launch {}: {}", entry.exec, e))?;
+    Ok(())
+}
+
+// Check that the .desktop file exists and can be read
+pub fn check(cmd: &str) -> Result<(), String> {
+    let id = parse_command(cmd).ok_or_else(|| format!("Invalid app command: {}", cmd))?;
+    load_entry(id).map(|_| ())
+}
+
+pub fn execute_command(cmd: &str) -> Result<(), String> {
+    let id = parse_command(cmd).ok_or_else(|| format!("Invalid app command: {}", cmd))?;
+    let entry = load_entry(id)?;
+    if focus_existing(&window_classes(id, &entry)) {
+        debug!("Focused running app {}", id);
+        return Ok(());
+    }
+    debug!("Launching app {}: {}", id, entry.exec);
+    launch(&entry)
+}
+
+fn find_icon(name: &str) -> Option<PathBuf> {
+    let path = Path::new(name);
+    if path.is_absolute() {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+    let file_name = format!("{}.png", name);
+
+    let mut bases: Vec<PathBuf> = home_dir().map(|h| h.join(".icons")).into_iter().collect();
+    bases.extend(data_dirs().into_iter().map(|d| d.join("icons")));
+    for base in &bases {
+        // hicolor first, then any other installed theme
+        let mut themes = vec![base.join("hicolor")];
+        if let Ok(entries) = fs::read_dir(base) {
+            let mut others: Vec<PathBuf> = entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.is_dir() && !p.ends_with("hicolor"))
+                .collect();
+            others.sort();
+            themes.extend(others);
+        }
+        for theme in &themes {
+            for size in ICON_SIZES {
+                let candidate = theme.join(size).join("apps").join(&file_name);
+                if candidate.is_file() {
+                    return Some(candidate);
+                }
+            }
+        }
+    }
+
+    let pixmap = PathBuf::from("/usr/share/pixmaps").join(&file_name);
+    pixmap.is_file().then_some(pixmap)
+}
+
+// Icon file of the app behind an __APP_ command, looked up once
+pub fn icon_path(cmd: &str) -> Option<PathBuf> {
+    let id = parse_command(cmd)?;
+    if let Some(cached) = ICONS.read().ok()?.get(id) {
+        return cached.clone();
+    }
+    let icon = load_entry(id).ok().and_then(|entry| entry.icon).and_then(|name| find_icon(&name));
+    if let Ok(mut icons) = ICONS.write() {
+        icons.insert(id.to_string(), icon.clone());
+    }
+    icon
+}
+
+// Icon for a window's class (Hyprland/Sway app_id or the X11 WM_CLASS): the
+// .desktop file named after it, else an icon theme entry of that name
+pub fn window_icon(class: &str) -> Option<PathBuf> {
+    if class.is_empty() {
+        return None;
+    }
+    let key = format!("class:{}", class);
+    if let Some(cached) = ICONS.read().ok()?.get(&key) {
+        return cached.clone();
+    }
+    let icon = [class.to_string(), class.to_lowercase()]
+        .iter()
+        .find_map(|id| load_entry(id).ok().and_then(|entry| entry.icon))
+        .and_then(|name| find_icon(&name))
+        .or_else(|| find_icon(&class.to_lowercase()));
+    if let Ok(mut icons) = ICONS.write() {
+        icons.insert(key, icon.clone());
+    }
+    icon
+}
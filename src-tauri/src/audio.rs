@@ -0,0 +1,175 @@
+// ============================================================================
+// Audio Output Switching (headset / speakers / HDMI)
+// ============================================================================
+//
+// Command formats:
+//   __AUDIO_OUT__          - widget with the current output device; pressing
+//                            switches to the next one
+//   __AUDIO_OUT_NEXT__     - switch to the next output device
+//   __AUDIO_OUT_<match>__  - switch to the first output whose name or
+//                            description contains <match> (any case), e.g.
+//                            __AUDIO_OUT_HDMI__, __AUDIO_OUT_Headset__
+//
+// Switching sets the default sink and moves every playing stream to it, so
+// the music follows right away. It goes through `pactl`, which talks the
+// PulseAudio protocol that both PulseAudio and pipewire-pulse serve. Only the
+// plain text output is parsed; the JSON one and wpctl's output have changed
+// between versions.
+
+use std::process::Stdio;
+use std::thread;
+
+use tracing::debug;
+
+use crate::{action_log, poller, request_refresh, sandbox};
+
+pub const WIDGET: &str = "__AUDIO_OUT__";
+pub const NEXT_COMMAND: &str = "__AUDIO_OUT_NEXT__";
+
+const POLL_SECS: u64 = 2;
+const MAX_LABEL_CHARS: usize = 10;
+
+#[derive(Debug, Clone)]
+struct Sink {
+    name: String,
+    description: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Target<'a> {
+    Next,
+    Matching(&'a str),
+}
+
+fn parse_target(cmd: &str) -> Option<Target<'_>> {
+    if cmd == WIDGET || cmd == NEXT_COMMAND {
+        return Some(Target::Next);
+    }
+    let text = cmd.strip_prefix("__AUDIO_OUT_")?.strip_suffix("__")?;
+    (!text.is_empty()).then_some(Target::Matching(text))
+}
+
+pub fn is_audio_command(cmd: &str) -> bool {
+    parse_target(cmd).is_some()
+}
+
+pub fn is_audio_widget(cmd: &str) -> bool {
+    cmd == WIDGET
+}
+
+fn pactl(args: &[&str]) -> Result<String, String> {
+    let output = sandbox::host_command_with("pactl", None, [("LC_ALL", "C")])
+        .args(args)
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| format!("Failed to run pactl: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("pactl {} failed: {}", args.join(" "), stderr.trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+// Output devices in pactl order, from the "Name:" / "Description:" lines
+fn sinks() -> Result<Vec<Sink>, String> {
+    let text = pactl(&["list", "sinks"])?;
+    let mut sinks: Vec<Sink> = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if let Some(name) = line.strip_prefix("Name: ") {
+            sinks.push(Sink {
+                name: name.to_string(),
+                description: name.to_string(),
+            });
+        } else if let Some(description) = line.strip_prefix("Description: ") {
+            if let Some(sink) = sinks.last_mut() {
+                sink.description = description.to_string();
+            }
+        }
+    }
+    Ok(sinks)
+}
+
+fn default_sink() -> Result<String, String> {
+    // `pactl info` also works on servers older than get-default-sink
+    let info = pactl(&["info"])?;
+    info.lines()
+        .find_map(|line| line.strip_prefix("Default Sink: "))
+        .map(|name| name.trim().to_string())
+        .ok_or_else(|| "No default output device".to_string())
+}
+
+// Make `sink` the default and move the playing streams over
+fn switch_to(sink: &Sink) -> Result<(), String> {
+    pactl(&["set-default-sink", &sink.name])?;
+    // <id>\t<sink>\t<client>\t<driver>\t<format>
+    let inputs = pactl(&["list", "short", "sink-inputs"])?;
+    for id in inputs.lines().filter_map(|line| line.split('\t').next()).filter(|id| !id.is_empty()) {
+        if let Err(e) = pactl(&["move-sink-input", id, &sink.name]) {
+            // A stream that ended meanwhile
+            debug!("{}", e);
+        }
+    }
+    debug!("Audio output: {}", sink.description);
+    Ok(())
+}
+
+fn switch(target: Target) -> Result<(), String> {
+    let sinks = sinks()?;
+    let sink = match target {
+        Target::Next => {
+            let current = default_sink().unwrap_or_default();
+            let index = sinks.iter().position(|s| s.name == current).map(|i| i + 1).unwrap_or(0);
+            sinks.get(index % sinks.len().max(1)).ok_or("No output devices")?
+        }
+        Target::Matching(text) => {
+            let text = text.to_lowercase();
+            sinks
+                .iter()
+                .find(|s| s.name.to_lowercase().contains(&text) || s.description.to_lowercase().contains(&text))
+                .ok_or_else(|| format!("No output device matching '{}'", text))?
+        }
+    };
+    switch_to(sink)
+}
+
+pub fn execute_command(cmd: &str) {
+    let cmd = cmd.to_string();
+    // Several pactl calls; keep the button listener free
+    thread::spawn(move || {
+        let result = parse_target(&cmd)
+            .ok_or_else(|| format!("Invalid audio command: {}", cmd))
+            .and_then(switch);
+        if let Err(e) = result {
+            action_log::record_error(&cmd, &e);
+        }
+        poller::invalidate_matching(|key| key == WIDGET);
+        request_refresh();
+    });
+}
+
+fn truncate(text: &str) -> String {
+    if text.chars().count() > MAX_LABEL_CHARS {
+        let head: String = text.chars().take(MAX_LABEL_CHARS - 1).collect();
+        format!("{}…", head)
+    } else {
+        text.to_string()
+    }
+}
+
+fn current_description() -> Result<String, String> {
+    let current = default_sink()?;
+    let sinks = sinks()?;
+    Ok(sinks
+        .into_iter()
+        .find(|s| s.name == current)
+        .map(|s| s.description)
+        .unwrap_or(current))
+}
+
+pub fn get_widget_text() -> String {
+    match poller::poll(WIDGET, POLL_SECS, current_description) {
+        Some(Ok(description)) => truncate(&description),
+        Some(Err(_)) => "ERR".to_string(),
+        None => "...".to_string(),
+    }
+}
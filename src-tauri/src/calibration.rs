@@ -0,0 +1,145 @@
+// ============================================================================
+// Key Calibration (custom physical -> logical key map)
+// ============================================================================
+//
+// Some SS-550 revisions seem to report a different key matrix, so presses land
+// on the wrong buttons. Calibration lights key 1 to 15 in turn and records the
+// code the device sends when the lit key is pressed. At the end the codes are
+// saved as "keyMap" in config.json and replace the built-in map:
+//
+//   "keyMap": { "11": 1, "12": 2, ... }   (device code -> key, decimal)
+//
+// start_calibration starts it (the listener owns the device and runs it
+// between reads), get_calibration_status follows it, cancel_calibration stops
+// it and reset_key_map goes back to the built-in map. While it runs the
+// listener reads raw codes and key presses run no actions.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tracing::debug;
+
+use crate::deck::Deck;
+use crate::{config_store, deck_reader, render_button_image, request_refresh, virtual_page};
+
+const KEY_COUNT: u8 = 15;
+const PROMPT_COLOR: &str = "#e94560";
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CalibrationStatus {
+    pub active: bool,
+    // Key lit now (1-15), 0 when not calibrating
+    pub key: u8,
+    // Keys mapped by the last calibration, 0 until it finishes
+    pub mapped: usize,
+}
+
+struct Calibration {
+    key: u8,
+    // Device code -> key
+    found: BTreeMap<u8, u8>,
+    drawn: bool,
+}
+
+lazy_static::lazy_static! {
+    static ref CALIBRATION: Mutex<Option<Calibration>> = Mutex::new(None);
+    static ref LAST_MAPPED: Mutex<usize> = Mutex::new(0);
+}
+
+// Set while calibrating so the listener sees raw device codes
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+pub fn active() -> bool {
+    ACTIVE.load(Ordering::SeqCst)
+}
+
+pub fn start() {
+    if let Ok(mut calibration) = CALIBRATION.lock() {
+        *calibration = Some(Calibration {
+            key: 1,
+            found: BTreeMap::new(),
+            drawn: false,
+        });
+        ACTIVE.store(true, Ordering::SeqCst);
+    }
+    if let Ok(mut mapped) = LAST_MAPPED.lock() {
+        *mapped = 0;
+    }
+    debug!("Key calibration started");
+    deck_reader::wake();
+}
+
+pub fn cancel() {
+    let was_active = CALIBRATION.lock().map(|mut c| c.take().is_some()).unwrap_or(false);
+    ACTIVE.store(false, Ordering::SeqCst);
+    if was_active {
+        debug!("Key calibration cancelled");
+        request_refresh();
+    }
+}
+
+pub fn status() -> CalibrationStatus {
+    let key = CALIBRATION.lock().ok().and_then(|c| c.as_ref().map(|c| c.key)).unwrap_or(0);
+    CalibrationStatus {
+        active: key != 0,
+        key,
+        mapped: LAST_MAPPED.lock().map(|m| *m).unwrap_or(0),
+    }
+}
+
+// Light the key to press; true while calibrating. Called by the listener.
+pub fn step(deck: &dyn Deck, icons_path: &Path) -> bool {
+    let mut calibration = match CALIBRATION.lock() {
+        Ok(c) => c,
+        Err(_) => return false,
+    };
+    let calibration = match calibration.as_mut() {
+        Some(c) => c,
+        None => return false,
+    };
+    if !calibration.drawn {
+        deck.clear().ok();
+        let prompt = virtual_page::button(&calibration.key.to_string(), "", PROMPT_COLOR);
+        deck.set_key_image(calibration.key, &render_button_image(&prompt, icons_path)).ok();
+        calibration.drawn = true;
+    }
+    true
+}
+
+// The device sent `code` for the lit key; saves the map after the last key
+pub fn pressed(code: u8, config_path: &Path) {
+    let found = {
+        let mut calibration = match CALIBRATION.lock() {
+            Ok(c) => c,
+            Err(_) => return,
+        };
+        let current = match calibration.as_mut() {
+            Some(c) => c,
+            None => return,
+        };
+        // Pressed a key that was already mapped: keep waiting for the lit one
+        if current.found.contains_key(&code) {
+            debug!("Code {:#04x} already mapped, waiting for key {}", code, current.key);
+            return;
+        }
+        debug!("Key {} sends code {:#04x}", current.key, code);
+        current.found.insert(code, current.key);
+        current.key += 1;
+        current.drawn = false;
+        if current.key <= KEY_COUNT {
+            return;
+        }
+        calibration.take().map(|c| c.found).unwrap_or_default()
+    };
+
+    ACTIVE.store(false, Ordering::SeqCst);
+    if let Ok(mut mapped) = LAST_MAPPED.lock() {
+        *mapped = found.len();
+    }
+    debug!("Key calibration done: {:?}", found);
+    config_store::update(config_path, |config| config.key_map = found);
+    request_refresh();
+}
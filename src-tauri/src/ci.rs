@@ -0,0 +1,262 @@
+// ============================================================================
+// CI Pipeline Status (GitHub Actions / GitLab CI)
+// ============================================================================
+//
+// Command formats:
+//   __CI_GITHUB_owner/repo__          - latest workflow run of a GitHub repo
+//   __CI_GITHUB_owner/repo@branch__   - same, restricted to a branch
+//   __CI_GITLAB_group/project__       - latest pipeline of a GitLab project
+//   __CI_GITLAB_group/project@ref__   - same, restricted to a ref
+//
+// The key turns green/red/yellow for passed/failed/running and pressing it
+// opens the run in the browser.
+//
+// Environment variables:
+//   GITHUB_TOKEN  - optional for public repos, required for private ones
+//   GITLAB_TOKEN  - personal access token with read_api scope
+//   GITLAB_URL    - optional, defaults to https://gitlab.com
+//
+// Without the variable a token is looked up in the desktop secrets store
+// (GNOME Keyring, KWallet) through libsecret's secret-tool, e.g.:
+//   secret-tool store --label="GitHub token" service redragon-streamdeck token GITHUB_TOKEN
+
+use std::process::Stdio;
+use std::time::Duration;
+
+use tracing::{debug, warn};
+
+use crate::{poller, sandbox};
+
+// Seconds between status polls for each repo
+const POLL_INTERVAL_SECS: u64 = 60;
+// `service` attribute of the tokens in the secrets store
+const SECRET_SERVICE: &str = "redragon-streamdeck";
+
+const COLOR_SUCCESS: &str = "#2e7d32";
+const COLOR_FAILURE: &str = "#c62828";
+const COLOR_RUNNING: &str = "#f9a825";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Provider {
+    GitHub,
+    GitLab,
+}
+
+#[derive(Debug, Clone)]
+struct CiTarget {
+    provider: Provider,
+    repo: String,
+    branch: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CiState {
+    Success,
+    Failure,
+    Running,
+    Unknown,
+    Error,
+}
+
+#[derive(Debug, Clone)]
+struct CiStatus {
+    state: CiState,
+    url: String,
+}
+
+pub fn is_ci_command(cmd: &str) -> bool {
+    parse_command(cmd).is_some()
+}
+
+fn parse_command(cmd: &str) -> Option<CiTarget> {
+    let inner = cmd.strip_prefix("__CI_")?.strip_suffix("__")?;
+    let (provider, target) = if let Some(rest) = inner.strip_prefix("GITHUB_") {
+        (Provider::GitHub, rest)
+    } else if let Some(rest) = inner.strip_prefix("GITLAB_") {
+        (Provider::GitLab, rest)
+    } else {
+        return None;
+    };
+
+    let (repo, branch) = match target.split_once('@') {
+        Some((repo, branch)) if !branch.is_empty() => (repo, Some(branch.to_string())),
+        Some((repo, _)) => (repo, None),
+        None => (target, None),
+    };
+    if !repo.contains('/') {
+        return None;
+    }
+
+    Some(CiTarget { provider, repo: repo.to_string(), branch })
+}
+
+fn http_client() -> Result<reqwest::blocking::Client, String> {
+    reqwest::blocking::Client::builder()
+        .user_agent("RedragonStreamDeck/2.0")
+        .timeout(Duration::from_secs(10))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))
+}
+
+fn encode(value: &str) -> String {
+    url::form_urlencoded::byte_serialize(value.as_bytes()).collect()
+}
+
+fn gitlab_base_url() -> String {
+    std::env::var("GITLAB_URL")
+        .unwrap_or_else(|_| "https://gitlab.com".to_string())
+        .trim_end_matches('/')
+        .to_string()
+}
+
+// Page to open when no run URL is known yet
+fn fallback_url(target: &CiTarget) -> String {
+    match target.provider {
+        Provider::GitHub => format!("https://github.com/{}/actions", target.repo),
+        Provider::GitLab => format!("{}/{}/-/pipelines", gitlab_base_url(), target.repo),
+    }
+}
+
+// The token from the environment, else from the secrets store
+fn token(name: &str) -> Option<String> {
+    if let Ok(token) = std::env::var(name) {
+        if !token.is_empty() {
+            return Some(token);
+        }
+    }
+    let output = sandbox::host_command("secret-tool")
+        .args(["lookup", "service", SECRET_SERVICE, "token", name])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let token = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!token.is_empty()).then_some(token)
+}
+
+fn fetch_github(target: &CiTarget) -> Result<CiStatus, String> {
+    let mut url = format!("https://api.github.com/repos/{}/actions/runs?per_page=1", target.repo);
+    if let Some(branch) = &target.branch {
+        url.push_str(&format!("&branch={}", encode(branch)));
+    }
+
+    let mut request = http_client()?
+        .get(&url)
+        .header("Accept", "application/vnd.github+json");
+    if let Some(token) = token("GITHUB_TOKEN") {
+        request = request.header("Authorization", format!("Bearer {}", token));
+    }
+
+    let response = request.send().map_err(|e| format!("GitHub API error: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("GitHub API error: {}", response.status()));
+    }
+    let data: serde_json::Value = response.json().map_err(|e| format!("Parse error: {}", e))?;
+
+    let run = &data["workflow_runs"][0];
+    if run.is_null() {
+        return Ok(CiStatus { state: CiState::Unknown, url: fallback_url(target) });
+    }
+
+    let state = match run["status"].as_str().unwrap_or("") {
+        "completed" => match run["conclusion"].as_str().unwrap_or("") {
+            "success" | "neutral" | "skipped" => CiState::Success,
+            "failure" | "timed_out" | "startup_failure" | "action_required" => CiState::Failure,
+            _ => CiState::Unknown,
+        },
+        "queued" | "in_progress" | "waiting" | "pending" | "requested" => CiState::Running,
+        _ => CiState::Unknown,
+    };
+    let url = run["html_url"].as_str().map(|s| s.to_string()).unwrap_or_else(|| fallback_url(target));
+
+    Ok(CiStatus { state, url })
+}
+
+fn fetch_gitlab(target: &CiTarget) -> Result<CiStatus, String> {
+    let mut url = format!(
+        "{}/api/v4/projects/{}/pipelines?per_page=1",
+        gitlab_base_url(),
+        encode(&target.repo)
+    );
+    if let Some(branch) = &target.branch {
+        url.push_str(&format!("&ref={}", encode(branch)));
+    }
+
+    let mut request = http_client()?.get(&url);
+    if let Some(token) = token("GITLAB_TOKEN") {
+        request = request.header("PRIVATE-TOKEN", token);
+    }
+
+    let response = request.send().map_err(|e| format!("GitLab API error: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("GitLab API error: {}", response.status()));
+    }
+    let data: serde_json::Value = response.json().map_err(|e| format!("Parse error: {}", e))?;
+
+    let pipeline = &data[0];
+    if pipeline.is_null() {
+        return Ok(CiStatus { state: CiState::Unknown, url: fallback_url(target) });
+    }
+
+    let state = match pipeline["status"].as_str().unwrap_or("") {
+        "success" => CiState::Success,
+        "failed" => CiState::Failure,
+        "created" | "waiting_for_resource" | "preparing" | "pending" | "running" | "scheduled" => CiState::Running,
+        _ => CiState::Unknown,
+    };
+    let url = pipeline["web_url"].as_str().map(|s| s.to_string()).unwrap_or_else(|| fallback_url(target));
+
+    Ok(CiStatus { state, url })
+}
+
+fn poll_status(cmd: &str) -> Option<CiStatus> {
+    let target = parse_command(cmd)?;
+    poller::poll(cmd, POLL_INTERVAL_SECS, move || {
+        let result = match target.provider {
+            Provider::GitHub => fetch_github(&target),
+            Provider::GitLab => fetch_gitlab(&target),
+        };
+        result.unwrap_or_else(|e| {
+            warn!("CI status error for {}: {}", target.repo, e);
+            CiStatus { state: CiState::Error, url: fallback_url(&target) }
+        })
+    })
+}
+
+// Widget text for a CI status key
+pub fn get_widget_text(cmd: &str) -> String {
+    match poll_status(cmd).map(|s| s.state) {
+        Some(CiState::Success) => "PASS".to_string(),
+        Some(CiState::Failure) => "FAIL".to_string(),
+        Some(CiState::Running) => "RUN".to_string(),
+        Some(CiState::Unknown) => "---".to_string(),
+        Some(CiState::Error) => "ERR".to_string(),
+        None => "...".to_string(),
+    }
+}
+
+// Background color for a CI status key, None keeps the button color
+pub fn get_widget_color(cmd: &str) -> Option<String> {
+    match poll_status(cmd)?.state {
+        CiState::Success => Some(COLOR_SUCCESS.to_string()),
+        CiState::Failure => Some(COLOR_FAILURE.to_string()),
+        CiState::Running => Some(COLOR_RUNNING.to_string()),
+        CiState::Unknown | CiState::Error => None,
+    }
+}
+
+// Open the latest run (or the repo's CI page) in the browser
+pub fn open_run(cmd: &str) {
+    let url = match poller::peek::<CiStatus>(cmd) {
+        Some(status) => status.url,
+        None => match parse_command(cmd) {
+            Some(target) => fallback_url(&target),
+            None => return,
+        },
+    };
+
+    debug!("Opening CI run: {}", url);
+    sandbox::open_uri(&url).ok();
+}
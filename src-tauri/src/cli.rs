@@ -0,0 +1,344 @@
+// ============================================================================
+// Command Line Control (deckctl)
+// ============================================================================
+//
+// Lets shell scripts and desktop keybindings drive the running app:
+//
+//   redragon-streamdeck page 2              - switch to page 2 (1-based) or a page by name
+//   redragon-streamdeck brightness 80       - set the brightness (0-100)
+//   redragon-streamdeck press 7 [--confirm] - press key 7 (--confirm for keys that need a confirmation)
+//   redragon-streamdeck set-key 3 --label "Hi" --color "#ff0000" [--icon ..] [--command ..] [--page 2]
+//   redragon-streamdeck show                - bring up the window
+//
+// Linking the binary as `deckctl` (ln -s $(which redragon-streamdeck) ~/.local/bin/deckctl)
+// gives the shorter form. The command is sent as one JSON line over a Unix
+// socket to the running instance, which answers with one JSON line; nothing
+// is done without the app running (it owns the device).
+//
+// The socket is $XDG_RUNTIME_DIR/redragon-streamdeck.sock, or a per-user file
+// in the temp dir, readable only by this user.
+//
+// Only one instance may run: two would both claim interface 0 and fight over
+// the device. The first one holds a lock on redragon-streamdeck.lock next to
+// the socket; a second launch sends `show` to it and exits.
+
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::Duration;
+
+use clap::{Parser, Subcommand};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tracing::{debug, warn};
+
+use crate::{
+    button_for_key, config_store, handle_button_press, request_key_refresh, set_base_brightness, switch_page, tray,
+    ButtonConfig,
+};
+
+const SOCKET_NAME: &str = "redragon-streamdeck.sock";
+const LOCK_NAME: &str = "redragon-streamdeck.lock";
+const SUBCOMMANDS: [&str; 5] = ["page", "brightness", "press", "set-key", "show"];
+// How long the client waits for an answer
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Held (and locked) for the life of the first instance
+static INSTANCE_LOCK: OnceLock<File> = OnceLock::new();
+static APP: OnceLock<AppHandle> = OnceLock::new();
+
+#[derive(Debug, Parser)]
+#[command(name = "deckctl", about = "Control the running Redragon Stream Deck app")]
+struct Cli {
+    #[command(subcommand)]
+    command: DeckCommand,
+}
+
+#[derive(Debug, Clone, Subcommand, Serialize, Deserialize)]
+#[serde(tag = "cmd", rename_all = "kebab-case")]
+pub enum DeckCommand {
+    /// Switch to a page (1-based number or name)
+    Page { page: String },
+    /// Set the deck brightness
+    Brightness {
+        #[arg(value_parser = clap::value_parser!(u8).range(0..=100))]
+        level: u8,
+    },
+    /// Press a key (1-15)
+    Press {
+        #[arg(value_parser = clap::value_parser!(u8).range(1..=15))]
+        key: u8,
+        /// Run keys that need a hold or a second press on the deck
+        #[arg(long)]
+        #[serde(default)]
+        confirm: bool,
+    },
+    /// Change a key's label, color, icon or command
+    SetKey {
+        #[arg(value_parser = clap::value_parser!(u8).range(1..=15))]
+        key: u8,
+        #[arg(long)]
+        label: Option<String>,
+        #[arg(long)]
+        color: Option<String>,
+        #[arg(long)]
+        icon: Option<String>,
+        #[arg(long)]
+        command: Option<String>,
+        /// Page to change (1-based); the current page by default
+        #[arg(long)]
+        page: Option<usize>,
+    },
+    /// Show the app window
+    Show,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Reply {
+    ok: bool,
+    message: String,
+}
+
+// $XDG_RUNTIME_DIR/<name>, or <temp dir>/<name> with the user in it
+fn runtime_file(name: &str) -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR").filter(|d| !d.is_empty()) {
+        Some(dir) => PathBuf::from(dir).join(name),
+        None => {
+            let user = std::env::var("USER").unwrap_or_else(|_| "user".to_string());
+            std::env::temp_dir().join(name.replacen("redragon-streamdeck", &format!("redragon-streamdeck-{}", user), 1))
+        }
+    }
+}
+
+pub fn socket_path() -> PathBuf {
+    runtime_file(SOCKET_NAME)
+}
+
+// Become the only instance; false when another one already runs
+pub fn claim_instance() -> bool {
+    let path = runtime_file(LOCK_NAME);
+    let file = match File::options().create(true).truncate(false).write(true).open(&path) {
+        Ok(f) => f,
+        Err(e) => {
+            // Can't tell; better two instances than none (logging isn't up yet)
+            eprintln!("Failed to open {}: {}", path.display(), e);
+            return true;
+        }
+    };
+    if file.try_lock().is_err() {
+        return false;
+    }
+    INSTANCE_LOCK.set(file).ok();
+    true
+}
+
+// Second launch: bring up the running instance's window instead
+pub fn hand_off() -> i32 {
+    match send(&DeckCommand::Show) {
+        Ok(_) => 0,
+        Err(e) => {
+            eprintln!("Redragon Stream Deck is already running ({})", e);
+            1
+        }
+    }
+}
+
+fn find_page(target: &str) -> Result<usize, String> {
+    let pages = config_store::read(|c| c.pages.iter().map(|p| p.name.clone()).collect::<Vec<_>>())
+        .ok_or("Config unavailable")?;
+    if let Ok(number) = target.parse::<usize>() {
+        if number == 0 || number > pages.len() {
+            return Err(format!("No page {} (there are {})", number, pages.len()));
+        }
+        return Ok(number - 1);
+    }
+    pages
+        .iter()
+        .position(|name| name.eq_ignore_ascii_case(target))
+        .ok_or_else(|| format!("No page named \"{}\"", target))
+}
+
+fn set_key(config_path: &Path, key: u8, page: Option<usize>, edit: impl FnOnce(&mut ButtonConfig)) -> Result<String, String> {
+    let result = config_store::update(config_path, |config| {
+        let index = match page {
+            Some(number) if number >= 1 && number <= config.pages.len() => number - 1,
+            Some(number) => return Err(format!("No page {}", number)),
+            None => config.current_page,
+        };
+        let page = config.pages.get_mut(index).ok_or("No current page")?;
+        let button = page.buttons.entry(key.to_string()).or_insert_with(|| ButtonConfig {
+            label: String::new(),
+            command: String::new(),
+            color: "#1a1a2e".to_string(),
+            icon: String::new(),
+            action: None,
+            hold_confirm: false,
+            confirm: false,
+            sound: None,
+        });
+        edit(button);
+        button.normalize_action();
+        Ok(index)
+    })
+    .ok_or("Config unavailable")?;
+    let index = result?;
+    request_key_refresh(index, key);
+    Ok(format!("Key {} on page {} updated", key, index + 1))
+}
+
+fn execute(command: DeckCommand, config_path: &Path, icons_path: &Path) -> Result<String, String> {
+    match command {
+        DeckCommand::Page { page } => {
+            let index = find_page(&page)?;
+            if !switch_page(config_path, index) {
+                return Err(format!("No page {}", index + 1));
+            }
+            tray::update();
+            Ok(format!("Page {}", index + 1))
+        }
+        DeckCommand::Brightness { level } => {
+            set_base_brightness(config_path, level.min(100));
+            tray::update();
+            Ok(format!("Brightness {}%", level.min(100)))
+        }
+        DeckCommand::Press { key, confirm } => {
+            if !(1..=15).contains(&key) {
+                return Err("Keys are 1-15".to_string());
+            }
+            let needs_confirm = button_for_key(key).map(|b| b.hold_confirm || b.confirm).unwrap_or(false);
+            if needs_confirm && !confirm {
+                return Err(format!("Key {} needs a confirmation; pass --confirm", key));
+            }
+            // Actions can take a while (macros, delays); answer first
+            let (config_path, icons_path) = (config_path.to_path_buf(), icons_path.to_path_buf());
+            thread::spawn(move || handle_button_press(key, &config_path, &icons_path));
+            Ok(format!("Pressed key {}", key))
+        }
+        DeckCommand::SetKey { key, label, color, icon, command, page } => {
+            if !(1..=15).contains(&key) {
+                return Err("Keys are 1-15".to_string());
+            }
+            set_key(config_path, key, page, |button| {
+                if let Some(label) = label {
+                    button.label = label;
+                }
+                if let Some(color) = color {
+                    button.color = color;
+                }
+                if let Some(icon) = icon {
+                    button.icon = icon;
+                }
+                if let Some(command) = command {
+                    button.command = command;
+                    button.action = None;
+                }
+            })
+        }
+        DeckCommand::Show => {
+            let app = APP.get().ok_or("The window is not ready yet")?;
+            tray::show_window(app);
+            Ok("Window shown".to_string())
+        }
+    }
+}
+
+fn handle_connection(stream: UnixStream, config_path: &Path, icons_path: &Path) {
+    let mut line = String::new();
+    let mut reader = BufReader::new(&stream);
+    if reader.read_line(&mut line).is_err() {
+        return;
+    }
+    let reply = match serde_json::from_str::<DeckCommand>(line.trim()) {
+        Ok(command) => {
+            debug!("CLI command: {:?}", command);
+            match execute(command, config_path, icons_path) {
+                Ok(message) => Reply { ok: true, message },
+                Err(message) => Reply { ok: false, message },
+            }
+        }
+        Err(e) => Reply {
+            ok: false,
+            message: format!("Invalid command: {}", e),
+        },
+    };
+    let mut stream = &stream;
+    if let Ok(json) = serde_json::to_string(&reply) {
+        writeln!(stream, "{}", json).ok();
+    }
+}
+
+// Listen for deckctl commands and second launches; called once at startup
+pub fn start_server(app: &AppHandle, config_path: PathBuf, icons_path: PathBuf) {
+    APP.set(app.clone()).ok();
+    let path = socket_path();
+    // This instance holds the lock, so a socket file left over is stale
+    fs::remove_file(&path).ok();
+    let listener = match UnixListener::bind(&path) {
+        Ok(l) => l,
+        Err(e) => return warn!("Failed to listen on {}: {}", path.display(), e),
+    };
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).ok();
+    debug!("deckctl listening on {}", path.display());
+
+    thread::spawn(move || {
+        for stream in listener.incoming().filter_map(|s| s.ok()) {
+            let (config_path, icons_path) = (config_path.clone(), icons_path.clone());
+            thread::spawn(move || handle_connection(stream, &config_path, &icons_path));
+        }
+    });
+}
+
+fn send(command: &DeckCommand) -> Result<String, String> {
+    let path = socket_path();
+    let mut stream = UnixStream::connect(&path).map_err(|_| "Redragon Stream Deck is not running".to_string())?;
+    stream.set_read_timeout(Some(REPLY_TIMEOUT)).ok();
+    let json = serde_json::to_string(command).map_err(|e| e.to_string())?;
+    writeln!(stream, "{}", json).map_err(|e| format!("Failed to send the command: {}", e))?;
+
+    let mut line = String::new();
+    BufReader::new(&stream)
+        .read_line(&mut line)
+        .map_err(|e| format!("No answer from the app: {}", e))?;
+    let reply: Reply = serde_json::from_str(line.trim()).map_err(|e| format!("Invalid answer: {}", e))?;
+    if reply.ok {
+        Ok(reply.message)
+    } else {
+        Err(reply.message)
+    }
+}
+
+// Run a deckctl command and exit; None when the arguments are for the app
+pub fn run_cli() -> Option<i32> {
+    let args: Vec<String> = std::env::args().collect();
+    let as_deckctl = args
+        .first()
+        .and_then(|a| Path::new(a).file_name())
+        .map(|name| name == "deckctl")
+        .unwrap_or(false);
+    let is_command = args.get(1).map(|a| SUBCOMMANDS.contains(&a.as_str())).unwrap_or(false);
+    if !as_deckctl && !is_command {
+        return None;
+    }
+
+    let cli = match Cli::try_parse_from(&args) {
+        Ok(c) => c,
+        Err(e) => {
+            e.print().ok();
+            return Some(if e.use_stderr() { 2 } else { 0 });
+        }
+    };
+    match send(&cli.command) {
+        Ok(message) => {
+            println!("{}", message);
+            Some(0)
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            Some(1)
+        }
+    }
+}
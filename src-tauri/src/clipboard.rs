@@ -0,0 +1,195 @@
+// ============================================================================
+// Clipboard History
+// ============================================================================
+//
+// A background watcher records recent text copied to the clipboard
+// (wl-paste on Wayland, arboard on X11).
+//
+// Command format:
+//   __CLIPBOARD_PAGE__  - show a virtual page with the most recent entries;
+//                         pressing one pastes it
+//
+// Pasting puts the entry back on the clipboard and sends Ctrl+V. If the
+// clipboard can't be set the text is typed instead.
+
+use std::collections::VecDeque;
+use std::io::Write;
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use tracing::debug;
+
+use crate::virtual_page::{AfterPress, ListEntry, PageProvider};
+use crate::{execute_hotkey_sync, input, sandbox, virtual_page};
+
+pub const PAGE_COMMAND: &str = "__CLIPBOARD_PAGE__";
+
+// Three screens of the scrolling list page
+const HISTORY_LEN: usize = 33;
+// Entries longer than this are not recorded (images as text, huge dumps)
+const MAX_ENTRY_LEN: usize = 10_000;
+const POLL_INTERVAL: Duration = Duration::from_millis(1000);
+const LABEL_LEN: usize = 10;
+const ENTRY_COLOR: &str = "#2d4059";
+
+lazy_static::lazy_static! {
+    static ref HISTORY: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+    // Kept alive so X11 clipboard contents we set survive
+    static ref X11_CLIPBOARD: Mutex<Option<arboard::Clipboard>> = Mutex::new(None);
+}
+
+pub fn is_clipboard_command(cmd: &str) -> bool {
+    cmd == PAGE_COMMAND
+}
+
+pub fn is_wayland() -> bool {
+    std::env::var("WAYLAND_DISPLAY").map(|d| !d.is_empty()).unwrap_or(false)
+}
+
+pub fn read_clipboard() -> Option<String> {
+    if is_wayland() {
+        let output = sandbox::host_command("wl-paste")
+            .args(["--no-newline", "--type", "text"])
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        return String::from_utf8(output.stdout).ok();
+    }
+
+    let mut clipboard = X11_CLIPBOARD.lock().ok()?;
+    if clipboard.is_none() {
+        *clipboard = arboard::Clipboard::new().ok();
+    }
+    clipboard.as_mut()?.get_text().ok()
+}
+
+pub fn write_clipboard(text: &str) -> Result<(), String> {
+    if is_wayland() {
+        let mut child = sandbox::host_command("wl-copy")
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to run wl-copy: {}", e))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())
+                .map_err(|e| format!("Failed to write to wl-copy: {}", e))?;
+        }
+        child.wait().map_err(|e| format!("wl-copy failed: {}", e))?;
+        return Ok(());
+    }
+
+    let mut clipboard = X11_CLIPBOARD.lock().map_err(|e| e.to_string())?;
+    if clipboard.is_none() {
+        *clipboard = Some(arboard::Clipboard::new().map_err(|e| format!("Clipboard error: {}", e))?);
+    }
+    clipboard
+        .as_mut()
+        .ok_or("Clipboard unavailable")?
+        .set_text(text.to_string())
+        .map_err(|e| format!("Clipboard error: {}", e))
+}
+
+// Add an entry at the front, moving it up if it is already in the history
+fn record(text: String) {
+    if text.trim().is_empty() || text.len() > MAX_ENTRY_LEN {
+        return;
+    }
+    if let Ok(mut history) = HISTORY.lock() {
+        if history.front() == Some(&text) {
+            return;
+        }
+        history.retain(|entry| entry != &text);
+        history.push_front(text);
+        history.truncate(HISTORY_LEN);
+    }
+}
+
+// Start the clipboard watcher in a background thread
+pub fn start_watcher() {
+    thread::spawn(|| {
+        debug!("Clipboard watcher started");
+        let mut last = String::new();
+        loop {
+            if let Some(text) = read_clipboard() {
+                if text != last {
+                    last = text.clone();
+                    record(text);
+                }
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    });
+}
+
+pub fn get_history() -> Vec<String> {
+    HISTORY.lock().map(|h| h.iter().cloned().collect()).unwrap_or_default()
+}
+
+// Short single-line label for a key
+fn entry_label(text: &str) -> String {
+    let flat = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if flat.chars().count() > LABEL_LEN {
+        flat.chars().take(LABEL_LEN).collect()
+    } else {
+        flat
+    }
+}
+
+// The history as it was when the page opened, so entries keep their keys
+struct HistoryPage {
+    entries: Vec<String>,
+}
+
+impl PageProvider for HistoryPage {
+    fn title(&self) -> String {
+        "Clipboard".to_string()
+    }
+
+    fn entries(&mut self) -> Vec<ListEntry> {
+        self.entries.iter().map(|text| ListEntry::new(&entry_label(text))).collect()
+    }
+
+    fn color(&self) -> String {
+        ENTRY_COLOR.to_string()
+    }
+
+    fn pressed(&mut self, index: usize, _entry: &ListEntry) -> Result<AfterPress, String> {
+        let text = self.entries.get(index).cloned().ok_or_else(|| format!("No clipboard entry {}", index))?;
+        debug!("Pasting clipboard entry {}", index);
+        paste(text);
+        Ok(AfterPress::Stay)
+    }
+}
+
+pub fn open_history_page() {
+    virtual_page::open_provider(Box::new(HistoryPage { entries: get_history() }));
+}
+
+fn paste(text: String) {
+    thread::spawn(move || {
+        match write_clipboard(&text) {
+            Ok(()) => {
+                // Let the compositor pick up the new selection first
+                thread::sleep(Duration::from_millis(100));
+                execute_hotkey_sync("ctrl+v");
+            }
+            Err(e) => {
+                debug!("{}, typing instead", e);
+                input::type_text(&text);
+            }
+        }
+    });
+}
+
+// Execute a clipboard command (history page or paste an entry)
+pub fn execute_command(cmd: &str) -> Result<(), String> {
+    if cmd != PAGE_COMMAND {
+        return Err(format!("Invalid clipboard command: {}", cmd));
+    }
+    open_history_page();
+    Ok(())
+}
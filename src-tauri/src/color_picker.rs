@@ -0,0 +1,112 @@
+// ============================================================================
+// Color Picker
+// ============================================================================
+//
+// __COLORPICK__ picks a color from the screen with hyprpicker or, when it is
+// not installed or in Flatpak, the xdg-desktop-portal color picker. The hex
+// code is copied to the clipboard and the key shows the color with its code
+// for a few seconds.
+
+use std::collections::HashMap;
+use std::process::{Command, Stdio};
+use std::sync::RwLock;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use zbus::blocking::Connection;
+use zbus::zvariant::{OwnedValue, Structure};
+use tracing::{debug, warn};
+
+use crate::sandbox::{self, ScreenshotProxyBlocking};
+use crate::{clipboard, request_refresh};
+
+pub const COMMAND: &str = "__COLORPICK__";
+
+// How long the key shows the picked color
+const SHOW_DURATION: Duration = Duration::from_secs(5);
+
+lazy_static::lazy_static! {
+    // Last picked color and when it was picked
+    static ref PICKED: RwLock<Option<(String, Instant)>> = RwLock::new(None);
+}
+
+// "#rrggbb" from hyprpicker (Some(None) if cancelled), None if it is not installed
+fn pick_hyprpicker() -> Option<Option<String>> {
+    let output = Command::new("hyprpicker")
+        .args(["--format=hex", "--no-fancy"])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let color = String::from_utf8_lossy(&output.stdout).trim().to_lowercase();
+    Some((output.status.success() && color.starts_with('#') && color.len() == 7).then_some(color))
+}
+
+fn to_hex(value: &OwnedValue) -> Option<String> {
+    let rgb: Structure = value.try_clone().ok()?.try_into().ok()?;
+    let channels: Vec<u8> = rgb
+        .fields()
+        .iter()
+        .filter_map(|v| f64::try_from(v).ok())
+        .map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8)
+        .collect();
+    match channels[..] {
+        [r, g, b] => Some(format!("#{:02x}{:02x}{:02x}", r, g, b)),
+        _ => None,
+    }
+}
+
+// Ask the desktop portal; Ok(None) if the user cancelled
+fn pick_portal() -> Result<Option<String>, String> {
+    let conn = Connection::session().map_err(|e| format!("D-Bus connection failed: {}", e))?;
+    let portal = ScreenshotProxyBlocking::new(&conn).map_err(|e| format!("Portal not available: {}", e))?;
+    let results = sandbox::portal_request(&conn, HashMap::new(), |options| portal.pick_color("", options))?;
+    Ok(results.and_then(|r| r.get("color").and_then(to_hex)))
+}
+
+fn pick() -> Result<Option<String>, String> {
+    if sandbox::is_flatpak() {
+        return pick_portal();
+    }
+    match pick_hyprpicker() {
+        Some(color) => Ok(color),
+        None => pick_portal(),
+    }
+}
+
+pub fn execute_command() {
+    // Picking waits for a click, so keep the button listener free
+    thread::spawn(|| {
+        let color = match pick() {
+            Ok(Some(color)) => color,
+            Ok(None) => return,
+            Err(e) => {
+                warn!("{}", e);
+                return;
+            }
+        };
+        debug!("Picked color {}", color);
+        if let Err(e) = clipboard::write_clipboard(&color) {
+            warn!("{}", e);
+        }
+        if let Ok(mut picked) = PICKED.write() {
+            *picked = Some((color, Instant::now()));
+        }
+        request_refresh();
+    });
+}
+
+// The color picked in the last few seconds
+fn recent() -> Option<String> {
+    let picked = PICKED.read().ok()?;
+    let (color, at) = picked.as_ref()?;
+    (at.elapsed() < SHOW_DURATION).then(|| color.clone())
+}
+
+// The hex code while it is shown, otherwise the key keeps its label
+pub fn get_widget_text() -> Option<String> {
+    recent()
+}
+
+pub fn get_widget_color() -> Option<String> {
+    recent()
+}
@@ -0,0 +1,146 @@
+// ============================================================================
+// Shell Command Output Widget
+// ============================================================================
+//
+// __CMD_<interval>_<command>__ runs `sh -c <command>` every <interval> and shows
+// the first line of its trimmed stdout. The interval is in seconds, or use an
+// s/m/h suffix: __CMD_30_kubectl get pods | wc -l__, __CMD_5m_~/bin/temp.sh__.
+//
+// Non-zero exits show "ERR <code>" and commands running longer than the
+// timeout show "TIMEOUT", both on a red key. Pressing the key runs it again.
+// In safe mode nothing is run and the key shows "SAFE".
+
+use std::io::Read;
+use std::process::Stdio;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tracing::warn;
+
+use crate::{config_store, poller, request_refresh, sandbox, variables};
+
+const MIN_INTERVAL_SECS: u64 = 1;
+const MAX_TIMEOUT_SECS: u64 = 30;
+const MAX_LABEL_CHARS: usize = 10;
+const ERROR_COLOR: &str = "#c0392b";
+
+#[derive(Clone)]
+enum Output {
+    Text(String),
+    Failed(String),
+}
+
+fn parse_interval(text: &str) -> Option<u64> {
+    let (number, unit) = match text.char_indices().last()? {
+        (i, 's') => (&text[..i], 1),
+        (i, 'm') => (&text[..i], 60),
+        (i, 'h') => (&text[..i], 3600),
+        _ => (text, 1),
+    };
+    let secs = number.parse::<u64>().ok()?.checked_mul(unit)?;
+    Some(secs.max(MIN_INTERVAL_SECS))
+}
+
+// __CMD_<interval>_<command>__ -> (interval secs, command)
+fn parse_command(cmd: &str) -> Option<(u64, &str)> {
+    let inner = cmd.strip_prefix("__CMD_")?.strip_suffix("__")?;
+    let (interval, command) = inner.split_once('_')?;
+    if command.trim().is_empty() {
+        return None;
+    }
+    Some((parse_interval(interval)?, command))
+}
+
+pub fn is_command_widget(cmd: &str) -> bool {
+    parse_command(cmd).is_some()
+}
+
+// Shorten to what fits on a key
+fn truncate(text: &str) -> String {
+    if text.chars().count() > MAX_LABEL_CHARS {
+        let head: String = text.chars().take(MAX_LABEL_CHARS - 1).collect();
+        format!("{}…", head)
+    } else {
+        text.to_string()
+    }
+}
+
+fn run(command: &str, timeout: Duration) -> Output {
+    if config_store::read(|c| c.safe_mode).unwrap_or(false) {
+        return Output::Failed("SAFE".to_string());
+    }
+    let mut child = match sandbox::host_command("sh")
+        .arg("-c")
+        .arg(variables::interpolate(command))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+    {
+        Ok(c) => c,
+        Err(e) => {
+            warn!("Command widget failed to start: {}", e);
+            return Output::Failed("ERR".to_string());
+        }
+    };
+
+    // Read in the background so a large output can't block the wait
+    let reader = child.stdout.take().map(|mut stdout| {
+        thread::spawn(move || {
+            let mut text = String::new();
+            stdout.read_to_string(&mut text).ok();
+            text
+        })
+    });
+
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if started.elapsed() < timeout => thread::sleep(Duration::from_millis(50)),
+            _ => {
+                child.kill().ok();
+                child.wait().ok();
+                return Output::Failed("TIMEOUT".to_string());
+            }
+        }
+    };
+
+    let stdout = reader.and_then(|r| r.join().ok()).unwrap_or_default();
+    if !status.success() {
+        let code = status.code().map(|c| c.to_string()).unwrap_or_else(|| "SIG".to_string());
+        return Output::Failed(format!("ERR {}", code));
+    }
+    let first_line = stdout.trim().lines().next().unwrap_or("").trim().to_string();
+    if first_line.is_empty() {
+        Output::Text("-".to_string())
+    } else {
+        Output::Text(truncate(&first_line))
+    }
+}
+
+fn poll_output(cmd: &str) -> Option<Output> {
+    let (interval, command) = parse_command(cmd)?;
+    let command = command.to_string();
+    let timeout = Duration::from_secs(interval.min(MAX_TIMEOUT_SECS));
+    poller::poll(cmd, interval, move || run(&command, timeout))
+}
+
+pub fn get_widget_text(cmd: &str) -> String {
+    match poll_output(cmd) {
+        Some(Output::Text(text)) | Some(Output::Failed(text)) => text,
+        None => "...".to_string(),
+    }
+}
+
+pub fn get_widget_color(cmd: &str) -> Option<String> {
+    match poller::peek::<Output>(cmd)? {
+        Output::Failed(_) => Some(ERROR_COLOR.to_string()),
+        Output::Text(_) => None,
+    }
+}
+
+// Pressing the key runs the command again right away
+pub fn execute_command(cmd: &str) {
+    poller::invalidate_matching(|key| key == cmd);
+    request_refresh();
+}
@@ -0,0 +1,200 @@
+// ============================================================================
+// Config File Storage (atomic writes, backups, recovery)
+// ============================================================================
+//
+// config.json is written to a temporary file and renamed over the old one, so
+// a crash mid-write leaves the previous version intact. Before overwriting, the
+// old file is copied to <app data>/backups/config-<timestamp>.json (at most
+// every BACKUP_INTERVAL, keeping MAX_BACKUPS).
+//
+// If config.json can't be parsed at startup it is moved aside as
+// config.broken-<timestamp>.json and the app starts with defaults; the UI can
+// then offer the newest good backup (get_config_recovery /
+// restore_config_backup).
+//
+// The loaded config lives in memory, shared by the Tauri commands (AppState)
+// and the deck listener, hotkeys and widgets (read / update). The file is only
+// written as a side effect of a change, never read back while running.
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Duration, SystemTime};
+
+use serde::Serialize;
+use tracing::warn;
+
+use crate::Config;
+
+const MAX_BACKUPS: usize = 10;
+const BACKUP_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupInfo {
+    pub name: String,
+    // Unix seconds
+    pub modified: u64,
+}
+
+// Set when config.json was unreadable at startup
+#[derive(Debug, Clone, Serialize)]
+pub struct Recovery {
+    pub error: String,
+    // Where the unreadable file was moved
+    pub broken_file: Option<String>,
+    // Newest backup that parses
+    pub backup: Option<BackupInfo>,
+}
+
+lazy_static::lazy_static! {
+    static ref RECOVERY: RwLock<Option<Recovery>> = RwLock::new(None);
+    // Held from change to save, so the file ends up with the newest config
+    static ref SAVING: Mutex<()> = Mutex::new(());
+}
+
+static SHARED: OnceLock<Arc<RwLock<Config>>> = OnceLock::new();
+
+// Make `config` the app's config; called once at startup
+pub fn share(config: Config) -> Arc<RwLock<Config>> {
+    SHARED.get_or_init(|| Arc::new(RwLock::new(config))).clone()
+}
+
+// Look at the shared config (None before startup)
+pub fn read<T>(f: impl FnOnce(&Config) -> T) -> Option<T> {
+    let config = SHARED.get()?.read().ok()?;
+    Some(f(&config))
+}
+
+// Change the shared config and save it. The write lock is released before
+// the save, so readers (the render path) never wait for the disk.
+pub fn update<T>(config_path: &Path, f: impl FnOnce(&mut Config) -> T) -> Option<T> {
+    let _saving = SAVING.lock().ok()?;
+    let (result, config) = {
+        let mut config = SHARED.get()?.write().ok()?;
+        let result = f(&mut config);
+        (result, config.clone())
+    };
+    if let Err(e) = save(config_path, &config) {
+        warn!("Failed to save config: {}", e);
+    }
+    Some(result)
+}
+
+fn backups_dir(config_path: &Path) -> PathBuf {
+    config_path.with_file_name("backups")
+}
+
+fn timestamp() -> String {
+    chrono::Local::now().format("%Y%m%d-%H%M%S").to_string()
+}
+
+// Write via a temp file + rename so readers never see a partial file
+pub fn write_atomic(path: &Path, content: &str) -> Result<(), String> {
+    let tmp = path.with_extension("json.tmp");
+    let mut file = File::create(&tmp).map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+    file.write_all(content.as_bytes())
+        .and_then(|_| file.sync_all())
+        .map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+    fs::rename(&tmp, path).map_err(|e| format!("Failed to replace {}: {}", path.display(), e))
+}
+
+pub fn list_backups(config_path: &Path) -> Vec<BackupInfo> {
+    let entries = match fs::read_dir(backups_dir(config_path)) {
+        Ok(e) => e,
+        Err(_) => return Vec::new(),
+    };
+    let mut backups: Vec<BackupInfo> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let name = e.file_name().to_str()?.to_string();
+            if !name.starts_with("config-") || !name.ends_with(".json") {
+                return None;
+            }
+            let modified = e.metadata().ok()?.modified().ok()?;
+            let modified = modified.duration_since(SystemTime::UNIX_EPOCH).ok()?.as_secs();
+            Some(BackupInfo { name, modified })
+        })
+        .collect();
+    // Newest first (the name carries the timestamp)
+    backups.sort_by(|a, b| b.name.cmp(&a.name));
+    backups
+}
+
+// Copy the current config.json to the backups unless the last copy is recent
+fn backup(config_path: &Path) {
+    if !config_path.exists() {
+        return;
+    }
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let backups = list_backups(config_path);
+    if backups.first().map(|b| now.saturating_sub(b.modified) < BACKUP_INTERVAL.as_secs()).unwrap_or(false) {
+        return;
+    }
+
+    let dir = backups_dir(config_path);
+    fs::create_dir_all(&dir).ok();
+    let target = dir.join(format!("config-{}.json", timestamp()));
+    if let Err(e) = fs::copy(config_path, &target) {
+        warn!("Config backup failed: {}", e);
+        return;
+    }
+    for old in backups.iter().skip(MAX_BACKUPS - 1) {
+        fs::remove_file(dir.join(&old.name)).ok();
+    }
+}
+
+// Back up the old file and write the config atomically
+pub fn save(config_path: &Path, config: &Config) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(config).map_err(|e| e.to_string())?;
+    backup(config_path);
+    write_atomic(config_path, &content)
+}
+
+fn parse(path: &Path) -> Result<Config, String> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&content).map_err(|e| e.to_string())
+}
+
+pub fn read_backup(config_path: &Path, name: &str) -> Result<Config, String> {
+    // Only plain file names from list_backups
+    if name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(format!("Invalid backup name: {}", name));
+    }
+    parse(&backups_dir(config_path).join(name)).map_err(|e| format!("Backup {} is unreadable: {}", name, e))
+}
+
+// Load config.json. An unreadable file is moved aside and None returned, so
+// the caller starts from defaults without overwriting it.
+pub fn load(config_path: &Path) -> Option<Config> {
+    let error = match parse(config_path) {
+        Ok(config) => return Some(config),
+        Err(e) => e,
+    };
+    warn!("config.json is unreadable: {}", error);
+
+    let broken = config_path.with_file_name(format!("config.broken-{}.json", timestamp()));
+    let broken_file = fs::rename(config_path, &broken)
+        .ok()
+        .map(|_| broken.to_string_lossy().to_string());
+    let backup = list_backups(config_path)
+        .into_iter()
+        .find(|b| read_backup(config_path, &b.name).is_ok());
+    if let Ok(mut recovery) = RECOVERY.write() {
+        *recovery = Some(Recovery { error, broken_file, backup });
+    }
+    None
+}
+
+pub fn recovery() -> Option<Recovery> {
+    RECOVERY.read().ok()?.clone()
+}
+
+pub fn clear_recovery() {
+    if let Ok(mut recovery) = RECOVERY.write() {
+        *recovery = None;
+    }
+}
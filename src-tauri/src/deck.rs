@@ -0,0 +1,463 @@
+// ============================================================================
+// Deck Backends
+// ============================================================================
+//
+// Page loading, widget refresh and the key listener talk to a `Deck`: the
+// SS-550 over USB (UsbDeck in lib.rs) or a MemoryDeck, which keeps the key
+// images in memory and replays queued key events. A MemoryDeck can stand in
+// for the hardware in tests and backs the simulator.
+//
+// Simulator mode renders the 15 keys in memory instead of on the hardware, so
+// actions and widgets can be developed without an SS-550. Enable it with
+// `--simulator` or REDRAGON_SIMULATOR=1; the app window then shows the keys
+// (get_simulator_keys) and mouse presses go through simulate_key to the same
+// listener as real presses.
+//
+// Every deck from open_deck is wrapped in Mirrored, which reports each key
+// image, clear and brightness change to a sink (the "deck-mirror" Tauri event)
+// and keeps the last images for windows opened later (get_deck_mirror).
+
+use std::collections::{HashMap, VecDeque};
+use std::io::Cursor;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::thread;
+use std::time::Duration;
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use image::{DynamicImage, RgbImage};
+use serde::Serialize;
+
+// How long read_event waits for a key, like the USB read timeout
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeckEvent {
+    KeyDown(u8),
+    KeyUp(u8),
+}
+
+pub trait Deck {
+    fn wake(&self) -> Result<(), String>;
+    fn clear(&self) -> Result<(), String>;
+    // 0-100
+    fn set_brightness(&self, brightness: u8) -> Result<(), String>;
+    // Upright key image; backends rotate/encode as they need
+    fn set_key_image(&self, key_id: u8, image: &RgbImage) -> Result<(), String>;
+    // Next key event, None if nothing happened within the read timeout.
+    // Errors mean the deck is gone.
+    fn read_event(&self) -> Result<Option<DeckEvent>, String>;
+    // Round trip to the device for diagnostics; describes the reply
+    fn self_test(&self) -> Result<String, String>;
+    // Raw protocol access for the developer console (protocol_console.rs)
+    fn send_raw(&self, data: &[u8], use_prefix: bool) -> Result<(), String>;
+    fn read_raw(&self, timeout: Duration) -> Result<Option<Vec<u8>>, String>;
+    // Send key images held back for the next frame (frame_scheduler.rs)
+    fn flush(&self) {}
+}
+
+impl<T: Deck + ?Sized> Deck for Arc<T> {
+    fn wake(&self) -> Result<(), String> {
+        (**self).wake()
+    }
+
+    fn clear(&self) -> Result<(), String> {
+        (**self).clear()
+    }
+
+    fn set_brightness(&self, brightness: u8) -> Result<(), String> {
+        (**self).set_brightness(brightness)
+    }
+
+    fn set_key_image(&self, key_id: u8, image: &RgbImage) -> Result<(), String> {
+        (**self).set_key_image(key_id, image)
+    }
+
+    fn read_event(&self) -> Result<Option<DeckEvent>, String> {
+        (**self).read_event()
+    }
+
+    fn self_test(&self) -> Result<String, String> {
+        (**self).self_test()
+    }
+
+    fn send_raw(&self, data: &[u8], use_prefix: bool) -> Result<(), String> {
+        (**self).send_raw(data, use_prefix)
+    }
+
+    fn read_raw(&self, timeout: Duration) -> Result<Option<Vec<u8>>, String> {
+        (**self).read_raw(timeout)
+    }
+
+    fn flush(&self) {
+        (**self).flush()
+    }
+}
+
+// What reached a MemoryDeck, in order
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sent {
+    Wake,
+    Clear,
+    Brightness(u8),
+    Key(u8),
+}
+
+pub struct MemoryDeck {
+    images: RwLock<HashMap<u8, RgbImage>>,
+    brightness: AtomicU8,
+    events: Mutex<VecDeque<DeckEvent>>,
+    sent: Mutex<Vec<Sent>>,
+}
+
+impl MemoryDeck {
+    pub fn new() -> Self {
+        MemoryDeck {
+            images: RwLock::new(HashMap::new()),
+            brightness: AtomicU8::new(100),
+            events: Mutex::new(VecDeque::new()),
+            sent: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn record(&self, sent: Sent) {
+        if let Ok(mut log) = self.sent.lock() {
+            log.push(sent);
+        }
+    }
+
+    // Everything sent so far
+    #[cfg(test)]
+    pub fn sent(&self) -> Vec<Sent> {
+        self.sent.lock().map(|log| log.clone()).unwrap_or_default()
+    }
+
+    // Queue an event for read_event
+    pub fn push_event(&self, event: DeckEvent) -> Result<(), String> {
+        self.events.lock().map_err(|e| e.to_string())?.push_back(event);
+        Ok(())
+    }
+
+    // What each key shows (keys never set or cleared are missing)
+    pub fn images(&self) -> HashMap<u8, RgbImage> {
+        self.images.read().map(|images| images.clone()).unwrap_or_default()
+    }
+
+    pub fn brightness(&self) -> u8 {
+        self.brightness.load(Ordering::SeqCst)
+    }
+}
+
+impl Deck for MemoryDeck {
+    fn wake(&self) -> Result<(), String> {
+        self.record(Sent::Wake);
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<(), String> {
+        self.images.write().map_err(|e| e.to_string())?.clear();
+        self.record(Sent::Clear);
+        Ok(())
+    }
+
+    fn set_brightness(&self, brightness: u8) -> Result<(), String> {
+        self.brightness.store(brightness.min(100), Ordering::SeqCst);
+        self.record(Sent::Brightness(brightness.min(100)));
+        Ok(())
+    }
+
+    fn set_key_image(&self, key_id: u8, image: &RgbImage) -> Result<(), String> {
+        if !(1..=15).contains(&key_id) {
+            return Err(format!("Invalid key: {}", key_id));
+        }
+        self.images.write().map_err(|e| e.to_string())?.insert(key_id, image.clone());
+        self.record(Sent::Key(key_id));
+        Ok(())
+    }
+
+    fn read_event(&self) -> Result<Option<DeckEvent>, String> {
+        if let Some(event) = self.events.lock().map_err(|e| e.to_string())?.pop_front() {
+            return Ok(Some(event));
+        }
+        thread::sleep(READ_TIMEOUT);
+        Ok(None)
+    }
+
+    fn self_test(&self) -> Result<String, String> {
+        Ok("Simulator, no hardware involved".to_string())
+    }
+
+    fn send_raw(&self, _data: &[u8], _use_prefix: bool) -> Result<(), String> {
+        Err("The simulator has no raw protocol".to_string())
+    }
+
+    fn read_raw(&self, _timeout: Duration) -> Result<Option<Vec<u8>>, String> {
+        Err("The simulator has no raw protocol".to_string())
+    }
+}
+
+// ============================================================================
+// Simulator
+// ============================================================================
+
+// What a deck shows
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyImages {
+    pub brightness: u8,
+    // Key -> PNG data URL
+    pub keys: HashMap<u8, String>,
+}
+
+lazy_static::lazy_static! {
+    static ref ENABLED: bool = std::env::args().any(|a| a == "--simulator")
+        || std::env::var("REDRAGON_SIMULATOR").map(|v| v == "1").unwrap_or(false);
+    static ref SIMULATOR: Arc<MemoryDeck> = Arc::new(MemoryDeck::new());
+}
+
+pub fn simulator_enabled() -> bool {
+    *ENABLED
+}
+
+pub fn simulator() -> Arc<MemoryDeck> {
+    SIMULATOR.clone()
+}
+
+// Queue a press or release of a simulated key
+pub fn send_key_event(key_id: u8, pressed: bool) -> Result<(), String> {
+    if !(1..=15).contains(&key_id) {
+        return Err(format!("Invalid key: {}", key_id));
+    }
+    SIMULATOR.push_event(if pressed { DeckEvent::KeyDown(key_id) } else { DeckEvent::KeyUp(key_id) })
+}
+
+// Key image as a PNG data URL for the UI
+pub fn png_data_url(image: RgbImage) -> Option<String> {
+    let mut png = Vec::new();
+    DynamicImage::ImageRgb8(image)
+        .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+        .ok()?;
+    Some(format!("data:image/png;base64,{}", STANDARD.encode(png)))
+}
+
+pub fn simulator_state() -> KeyImages {
+    let keys = SIMULATOR
+        .images()
+        .into_iter()
+        .filter_map(|(key, image)| Some((key, png_data_url(image)?)))
+        .collect();
+    KeyImages {
+        brightness: SIMULATOR.brightness(),
+        keys,
+    }
+}
+
+// ============================================================================
+// Mirror
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum MirrorEvent {
+    // PNG data URL of the upright image
+    Key { key: u8, image: String },
+    Clear,
+    Brightness { brightness: u8 },
+}
+
+type MirrorSink = Box<dyn Fn(MirrorEvent) + Send + Sync>;
+
+static MIRROR_SINK: OnceLock<MirrorSink> = OnceLock::new();
+
+lazy_static::lazy_static! {
+    static ref MIRROR: RwLock<HashMap<u8, String>> = RwLock::new(HashMap::new());
+}
+static MIRROR_BRIGHTNESS: AtomicU8 = AtomicU8::new(100);
+
+// Where mirror events go; set once at startup
+pub fn set_mirror_sink(sink: impl Fn(MirrorEvent) + Send + Sync + 'static) {
+    MIRROR_SINK.set(Box::new(sink)).ok();
+}
+
+fn mirror(event: MirrorEvent) {
+    match &event {
+        MirrorEvent::Key { key, image } => {
+            if let Ok(mut keys) = MIRROR.write() {
+                keys.insert(*key, image.clone());
+            }
+        }
+        MirrorEvent::Clear => {
+            if let Ok(mut keys) = MIRROR.write() {
+                keys.clear();
+            }
+        }
+        MirrorEvent::Brightness { brightness } => MIRROR_BRIGHTNESS.store(*brightness, Ordering::SeqCst),
+    }
+    if let Some(sink) = MIRROR_SINK.get() {
+        sink(event);
+    }
+}
+
+// The last images sent to the deck
+pub fn mirror_state() -> KeyImages {
+    KeyImages {
+        brightness: MIRROR_BRIGHTNESS.load(Ordering::SeqCst),
+        keys: MIRROR.read().map(|keys| keys.clone()).unwrap_or_default(),
+    }
+}
+
+// Reports what reaches the wrapped deck
+pub struct Mirrored<D: Deck>(pub D);
+
+impl<D: Deck> Deck for Mirrored<D> {
+    fn wake(&self) -> Result<(), String> {
+        self.0.wake()
+    }
+
+    fn clear(&self) -> Result<(), String> {
+        self.0.clear()?;
+        mirror(MirrorEvent::Clear);
+        Ok(())
+    }
+
+    fn set_brightness(&self, brightness: u8) -> Result<(), String> {
+        self.0.set_brightness(brightness)?;
+        mirror(MirrorEvent::Brightness { brightness });
+        Ok(())
+    }
+
+    fn set_key_image(&self, key_id: u8, image: &RgbImage) -> Result<(), String> {
+        self.0.set_key_image(key_id, image)?;
+        if let Some(image) = png_data_url(image.clone()) {
+            mirror(MirrorEvent::Key { key: key_id, image });
+        }
+        Ok(())
+    }
+
+    fn read_event(&self) -> Result<Option<DeckEvent>, String> {
+        self.0.read_event()
+    }
+
+    fn self_test(&self) -> Result<String, String> {
+        self.0.self_test()
+    }
+
+    fn send_raw(&self, data: &[u8], use_prefix: bool) -> Result<(), String> {
+        self.0.send_raw(data, use_prefix)
+    }
+
+    fn read_raw(&self, timeout: Duration) -> Result<Option<Vec<u8>>, String> {
+        self.0.read_raw(timeout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use image::{Rgb, RgbImage};
+
+    use super::{Deck, DeckEvent, MemoryDeck, Sent};
+    use crate::frame_scheduler::FrameScheduler;
+    use crate::{confirm_hold, load_page_to_device, render_button_image, ButtonConfig, Page, BUTTON_SIZE};
+
+    fn button(label: &str) -> ButtonConfig {
+        ButtonConfig {
+            label: label.to_string(),
+            command: String::new(),
+            color: "#27ae60".to_string(),
+            icon: String::new(),
+            action: None,
+            hold_confirm: false,
+            confirm: false,
+            sound: None,
+        }
+    }
+
+    fn icons_path() -> PathBuf {
+        PathBuf::from("/nonexistent/icons")
+    }
+
+    fn solid(value: u8) -> RgbImage {
+        RgbImage::from_pixel(BUTTON_SIZE, BUTTON_SIZE, Rgb([value, value, value]))
+    }
+
+    #[test]
+    fn page_load_wakes_clears_sets_brightness_then_sends_keys() {
+        let deck = MemoryDeck::new();
+        let page = Page {
+            name: "Test".to_string(),
+            buttons: HashMap::from([("1".to_string(), button("A")), ("7".to_string(), button("B"))]),
+            brightness: Some(30),
+            default_color: None,
+        };
+
+        load_page_to_device(&deck, &page, 80, &icons_path()).unwrap();
+
+        let sent = deck.sent();
+        // The page's brightness overrides the config's
+        assert_eq!(sent[..3], [Sent::Wake, Sent::Clear, Sent::Brightness(30)]);
+        let mut keys: Vec<Sent> = sent[3..].to_vec();
+        keys.sort_by_key(|s| match s {
+            Sent::Key(key) => *key,
+            _ => 0,
+        });
+        assert_eq!(keys, [Sent::Key(1), Sent::Key(7)]);
+        assert_eq!(deck.brightness(), 30);
+        assert_eq!(deck.images()[&1], render_button_image(&page.buttons["1"], &icons_path()));
+    }
+
+    #[test]
+    fn brightness_change_fades_from_the_last_level() {
+        let deck = Arc::new(MemoryDeck::new());
+        let scheduler = FrameScheduler::new(Box::new(deck.clone()));
+
+        scheduler.set_brightness(100).unwrap();
+        scheduler.set_brightness(40).unwrap();
+
+        // Default 300 ms fade in 30 ms steps: ten levels down to the target
+        let expected: Vec<Sent> = std::iter::once(100).chain((1..=10).map(|i| 100 - 6 * i)).map(Sent::Brightness).collect();
+        assert_eq!(deck.sent(), expected);
+        assert_eq!(deck.brightness(), 40);
+    }
+
+    #[test]
+    fn key_images_go_out_in_a_frame_before_the_next_event() {
+        let deck = Arc::new(MemoryDeck::new());
+        let scheduler = FrameScheduler::new(Box::new(deck.clone()));
+
+        scheduler.set_key_image(2, &solid(10)).unwrap();
+        scheduler.set_key_image(9, &solid(20)).unwrap();
+        scheduler.set_key_image(2, &solid(30)).unwrap();
+        assert!(deck.sent().is_empty());
+
+        deck.push_event(DeckEvent::KeyDown(9)).unwrap();
+        deck.push_event(DeckEvent::KeyUp(9)).unwrap();
+        assert_eq!(scheduler.read_event().unwrap(), Some(DeckEvent::KeyDown(9)));
+        assert_eq!(scheduler.read_event().unwrap(), Some(DeckEvent::KeyUp(9)));
+        assert_eq!(scheduler.read_event().unwrap(), None);
+
+        // Key 2 is sent once, with its newest image
+        assert_eq!(deck.sent(), [Sent::Key(2), Sent::Key(9)]);
+        assert_eq!(deck.images()[&2], solid(30));
+    }
+
+    #[test]
+    fn releasing_a_hold_confirm_key_early_cancels_it() {
+        let deck = MemoryDeck::new();
+        let held = ButtonConfig {
+            hold_confirm: true,
+            ..button("Off")
+        };
+        deck.push_event(DeckEvent::KeyUp(4)).unwrap();
+
+        assert!(!confirm_hold(&deck, 4, &held, &icons_path()));
+
+        // A progress frame, then the key's own image back
+        let sent = deck.sent();
+        assert!(sent.len() >= 2);
+        assert!(sent.iter().all(|s| *s == Sent::Key(4)));
+        assert_eq!(deck.images()[&4], render_button_image(&held, &icons_path()));
+    }
+}
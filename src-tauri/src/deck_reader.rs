@@ -0,0 +1,178 @@
+// ============================================================================
+// Key Event Reader Thread
+// ============================================================================
+//
+// The listener used to block in a 100 ms read between its turns, so a page
+// refresh, a toast or a widget image waited for the read to time out and a
+// key press waited for the listener to finish its turn. Now a reader thread
+// owns the reads and hands key events over a channel; the listener waits on
+// that channel, and wake() (called by request_refresh, overlays, alerts, the
+// widget worker...) ends the wait right away. With nothing to do the listener
+// still runs every `usb.readTimeoutMs` for its timed work (alert blinking,
+// key repeat, the screensaver clock).
+//
+// Raw packets (device self-test, protocol console) pause the reader thread
+// until the listener waits for keys again, so it does not swallow the replies
+// they wait for.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+
+use tracing::debug;
+
+use crate::deck::{Deck, DeckEvent};
+use crate::usb_settings;
+
+// How often a paused reader looks again
+const PAUSE_POLL: Duration = Duration::from_millis(10);
+
+enum Wake {
+    Event(Result<DeckEvent, String>),
+    Nudge,
+}
+
+lazy_static::lazy_static! {
+    // The listener's channel, for wake()
+    static ref WAKER: Mutex<Option<Sender<Wake>>> = Mutex::new(None);
+}
+
+// A nudge is queued and not yet seen by the listener
+static NUDGED: AtomicBool = AtomicBool::new(false);
+
+// Wake the listener now instead of after its read timeout
+pub fn wake() {
+    if NUDGED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    if let Ok(waker) = WAKER.lock() {
+        if let Some(sender) = waker.as_ref() {
+            sender.send(Wake::Nudge).ok();
+        }
+    }
+}
+
+struct Shared {
+    deck: Box<dyn Deck + Send + Sync>,
+    // Held by the reader thread for each read and by raw reads
+    reading: Mutex<()>,
+    paused: AtomicBool,
+    stopped: AtomicBool,
+}
+
+impl Shared {
+    // Keep the reader thread off the IN endpoint until the next read_event;
+    // the guard waits for a read in progress
+    fn pause(&self) -> Option<MutexGuard<'_, ()>> {
+        self.paused.store(true, Ordering::SeqCst);
+        self.reading.lock().ok()
+    }
+}
+
+pub struct ThreadedReader {
+    shared: Arc<Shared>,
+    events: Receiver<Wake>,
+}
+
+fn run(shared: Arc<Shared>, events: Sender<Wake>) {
+    while !shared.stopped.load(Ordering::SeqCst) {
+        if shared.paused.load(Ordering::SeqCst) {
+            thread::sleep(PAUSE_POLL);
+            continue;
+        }
+        let result = match shared.reading.lock() {
+            Ok(_reading) => shared.deck.read_event(),
+            Err(_) => return,
+        };
+        match result {
+            Ok(Some(event)) => {
+                if events.send(Wake::Event(Ok(event))).is_err() {
+                    return;
+                }
+            }
+            Ok(None) => {}
+            Err(e) => {
+                events.send(Wake::Event(Err(e))).ok();
+                return;
+            }
+        }
+    }
+    debug!("Deck reader stopped");
+}
+
+impl ThreadedReader {
+    pub fn new(deck: Box<dyn Deck + Send + Sync>) -> Self {
+        let (sender, events) = mpsc::channel();
+        if let Ok(mut waker) = WAKER.lock() {
+            *waker = Some(sender.clone());
+        }
+        NUDGED.store(false, Ordering::SeqCst);
+
+        let shared = Arc::new(Shared {
+            deck,
+            reading: Mutex::new(()),
+            paused: AtomicBool::new(false),
+            stopped: AtomicBool::new(false),
+        });
+        let reader = shared.clone();
+        thread::spawn(move || run(reader, sender));
+        ThreadedReader { shared, events }
+    }
+}
+
+impl Drop for ThreadedReader {
+    fn drop(&mut self) {
+        self.shared.stopped.store(true, Ordering::SeqCst);
+    }
+}
+
+impl Deck for ThreadedReader {
+    fn wake(&self) -> Result<(), String> {
+        self.shared.deck.wake()
+    }
+
+    fn clear(&self) -> Result<(), String> {
+        self.shared.deck.clear()
+    }
+
+    fn set_brightness(&self, brightness: u8) -> Result<(), String> {
+        self.shared.deck.set_brightness(brightness)
+    }
+
+    fn set_key_image(&self, key_id: u8, image: &image::RgbImage) -> Result<(), String> {
+        self.shared.deck.set_key_image(key_id, image)
+    }
+
+    // The next key event from the reader thread; None after a wake() or when
+    // nothing happened within the read timeout
+    fn read_event(&self) -> Result<Option<DeckEvent>, String> {
+        self.shared.paused.store(false, Ordering::SeqCst);
+        let timeout = Duration::from_millis(usb_settings().read_timeout_ms);
+        match self.events.recv_timeout(timeout) {
+            Ok(Wake::Event(event)) => event.map(Some),
+            Ok(Wake::Nudge) => {
+                NUDGED.store(false, Ordering::SeqCst);
+                Ok(None)
+            }
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => Err("Deck reader stopped".to_string()),
+        }
+    }
+
+    fn self_test(&self) -> Result<String, String> {
+        let _reading = self.shared.pause();
+        self.shared.deck.self_test()
+    }
+
+    fn send_raw(&self, data: &[u8], use_prefix: bool) -> Result<(), String> {
+        let _reading = self.shared.pause();
+        self.shared.deck.send_raw(data, use_prefix)
+    }
+
+    fn read_raw(&self, timeout: Duration) -> Result<Option<Vec<u8>>, String> {
+        let _reading = self.shared.pause();
+        self.shared.deck.read_raw(timeout)
+    }
+}
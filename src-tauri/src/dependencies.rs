@@ -0,0 +1,89 @@
+// ============================================================================
+// External Tool Detection
+// ============================================================================
+//
+// Many actions run tools that are not dependencies of the package: playerctl
+// for media keys, wpctl for volume, grim and slurp (maim on X11) for
+// screenshots, hyprctl for workspaces, xdg-open for URLs, and ydotool when it
+// is the input backend. Missing ones used to show up only as a key that did
+// nothing. check_dependencies probes each tool and lists the command
+// fragments that need it ("playerctl", "__SCREENSHOT_"...), so the UI can mark
+// the presets and buttons that will not work on this system.
+
+
+use serde::Serialize;
+
+use crate::input::{self, BackendKind};
+use crate::{sandbox, session};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Dependency {
+    pub name: &'static str,
+    pub available: bool,
+    pub detail: String,
+    // Command fragments that need it; a command containing one fails without it
+    #[serde(rename = "usedBy")]
+    pub used_by: Vec<&'static str>,
+}
+
+fn command_exists(name: &str) -> bool {
+    sandbox::host_command("which").arg(name).output().map(|o| o.status.success()).unwrap_or(false)
+}
+
+fn process_running(name: &str) -> bool {
+    sandbox::host_command("pgrep").args(["-x", name]).output().map(|o| o.status.success()).unwrap_or(false)
+}
+
+fn tool(name: &'static str, used_by: Vec<&'static str>) -> Dependency {
+    let available = command_exists(name);
+    Dependency {
+        name,
+        available,
+        detail: if available { "Installed".to_string() } else { format!("{} is not installed", name) },
+        used_by,
+    }
+}
+
+// ydotool only matters when input goes through it
+fn ydotool() -> Dependency {
+    let used_by = if input::active_backend() == BackendKind::Ydotool {
+        vec!["__KEY_", "__TYPE_", "__MOUSE_", "__SCROLL_", "__SNIPPET_"]
+    } else {
+        Vec::new()
+    };
+    let mut dependency = tool("ydotool", used_by);
+    if dependency.available && !process_running("ydotoold") {
+        dependency.available = false;
+        dependency.detail = "ydotoold is not running (systemctl --user start ydotoold)".to_string();
+    }
+    dependency
+}
+
+// hyprctl needs a running Hyprland, not just the binary
+fn hyprctl() -> Dependency {
+    let mut dependency = tool("hyprctl", vec!["hyprctl"]);
+    if dependency.available && std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_none() {
+        dependency.available = false;
+        dependency.detail = "Installed, but this session is not Hyprland".to_string();
+    }
+    dependency
+}
+
+pub fn check() -> Vec<Dependency> {
+    let wayland = session::is_wayland();
+    let (grim, slurp, maim) = if wayland {
+        (vec!["grim", "__SCREENSHOT_"], vec!["slurp", "__SCREENSHOT_region"], vec!["maim"])
+    } else {
+        (vec!["grim"], vec!["slurp"], vec!["maim", "__SCREENSHOT_"])
+    };
+    vec![
+        ydotool(),
+        tool("playerctl", vec!["playerctl"]),
+        tool("wpctl", vec!["wpctl"]),
+        tool("grim", grim),
+        tool("slurp", slurp),
+        tool("maim", maim),
+        hyprctl(),
+        tool("xdg-open", vec!["xdg-open", "__URL_"]),
+    ]
+}
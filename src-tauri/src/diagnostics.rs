@@ -0,0 +1,283 @@
+// ============================================================================
+// Device Info and Diagnostics
+// ============================================================================
+//
+// get_device_info answers "is it detected?": the USB descriptors of the SS-550
+// (IDs, strings, firmware version, endpoints), whether it can be opened, and a
+// self-test that sends DIS and waits for the device's reply. The listener owns
+// the open handle, so the self-test is queued and run between two reads.
+//
+// get_diagnostics collects the usual causes of support questions: the udev
+// rule, the input backends (ydotool, uinput), fonts, OBS and Twitch.
+
+use std::fs;
+use std::sync::mpsc::{self, Sender};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use rusb::{Context, Direction, TransferType, UsbContext};
+use serde::Serialize;
+use tracing::debug;
+
+use crate::deck::Deck;
+use crate::{deck_reader, icons, input, obs_request, sandbox, session, twitch_init, PRODUCT_ID, VENDOR_ID};
+
+const UDEV_RULE_DIRS: [&str; 3] = ["/etc/udev/rules.d", "/usr/lib/udev/rules.d", "/lib/udev/rules.d"];
+// How long get_device_info waits for the listener to run the self-test
+const SELF_TEST_TIMEOUT: Duration = Duration::from_secs(3);
+
+lazy_static::lazy_static! {
+    // Callers waiting for the listener to run a self-test
+    static ref SELF_TESTS: Mutex<Vec<Sender<Result<String, String>>>> = Mutex::new(Vec::new());
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Endpoint {
+    pub address: String,
+    pub direction: String,
+    #[serde(rename = "transferType")]
+    pub transfer_type: String,
+    #[serde(rename = "maxPacketSize")]
+    pub max_packet_size: u16,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceInfo {
+    pub detected: bool,
+    #[serde(rename = "vendorId")]
+    pub vendor_id: String,
+    #[serde(rename = "productId")]
+    pub product_id: String,
+    pub bus: Option<u8>,
+    pub address: Option<u8>,
+    pub manufacturer: Option<String>,
+    pub product: Option<String>,
+    pub serial: Option<String>,
+    // bcdDevice, the only firmware version the SS-550 reports
+    pub firmware: Option<String>,
+    #[serde(rename = "usbVersion")]
+    pub usb_version: Option<String>,
+    pub endpoints: Vec<Endpoint>,
+    // Whether this user may open it (udev rule)
+    pub openable: bool,
+    // Reply to DIS as hex, or why the test failed
+    #[serde(rename = "selfTest")]
+    pub self_test: Result<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub name: String,
+    pub ok: bool,
+    pub detail: String,
+}
+
+pub(crate) fn check(name: &str, ok: bool, detail: impl Into<String>) -> Check {
+    Check {
+        name: name.to_string(),
+        ok,
+        detail: detail.into(),
+    }
+}
+
+fn version_string(version: rusb::Version) -> String {
+    format!("{}.{}.{}", version.major(), version.minor(), version.sub_minor())
+}
+
+pub fn device_info() -> DeviceInfo {
+    let mut info = DeviceInfo {
+        detected: false,
+        vendor_id: format!("{:04x}", VENDOR_ID),
+        product_id: format!("{:04x}", PRODUCT_ID),
+        bus: None,
+        address: None,
+        manufacturer: None,
+        product: None,
+        serial: None,
+        firmware: None,
+        usb_version: None,
+        endpoints: Vec::new(),
+        openable: false,
+        self_test: Err("Device not detected".to_string()),
+    };
+
+    let device = Context::new().ok().and_then(|context| {
+        context.devices().ok()?.iter().find(|d| {
+            d.device_descriptor()
+                .map(|desc| desc.vendor_id() == VENDOR_ID && desc.product_id() == PRODUCT_ID)
+                .unwrap_or(false)
+        })
+    });
+    let device = match device {
+        Some(d) => d,
+        None => return info,
+    };
+    info.detected = true;
+    info.bus = Some(device.bus_number());
+    info.address = Some(device.address());
+
+    if let Ok(desc) = device.device_descriptor() {
+        info.firmware = Some(version_string(desc.device_version()));
+        info.usb_version = Some(version_string(desc.usb_version()));
+
+        // String descriptors need an open handle, not the claimed interface
+        if let Ok(handle) = device.open() {
+            info.openable = true;
+            info.manufacturer = handle.read_manufacturer_string_ascii(&desc).ok();
+            info.product = handle.read_product_string_ascii(&desc).ok();
+            info.serial = handle.read_serial_number_string_ascii(&desc).ok();
+        }
+    }
+
+    if let Ok(config) = device.config_descriptor(0) {
+        for interface in config.interfaces() {
+            for setting in interface.descriptors() {
+                for endpoint in setting.endpoint_descriptors() {
+                    info.endpoints.push(Endpoint {
+                        address: format!("0x{:02x}", endpoint.address()),
+                        direction: match endpoint.direction() {
+                            Direction::In => "in",
+                            Direction::Out => "out",
+                        }
+                        .to_string(),
+                        transfer_type: match endpoint.transfer_type() {
+                            TransferType::Control => "control",
+                            TransferType::Isochronous => "isochronous",
+                            TransferType::Bulk => "bulk",
+                            TransferType::Interrupt => "interrupt",
+                        }
+                        .to_string(),
+                        max_packet_size: endpoint.max_packet_size(),
+                    });
+                }
+            }
+        }
+    }
+
+    info.self_test = request_self_test();
+    info
+}
+
+// Ask the listener to run Deck::self_test and wait for the result
+fn request_self_test() -> Result<String, String> {
+    let (sender, receiver) = mpsc::channel();
+    SELF_TESTS.lock().map_err(|e| e.to_string())?.push(sender);
+    deck_reader::wake();
+    receiver
+        .recv_timeout(SELF_TEST_TIMEOUT)
+        .map_err(|_| "The listener did not run the test (device not open)".to_string())?
+}
+
+// Called by the listener between reads
+pub fn run_pending_self_tests(deck: &dyn Deck) {
+    let waiting: Vec<_> = match SELF_TESTS.lock() {
+        Ok(mut waiting) if !waiting.is_empty() => waiting.drain(..).collect(),
+        _ => return,
+    };
+    let result = deck.self_test();
+    debug!("Device self-test: {:?}", result);
+    for sender in waiting {
+        sender.send(result.clone()).ok();
+    }
+}
+
+fn udev_rule() -> Check {
+    let id = format!("{:04x}", VENDOR_ID);
+    let rule = UDEV_RULE_DIRS
+        .iter()
+        .flat_map(|dir| fs::read_dir(dir).into_iter().flatten().flatten())
+        .map(|entry| entry.path())
+        .find(|path| {
+            fs::read_to_string(path)
+                .map(|content| content.contains(&id) && content.contains(&format!("{:04x}", PRODUCT_ID)))
+                .unwrap_or(false)
+        });
+    match rule {
+        Some(path) => check("udev", true, path.display().to_string()),
+        None => check("udev", false, "No rule for 0200:1000; run the installer or see README"),
+    }
+}
+
+fn command_exists(name: &str) -> bool {
+    sandbox::host_command("which").arg(name).output().map(|o| o.status.success()).unwrap_or(false)
+}
+
+fn ydotool() -> Check {
+    if !command_exists("ydotool") {
+        return check("ydotool", false, "ydotool is not installed");
+    }
+    let daemon = sandbox::host_command("pgrep")
+        .arg("ydotoold")
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false);
+    if daemon {
+        check("ydotool", true, "ydotoold is running")
+    } else {
+        check("ydotool", false, "ydotoold is not running (systemctl --user start ydotoold)")
+    }
+}
+
+fn fonts() -> Vec<Check> {
+    let emoji = match icons::emoji_font_path() {
+        Some(path) => check("emoji font", true, path),
+        None => check("emoji font", false, "Noto Color Emoji not found; emoji icons are unavailable"),
+    };
+    // Key labels use DejaVu Sans, built into the binary
+    vec![check("label font", true, "DejaVu Sans (built in)"), emoji]
+}
+
+fn obs() -> Check {
+    match obs_request("GetVersion", None) {
+        Ok(version) => {
+            let obs = version["obsVersion"].as_str().unwrap_or("?").to_string();
+            check("obs", true, format!("OBS {} reachable", obs))
+        }
+        Err(e) => check("obs", false, e),
+    }
+}
+
+fn twitch() -> Check {
+    match twitch_init() {
+        Ok(()) => check("twitch", true, "Credentials accepted"),
+        Err(e) => check("twitch", false, e),
+    }
+}
+
+pub fn report() -> Vec<Check> {
+    let device = device_info();
+    let mut checks = vec![
+        check(
+            "device",
+            device.detected && device.openable,
+            if !device.detected {
+                "Not detected on USB".to_string()
+            } else if !device.openable {
+                "Detected but not openable (permissions)".to_string()
+            } else {
+                format!("Bus {} address {}", device.bus.unwrap_or(0), device.address.unwrap_or(0))
+            },
+        ),
+        match &device.self_test {
+            Ok(reply) => check("self-test", true, reply.clone()),
+            Err(e) => check("self-test", false, e.clone()),
+        },
+        udev_rule(),
+        check(
+            "input",
+            true,
+            format!("{} on {}", input::active_backend().as_str(), session::session_type().as_str()),
+        ),
+        ydotool(),
+        check(
+            "uinput",
+            input::uinput_accessible(),
+            if input::uinput_accessible() { "/dev/uinput writable" } else { "/dev/uinput not writable" },
+        ),
+    ];
+    checks.extend(fonts());
+    checks.push(obs());
+    checks.push(twitch());
+    checks.extend(sandbox::report());
+    checks
+}
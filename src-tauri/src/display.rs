@@ -0,0 +1,230 @@
+// ============================================================================
+// Monitor Brightness and Night Light
+// ============================================================================
+//
+// Command formats:
+//   __MONITOR_BRIGHT__       - widget with the monitor brightness in percent
+//   __MONITOR_BRIGHT_+10__   - raise brightness by 10 points (-10 lowers it)
+//   __MONITOR_BRIGHT_70__    - set brightness to 70%
+//   __NIGHTLIGHT__           - toggle the night light; as a widget shows ON/OFF
+//
+// Laptop panels are set through /sys/class/backlight (written via logind);
+// external monitors through DDC/CI with ddcutil (first display only). The
+// night light runs gammastep, or hyprsunset on Hyprland, and is turned off by
+// stopping gammastep, hyprsunset or wlsunset, whichever is running.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::thread;
+
+use tracing::{debug, warn};
+
+use crate::{poller, power, request_refresh, sandbox};
+
+pub const BRIGHTNESS_WIDGET: &str = "__MONITOR_BRIGHT__";
+pub const NIGHT_LIGHT_COMMAND: &str = "__NIGHTLIGHT__";
+
+// ddcutil takes a second or more per call
+const BRIGHTNESS_POLL_SECS: u64 = 30;
+const NIGHT_LIGHT_POLL_SECS: u64 = 5;
+const NIGHT_TEMPERATURE: &str = "4000";
+const NIGHT_LIGHT_TOOLS: [&str; 3] = ["gammastep", "hyprsunset", "wlsunset"];
+// DDC/CI feature code for luminance
+const VCP_BRIGHTNESS: &str = "10";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BrightnessChange {
+    Relative(i32),
+    Absolute(u32),
+}
+
+fn parse_brightness(cmd: &str) -> Option<BrightnessChange> {
+    let value = cmd.strip_prefix("__MONITOR_BRIGHT_")?.strip_suffix("__")?;
+    if value.starts_with('+') || value.starts_with('-') {
+        value.parse().ok().map(BrightnessChange::Relative)
+    } else {
+        value.parse().ok().filter(|v| *v <= 100).map(BrightnessChange::Absolute)
+    }
+}
+
+pub fn is_display_command(cmd: &str) -> bool {
+    cmd == BRIGHTNESS_WIDGET || cmd == NIGHT_LIGHT_COMMAND || parse_brightness(cmd).is_some()
+}
+
+pub fn is_display_widget(cmd: &str) -> bool {
+    cmd == BRIGHTNESS_WIDGET || cmd == NIGHT_LIGHT_COMMAND
+}
+
+// First device in /sys/class/backlight
+fn backlight_device() -> Option<PathBuf> {
+    let mut devices: Vec<PathBuf> = fs::read_dir("/sys/class/backlight")
+        .ok()?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .collect();
+    devices.sort();
+    devices.into_iter().next()
+}
+
+fn read_number(path: PathBuf) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+// (current, max) of the backlight device
+fn backlight_values(device: &Path) -> Option<(u32, u32)> {
+    let current = read_number(device.join("brightness"))?;
+    let max = read_number(device.join("max_brightness")).filter(|m| *m > 0)?;
+    Some((current, max))
+}
+
+fn ddcutil(args: &[&str]) -> Result<String, String> {
+    let output = sandbox::host_command("ddcutil")
+        .args(args)
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| format!("Failed to run ddcutil (is it installed?): {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("ddcutil failed: {}", stderr.trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+// `ddcutil getvcp 10 --brief` prints "VCP 10 C <current> <max>"
+fn ddc_values() -> Result<(u32, u32), String> {
+    let output = ddcutil(&["getvcp", VCP_BRIGHTNESS, "--brief"])?;
+    let fields: Vec<&str> = output.split_whitespace().collect();
+    match fields[..] {
+        [_, _, _, current, max, ..] => match (current.parse(), max.parse()) {
+            (Ok(current), Ok(max)) if max > 0 => Ok((current, max)),
+            _ => Err(format!("Unexpected ddcutil output: {}", output.trim())),
+        },
+        _ => Err(format!("Unexpected ddcutil output: {}", output.trim())),
+    }
+}
+
+fn percent(current: u32, max: u32) -> u32 {
+    (current as f64 * 100.0 / max as f64).round() as u32
+}
+
+fn read_brightness() -> Result<u32, String> {
+    if let Some(device) = backlight_device() {
+        let (current, max) = backlight_values(&device).ok_or("Cannot read the backlight brightness")?;
+        return Ok(percent(current, max));
+    }
+    ddc_values().map(|(current, max)| percent(current, max))
+}
+
+fn target_percent(change: BrightnessChange, current: u32) -> u32 {
+    match change {
+        BrightnessChange::Absolute(value) => value,
+        BrightnessChange::Relative(delta) => (current as i32 + delta).clamp(0, 100) as u32,
+    }
+}
+
+// Apply a change and return the new brightness in percent
+fn change_brightness(change: BrightnessChange) -> Result<u32, String> {
+    if let Some(device) = backlight_device() {
+        let name = device.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_string();
+        let (current, max) = backlight_values(&device).ok_or("Cannot read the backlight brightness")?;
+        // Never turn the panel fully off
+        let target = target_percent(change, percent(current, max)).max(1);
+        power::set_backlight(&name, (max as f64 * target as f64 / 100.0).round() as u32)?;
+        return Ok(target);
+    }
+
+    let (current, max) = ddc_values()?;
+    let target = target_percent(change, percent(current, max));
+    let value = (max as f64 * target as f64 / 100.0).round() as u32;
+    ddcutil(&["setvcp", VCP_BRIGHTNESS, &value.to_string()])?;
+    Ok(target)
+}
+
+fn night_light_running() -> bool {
+    NIGHT_LIGHT_TOOLS.iter().any(|tool| {
+        sandbox::host_command("pgrep")
+            .args(["-x", tool])
+            .stdout(Stdio::null())
+            .status()
+            .map(|s| s.success())
+            .unwrap_or(false)
+    })
+}
+
+fn start_night_light() -> Result<(), String> {
+    let hyprland = std::env::var("HYPRLAND_INSTANCE_SIGNATURE").is_ok();
+    let attempts: [(&str, [&str; 2]); 2] = if hyprland {
+        [("hyprsunset", ["-t", NIGHT_TEMPERATURE]), ("gammastep", ["-O", NIGHT_TEMPERATURE])]
+    } else {
+        [("gammastep", ["-O", NIGHT_TEMPERATURE]), ("hyprsunset", ["-t", NIGHT_TEMPERATURE])]
+    };
+    for (program, args) in attempts {
+        let started = sandbox::host_command(program)
+            .args(args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        if started.is_ok() {
+            debug!("Night light on ({})", program);
+            return Ok(());
+        }
+    }
+    Err("No night light tool found (install gammastep or hyprsunset)".to_string())
+}
+
+fn stop_night_light() {
+    for tool in NIGHT_LIGHT_TOOLS {
+        sandbox::host_command("pkill").args(["-x", tool]).status().ok();
+    }
+    debug!("Night light off");
+}
+
+fn toggle_night_light() -> Result<(), String> {
+    let on = !night_light_running();
+    if on {
+        start_night_light()?;
+    } else {
+        stop_night_light();
+    }
+    poller::store(NIGHT_LIGHT_COMMAND, on);
+    request_refresh();
+    Ok(())
+}
+
+pub fn execute_command(cmd: &str) -> Result<(), String> {
+    if cmd == NIGHT_LIGHT_COMMAND {
+        return toggle_night_light();
+    }
+    if cmd == BRIGHTNESS_WIDGET {
+        poller::invalidate_matching(|key| key == BRIGHTNESS_WIDGET);
+        request_refresh();
+        return Ok(());
+    }
+    let change = parse_brightness(cmd).ok_or_else(|| format!("Invalid brightness command: {}", cmd))?;
+    // ddcutil is slow; don't hold up the button listener
+    thread::spawn(move || match change_brightness(change) {
+        Ok(value) => {
+            debug!("Monitor brightness {}%", value);
+            poller::store(BRIGHTNESS_WIDGET, Ok::<u32, String>(value));
+            request_refresh();
+        }
+        Err(e) => warn!("{}", e),
+    });
+    Ok(())
+}
+
+pub fn get_widget_text(cmd: &str) -> String {
+    if cmd == NIGHT_LIGHT_COMMAND {
+        return match poller::poll(cmd, NIGHT_LIGHT_POLL_SECS, night_light_running) {
+            Some(true) => "Night ON".to_string(),
+            Some(false) => "Night OFF".to_string(),
+            None => "...".to_string(),
+        };
+    }
+    match poller::poll(cmd, BRIGHTNESS_POLL_SECS, read_brightness) {
+        Some(Ok(value)) => format!("☀ {}%", value),
+        Some(Err(_)) => "N/A".to_string(),
+        None => "...".to_string(),
+    }
+}
@@ -0,0 +1,335 @@
+// ============================================================================
+// Docker Integration (Engine API over the Unix socket)
+// ============================================================================
+//
+// Command formats:
+//   __DOCKER_<container>__          - widget: UP/DOWN state, press toggles start/stop
+//   __DOCKER_CPU_<container>__      - widget: container CPU usage %
+//   __DOCKER_START_<container>__    - start a container
+//   __DOCKER_STOP_<container>__     - stop a container
+//   __DOCKER_RESTART_<container>__  - restart a container
+//   __COMPOSE_START_<project>__     - start every container of a compose project
+//   __COMPOSE_STOP_<project>__      - stop every container of a compose project
+//   __COMPOSE_RESTART_<project>__   - restart every container of a compose project
+
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::thread;
+use std::time::Duration;
+
+use tracing::{debug, warn};
+
+use crate::poller;
+
+const DEFAULT_SOCKET: &str = "/var/run/docker.sock";
+
+// Seconds before cached widget values are refreshed in the background
+const STATUS_CACHE_SECS: u64 = 3;
+const CPU_CACHE_SECS: u64 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ContainerOp {
+    Start,
+    Stop,
+    Restart,
+}
+
+impl ContainerOp {
+    fn endpoint(self) -> &'static str {
+        match self {
+            ContainerOp::Start => "start",
+            ContainerOp::Stop => "stop",
+            ContainerOp::Restart => "restart",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum DockerCommand {
+    Status(String),
+    Cpu(String),
+    Container(ContainerOp, String),
+    Compose(ContainerOp, String),
+}
+
+fn parse_command(cmd: &str) -> Option<DockerCommand> {
+    let inner = cmd.strip_suffix("__")?;
+
+    let (op_prefixes, compose) = if let Some(rest) = inner.strip_prefix("__COMPOSE_") {
+        (rest, true)
+    } else if let Some(rest) = inner.strip_prefix("__DOCKER_") {
+        (rest, false)
+    } else {
+        return None;
+    };
+
+    let ops = [
+        ("START_", ContainerOp::Start),
+        ("STOP_", ContainerOp::Stop),
+        ("RESTART_", ContainerOp::Restart),
+    ];
+    for (prefix, op) in ops {
+        if let Some(name) = op_prefixes.strip_prefix(prefix) {
+            if name.is_empty() {
+                return None;
+            }
+            return Some(if compose {
+                DockerCommand::Compose(op, name.to_string())
+            } else {
+                DockerCommand::Container(op, name.to_string())
+            });
+        }
+    }
+
+    if compose {
+        return None;
+    }
+    if let Some(name) = op_prefixes.strip_prefix("CPU_") {
+        if !name.is_empty() {
+            return Some(DockerCommand::Cpu(name.to_string()));
+        }
+        return None;
+    }
+    if op_prefixes.is_empty() {
+        return None;
+    }
+    Some(DockerCommand::Status(op_prefixes.to_string()))
+}
+
+pub fn is_docker_command(cmd: &str) -> bool {
+    parse_command(cmd).is_some()
+}
+
+pub fn is_docker_widget(cmd: &str) -> bool {
+    matches!(parse_command(cmd), Some(DockerCommand::Status(_)) | Some(DockerCommand::Cpu(_)))
+}
+
+// Socket path from DOCKER_HOST (unix:// only) or the default location
+fn socket_path() -> String {
+    std::env::var("DOCKER_HOST")
+        .ok()
+        .and_then(|host| host.strip_prefix("unix://").map(|p| p.to_string()))
+        .unwrap_or_else(|| DEFAULT_SOCKET.to_string())
+}
+
+// Minimal HTTP/1.0 request over the Docker socket, returns (status, body)
+fn docker_request(method: &str, path: &str, timeout: Duration) -> Result<(u16, String), String> {
+    let mut stream = UnixStream::connect(socket_path())
+        .map_err(|e| format!("Docker socket error: {}", e))?;
+    stream.set_read_timeout(Some(timeout)).ok();
+    stream.set_write_timeout(Some(Duration::from_secs(5))).ok();
+
+    let request = format!(
+        "{} {} HTTP/1.0\r\nHost: docker\r\nContent-Length: 0\r\n\r\n",
+        method, path
+    );
+    stream.write_all(request.as_bytes())
+        .map_err(|e| format!("Docker write error: {}", e))?;
+
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response)
+        .map_err(|e| format!("Docker read error: {}", e))?;
+    let response = String::from_utf8_lossy(&response);
+
+    let (head, body) = response.split_once("\r\n\r\n")
+        .ok_or("Malformed Docker response")?;
+    let status = head
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or("Missing Docker status code")?;
+
+    Ok((status, body.to_string()))
+}
+
+fn encode(value: &str) -> String {
+    url::form_urlencoded::byte_serialize(value.as_bytes()).collect()
+}
+
+// Get container state string (running, exited, paused, ...)
+fn container_state(name: &str) -> Result<String, String> {
+    let (status, body) = docker_request(
+        "GET",
+        &format!("/containers/{}/json", encode(name)),
+        Duration::from_secs(5),
+    )?;
+    if status == 404 {
+        return Ok("missing".to_string());
+    }
+    if status != 200 {
+        return Err(format!("Docker API error {}", status));
+    }
+
+    let data: serde_json::Value = serde_json::from_str(&body)
+        .map_err(|e| format!("Parse error: {}", e))?;
+    Ok(data["State"]["Status"].as_str().unwrap_or("unknown").to_string())
+}
+
+// Get container CPU usage in percent (one stats sample)
+fn container_cpu(name: &str) -> Result<f64, String> {
+    let (status, body) = docker_request(
+        "GET",
+        &format!("/containers/{}/stats?stream=false", encode(name)),
+        Duration::from_secs(10),
+    )?;
+    if status != 200 {
+        return Err(format!("Docker API error {}", status));
+    }
+
+    let data: serde_json::Value = serde_json::from_str(&body)
+        .map_err(|e| format!("Parse error: {}", e))?;
+
+    let cpu_total = data["cpu_stats"]["cpu_usage"]["total_usage"].as_f64().unwrap_or(0.0);
+    let pre_total = data["precpu_stats"]["cpu_usage"]["total_usage"].as_f64().unwrap_or(0.0);
+    let sys_total = data["cpu_stats"]["system_cpu_usage"].as_f64().unwrap_or(0.0);
+    let pre_sys = data["precpu_stats"]["system_cpu_usage"].as_f64().unwrap_or(0.0);
+    let online = data["cpu_stats"]["online_cpus"].as_f64()
+        .or_else(|| data["cpu_stats"]["cpu_usage"]["percpu_usage"].as_array().map(|a| a.len() as f64))
+        .unwrap_or(1.0);
+
+    let cpu_delta = cpu_total - pre_total;
+    let sys_delta = sys_total - pre_sys;
+    if cpu_delta <= 0.0 || sys_delta <= 0.0 {
+        return Ok(0.0);
+    }
+    Ok(cpu_delta / sys_delta * online * 100.0)
+}
+
+fn container_op(op: ContainerOp, name: &str) -> Result<(), String> {
+    let (status, body) = docker_request(
+        "POST",
+        &format!("/containers/{}/{}", encode(name), op.endpoint()),
+        Duration::from_secs(30),
+    )?;
+    // 204 = done, 304 = already in the requested state
+    match status {
+        204 | 304 => Ok(()),
+        _ => Err(format!("Docker {} {} failed ({}): {}", op.endpoint(), name, status, body.trim())),
+    }
+}
+
+// List container IDs belonging to a compose project
+fn compose_containers(project: &str) -> Result<Vec<String>, String> {
+    let filters = serde_json::json!({
+        "label": [format!("com.docker.compose.project={}", project)]
+    });
+    let (status, body) = docker_request(
+        "GET",
+        &format!("/containers/json?all=true&filters={}", encode(&filters.to_string())),
+        Duration::from_secs(5),
+    )?;
+    if status != 200 {
+        return Err(format!("Docker API error {}", status));
+    }
+
+    let data: serde_json::Value = serde_json::from_str(&body)
+        .map_err(|e| format!("Parse error: {}", e))?;
+    Ok(data.as_array()
+        .map(|list| {
+            list.iter()
+                .filter_map(|c| c["Id"].as_str().map(|s| s.to_string()))
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+fn invalidate_widgets_for(name: &str) {
+    poller::invalidate_matching(|cmd| match parse_command(cmd) {
+        Some(DockerCommand::Status(n)) | Some(DockerCommand::Cpu(n)) => n == name,
+        _ => false,
+    });
+}
+
+// Execute a Docker action command (runs in background)
+pub fn execute_command(cmd: &str) {
+    let parsed = match parse_command(cmd) {
+        Some(p) => p,
+        None => return,
+    };
+
+    thread::spawn(move || {
+        let result = match &parsed {
+            DockerCommand::Status(name) => {
+                // Toggle: stop if running, start otherwise
+                container_state(name).and_then(|state| {
+                    let op = if state == "running" || state == "restarting" {
+                        ContainerOp::Stop
+                    } else {
+                        ContainerOp::Start
+                    };
+                    debug!("Docker {} {}", op.endpoint(), name);
+                    container_op(op, name)
+                })
+            }
+            DockerCommand::Cpu(_) => Ok(()),
+            DockerCommand::Container(op, name) => {
+                debug!("Docker {} {}", op.endpoint(), name);
+                container_op(*op, name)
+            }
+            DockerCommand::Compose(op, project) => {
+                debug!("Docker compose {} {}", op.endpoint(), project);
+                compose_containers(project).and_then(|ids| {
+                    if ids.is_empty() {
+                        return Err(format!("No containers for compose project {}", project));
+                    }
+                    let errors: Vec<String> = ids
+                        .iter()
+                        .filter_map(|id| container_op(*op, id).err())
+                        .collect();
+                    if errors.is_empty() { Ok(()) } else { Err(errors.join("; ")) }
+                })
+            }
+        };
+
+        if let Err(e) = result {
+            warn!("Docker action error: {}", e);
+        }
+
+        match &parsed {
+            DockerCommand::Status(name) | DockerCommand::Container(_, name) => invalidate_widgets_for(name),
+            DockerCommand::Compose(..) => poller::invalidate_matching(is_docker_widget),
+            DockerCommand::Cpu(_) => {}
+        }
+    });
+}
+
+fn status_label(state: &str) -> &str {
+    match state {
+        "running" => "UP",
+        "exited" => "DOWN",
+        "created" => "CREATED",
+        "paused" => "PAUSED",
+        "restarting" => "RESTART",
+        "dead" => "DEAD",
+        "missing" => "N/A",
+        other => other,
+    }
+}
+
+// Widget text for Docker widgets (cached, refreshed in the background)
+pub fn get_widget_text(cmd: &str) -> String {
+    let text = match parse_command(cmd) {
+        Some(DockerCommand::Status(name)) => poller::poll(cmd, STATUS_CACHE_SECS, move || {
+            match container_state(&name) {
+                Ok(state) => status_label(&state).to_string(),
+                Err(e) => {
+                    warn!("Docker status error for {}: {}", name, e);
+                    "ERR".to_string()
+                }
+            }
+        }),
+        Some(DockerCommand::Cpu(name)) => poller::poll(cmd, CPU_CACHE_SECS, move || {
+            match container_cpu(&name) {
+                Ok(cpu) => format!("{:.0}%", cpu),
+                Err(e) => {
+                    warn!("Docker stats error for {}: {}", name, e);
+                    "ERR".to_string()
+                }
+            }
+        }),
+        _ => return String::new(),
+    };
+
+    text.unwrap_or_else(|| "...".to_string())
+}
@@ -0,0 +1,500 @@
+// ============================================================================
+// Elgato Stream Deck Plugin Compatibility
+// ============================================================================
+//
+// Runs unmodified Stream Deck plugins (SDK WebSocket protocol) against this
+// deck. Install them as <app data>/sdplugins/<uuid>.sdPlugin/ with their
+// manifest.json; the plugin is started the way the Stream Deck app does it:
+//
+//   <CodePathLin|CodePath> -port <n> -pluginUUID <uuid> -registerEvent registerPlugin -info <json>
+//
+// .js/.mjs/.cjs code paths run with node; HTML (browser) plugins are not
+// supported.
+//
+// Buttons use __SD_<action uuid>__, or __SD_<action uuid>#<name>__ for several
+// keys with the same action. The whole command is the context the plugin
+// talks to; its settings are kept in <app data>/sdplugins/settings.json.
+//
+// Host -> plugin: deviceDidConnect, willAppear, willDisappear, keyDown, keyUp,
+// didReceiveSettings, didReceiveGlobalSettings
+// Plugin -> host: setTitle, setImage (PNG/JPEG), setSettings, getSettings,
+// setGlobalSettings, getGlobalSettings, openUrl, logMessage, showAlert, showOk
+
+use std::collections::HashMap;
+use std::fs;
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Mutex, RwLock};
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::{debug, warn};
+
+use crate::{action_log, plugins, request_refresh, sandbox};
+
+const MANIFEST_FILE: &str = "manifest.json";
+const SETTINGS_FILE: &str = "settings.json";
+const DEVICE_ID: &str = "redragon-ss550";
+const COLUMNS: u8 = 5;
+const ROWS: u8 = 3;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Manifest {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    version: String,
+    #[serde(rename = "UUID", default)]
+    uuid: String,
+    #[serde(default)]
+    code_path: String,
+    #[serde(default)]
+    code_path_lin: String,
+    #[serde(default)]
+    actions: Vec<ManifestAction>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct ManifestAction {
+    #[serde(rename = "UUID")]
+    uuid: String,
+    #[serde(default)]
+    name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SdActionInfo {
+    pub uuid: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SdPluginInfo {
+    pub uuid: String,
+    pub name: String,
+    pub running: bool,
+    pub actions: Vec<SdActionInfo>,
+}
+
+struct SdPlugin {
+    info: SdPluginInfo,
+    child: Child,
+    sender: Sender<String>,
+    registered: bool,
+}
+
+// Title and image a plugin set for one context
+#[derive(Default)]
+struct KeyState {
+    title: Option<String>,
+    image: Option<Vec<u8>>,
+}
+
+// Persisted per-context and per-plugin settings
+#[derive(Default, Serialize, Deserialize)]
+struct Settings {
+    #[serde(default)]
+    actions: HashMap<String, Value>,
+    #[serde(default)]
+    global: HashMap<String, Value>,
+}
+
+lazy_static::lazy_static! {
+    static ref PLUGINS: Mutex<HashMap<String, SdPlugin>> = Mutex::new(HashMap::new());
+    static ref KEY_STATES: RwLock<HashMap<String, KeyState>> = RwLock::new(HashMap::new());
+    // Context -> key (1-15) of the __SD_ buttons on the current page
+    static ref VISIBLE: RwLock<HashMap<String, u8>> = RwLock::new(HashMap::new());
+    static ref SETTINGS: Mutex<Settings> = Mutex::new(Settings::default());
+    static ref SETTINGS_PATH: RwLock<Option<PathBuf>> = RwLock::new(None);
+}
+
+// __SD_<action uuid>[#<name>]__ -> action uuid
+fn parse_command(cmd: &str) -> Option<&str> {
+    let inner = cmd.strip_prefix("__SD_")?.strip_suffix("__")?;
+    let action = inner.split_once('#').map(|(a, _)| a).unwrap_or(inner);
+    if action.is_empty() {
+        None
+    } else {
+        Some(action)
+    }
+}
+
+pub fn is_sd_command(cmd: &str) -> bool {
+    parse_command(cmd).is_some()
+}
+
+// Keys the plugin set a title or image for
+pub fn is_sd_widget(cmd: &str) -> bool {
+    KEY_STATES.read().map(|states| states.contains_key(cmd)).unwrap_or(false)
+}
+
+pub fn get_widget_text(cmd: &str) -> Option<String> {
+    KEY_STATES.read().ok()?.get(cmd)?.title.clone()
+}
+
+pub fn get_key_image(cmd: &str) -> Option<Vec<u8>> {
+    KEY_STATES.read().ok()?.get(cmd)?.image.clone()
+}
+
+// Plugin that declares `action`; actions are usually named <plugin uuid>.<x>
+fn plugin_for_action(plugins: &HashMap<String, SdPlugin>, action: &str) -> Option<String> {
+    plugins
+        .values()
+        .find(|p| p.info.actions.iter().any(|a| a.uuid == action))
+        .or_else(|| plugins.values().find(|p| action.starts_with(&format!("{}.", p.info.uuid))))
+        .map(|p| p.info.uuid.clone())
+}
+
+fn send_to(plugin: &str, message: Value) {
+    if let Ok(plugins) = PLUGINS.lock() {
+        if let Some(p) = plugins.get(plugin).filter(|p| p.registered) {
+            p.sender.send(message.to_string()).ok();
+        }
+    }
+}
+
+// Send an event for a context to the plugin owning its action
+fn send_for_context(context: &str, message: Value) -> Result<(), String> {
+    let action = parse_command(context).ok_or_else(|| format!("Invalid Stream Deck command: {}", context))?;
+    let plugins = PLUGINS.lock().map_err(|e| e.to_string())?;
+    let uuid = plugin_for_action(&plugins, action).ok_or_else(|| format!("No Stream Deck plugin provides {}", action))?;
+    let plugin = &plugins[&uuid];
+    if !plugin.info.running || !plugin.registered {
+        return Err(format!("Stream Deck plugin {} is not running", uuid));
+    }
+    plugin
+        .sender
+        .send(message.to_string())
+        .map_err(|_| format!("Stream Deck plugin {} is not running", uuid))
+}
+
+fn action_settings(context: &str) -> Value {
+    SETTINGS
+        .lock()
+        .ok()
+        .and_then(|s| s.actions.get(context).cloned())
+        .unwrap_or_else(|| json!({}))
+}
+
+fn save_settings() {
+    let path = match SETTINGS_PATH.read().ok().and_then(|p| p.clone()) {
+        Some(p) => p,
+        None => return,
+    };
+    let content = match SETTINGS.lock() {
+        Ok(settings) => serde_json::to_string_pretty(&*settings).unwrap_or_default(),
+        Err(_) => return,
+    };
+    if let Err(e) = fs::write(&path, content) {
+        warn!("Failed to save Stream Deck settings: {}", e);
+    }
+}
+
+// Keys 1-5 are the bottom row of the deck, 11-15 the top row
+fn coordinates(key_id: u8) -> Value {
+    let index = key_id.saturating_sub(1);
+    json!({ "column": index % COLUMNS, "row": ROWS - 1 - index / COLUMNS })
+}
+
+// Event about one context, with the payload every key event carries
+fn key_event(event: &str, context: &str, key_id: u8) -> Value {
+    json!({
+        "event": event,
+        "action": parse_command(context).unwrap_or_default(),
+        "context": context,
+        "device": DEVICE_ID,
+        "payload": {
+            "settings": action_settings(context),
+            "coordinates": coordinates(key_id),
+            "controller": "Keypad",
+            "state": 0,
+            "isInMultiAction": false,
+        },
+    })
+}
+
+fn device_info() -> Value {
+    json!({
+        "id": DEVICE_ID,
+        "name": "Redragon SS-550",
+        "size": { "columns": COLUMNS, "rows": ROWS },
+        "type": 0,
+    })
+}
+
+// Tell plugins which of their keys appeared or disappeared with a page change
+pub fn page_shown<'a>(keys: impl Iterator<Item = (u8, &'a str)>) {
+    let shown: HashMap<String, u8> = keys
+        .filter(|(_, cmd)| is_sd_command(cmd))
+        .map(|(key, cmd)| (cmd.to_string(), key))
+        .collect();
+    let previous = match VISIBLE.write() {
+        Ok(mut visible) => std::mem::replace(&mut *visible, shown.clone()),
+        Err(_) => return,
+    };
+
+    for (context, key) in &previous {
+        if shown.get(context) != Some(key) {
+            send_for_context(context, key_event("willDisappear", context, *key)).ok();
+        }
+    }
+    for (context, key) in &shown {
+        if previous.get(context) != Some(key) {
+            send_for_context(context, key_event("willAppear", context, *key)).ok();
+        }
+    }
+}
+
+fn handle_message(plugin: &str, text: &str) {
+    let msg: Value = match serde_json::from_str(text) {
+        Ok(v) => v,
+        Err(e) => {
+            warn!("Stream Deck plugin {} sent invalid JSON: {}", plugin, e);
+            return;
+        }
+    };
+    let field = |name: &str| msg.get(name).and_then(|v| v.as_str()).unwrap_or("").to_string();
+    let payload = msg.get("payload").cloned().unwrap_or_else(|| json!({}));
+    let context = field("context");
+
+    match field("event").as_str() {
+        "registerPlugin" => {
+            if let Ok(mut plugins) = PLUGINS.lock() {
+                if let Some(p) = plugins.get_mut(plugin) {
+                    p.registered = true;
+                }
+            }
+            debug!("Stream Deck plugin {} registered", plugin);
+            send_to(plugin, json!({ "event": "deviceDidConnect", "device": DEVICE_ID, "deviceInfo": device_info() }));
+            let visible: Vec<(String, u8)> = VISIBLE
+                .read()
+                .map(|v| v.iter().map(|(c, k)| (c.clone(), *k)).collect())
+                .unwrap_or_default();
+            for (context, key) in visible {
+                let owner = parse_command(&context)
+                    .and_then(|action| PLUGINS.lock().ok().and_then(|p| plugin_for_action(&p, action)));
+                if owner.as_deref() == Some(plugin) {
+                    send_to(plugin, key_event("willAppear", &context, key));
+                }
+            }
+        }
+        "setTitle" => {
+            // A missing title restores the button's own label
+            let title = payload.get("title").and_then(|t| t.as_str()).map(|t| t.to_string());
+            if let Ok(mut states) = KEY_STATES.write() {
+                states.entry(context).or_default().title = title;
+            }
+            request_refresh();
+        }
+        "setImage" => {
+            let image = payload.get("image").and_then(|i| i.as_str()).unwrap_or("");
+            let bytes = if image.is_empty() {
+                None
+            } else {
+                let data = image.split_once("base64,").map(|(_, d)| d).unwrap_or(image);
+                match STANDARD.decode(data.trim()) {
+                    Ok(bytes) => Some(bytes),
+                    Err(e) => {
+                        warn!("Stream Deck plugin {} sent an invalid image: {}", plugin, e);
+                        return;
+                    }
+                }
+            };
+            if let Ok(mut states) = KEY_STATES.write() {
+                states.entry(context).or_default().image = bytes;
+            }
+            request_refresh();
+        }
+        "setSettings" => {
+            if let Ok(mut settings) = SETTINGS.lock() {
+                settings.actions.insert(context, payload);
+            }
+            save_settings();
+        }
+        "getSettings" => {
+            let key = VISIBLE.read().ok().and_then(|v| v.get(&context).copied()).unwrap_or(1);
+            let mut event = key_event("didReceiveSettings", &context, key);
+            if let Some(payload) = event["payload"].as_object_mut() {
+                payload.retain(|k, _| k == "settings" || k == "coordinates" || k == "isInMultiAction");
+            }
+            send_to(plugin, event);
+        }
+        "setGlobalSettings" => {
+            if let Ok(mut settings) = SETTINGS.lock() {
+                settings.global.insert(plugin.to_string(), payload);
+            }
+            save_settings();
+        }
+        "getGlobalSettings" => {
+            let settings = SETTINGS
+                .lock()
+                .ok()
+                .and_then(|s| s.global.get(plugin).cloned())
+                .unwrap_or_else(|| json!({}));
+            send_to(plugin, json!({ "event": "didReceiveGlobalSettings", "payload": { "settings": settings } }));
+        }
+        "openUrl" => {
+            if let Some(url) = payload.get("url").and_then(|u| u.as_str()) {
+                sandbox::open_uri(url).ok();
+            }
+        }
+        "logMessage" => {
+            let message = payload.get("message").and_then(|m| m.as_str()).unwrap_or("");
+            debug!("[sdplugin {}] {}", plugin, message);
+        }
+        "showAlert" => action_log::record_error(&context, &format!("Stream Deck plugin {} reported an error", plugin)),
+        "showOk" => debug!("Stream Deck plugin {} reported OK for {}", plugin, context),
+        // Multi-state keys and the property inspector are not supported
+        "setState" | "sendToPropertyInspector" | "switchToProfile" => {}
+        other => debug!("Stream Deck plugin {} sent unknown event '{}'", plugin, other),
+    }
+}
+
+fn mark_stopped(plugin: &str) {
+    debug!("Stream Deck plugin {} disconnected", plugin);
+    if let Ok(mut plugins) = PLUGINS.lock() {
+        if let Some(p) = plugins.get_mut(plugin) {
+            p.info.running = false;
+        }
+    }
+}
+
+fn read_manifest(dir: &Path) -> Result<Manifest, String> {
+    let content = fs::read_to_string(dir.join(MANIFEST_FILE)).map_err(|e| e.to_string())?;
+    serde_json::from_str(&content).map_err(|e| e.to_string())
+}
+
+fn launch(dir: &Path, manifest: Manifest) -> Result<(), String> {
+    // The Stream Deck app identifies plugins by their directory name
+    let uuid = if manifest.uuid.is_empty() {
+        dir.file_name()
+            .and_then(|n| n.to_str())
+            .and_then(|n| n.strip_suffix(".sdPlugin"))
+            .unwrap_or_default()
+            .to_string()
+    } else {
+        manifest.uuid.clone()
+    };
+    if uuid.is_empty() {
+        return Err(format!("No plugin UUID for {}", dir.display()));
+    }
+
+    let code_path = if manifest.code_path_lin.is_empty() { &manifest.code_path } else { &manifest.code_path_lin };
+    if code_path.is_empty() {
+        return Err(format!("{} has no CodePath", uuid));
+    }
+    let extension = Path::new(code_path).extension().and_then(|e| e.to_str()).unwrap_or("");
+    let mut command = match extension {
+        "js" | "mjs" | "cjs" => {
+            let mut node = Command::new("node");
+            node.arg(code_path);
+            node
+        }
+        "html" | "htm" => return Err(format!("{} is an HTML plugin, which is not supported", uuid)),
+        _ => Command::new(dir.join(code_path)),
+    };
+
+    let listener = TcpListener::bind("127.0.0.1:0").map_err(|e| format!("Failed to listen: {}", e))?;
+    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+    let info = json!({
+        "application": { "language": "en", "platform": "linux", "version": "6.0.0" },
+        "plugin": { "uuid": uuid, "version": manifest.version },
+        "devicePixelRatio": 1,
+        "devices": [device_info()],
+    });
+    command
+        .args(["-port", &port.to_string(), "-pluginUUID", &uuid, "-registerEvent", "registerPlugin", "-info"])
+        .arg(info.to_string())
+        .current_dir(dir);
+    let child = command
+        .spawn()
+        .map_err(|e| format!("Failed to start Stream Deck plugin {}: {}", uuid, e))?;
+    debug!("Started Stream Deck plugin {} on port {}", uuid, port);
+
+    let (sender, receiver) = mpsc::channel();
+    let info = SdPluginInfo {
+        uuid: uuid.clone(),
+        name: if manifest.name.is_empty() { uuid.clone() } else { manifest.name.clone() },
+        running: true,
+        actions: manifest
+            .actions
+            .into_iter()
+            .map(|a| SdActionInfo { name: if a.name.is_empty() { a.uuid.clone() } else { a.name }, uuid: a.uuid })
+            .collect(),
+    };
+    PLUGINS
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(uuid.clone(), SdPlugin { info, child, sender, registered: false });
+
+    plugins::serve_websocket(&uuid, listener, receiver, handle_message, mark_stopped);
+    Ok(())
+}
+
+pub fn plugins_dir(config_path: &Path) -> PathBuf {
+    config_path.with_file_name("sdplugins")
+}
+
+// Launch every *.sdPlugin in the directory
+pub fn start_all(plugins_dir: &Path) {
+    let entries = match fs::read_dir(plugins_dir) {
+        Ok(e) => e,
+        Err(_) => return,
+    };
+
+    let settings_path = plugins_dir.join(SETTINGS_FILE);
+    let settings: Settings = fs::read_to_string(&settings_path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    if let Ok(mut s) = SETTINGS.lock() {
+        *s = settings;
+    }
+    if let Ok(mut path) = SETTINGS_PATH.write() {
+        *path = Some(settings_path);
+    }
+
+    let dirs = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_dir() && p.extension().map(|e| e == "sdPlugin").unwrap_or(false));
+    for dir in dirs {
+        let result = read_manifest(&dir).and_then(|manifest| launch(&dir, manifest));
+        if let Err(e) = result {
+            debug!("Skipping Stream Deck plugin in {}: {}", dir.display(), e);
+        }
+    }
+}
+
+pub fn stop_all() {
+    if let Ok(mut plugins) = PLUGINS.lock() {
+        for (_, mut plugin) in plugins.drain() {
+            plugin.child.kill().ok();
+            plugin.child.wait().ok();
+        }
+    }
+    if let Ok(mut states) = KEY_STATES.write() {
+        states.clear();
+    }
+}
+
+pub fn list() -> Vec<SdPluginInfo> {
+    let mut list: Vec<SdPluginInfo> = PLUGINS
+        .lock()
+        .map(|plugins| plugins.values().map(|p| p.info.clone()).collect())
+        .unwrap_or_default();
+    list.sort_by(|a, b| a.name.cmp(&b.name));
+    list
+}
+
+// keyDown followed by keyUp, like a tap on a real Stream Deck
+pub fn execute_command(cmd: &str) -> Result<(), String> {
+    let key = VISIBLE.read().ok().and_then(|v| v.get(cmd).copied()).unwrap_or(1);
+    send_for_context(cmd, key_event("keyDown", cmd, key))?;
+    send_for_context(cmd, key_event("keyUp", cmd, key))
+}
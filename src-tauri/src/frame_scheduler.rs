@@ -0,0 +1,136 @@
+// ============================================================================
+// Device Update Scheduler
+// ============================================================================
+//
+// Every key image for the deck (page loads, widget refreshes, alerts, the
+// screensaver, hold and confirm prompts) goes through one FrameScheduler
+// instead of straight to the 512-byte interrupt pipe. set_key_image only marks
+// the key dirty with its newest image; the images go out together as one frame
+// when the listener reads the next key event, at most `usb.maxFps` frames per
+// second. A key redrawn several times between two frames (an alert blinking,
+// a busy widget) is sent once with its last image, and keys are sent in the
+// order they became dirty, so one busy key cannot hold the others back.
+//
+// Clearing the screen drops the images still waiting; wake and raw packets go
+// to the device right away. Brightness changes fade from the level last sent
+// (see transitions.rs).
+
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use image::RgbImage;
+use tracing::{trace, warn};
+
+use crate::deck::{Deck, DeckEvent};
+use crate::{transitions, usb_settings};
+
+#[derive(Default)]
+struct Frames {
+    // Dirty keys with their newest image, oldest first
+    pending: Vec<(u8, RgbImage)>,
+    // Earliest start of the next frame
+    next: Option<Instant>,
+    // Last LIG level sent
+    brightness: Option<u8>,
+}
+
+pub struct FrameScheduler {
+    deck: Box<dyn Deck>,
+    frames: Mutex<Frames>,
+}
+
+fn frame_interval() -> Duration {
+    Duration::from_secs(1) / usb_settings().max_fps.max(1)
+}
+
+impl FrameScheduler {
+    pub fn new(deck: Box<dyn Deck>) -> Self {
+        FrameScheduler {
+            deck,
+            frames: Mutex::new(Frames::default()),
+        }
+    }
+}
+
+impl Deck for FrameScheduler {
+    fn wake(&self) -> Result<(), String> {
+        self.deck.wake()
+    }
+
+    fn clear(&self) -> Result<(), String> {
+        if let Ok(mut frames) = self.frames.lock() {
+            frames.pending.clear();
+        }
+        self.deck.clear()
+    }
+
+    fn set_brightness(&self, brightness: u8) -> Result<(), String> {
+        let from = self.frames.lock().ok().and_then(|mut frames| frames.brightness.replace(brightness));
+        let levels = match from {
+            Some(from) if from != brightness => transitions::fade_levels(from, brightness),
+            _ => vec![brightness],
+        };
+        for (i, level) in levels.into_iter().enumerate() {
+            if i > 0 {
+                thread::sleep(transitions::FADE_STEP);
+            }
+            self.deck.set_brightness(level)?;
+        }
+        Ok(())
+    }
+
+    fn set_key_image(&self, key_id: u8, image: &RgbImage) -> Result<(), String> {
+        let mut frames = self.frames.lock().map_err(|e| e.to_string())?;
+        match frames.pending.iter_mut().find(|(id, _)| *id == key_id) {
+            Some(pending) => pending.1 = image.clone(),
+            None => frames.pending.push((key_id, image.clone())),
+        }
+        Ok(())
+    }
+
+    fn read_event(&self) -> Result<Option<DeckEvent>, String> {
+        self.flush();
+        self.deck.read_event()
+    }
+
+    fn self_test(&self) -> Result<String, String> {
+        self.deck.self_test()
+    }
+
+    fn send_raw(&self, data: &[u8], use_prefix: bool) -> Result<(), String> {
+        self.deck.send_raw(data, use_prefix)
+    }
+
+    fn read_raw(&self, timeout: Duration) -> Result<Option<Vec<u8>>, String> {
+        self.deck.read_raw(timeout)
+    }
+
+    // Send the dirty keys, waiting for the frame slot if the last frame was
+    // too recent
+    fn flush(&self) {
+        let wait = match self.frames.lock() {
+            Ok(frames) if !frames.pending.is_empty() => {
+                frames.next.map(|next| next.saturating_duration_since(Instant::now())).unwrap_or_default()
+            }
+            _ => return,
+        };
+        if !wait.is_zero() {
+            thread::sleep(wait);
+        }
+
+        let images = match self.frames.lock() {
+            Ok(mut frames) => {
+                frames.next = Some(Instant::now() + frame_interval());
+                std::mem::take(&mut frames.pending)
+            }
+            Err(_) => return,
+        };
+        trace!("Frame with {} keys", images.len());
+        for (key_id, image) in &images {
+            if let Err(e) = self.deck.set_key_image(*key_id, image) {
+                warn!("Failed to set key {}: {}", key_id, e);
+            }
+        }
+    }
+}
@@ -0,0 +1,136 @@
+// ============================================================================
+// GitHub Notifications / Review Requests
+// ============================================================================
+//
+// Widgets:
+//   __GITHUB_NOTIFS__   - unread notifications count
+//   __GITHUB_REVIEWS__  - open pull requests waiting for my review
+//
+// The key flashes for a few seconds when the count goes up. Pressing it
+// opens the matching GitHub page.
+//
+// Environment variables:
+//   GITHUB_TOKEN - personal access token (notifications + repo read scopes)
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tracing::{debug, warn};
+
+use crate::{chrono_lite, poller, sandbox};
+
+const POLL_INTERVAL_SECS: u64 = 60;
+const FLASH_SECS: u64 = 10;
+const FLASH_COLOR: &str = "#e94560";
+
+// Last seen count and flash deadline per widget command
+lazy_static::lazy_static! {
+    static ref LAST_COUNTS: Mutex<HashMap<String, (u64, u64)>> = Mutex::new(HashMap::new());
+}
+
+pub fn is_github_command(cmd: &str) -> bool {
+    cmd == "__GITHUB_NOTIFS__" || cmd == "__GITHUB_REVIEWS__"
+}
+
+fn get_token() -> Result<String, String> {
+    match std::env::var("GITHUB_TOKEN") {
+        Ok(token) if !token.is_empty() => Ok(token),
+        _ => Err("GITHUB_TOKEN not configured".to_string()),
+    }
+}
+
+fn github_get(url: &str) -> Result<serde_json::Value, String> {
+    let token = get_token()?;
+    let client = reqwest::blocking::Client::builder()
+        .user_agent("RedragonStreamDeck/2.0")
+        .timeout(Duration::from_secs(10))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let response = client
+        .get(url)
+        .header("Accept", "application/vnd.github+json")
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .map_err(|e| format!("GitHub API error: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("GitHub API error: {}", response.status()));
+    }
+    response.json().map_err(|e| format!("Parse error: {}", e))
+}
+
+fn fetch_count(cmd: &str) -> Result<u64, String> {
+    if cmd == "__GITHUB_NOTIFS__" {
+        let data = github_get("https://api.github.com/notifications?per_page=100")?;
+        Ok(data.as_array().map(|a| a.len() as u64).unwrap_or(0))
+    } else {
+        let data = github_get(
+            "https://api.github.com/search/issues?q=is:pr+is:open+review-requested:@me&per_page=1",
+        )?;
+        Ok(data["total_count"].as_u64().unwrap_or(0))
+    }
+}
+
+// Remember the new count and start flashing if it went up
+fn track_count(cmd: &str, count: u64) {
+    if let Ok(mut counts) = LAST_COUNTS.lock() {
+        let flash_until = match counts.get(cmd) {
+            Some((previous, _)) if count > *previous => chrono_lite() + FLASH_SECS,
+            Some((_, until)) => *until,
+            None => 0,
+        };
+        counts.insert(cmd.to_string(), (count, flash_until));
+    }
+}
+
+fn poll_count(cmd: &str) -> Option<Result<u64, String>> {
+    let key = cmd.to_string();
+    poller::poll(cmd, POLL_INTERVAL_SECS, move || {
+        let result = fetch_count(&key);
+        match &result {
+            Ok(count) => track_count(&key, *count),
+            Err(e) => warn!("GitHub widget error: {}", e),
+        }
+        result
+    })
+}
+
+pub fn get_widget_text(cmd: &str) -> String {
+    let suffix = if cmd == "__GITHUB_NOTIFS__" { "n" } else { "pr" };
+    match poll_count(cmd) {
+        Some(Ok(count)) if count >= 100 => format!("99+{}", suffix),
+        Some(Ok(count)) => format!("{}{}", count, suffix),
+        Some(Err(_)) => "GH ERR".to_string(),
+        None => "...".to_string(),
+    }
+}
+
+// Alternate the key color every second while a flash is active
+pub fn get_widget_color(cmd: &str) -> Option<String> {
+    let now = chrono_lite();
+    let flash_until = LAST_COUNTS.lock().ok()?.get(cmd).map(|(_, until)| *until)?;
+    if now < flash_until && now.is_multiple_of(2) {
+        Some(FLASH_COLOR.to_string())
+    } else {
+        None
+    }
+}
+
+// Open the GitHub page for the widget and stop flashing
+pub fn open_page(cmd: &str) {
+    if let Ok(mut counts) = LAST_COUNTS.lock() {
+        if let Some(entry) = counts.get_mut(cmd) {
+            entry.1 = 0;
+        }
+    }
+
+    let url = if cmd == "__GITHUB_NOTIFS__" {
+        "https://github.com/notifications"
+    } else {
+        "https://github.com/pulls/review-requested"
+    };
+    debug!("Opening {}", url);
+    sandbox::open_uri(url).ok();
+}
@@ -0,0 +1,87 @@
+// ============================================================================
+// Config Undo/Redo History
+// ============================================================================
+//
+// AppState::save_config records the config as it was before each edit, so
+// undo_config / redo_config can step back over a cleared page or an
+// overwritten button. Page changes alone (the listener moving currentPage)
+// are not edits and are never recorded or reverted.
+
+use std::collections::VecDeque;
+
+use crate::Config;
+
+const MAX_STEPS: usize = 50;
+
+pub struct ConfigHistory {
+    undo: VecDeque<Config>,
+    redo: Vec<Config>,
+    // The config as last saved
+    saved: Config,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct HistoryState {
+    pub undo: usize,
+    pub redo: usize,
+}
+
+// Config as JSON without the page shown on the deck
+fn content(config: &Config) -> serde_json::Value {
+    let mut value = serde_json::to_value(config).unwrap_or_default();
+    if let Some(object) = value.as_object_mut() {
+        object.remove("currentPage");
+    }
+    value
+}
+
+impl ConfigHistory {
+    pub fn new(config: &Config) -> Self {
+        ConfigHistory {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            saved: config.clone(),
+        }
+    }
+
+    // Called with every config about to be saved
+    pub fn record(&mut self, config: &Config) {
+        if content(config) == content(&self.saved) {
+            self.saved = config.clone();
+            return;
+        }
+        let previous = std::mem::replace(&mut self.saved, config.clone());
+        self.undo.push_back(previous);
+        if self.undo.len() > MAX_STEPS {
+            self.undo.pop_front();
+        }
+        self.redo.clear();
+    }
+
+    // The config to go back to; `current` becomes redoable
+    pub fn undo(&mut self, current: &Config) -> Option<Config> {
+        let target = self.undo.pop_back()?;
+        self.redo.push(current.clone());
+        Some(self.restore(target, current))
+    }
+
+    pub fn redo(&mut self, current: &Config) -> Option<Config> {
+        let target = self.redo.pop()?;
+        self.undo.push_back(current.clone());
+        Some(self.restore(target, current))
+    }
+
+    // Keep showing the current page where it still exists
+    fn restore(&mut self, mut target: Config, current: &Config) -> Config {
+        target.current_page = current.current_page.min(target.pages.len().saturating_sub(1));
+        self.saved = target.clone();
+        target
+    }
+
+    pub fn state(&self) -> HistoryState {
+        HistoryState {
+            undo: self.undo.len(),
+            redo: self.redo.len(),
+        }
+    }
+}
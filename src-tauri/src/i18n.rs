@@ -0,0 +1,316 @@
+// ============================================================================
+// Language of Widget Text and Presets
+// ============================================================================
+//
+// "language" in config.json: "es", "en" or "auto" (default), which follows
+// the system locale (LC_ALL, LC_MESSAGES, LANG): Spanish for es_*, English
+// otherwise. It picks the weekday names, the date order (day/month in
+// Spanish, month/day in English), the short texts widgets show ("DONE!",
+// "OBS OFF"...) and the labels and descriptions of get_preset_commands.
+//
+// Widget texts are looked up by id with tr(). The presets are written in
+// Spanish in lib.rs and preset() translates them by their Spanish text, so a
+// new preset without an entry here simply stays in Spanish.
+
+use serde::{Deserialize, Serialize};
+
+use crate::config_store;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    #[default]
+    Auto,
+    Es,
+    En,
+}
+
+// Id, Spanish, English
+const MESSAGES: &[(&str, &str, &str)] = &[
+    ("timer.done", "¡LISTO!", "DONE!"),
+    ("obs.off", "SIN OBS", "OBS OFF"),
+    ("obs.not_live", "NO LIVE", "NOT LIVE"),
+];
+
+const WEEKDAYS_ES: [&str; 7] = ["Dom", "Lun", "Mar", "Mié", "Jue", "Vie", "Sáb"];
+const WEEKDAYS_EN: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+
+// Spanish preset label or description, English
+const PRESETS: &[(&str, &str)] = &[
+    // Labels
+    ("Salida", "Output"),
+    ("Copiar", "Copy"),
+    ("Pegar", "Paste"),
+    ("Cortar", "Cut"),
+    ("Deshacer", "Undo"),
+    ("Rehacer", "Redo"),
+    ("Guardar", "Save"),
+    ("Buscar", "Find"),
+    ("Seleccionar todo", "Select all"),
+    ("Cerrar ventana", "Close window"),
+    ("Cambiar ventana", "Switch window"),
+    ("Pantalla completa", "Fullscreen"),
+    ("Saludo", "Greeting"),
+    ("Firma", "Signature"),
+    ("Abrir+Escribir", "Open+Type"),
+    ("Reloj", "Clock"),
+    ("Reloj+Seg", "Clock+Sec"),
+    ("Fecha", "Date"),
+    ("Fecha completa", "Full date"),
+    ("Reloj 12h", "Clock 12h"),
+    ("Fecha ISO", "ISO date"),
+    ("Semana", "Week"),
+    ("Día semana", "Weekday"),
+    ("Brillo ?", "Bright ?"),
+    ("Transición ?", "Transition ?"),
+    ("Sonido", "Sound"),
+    ("Aviso", "Notify"),
+    ("Escenas", "Scenes"),
+    ("Capítulo", "Chapter"),
+    ("OBS Salud", "OBS Health"),
+    ("OBS Abrir", "OBS Launch"),
+    ("Escena 1", "Scene 1"),
+    ("Escena Gaming", "Scene Gaming"),
+    ("Escena Webcam", "Scene Webcam"),
+    ("Escena BRB", "Scene BRB"),
+    ("Servicio", "Service"),
+    ("Servicio usuario", "User service"),
+    ("Contenedor", "Container"),
+    ("Contenedor CPU", "Container CPU"),
+    ("Reiniciar contenedor", "Restart container"),
+    ("Mover mouse", "Move mouse"),
+    ("Scroll arriba", "Scroll up"),
+    ("Scroll abajo", "Scroll down"),
+    ("Portapapeles", "Clipboard"),
+    ("Kube contexto", "Kube context"),
+    ("Kube cambiar", "Kube switch"),
+    ("Salida comando", "Command output"),
+    ("Captura", "Screenshot"),
+    ("Pantalla", "Screen"),
+    ("Grabar", "Record"),
+    ("Bloquear", "Lock"),
+    ("Suspender", "Suspend"),
+    ("Apagar", "Power off"),
+    ("Reiniciar", "Reboot"),
+    ("Brillo +", "Bright +"),
+    ("Brillo -", "Bright -"),
+    ("Noche", "Night"),
+    ("Ventana", "Window"),
+    ("Perfil", "Profile"),
+    ("Tarea", "Task"),
+    // Descriptions
+    ("Subir volumen", "Volume up"),
+    ("Bajar volumen", "Volume down"),
+    ("Silenciar/Activar audio", "Mute/unmute audio"),
+    ("Widget: salida de audio; presionar cambia a la siguiente", "Widget: audio output; press to switch to the next one"),
+    ("Sacar el audio por HDMI", "Play audio through HDMI"),
+    ("Reproducir/Pausar media", "Play/pause media"),
+    ("Siguiente pista", "Next track"),
+    ("Pista anterior", "Previous track"),
+    ("Navegador Firefox", "Firefox browser"),
+    ("Navegador Chrome/Chromium", "Chrome/Chromium browser"),
+    ("Administrador de archivos", "File manager"),
+    ("Abrir YouTube", "Open YouTube"),
+    ("Abrir Twitch", "Open Twitch"),
+    ("Abrir GitHub", "Open GitHub"),
+    ("Abrir Twitter/X", "Open Twitter/X"),
+    ("Abrir ChatGPT", "Open ChatGPT"),
+    ("Abrir Claude AI", "Open Claude AI"),
+    ("Ctrl+C - Copiar", "Ctrl+C - Copy"),
+    ("Ctrl+V - Pegar", "Ctrl+V - Paste"),
+    ("Ctrl+X - Cortar", "Ctrl+X - Cut"),
+    ("Ctrl+Z - Deshacer", "Ctrl+Z - Undo"),
+    ("Ctrl+Shift+Z - Rehacer", "Ctrl+Shift+Z - Redo"),
+    ("Ctrl+S - Guardar", "Ctrl+S - Save"),
+    ("Ctrl+F - Buscar", "Ctrl+F - Find"),
+    ("Ctrl+A - Seleccionar todo", "Ctrl+A - Select all"),
+    ("Alt+F4 - Cerrar ventana", "Alt+F4 - Close window"),
+    ("Alt+Tab - Cambiar ventana", "Alt+Tab - Switch window"),
+    ("F11 - Pantalla completa", "F11 - Fullscreen"),
+    ("Super+. - Selector de emojis", "Super+. - Emoji picker"),
+    ("Escribir email (editar)", "Type an email address (edit)"),
+    ("Escribir saludo", "Type a greeting"),
+    ("Escribir firma", "Type a signature"),
+    ("Abrir Firefox y escribir URL", "Open Firefox and type a URL"),
+    ("Copiar y pegar", "Copy and paste"),
+    ("Muestra hora actual (HH:MM)", "Shows the time (HH:MM)"),
+    ("Muestra hora con segundos", "Shows the time with seconds"),
+    ("Hora en formato 12 horas (editar formato strftime)", "12-hour time (edit the strftime format)"),
+    ("Fecha ISO 8601 (editar formato strftime)", "ISO 8601 date (edit the strftime format)"),
+    ("Número de semana ISO", "ISO week number"),
+    ("Muestra fecha (DD/MM)", "Shows the date"),
+    ("Muestra fecha completa", "Shows the full date"),
+    ("Muestra día de la semana", "Shows the weekday"),
+    ("Muestra uso de CPU", "Shows CPU usage"),
+    ("Muestra uso de RAM", "Shows RAM usage"),
+    ("Muestra temperatura CPU", "Shows CPU temperature"),
+    ("Muestra el tiempo desde el arranque", "Shows the time since boot"),
+    ("Muestra la carga media del último minuto", "Shows the one-minute load average"),
+    ("Actualizaciones pendientes; presionar abre la terminal para actualizar", "Pending updates; press to open a terminal that upgrades"),
+    ("Errores del journal en la última hora; presionar los muestra en la terminal", "Journal errors in the last hour; press to show them in a terminal"),
+    ("Proceso", "Process"),
+    ("Widget: proceso en marcha (verde, con CPU/RAM) o caído (rojo); presionar lo detiene o lo inicia (editar nombre)", "Widget: process running (green, with CPU/RAM) or down (red); press stops or starts it (edit name)"),
+    ("Temporizador 1 minuto", "1 minute timer"),
+    ("Temporizador 5 minutos", "5 minute timer"),
+    ("Temporizador 10 minutos", "10 minute timer"),
+    ("Temporizador 15 minutos", "15 minute timer"),
+    ("Temporizador 30 minutos", "30 minute timer"),
+    ("Temporizador: escribir los minutos en el deck", "Timer: type the minutes on the deck"),
+    ("Brillo: escribir el valor (0-100) en el deck", "Brightness: type the value (0-100) on the deck"),
+    ("OBS: escribir la duración de la transición (ms) en el deck", "OBS: type the transition duration (ms) on the deck"),
+    ("Silenciar/activar los sonidos", "Mute/unmute the sounds"),
+    ("Mostrar una notificación del escritorio", "Show a desktop notification"),
+    ("Iniciar/Detener streaming", "Start/stop streaming"),
+    ("Página con una tecla por escena de OBS", "Page with one key per OBS scene"),
+    ("Marcar un capítulo en la grabación", "Mark a chapter in the recording"),
+    ("Iniciar/Detener grabación", "Start/stop recording"),
+    ("Abrir OBS y esperar a su WebSocket", "Open OBS and wait for its WebSocket"),
+    ("Mutear/Desmutear micrófono", "Mute/unmute the microphone"),
+    ("Widget: muestra LIVE/REC", "Widget: shows LIVE/REC"),
+    ("Widget: frames perdidos y bitrate del stream", "Widget: dropped frames and stream bitrate"),
+    ("Cambiar a escena (editar nombre)", "Switch to a scene (edit name)"),
+    ("Cambiar a escena Gaming", "Switch to the Gaming scene"),
+    ("Cambiar a escena Webcam", "Switch to the Webcam scene"),
+    ("Cambiar a escena BRB", "Switch to the BRB scene"),
+    ("Widget: muestra viewers actuales", "Widget: shows current viewers"),
+    ("Widget: muestra total followers", "Widget: shows total followers"),
+    ("Crear clip del stream", "Create a clip of the stream"),
+    ("Comercial de 30 segundos", "30 second ad"),
+    ("Comercial de 60 segundos", "60 second ad"),
+    ("Comercial de 90 segundos", "90 second ad"),
+    ("Enviar mensaje al chat", "Send a chat message"),
+    ("Enviar BRB al chat", "Send BRB to the chat"),
+    (
+        "Widget: estado de servicio del sistema, toggle start/stop (editar nombre)",
+        "Widget: system service state, toggles start/stop (edit name)",
+    ),
+    (
+        "Widget: estado de servicio de usuario, toggle start/stop (editar nombre)",
+        "Widget: user service state, toggles start/stop (edit name)",
+    ),
+    ("Widget: estado del contenedor, toggle start/stop (editar nombre)", "Widget: container state, toggles start/stop (edit name)"),
+    ("Widget: uso de CPU del contenedor", "Widget: container CPU usage"),
+    ("Reiniciar contenedor (editar nombre)", "Restart a container (edit name)"),
+    ("Reiniciar proyecto docker compose", "Restart a docker compose project"),
+    ("Widget: estado del último workflow (editar repo)", "Widget: state of the last workflow (edit repo)"),
+    ("Widget: estado del último pipeline (editar proyecto)", "Widget: state of the last pipeline (edit project)"),
+    ("Widget: notificaciones sin leer", "Widget: unread notifications"),
+    ("Widget: PRs esperando tu review", "Widget: PRs waiting for your review"),
+    ("Click izquierdo (left/right/middle)", "Left click (left/right/middle)"),
+    ("Mover el puntero a x,y (editar posición)", "Move the pointer to x,y (edit position)"),
+    ("Rueda hacia arriba", "Scroll wheel up"),
+    ("Rueda hacia abajo 3 pasos", "Scroll wheel down 3 steps"),
+    ("Insertar snippet de la biblioteca (editar nombre)", "Insert a snippet from the library (edit name)"),
+    ("Página con el historial del portapapeles, presionar pega", "Page with the clipboard history, press to paste"),
+    ("Página con los hosts de ~/.ssh/config", "Page with the hosts in ~/.ssh/config"),
+    ("Ventanas", "Windows"),
+    ("Página con las ventanas abiertas; pulsar una la enfoca", "Page with the open windows; pressing one focuses it"),
+    ("Abrir terminal con ssh al host (editar nombre)", "Open a terminal with ssh to the host (edit name)"),
+    ("Widget: contexto actual, presionar cambia al siguiente", "Widget: current context, press to switch to the next one"),
+    ("Cambiar a un contexto (editar nombre)", "Switch to a context (edit name)"),
+    ("Widget: pods que no están Running (editar namespace)", "Widget: pods that are not Running (edit namespace)"),
+    ("Widget: salida de un comando cada N segundos (editar)", "Widget: output of a command every N seconds (edit)"),
+    ("Abrir Firefox o enfocar su ventana", "Open Firefox or focus its window"),
+    ("Captura de una región", "Screenshot of a region"),
+    ("Captura de pantalla", "Screenshot"),
+    ("Captura de pantalla completa", "Full screenshot"),
+    ("Iniciar/detener grabación de pantalla", "Start/stop screen recording"),
+    ("Tomar un color de la pantalla y copiar su código", "Pick a color on screen and copy its code"),
+    ("Bloquear la sesión", "Lock the session"),
+    ("Suspender el equipo", "Suspend the computer"),
+    ("Apagar el equipo (activar mantener para confirmar)", "Power off the computer (turn on hold to confirm)"),
+    ("Reiniciar el equipo (activar mantener para confirmar)", "Reboot the computer (turn on hold to confirm)"),
+    ("Widget: brillo del monitor", "Widget: monitor brightness"),
+    ("Subir brillo del monitor", "Monitor brightness up"),
+    ("Bajar brillo del monitor", "Monitor brightness down"),
+    ("Activar/desactivar luz nocturna", "Night light on/off"),
+    ("Widget: red Wi-Fi y señal", "Widget: Wi-Fi network and signal"),
+    ("Activar/desactivar Wi-Fi", "Wi-Fi on/off"),
+    ("Widget: cámara/micrófono en uso", "Widget: camera/microphone in use"),
+    ("Pantalla", "Screen"),
+    ("Widget: pantalla compartida (screencast del portal)", "Widget: screen being shared (portal screencast)"),
+    ("Widget: título de la ventana activa", "Widget: title of the active window"),
+    ("VLC: reproducir/pausar", "VLC: play/pause"),
+    ("Widget: posición en VLC", "Widget: VLC position"),
+    ("mpv: reproducir/pausar", "mpv: play/pause"),
+    ("mpv: adelantar 10 s", "mpv: forward 10 s"),
+    ("Widget: posición en mpv", "Widget: mpv position"),
+    ("Spotify/MPRIS: adelantar 10 s (mantener para seguir)", "Spotify/MPRIS: forward 10 s (hold to keep going)"),
+    ("Spotify/MPRIS: retroceder 10 s (mantener para seguir)", "Spotify/MPRIS: back 10 s (hold to keep going)"),
+    ("Spotify/MPRIS: subir volumen 5 %", "Spotify/MPRIS: volume up 5 %"),
+    ("MIDI: nota fija de la tecla (para MIDI learn)", "MIDI: the key's own note (for MIDI learn)"),
+    ("MIDI: control change 7 = 127 (editar)", "MIDI: control change 7 = 127 (edit)"),
+    ("Cambiar al perfil Principal (editar)", "Switch to the Principal profile (edit)"),
+    ("Activar/pausar la tarea programada backup (editar)", "Enable/pause the scheduled task backup (edit)"),
+    ("Ir a workspace 1", "Go to workspace 1"),
+    ("Ir a workspace 2", "Go to workspace 2"),
+    ("Ir a workspace 3", "Go to workspace 3"),
+    ("Ir a workspace 4", "Go to workspace 4"),
+    ("Ir a workspace 5", "Go to workspace 5"),
+    ("Bloquear pantalla", "Lock the screen"),
+    ("Suspender sistema", "Suspend the system"),
+    ("Siguiente página", "Next page"),
+    ("Página anterior", "Previous page"),
+    ("Ir a página principal", "Go to the home page"),
+    ("Activar con tecla F1", "Trigger with F1"),
+    ("Activar con Ctrl+F1", "Trigger with Ctrl+F1"),
+    ("Activar con Ctrl+Shift+1", "Trigger with Ctrl+Shift+1"),
+];
+
+fn system_language() -> Language {
+    let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default();
+    if locale.starts_with("es") {
+        Language::Es
+    } else {
+        Language::En
+    }
+}
+
+// The language in use, Es or En
+pub fn current() -> Language {
+    match config_store::read(|c| c.language).unwrap_or_default() {
+        Language::Auto => system_language(),
+        language => language,
+    }
+}
+
+// A widget text by id (see MESSAGES); the id itself if unknown
+pub fn tr(id: &'static str) -> &'static str {
+    let english = current() == Language::En;
+    MESSAGES
+        .iter()
+        .find(|(key, _, _)| *key == id)
+        .map(|(_, es, en)| if english { *en } else { *es })
+        .unwrap_or(id)
+}
+
+// Short weekday name, 0 = Sunday
+pub fn weekday(day: usize) -> &'static str {
+    let names = if current() == Language::En { WEEKDAYS_EN } else { WEEKDAYS_ES };
+    names[day % 7]
+}
+
+// strftime formats of __DATE__ and __DATE_FULL__
+pub fn date_formats() -> (&'static str, &'static str) {
+    if current() == Language::En {
+        ("%m/%d", "%m/%d/%Y")
+    } else {
+        ("%d/%m", "%d/%m/%Y")
+    }
+}
+
+// A preset label or description in the current language
+pub fn preset(spanish: String) -> String {
+    if current() != Language::En {
+        return spanish;
+    }
+    PRESETS
+        .iter()
+        .find(|(es, _)| *es == spanish)
+        .map(|(_, en)| en.to_string())
+        .unwrap_or(spanish)
+}
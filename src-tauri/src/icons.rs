@@ -0,0 +1,288 @@
+// ============================================================================
+// Icon Library
+// ============================================================================
+//
+// Icons are files in <app data>/icons referenced by name from ButtonConfig.icon
+// in every profile. Renaming rewrites those references, deleting refuses an
+// icon still in use unless forced (the buttons then lose their icon), dedupe
+// keeps one copy of identical files (by SHA-256) and points the buttons at it,
+// and orphans lists files no button shows.
+//
+// Uploads from the UI (dropped or pasted images) arrive as base64 and are
+// stored as PNG no larger than a key (save_data). render_emoji draws an emoji
+// from a color emoji font (Noto Color Emoji) onto the button color; only
+// single-codepoint emoji, since ZWJ sequences and flags need text shaping.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use ab_glyph::{Font, FontVec, GlyphImageFormat};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use image::imageops::{self, FilterType};
+use image::{ImageBuffer, Rgba};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tracing::debug;
+
+use crate::{chrono_lite, parse_hex_color, ButtonConfig, Config, BUTTON_SIZE};
+
+const EXTENSIONS: [&str; 3] = ["png", "jpg", "jpeg"];
+
+// Where distributions install Noto Color Emoji, if fontconfig can't say
+const EMOJI_FONTS: [&str; 4] = [
+    "/usr/share/fonts/noto/NotoColorEmoji.ttf",
+    "/usr/share/fonts/truetype/noto/NotoColorEmoji.ttf",
+    "/usr/share/fonts/google-noto-emoji/NotoColorEmoji.ttf",
+    "/usr/share/fonts/noto-emoji/NotoColorEmoji.ttf",
+];
+// Share of the key the emoji fills
+const EMOJI_SCALE: f32 = 0.8;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IconUsage {
+    pub profile: String,
+    pub page: usize,
+    #[serde(rename = "pageName")]
+    pub page_name: String,
+    pub key: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DedupeResult {
+    // Removed file -> the identical file kept
+    pub merged: HashMap<String, String>,
+    #[serde(rename = "bytesFreed")]
+    pub bytes_freed: u64,
+}
+
+// Icon file names, sorted
+pub fn list(icons_path: &Path) -> Vec<String> {
+    let mut icons: Vec<String> = fs::read_dir(icons_path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|e| e.file_name().to_str().map(str::to_string))
+                .filter(|name| {
+                    Path::new(name)
+                        .extension()
+                        .and_then(|e| e.to_str())
+                        .map(|e| EXTENSIONS.contains(&e.to_lowercase().as_str()))
+                        .unwrap_or(false)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    icons.sort();
+    icons
+}
+
+// Plain file name, nothing that could leave the icons directory
+fn check_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(format!("Invalid icon name: {}", name));
+    }
+    Ok(())
+}
+
+// Every button of every profile
+fn buttons_mut(config: &mut Config) -> impl Iterator<Item = &mut ButtonConfig> {
+    config
+        .pages
+        .iter_mut()
+        .chain(config.profiles.iter_mut().flat_map(|p| p.pages.iter_mut()))
+        .flat_map(|p| p.buttons.values_mut())
+}
+
+pub fn usage(config: &Config, icon: &str) -> Vec<IconUsage> {
+    let profiles = std::iter::once((&config.profile, &config.pages))
+        .chain(config.profiles.iter().map(|p| (&p.name, &p.pages)));
+    let mut found = Vec::new();
+    for (profile, pages) in profiles {
+        for (index, page) in pages.iter().enumerate() {
+            for (key, button) in &page.buttons {
+                if button.icon == icon {
+                    found.push(IconUsage {
+                        profile: profile.clone(),
+                        page: index,
+                        page_name: page.name.clone(),
+                        key: key.clone(),
+                    });
+                }
+            }
+        }
+    }
+    found.sort_by(|a, b| (&a.profile, a.page, &a.key).cmp(&(&b.profile, b.page, &b.key)));
+    found
+}
+
+pub fn in_use(config: &Config, icon: &str) -> bool {
+    !usage(config, icon).is_empty()
+}
+
+// Rename the file and the buttons showing it; returns how many buttons changed
+pub fn rename(config: &mut Config, icons_path: &Path, old: &str, new: &str) -> Result<usize, String> {
+    check_name(old)?;
+    check_name(new)?;
+    if old == new {
+        return Ok(0);
+    }
+    let target = icons_path.join(new);
+    if target.exists() {
+        return Err(format!("Icon {} already exists", new));
+    }
+    fs::rename(icons_path.join(old), &target).map_err(|e| format!("Failed to rename {}: {}", old, e))?;
+
+    let mut changed = 0;
+    for button in buttons_mut(config).filter(|b| b.icon == old) {
+        button.icon = new.to_string();
+        changed += 1;
+    }
+    Ok(changed)
+}
+
+// Delete an icon. One still in use is refused unless `force`, which also
+// removes it from the buttons. Returns the buttons that lost it.
+pub fn delete(config: &mut Config, icons_path: &Path, name: &str, force: bool) -> Result<Vec<IconUsage>, String> {
+    check_name(name)?;
+    let users = usage(config, name);
+    if !users.is_empty() && !force {
+        return Err(format!("Icon {} is used by {} button(s)", name, users.len()));
+    }
+    fs::remove_file(icons_path.join(name)).map_err(|e| format!("Failed to delete {}: {}", name, e))?;
+    for button in buttons_mut(config).filter(|b| b.icon == name) {
+        button.icon.clear();
+    }
+    Ok(users)
+}
+
+// Keep one file per content, preferring one that is already used
+pub fn dedupe(config: &mut Config, icons_path: &Path) -> DedupeResult {
+    let mut by_hash: HashMap<Vec<u8>, Vec<(String, u64)>> = HashMap::new();
+    for name in list(icons_path) {
+        if let Ok(data) = fs::read(icons_path.join(&name)) {
+            let hash = Sha256::digest(&data).to_vec();
+            by_hash.entry(hash).or_default().push((name, data.len() as u64));
+        }
+    }
+
+    let mut result = DedupeResult {
+        merged: HashMap::new(),
+        bytes_freed: 0,
+    };
+    for mut copies in by_hash.into_values().filter(|c| c.len() > 1) {
+        copies.sort_by_key(|(name, _)| (!in_use(config, name), name.clone()));
+        let (kept, _) = copies.remove(0);
+        for (name, size) in copies {
+            if fs::remove_file(icons_path.join(&name)).is_err() {
+                continue;
+            }
+            for button in buttons_mut(config).filter(|b| b.icon == name) {
+                button.icon = kept.clone();
+            }
+            result.bytes_freed += size;
+            result.merged.insert(name, kept.clone());
+        }
+    }
+    debug!("Icon dedupe removed {} file(s)", result.merged.len());
+    result
+}
+
+// Files no button in any profile shows
+pub fn orphans(config: &Config, icons_path: &Path) -> Vec<String> {
+    list(icons_path).into_iter().filter(|name| !in_use(config, name)).collect()
+}
+
+// Save an uploaded image (base64, optionally a data: URL) as <name>.png,
+// scaled down to fit a key. A taken name gets -2, -3, ...; returns the file name.
+pub fn save_data(icons_path: &Path, name: &str, data: &str) -> Result<String, String> {
+    let encoded = match data.split_once(";base64,") {
+        Some((_, encoded)) => encoded,
+        None => data,
+    };
+    let bytes = STANDARD.decode(encoded.trim()).map_err(|e| format!("Invalid base64: {}", e))?;
+    let mut img = image::load_from_memory(&bytes).map_err(|e| format!("Unsupported image: {}", e))?;
+    if img.width() > BUTTON_SIZE || img.height() > BUTTON_SIZE {
+        img = img.resize(BUTTON_SIZE, BUTTON_SIZE, FilterType::Lanczos3);
+    }
+
+    let stem = Path::new(name.trim())
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("custom_{}", chrono_lite()));
+    check_name(&stem)?;
+    let mut file_name = format!("{}.png", stem);
+    let mut n = 2;
+    while icons_path.join(&file_name).exists() {
+        file_name = format!("{}-{}.png", stem, n);
+        n += 1;
+    }
+
+    fs::create_dir_all(icons_path).ok();
+    img.save_with_format(icons_path.join(&file_name), image::ImageFormat::Png)
+        .map_err(|e| format!("Failed to save icon: {}", e))?;
+    debug!("Saved uploaded icon {} ({}x{})", file_name, img.width(), img.height());
+    Ok(file_name)
+}
+
+pub fn emoji_font_path() -> Option<String> {
+    let from_fontconfig = Command::new("fc-match")
+        .args(["-f", "%{file}", "Noto Color Emoji"])
+        .output()
+        .ok()
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .filter(|path| path.contains("Emoji"));
+    from_fontconfig
+        .into_iter()
+        .chain(EMOJI_FONTS.iter().map(|p| p.to_string()))
+        .find(|p| Path::new(p).exists())
+}
+
+fn emoji_font() -> Result<FontVec, String> {
+    let path = emoji_font_path().ok_or("No color emoji font found (install Noto Color Emoji)")?;
+    let data = fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    FontVec::try_from_vec(data).map_err(|e| format!("Invalid font {}: {}", path, e))
+}
+
+// Draw `emoji` centered on `background` and save it as an icon; returns the file name
+pub fn render_emoji(icons_path: &Path, emoji: &str, background: &str) -> Result<String, String> {
+    // Variation selectors only ask for the emoji style, which is all we draw
+    let mut chars = emoji.trim().chars().filter(|c| !matches!(c, '\u{fe0e}' | '\u{fe0f}'));
+    let c = match (chars.next(), chars.next()) {
+        (Some(c), None) => c,
+        (None, _) => return Err("No emoji given".to_string()),
+        _ => return Err(format!("Only single emoji are supported: {}", emoji)),
+    };
+    let (r, g, b) = parse_hex_color(background);
+    let file_name = format!("emoji_{:x}_{:02x}{:02x}{:02x}.png", c as u32, r, g, b);
+    if icons_path.join(&file_name).exists() {
+        return Ok(file_name);
+    }
+
+    let font = emoji_font()?;
+    let glyph = font.glyph_id(c);
+    if glyph.0 == 0 {
+        return Err(format!("The emoji font has no {}", emoji));
+    }
+    let raster = font
+        .glyph_raster_image2(glyph, u16::MAX)
+        .filter(|r| matches!(r.format, GlyphImageFormat::Png))
+        .ok_or_else(|| format!("The emoji font has no color image for {}", emoji))?;
+    let drawn = image::load_from_memory(raster.data).map_err(|e| format!("Bad emoji image: {}", e))?;
+
+    let size = (BUTTON_SIZE as f32 * EMOJI_SCALE) as u32;
+    let drawn = drawn.resize(size, size, FilterType::Lanczos3).to_rgba8();
+    let mut img = ImageBuffer::from_pixel(BUTTON_SIZE, BUTTON_SIZE, Rgba([r, g, b, 255]));
+    let x = (BUTTON_SIZE - drawn.width()) / 2;
+    let y = (BUTTON_SIZE - drawn.height()) / 2;
+    imageops::overlay(&mut img, &drawn, x as i64, y as i64);
+
+    fs::create_dir_all(icons_path).ok();
+    img.save_with_format(icons_path.join(&file_name), image::ImageFormat::Png)
+        .map_err(|e| format!("Failed to save icon: {}", e))?;
+    debug!("Rendered emoji icon {}", file_name);
+    Ok(file_name)
+}
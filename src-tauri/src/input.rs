@@ -0,0 +1,675 @@
+// ============================================================================
+// Input Backends (key combos and text typing)
+// ============================================================================
+//
+// __KEY_, __TYPE_ and everything else that injects input goes through
+// `press_keys` / `type_text`, which dispatch to the selected backend:
+//   ydotool - works on Wayland and X11, needs the ydotoold daemon
+//   x11     - XTest for key combos and xdotool for text, X11 sessions only
+//   uinput  - virtual keyboard created by the app, needs write access to
+//             /dev/uinput (see `setup_uinput_rules`)
+//   auto    - x11 on X11 sessions, uinput if accessible, ydotool otherwise
+//
+// Key names are resolved with `key_name_to_code` (Linux input event codes);
+// X11 keycodes are the same codes offset by 8.
+//
+// Mouse actions (__MOUSE_CLICK_left__, __MOUSE_MOVE_x,y__, __SCROLL_up__) use
+// the same backends. A scroll sends at most MAX_SCROLL wheel steps.
+//
+// ydotool and uinput type by key code, which only works for ASCII on a US
+// layout. Text with other characters is typed with wtype on Wayland, or
+// pasted through the clipboard when wtype is not installed.
+
+use std::fs::OpenOptions;
+use std::sync::{Mutex, RwLock};
+use std::thread;
+use std::time::Duration;
+
+use evdev::uinput::VirtualDevice;
+use evdev::{AttributeSet, EventType, InputEvent, KeyCode, RelativeAxisCode};
+use serde::{Deserialize, Serialize};
+use x11rb::connection::Connection;
+use x11rb::protocol::xproto::{
+    ConnectionExt as _, BUTTON_PRESS_EVENT, BUTTON_RELEASE_EVENT, KEY_PRESS_EVENT, KEY_RELEASE_EVENT,
+    MOTION_NOTIFY_EVENT,
+};
+use x11rb::rust_connection::RustConnection;
+use x11rb::protocol::xtest::ConnectionExt as _;
+use tracing::{debug, warn};
+
+use crate::session::{self, SessionType};
+use crate::{clipboard, key_name_to_code, sandbox};
+
+// Offset between evdev key codes and X11 keycodes
+const X11_KEYCODE_OFFSET: u16 = 8;
+
+const UINPUT_PATH: &str = "/dev/uinput";
+const KEY_LEFTSHIFT: u16 = 42;
+// Delay between key events when typing through uinput
+const UINPUT_KEY_DELAY: Duration = Duration::from_millis(5);
+// Relative motion large enough to pin the pointer to the top-left corner
+const POINTER_RESET_DISTANCE: i32 = -100_000;
+// Most wheel steps one scroll action sends
+const MAX_SCROLL: u32 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+    #[default]
+    Auto,
+    Ydotool,
+    X11,
+    Uinput,
+}
+
+impl BackendKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BackendKind::Auto => "auto",
+            BackendKind::Ydotool => "ydotool",
+            BackendKind::X11 => "x11",
+            BackendKind::Uinput => "uinput",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<BackendKind> {
+        match name.to_lowercase().as_str() {
+            "auto" => Some(BackendKind::Auto),
+            "ydotool" => Some(BackendKind::Ydotool),
+            "x11" | "xdotool" | "xtest" => Some(BackendKind::X11),
+            "uinput" => Some(BackendKind::Uinput),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+}
+
+impl MouseButton {
+    fn as_str(self) -> &'static str {
+        match self {
+            MouseButton::Left => "left",
+            MouseButton::Right => "right",
+            MouseButton::Middle => "middle",
+        }
+    }
+
+    fn x11_button(self) -> u8 {
+        match self {
+            MouseButton::Left => 1,
+            MouseButton::Middle => 2,
+            MouseButton::Right => 3,
+        }
+    }
+
+    fn evdev_code(self) -> u16 {
+        match self {
+            MouseButton::Left => 0x110,
+            MouseButton::Right => 0x111,
+            MouseButton::Middle => 0x112,
+        }
+    }
+
+    // ydotool click code: 0xC0 = press + release of button 0
+    fn ydotool_code(self) -> &'static str {
+        match self {
+            MouseButton::Left => "0xC0",
+            MouseButton::Right => "0xC1",
+            MouseButton::Middle => "0xC2",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScrollDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl ScrollDirection {
+    fn as_str(self) -> &'static str {
+        match self {
+            ScrollDirection::Up => "up",
+            ScrollDirection::Down => "down",
+            ScrollDirection::Left => "left",
+            ScrollDirection::Right => "right",
+        }
+    }
+
+    // (horizontal, vertical) wheel steps, positive = right / up
+    fn wheel_delta(self, amount: u32) -> (i32, i32) {
+        let amount = amount as i32;
+        match self {
+            ScrollDirection::Up => (0, amount),
+            ScrollDirection::Down => (0, -amount),
+            ScrollDirection::Left => (-amount, 0),
+            ScrollDirection::Right => (amount, 0),
+        }
+    }
+
+    fn x11_button(self) -> u8 {
+        match self {
+            ScrollDirection::Up => 4,
+            ScrollDirection::Down => 5,
+            ScrollDirection::Left => 6,
+            ScrollDirection::Right => 7,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum MouseAction {
+    Click { button: MouseButton },
+    // Absolute position in screen pixels
+    Move { x: i32, y: i32 },
+    Scroll { direction: ScrollDirection, amount: u32 },
+}
+
+impl MouseAction {
+    // Inverse of `parse_mouse_command`
+    pub fn to_command(self) -> String {
+        match self {
+            MouseAction::Click { button } => format!("__MOUSE_CLICK_{}__", button.as_str()),
+            MouseAction::Move { x, y } => format!("__MOUSE_MOVE_{},{}__", x, y),
+            MouseAction::Scroll { direction, amount: 1 } => format!("__SCROLL_{}__", direction.as_str()),
+            MouseAction::Scroll { direction, amount } => format!("__SCROLL_{}_{}__", direction.as_str(), amount),
+        }
+    }
+}
+
+trait InputBackend {
+    // Press the keys in order, then release them in reverse order
+    fn key_combo(&self, codes: &[u16]) -> Result<(), String>;
+    fn type_text(&self, text: &str) -> Result<(), String>;
+
+    // Whether `type_text` handles any UTF-8 text, not just ASCII
+    fn types_unicode(&self) -> bool {
+        false
+    }
+
+    fn mouse(&self, action: MouseAction) -> Result<(), String>;
+}
+
+struct YdotoolBackend;
+
+impl InputBackend for YdotoolBackend {
+    fn key_combo(&self, codes: &[u16]) -> Result<(), String> {
+        let mut events: Vec<String> = codes.iter().map(|c| format!("{}:1", c)).collect();
+        events.extend(codes.iter().rev().map(|c| format!("{}:0", c)));
+        debug!("ydotool key {}", events.join(" "));
+
+        sandbox::host_command("ydotool")
+            .arg("key")
+            .args(events)
+            .status()
+            .map_err(|e| format!("Failed to run ydotool: {}", e))?;
+        Ok(())
+    }
+
+    fn type_text(&self, text: &str) -> Result<(), String> {
+        sandbox::host_command("ydotool")
+            .args(["type", "--clearmodifiers", "--", text])
+            .status()
+            .map_err(|e| format!("Failed to run ydotool: {}", e))?;
+        Ok(())
+    }
+
+    fn mouse(&self, action: MouseAction) -> Result<(), String> {
+        let args: Vec<String> = match action {
+            MouseAction::Click { button } => vec!["click".to_string(), button.ydotool_code().to_string()],
+            MouseAction::Move { x, y } => vec![
+                "mousemove".to_string(),
+                "--absolute".to_string(),
+                "-x".to_string(),
+                x.to_string(),
+                "-y".to_string(),
+                y.to_string(),
+            ],
+            MouseAction::Scroll { direction, amount } => {
+                let (dx, dy) = direction.wheel_delta(amount);
+                vec![
+                    "mousemove".to_string(),
+                    "--wheel".to_string(),
+                    "-x".to_string(),
+                    dx.to_string(),
+                    "-y".to_string(),
+                    dy.to_string(),
+                ]
+            }
+        };
+        sandbox::host_command("ydotool")
+            .args(args)
+            .status()
+            .map_err(|e| format!("Failed to run ydotool: {}", e))?;
+        Ok(())
+    }
+}
+
+struct X11Backend;
+
+impl X11Backend {
+    // Send XTest events (type, detail, x, y) and wait until they are processed
+    fn fake_events(events: &[(u8, u8, i16, i16)]) -> Result<(), String> {
+        let (conn, screen_num): (RustConnection, usize) = x11rb::connect(None)
+            .map_err(|e| format!("X11 connection failed: {}", e))?;
+        let root = conn.setup().roots[screen_num].root;
+
+        for (event, detail, x, y) in events {
+            conn.xtest_fake_input(*event, *detail, x11rb::CURRENT_TIME, root, *x, *y, 0)
+                .map_err(|e| format!("XTest error: {}", e))?;
+        }
+
+        // Round trip so the events are processed before the connection closes
+        conn.get_input_focus()
+            .map_err(|e| format!("X11 error: {}", e))?
+            .reply()
+            .map_err(|e| format!("X11 error: {}", e))?;
+        Ok(())
+    }
+}
+
+impl InputBackend for X11Backend {
+    fn key_combo(&self, codes: &[u16]) -> Result<(), String> {
+        let keycode = |code: &u16| (code + X11_KEYCODE_OFFSET) as u8;
+        let mut events: Vec<(u8, u8, i16, i16)> =
+            codes.iter().map(|c| (KEY_PRESS_EVENT, keycode(c), 0, 0)).collect();
+        events.extend(codes.iter().rev().map(|c| (KEY_RELEASE_EVENT, keycode(c), 0, 0)));
+        Self::fake_events(&events)
+    }
+
+    fn type_text(&self, text: &str) -> Result<(), String> {
+        sandbox::host_command("xdotool")
+            .args(["type", "--clearmodifiers", "--", text])
+            .status()
+            .map_err(|e| format!("Failed to run xdotool: {}", e))?;
+        Ok(())
+    }
+
+    fn types_unicode(&self) -> bool {
+        true
+    }
+
+    fn mouse(&self, action: MouseAction) -> Result<(), String> {
+        match action {
+            MouseAction::Click { button } => Self::fake_events(&[
+                (BUTTON_PRESS_EVENT, button.x11_button(), 0, 0),
+                (BUTTON_RELEASE_EVENT, button.x11_button(), 0, 0),
+            ]),
+            // Detail 0 = absolute position on the root window
+            MouseAction::Move { x, y } => {
+                let (x16, y16) = i16::try_from(x)
+                    .ok()
+                    .zip(i16::try_from(y).ok())
+                    .ok_or_else(|| format!("Position {},{} is outside the X11 range", x, y))?;
+                Self::fake_events(&[(MOTION_NOTIFY_EVENT, 0, x16, y16)])
+            }
+            MouseAction::Scroll { direction, amount } => {
+                let button = direction.x11_button();
+                let events: Vec<(u8, u8, i16, i16)> = (0..amount)
+                    .flat_map(|_| [(BUTTON_PRESS_EVENT, button, 0, 0), (BUTTON_RELEASE_EVENT, button, 0, 0)])
+                    .collect();
+                Self::fake_events(&events)
+            }
+        }
+    }
+}
+
+// US layout: key code and whether shift is needed for an ASCII character
+fn char_to_key(c: char) -> Option<(u16, bool)> {
+    const LETTERS: &[u8; 26] = b"abcdefghijklmnopqrstuvwxyz";
+    const LETTER_CODES: [u16; 26] = [
+        30, 48, 46, 32, 18, 33, 34, 35, 23, 36, 37, 38, 50,
+        49, 24, 25, 16, 19, 31, 20, 22, 47, 17, 45, 21, 44,
+    ];
+    if c.is_ascii_alphabetic() {
+        let lower = c.to_ascii_lowercase() as u8;
+        let index = LETTERS.iter().position(|l| *l == lower)?;
+        return Some((LETTER_CODES[index], c.is_ascii_uppercase()));
+    }
+
+    let key = match c {
+        '1' => (2, false), '!' => (2, true),
+        '2' => (3, false), '@' => (3, true),
+        '3' => (4, false), '#' => (4, true),
+        '4' => (5, false), '$' => (5, true),
+        '5' => (6, false), '%' => (6, true),
+        '6' => (7, false), '^' => (7, true),
+        '7' => (8, false), '&' => (8, true),
+        '8' => (9, false), '*' => (9, true),
+        '9' => (10, false), '(' => (10, true),
+        '0' => (11, false), ')' => (11, true),
+        '-' => (12, false), '_' => (12, true),
+        '=' => (13, false), '+' => (13, true),
+        '[' => (26, false), '{' => (26, true),
+        ']' => (27, false), '}' => (27, true),
+        '\\' => (43, false), '|' => (43, true),
+        ';' => (39, false), ':' => (39, true),
+        '\'' => (40, false), '"' => (40, true),
+        '`' => (41, false), '~' => (41, true),
+        ',' => (51, false), '<' => (51, true),
+        '.' => (52, false), '>' => (52, true),
+        '/' => (53, false), '?' => (53, true),
+        ' ' => (57, false),
+        '\t' => (15, false),
+        '\n' => (28, false),
+        _ => return None,
+    };
+    Some(key)
+}
+
+struct UinputBackend;
+
+impl UinputBackend {
+    fn create_keyboard() -> Result<VirtualDevice, String> {
+        let mut keys = AttributeSet::<KeyCode>::new();
+        for code in 1..=248 {
+            keys.insert(KeyCode::new(code));
+        }
+        let device = VirtualDevice::builder()
+            .map_err(|e| format!("Cannot open {}: {}", UINPUT_PATH, e))?
+            .name("Redragon Stream Deck Keyboard")
+            .with_keys(&keys)
+            .map_err(|e| format!("uinput setup failed: {}", e))?
+            .build()
+            .map_err(|e| format!("uinput device creation failed: {}", e))?;
+
+        // Give the compositor time to pick up the new device
+        thread::sleep(Duration::from_millis(300));
+        Ok(device)
+    }
+
+    fn create_pointer() -> Result<VirtualDevice, String> {
+        let mut buttons = AttributeSet::<KeyCode>::new();
+        for button in [MouseButton::Left, MouseButton::Right, MouseButton::Middle] {
+            buttons.insert(KeyCode::new(button.evdev_code()));
+        }
+        let mut axes = AttributeSet::<RelativeAxisCode>::new();
+        for axis in [
+            RelativeAxisCode::REL_X,
+            RelativeAxisCode::REL_Y,
+            RelativeAxisCode::REL_WHEEL,
+            RelativeAxisCode::REL_HWHEEL,
+        ] {
+            axes.insert(axis);
+        }
+        let device = VirtualDevice::builder()
+            .map_err(|e| format!("Cannot open {}: {}", UINPUT_PATH, e))?
+            .name("Redragon Stream Deck Pointer")
+            .with_keys(&buttons)
+            .map_err(|e| format!("uinput setup failed: {}", e))?
+            .with_relative_axes(&axes)
+            .map_err(|e| format!("uinput setup failed: {}", e))?
+            .build()
+            .map_err(|e| format!("uinput device creation failed: {}", e))?;
+
+        thread::sleep(Duration::from_millis(300));
+        Ok(device)
+    }
+
+    // Run `f` with the shared virtual keyboard, creating it on first use
+    fn with_device<F>(f: F) -> Result<(), String>
+    where
+        F: FnOnce(&mut VirtualDevice) -> std::io::Result<()>,
+    {
+        Self::with_shared_device(&UINPUT_DEVICE, Self::create_keyboard, f)
+    }
+
+    fn with_shared_device<F>(
+        slot: &Mutex<Option<VirtualDevice>>,
+        create: fn() -> Result<VirtualDevice, String>,
+        f: F,
+    ) -> Result<(), String>
+    where
+        F: FnOnce(&mut VirtualDevice) -> std::io::Result<()>,
+    {
+        let mut device = slot.lock().map_err(|e| e.to_string())?;
+        if device.is_none() {
+            *device = Some(create()?);
+        }
+        let result = f(device.as_mut().ok_or("uinput device unavailable")?);
+        if result.is_err() {
+            // Recreate the device next time
+            *device = None;
+        }
+        result.map_err(|e| format!("uinput write failed: {}", e))
+    }
+}
+
+fn key_event(code: u16, value: i32) -> InputEvent {
+    InputEvent::new(EventType::KEY.0, code, value)
+}
+
+fn rel_event(axis: RelativeAxisCode, value: i32) -> InputEvent {
+    InputEvent::new(EventType::RELATIVE.0, axis.0, value)
+}
+
+impl InputBackend for UinputBackend {
+    fn key_combo(&self, codes: &[u16]) -> Result<(), String> {
+        Self::with_device(|device| {
+            let mut events: Vec<InputEvent> = codes.iter().map(|c| key_event(*c, 1)).collect();
+            device.emit(&events)?;
+            events = codes.iter().rev().map(|c| key_event(*c, 0)).collect();
+            device.emit(&events)
+        })
+    }
+
+    fn type_text(&self, text: &str) -> Result<(), String> {
+        Self::with_device(|device| {
+            for c in text.chars() {
+                let (code, shift) = match char_to_key(c) {
+                    Some(key) => key,
+                    None => {
+                        debug!("uinput can't type '{}', skipping", c);
+                        continue;
+                    }
+                };
+                if shift {
+                    device.emit(&[key_event(KEY_LEFTSHIFT, 1)])?;
+                }
+                device.emit(&[key_event(code, 1)])?;
+                device.emit(&[key_event(code, 0)])?;
+                if shift {
+                    device.emit(&[key_event(KEY_LEFTSHIFT, 0)])?;
+                }
+                thread::sleep(UINPUT_KEY_DELAY);
+            }
+            Ok(())
+        })
+    }
+
+    fn mouse(&self, action: MouseAction) -> Result<(), String> {
+        Self::with_shared_device(&UINPUT_POINTER, Self::create_pointer, |device| match action {
+            MouseAction::Click { button } => {
+                device.emit(&[key_event(button.evdev_code(), 1)])?;
+                device.emit(&[key_event(button.evdev_code(), 0)])
+            }
+            MouseAction::Move { x, y } => {
+                // uinput pointers are relative: pin to the top-left corner,
+                // then move by the target offset (exact with flat acceleration)
+                device.emit(&[
+                    rel_event(RelativeAxisCode::REL_X, POINTER_RESET_DISTANCE),
+                    rel_event(RelativeAxisCode::REL_Y, POINTER_RESET_DISTANCE),
+                ])?;
+                thread::sleep(Duration::from_millis(20));
+                device.emit(&[rel_event(RelativeAxisCode::REL_X, x), rel_event(RelativeAxisCode::REL_Y, y)])
+            }
+            MouseAction::Scroll { direction, amount } => {
+                let (dx, dy) = direction.wheel_delta(amount);
+                device.emit(&[
+                    rel_event(RelativeAxisCode::REL_HWHEEL, dx),
+                    rel_event(RelativeAxisCode::REL_WHEEL, dy),
+                ])
+            }
+        })
+    }
+}
+
+// Whether /dev/uinput can be opened for writing by this user
+pub fn uinput_accessible() -> bool {
+    OpenOptions::new().write(true).open(UINPUT_PATH).is_ok()
+}
+
+lazy_static::lazy_static! {
+    static ref SELECTED_BACKEND: RwLock<BackendKind> = RwLock::new(BackendKind::Auto);
+    static ref UINPUT_DEVICE: Mutex<Option<VirtualDevice>> = Mutex::new(None);
+    static ref UINPUT_POINTER: Mutex<Option<VirtualDevice>> = Mutex::new(None);
+}
+
+pub fn set_backend(kind: BackendKind) {
+    debug!("Input backend set to {}", kind.as_str());
+    if let Ok(mut selected) = SELECTED_BACKEND.write() {
+        *selected = kind;
+    }
+}
+
+pub fn selected_backend() -> BackendKind {
+    SELECTED_BACKEND.read().map(|k| *k).unwrap_or_default()
+}
+
+// Backend that `auto` resolves to right now
+pub fn active_backend() -> BackendKind {
+    match selected_backend() {
+        BackendKind::Auto if session::session_type() == SessionType::X11 => BackendKind::X11,
+        BackendKind::Auto if uinput_accessible() => BackendKind::Uinput,
+        BackendKind::Auto => BackendKind::Ydotool,
+        kind => kind,
+    }
+}
+
+fn backend() -> Box<dyn InputBackend> {
+    match active_backend() {
+        BackendKind::X11 => Box::new(X11Backend),
+        BackendKind::Uinput => Box::new(UinputBackend),
+        _ => Box::new(YdotoolBackend),
+    }
+}
+
+// Press a combo like "ctrl+shift+a" (blocking)
+pub fn press_keys(keys: &str) {
+    let codes: Vec<u16> = keys
+        .split('+')
+        .filter_map(|k| key_name_to_code(k.trim()))
+        .filter_map(|c| c.parse().ok())
+        .collect();
+    if codes.is_empty() {
+        debug!("No known keys in '{}'", keys);
+        return;
+    }
+    if let Err(e) = backend().key_combo(&codes) {
+        warn!("Key combo failed: {}", e);
+    }
+}
+
+fn command_exists(name: &str) -> bool {
+    sandbox::host_command("which")
+        .arg(name)
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+// Paste text through the clipboard, restoring the previous contents after
+fn paste_text(text: &str) -> Result<(), String> {
+    let previous = clipboard::read_clipboard();
+    clipboard::write_clipboard(text)?;
+    thread::sleep(Duration::from_millis(100));
+    press_keys("ctrl+v");
+
+    if let Some(previous) = previous {
+        // Wait for the target app to read the selection before restoring it
+        thread::sleep(Duration::from_millis(300));
+        clipboard::write_clipboard(&previous)?;
+    }
+    Ok(())
+}
+
+fn type_unicode(text: &str) -> Result<(), String> {
+    if session::is_wayland() && command_exists("wtype") {
+        let status = sandbox::host_command("wtype")
+            .args(["--", text])
+            .status()
+            .map_err(|e| format!("Failed to run wtype: {}", e))?;
+        if status.success() {
+            return Ok(());
+        }
+        warn!("wtype failed, pasting instead");
+    }
+    paste_text(text)
+}
+
+// Type a string as keyboard input (blocking)
+pub fn type_text(text: &str) {
+    let backend = backend();
+    let result = if text.is_ascii() || backend.types_unicode() {
+        backend.type_text(text)
+    } else {
+        type_unicode(text)
+    };
+    if let Err(e) = result {
+        warn!("Typing failed: {}", e);
+    }
+}
+
+// Parse __MOUSE_CLICK_<button>__, __MOUSE_MOVE_<x>,<y>__ and
+// __SCROLL_<direction>[_<amount>]__
+pub fn parse_mouse_command(cmd: &str) -> Option<MouseAction> {
+    let inner = cmd.strip_suffix("__")?;
+
+    if let Some(button) = inner.strip_prefix("__MOUSE_CLICK_") {
+        let button = match button.to_lowercase().as_str() {
+            "left" => MouseButton::Left,
+            "right" => MouseButton::Right,
+            "middle" => MouseButton::Middle,
+            _ => return None,
+        };
+        return Some(MouseAction::Click { button });
+    }
+
+    if let Some(position) = inner.strip_prefix("__MOUSE_MOVE_") {
+        let (x, y) = position.split_once(',')?;
+        return Some(MouseAction::Move {
+            x: x.trim().parse().ok()?,
+            y: y.trim().parse().ok()?,
+        });
+    }
+
+    if let Some(scroll) = inner.strip_prefix("__SCROLL_") {
+        let (direction, amount) = match scroll.split_once('_') {
+            Some((d, a)) => (d, a.parse::<u32>().ok()?.min(MAX_SCROLL)),
+            None => (scroll, 1),
+        };
+        let direction = match direction.to_lowercase().as_str() {
+            "up" => ScrollDirection::Up,
+            "down" => ScrollDirection::Down,
+            "left" => ScrollDirection::Left,
+            "right" => ScrollDirection::Right,
+            _ => return None,
+        };
+        return Some(MouseAction::Scroll { direction, amount });
+    }
+
+    None
+}
+
+// Run a mouse action (blocking)
+pub fn mouse(action: MouseAction) {
+    // Structured actions from config.json skip parse_mouse_command
+    let action = match action {
+        MouseAction::Scroll { direction, amount } => MouseAction::Scroll { direction, amount: amount.min(MAX_SCROLL) },
+        other => other,
+    };
+    debug!("Mouse action {:?}", action);
+    if let Err(e) = backend().mouse(action) {
+        warn!("Mouse action failed: {}", e);
+    }
+}
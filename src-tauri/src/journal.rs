@@ -0,0 +1,60 @@
+// ============================================================================
+// Journal Error Counter
+// ============================================================================
+//
+// Command format:
+//   __JOURNAL_ERRORS__  - entries of priority err or worse logged in the last
+//                         hour, red while there are any; pressing opens a
+//                         terminal following the errors
+//
+// Counted with `journalctl -p err --since "1 hour ago" -o json`, one entry per
+// line, in the background every minute. Without the systemd-journal (or adm,
+// wheel) group journalctl only shows the user's own journal.
+
+use std::process::Stdio;
+
+use tracing::debug;
+
+use crate::{poller, sandbox, ssh};
+
+pub const COMMAND: &str = "__JOURNAL_ERRORS__";
+
+const POLL_SECS: u64 = 60;
+const SINCE: &str = "1 hour ago";
+const ERROR_COLOR: &str = "#c0392b";
+
+fn count_errors() -> Option<usize> {
+    let output = sandbox::host_command("journalctl")
+        .args(["-p", "err", "--since", SINCE, "-q", "--no-pager", "-o", "json", "--output-fields=PRIORITY"])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        debug!("journalctl failed ({})", output.status);
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).lines().filter(|l| l.starts_with('{')).count())
+}
+
+pub fn get_widget_text() -> String {
+    match poller::poll(COMMAND, POLL_SECS, count_errors) {
+        Some(Some(count)) => format!("{} err", count),
+        Some(None) => "ERR".to_string(),
+        None => "...".to_string(),
+    }
+}
+
+pub fn get_widget_color() -> Option<String> {
+    let count = poller::peek::<Option<usize>>(COMMAND)??;
+    (count > 0).then(|| ERROR_COLOR.to_string())
+}
+
+pub fn execute_command() -> Result<(), String> {
+    let terminal = ssh::find_terminal().ok_or("No terminal emulator found (set $TERMINAL)")?;
+    debug!("Opening journal errors in {}", terminal);
+    sandbox::host_command(&terminal)
+        .args(ssh::terminal_args(&terminal, &["journalctl", "-p", "err", "--since", SINCE, "-f"]))
+        .spawn()
+        .map_err(|e| format!("Failed to launch {}: {}", terminal, e))?;
+    Ok(())
+}
@@ -0,0 +1,226 @@
+// ============================================================================
+// Kubernetes Integration (kube client)
+// ============================================================================
+//
+// Command formats:
+//   __KUBE_CONTEXT__          - widget: current kubeconfig context, press cycles to the next one
+//   __KUBE_CTX_<context>__    - switch the current context
+//   __KUBE_PODS_<namespace>__ - widget: pods in the namespace that are not Running, press refreshes
+//
+// Uses the same kubeconfig as kubectl (KUBECONFIG or ~/.kube/config), so a
+// context switch made here is visible to kubectl and vice versa. Errors are
+// shown on the key instead of a count.
+
+use std::path::PathBuf;
+use std::thread;
+
+use k8s_openapi::api::core::v1::Pod;
+use kube::api::{Api, ListParams};
+use kube::config::Kubeconfig;
+use kube::Client;
+use tracing::{debug, warn};
+
+use crate::poller;
+
+const CONTEXT_CACHE_SECS: u64 = 5;
+const PODS_CACHE_SECS: u64 = 15;
+
+// Longest context name that still fits on a key
+const MAX_LABEL_LEN: usize = 10;
+
+#[derive(Debug, Clone, PartialEq)]
+enum KubeCommand {
+    Context,
+    Switch(String),
+    Pods(String),
+}
+
+fn parse_command(cmd: &str) -> Option<KubeCommand> {
+    if cmd == "__KUBE_CONTEXT__" {
+        return Some(KubeCommand::Context);
+    }
+    let inner = cmd.strip_prefix("__KUBE_")?.strip_suffix("__")?;
+    if let Some(context) = inner.strip_prefix("CTX_") {
+        if !context.is_empty() {
+            return Some(KubeCommand::Switch(context.to_string()));
+        }
+    } else if let Some(namespace) = inner.strip_prefix("PODS_") {
+        if !namespace.is_empty() {
+            return Some(KubeCommand::Pods(namespace.to_string()));
+        }
+    }
+    None
+}
+
+pub fn is_kube_command(cmd: &str) -> bool {
+    parse_command(cmd).is_some()
+}
+
+pub fn is_kube_widget(cmd: &str) -> bool {
+    matches!(parse_command(cmd), Some(KubeCommand::Context) | Some(KubeCommand::Pods(_)))
+}
+
+// Short error label that fits on a key
+fn error_label(e: &kube::Error) -> String {
+    match e {
+        kube::Error::Api(resp) if resp.code == 401 || resp.code == 403 => "DENIED".to_string(),
+        kube::Error::Api(resp) if resp.code == 404 => "NO NS".to_string(),
+        kube::Error::Api(resp) => format!("API {}", resp.code),
+        kube::Error::InferConfig(_) => "NO CFG".to_string(),
+        kube::Error::Auth(_) => "AUTH".to_string(),
+        _ => "OFFLINE".to_string(),
+    }
+}
+
+fn read_kubeconfig() -> Result<Kubeconfig, String> {
+    Kubeconfig::read().map_err(|e| format!("Failed to read kubeconfig: {}", e))
+}
+
+// File that holds current-context (first KUBECONFIG entry, like kubectl)
+fn kubeconfig_path() -> Option<PathBuf> {
+    if let Ok(paths) = std::env::var("KUBECONFIG") {
+        if let Some(first) = std::env::split_paths(&paths).find(|p| !p.as_os_str().is_empty()) {
+            return Some(first);
+        }
+    }
+    std::env::var("HOME").ok().map(|home| PathBuf::from(home).join(".kube").join("config"))
+}
+
+fn current_context() -> Result<String, String> {
+    read_kubeconfig()?
+        .current_context
+        .filter(|c| !c.is_empty())
+        .ok_or_else(|| "No current context set".to_string())
+}
+
+// Rewrite only the current-context line so comments and formatting survive
+fn set_current_context(context: &str) -> Result<(), String> {
+    let config = read_kubeconfig()?;
+    if !config.contexts.iter().any(|c| c.name == context) {
+        return Err(format!("Unknown kube context: {}", context));
+    }
+
+    let path = kubeconfig_path().ok_or("Could not determine kubeconfig path")?;
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    let new_line = format!("current-context: {}", context);
+    let mut replaced = false;
+    let mut lines: Vec<String> = content
+        .lines()
+        .map(|line| {
+            if !replaced && line.starts_with("current-context:") {
+                replaced = true;
+                new_line.clone()
+            } else {
+                line.to_string()
+            }
+        })
+        .collect();
+    if !replaced {
+        lines.push(new_line);
+    }
+
+    std::fs::write(&path, lines.join("\n") + "\n")
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn next_context() -> Result<String, String> {
+    let config = read_kubeconfig()?;
+    if config.contexts.is_empty() {
+        return Err("No kube contexts configured".to_string());
+    }
+    let current = config.current_context.unwrap_or_default();
+    let index = config.contexts.iter().position(|c| c.name == current);
+    let next = match index {
+        Some(i) => (i + 1) % config.contexts.len(),
+        None => 0,
+    };
+    Ok(config.contexts[next].name.clone())
+}
+
+// Count pods that are not Running (completed job pods are ignored)
+fn count_problem_pods(namespace: &str) -> Result<usize, kube::Error> {
+    let namespace = namespace.to_string();
+    tauri::async_runtime::block_on(async move {
+        let client = Client::try_default().await?;
+        let pods: Api<Pod> = Api::namespaced(client, &namespace);
+        let list = pods.list(&ListParams::default()).await?;
+        Ok(list
+            .items
+            .iter()
+            .filter(|pod| {
+                let phase = pod.status.as_ref().and_then(|s| s.phase.as_deref()).unwrap_or("Unknown");
+                phase != "Running" && phase != "Succeeded"
+            })
+            .count())
+    })
+}
+
+fn context_label(context: &str) -> String {
+    if context.chars().count() > MAX_LABEL_LEN {
+        context.chars().take(MAX_LABEL_LEN).collect()
+    } else {
+        context.to_string()
+    }
+}
+
+// Execute a Kubernetes action command (runs in background)
+pub fn execute_command(cmd: &str) {
+    let target = match parse_command(cmd) {
+        Some(KubeCommand::Context) => None,
+        Some(KubeCommand::Switch(context)) => Some(context),
+        Some(KubeCommand::Pods(_)) => {
+            // Pressing the pods widget just forces a refresh
+            poller::invalidate_matching(|key| key == cmd);
+            return;
+        }
+        None => return,
+    };
+
+    thread::spawn(move || {
+        let result = match target {
+            Some(context) => Ok(context),
+            None => next_context(),
+        }
+        .and_then(|context| {
+            debug!("Switching kube context to {}", context);
+            set_current_context(&context)
+        });
+
+        if let Err(e) = result {
+            warn!("Kube context switch error: {}", e);
+        }
+
+        // Every kube widget depends on the current context
+        poller::invalidate_matching(is_kube_widget);
+    });
+}
+
+// Widget text for Kubernetes widgets (cached, refreshed in the background)
+pub fn get_widget_text(cmd: &str) -> String {
+    let text = match parse_command(cmd) {
+        Some(KubeCommand::Context) => poller::poll(cmd, CONTEXT_CACHE_SECS, || {
+            match current_context() {
+                Ok(context) => context_label(&context),
+                Err(e) => {
+                    warn!("Kube context error: {}", e);
+                    "NO CTX".to_string()
+                }
+            }
+        }),
+        Some(KubeCommand::Pods(namespace)) => poller::poll(cmd, PODS_CACHE_SECS, move || {
+            match count_problem_pods(&namespace) {
+                Ok(0) => "OK".to_string(),
+                Ok(count) => format!("{} BAD", count),
+                Err(e) => {
+                    warn!("Kube pods error for {}: {}", namespace, e);
+                    error_label(&e)
+                }
+            }
+        }),
+        _ => return String::new(),
+    };
+
+    text.unwrap_or_else(|| "...".to_string())
+}
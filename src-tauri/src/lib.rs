@@ -1,17 +1,20 @@
 use rusb::{Context, DeviceHandle, UsbContext};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
 use std::fs;
-use std::io::{Cursor, Read as IoRead, Write as IoWrite};
+use std::io::{Read as IoRead, Write as IoWrite};
 use std::net::TcpStream;
-use std::path::PathBuf;
-use std::process::Command;
-use std::sync::{Mutex, RwLock};
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex, RwLock};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::thread;
-use tauri::{Manager, State};
-use image::{DynamicImage, ImageBuffer, Rgb, RgbImage, imageops};
+use tauri::{Emitter, Manager, State};
+use image::codecs::jpeg::JpegEncoder;
+use image::{ImageBuffer, Rgb, RgbImage, imageops};
 use imageproc::drawing::{draw_text_mut, text_size};
 use ab_glyph::{FontRef, PxScale};
 use chrono::{Local, Datelike};
@@ -20,17 +23,104 @@ use tungstenite::{connect, Message};
 use sha2::{Sha256, Digest};
 use base64::{Engine as _, engine::general_purpose::STANDARD};
 use rdev::{listen, Event, EventType, Key};
+use tracing::{debug, error, trace, warn};
+use action::{Action, PageTarget, ShellOptions};
+use deck::{Deck, DeckEvent};
+
+mod action;
+mod action_log;
+mod alerts;
+mod apps;
+mod audio;
+mod calibration;
+mod ci;
+mod cli;
+mod clipboard;
+mod color_picker;
+mod command_widget;
+mod config_store;
+mod deck;
+mod deck_reader;
+mod dependencies;
+mod diagnostics;
+mod display;
+mod docker;
+mod elgato;
+mod frame_scheduler;
+mod github;
+mod history;
+mod i18n;
+mod icons;
+mod input;
+mod journal;
+mod kubernetes;
+mod lock_screen;
+mod logging;
+mod macro_recorder;
+mod midi;
+mod mpris;
+mod mpv;
+mod network;
+mod notify;
+mod numpad;
+mod overlay;
+mod player;
+mod plugins;
+mod poller;
+mod power;
+mod privacy;
+mod process_monitor;
+mod profile_bundle;
+mod profiles;
+mod protocol_console;
+mod remote;
+mod render_cache;
+mod sandbox;
+mod scheduler;
+mod screen;
+mod screensaver;
+mod scripting;
+mod session;
+mod snippets;
+mod sound;
+mod ssh;
+mod systemd;
+mod timer_alarm;
+mod transitions;
+mod tray;
+mod updates;
+mod usage_stats;
+mod variables;
+mod virtual_page;
+mod vlc;
+mod widget_schedule;
+mod window;
+mod window_switcher;
+mod workspaces;
 
 // USB IDs for Redragon SS-550
 const VENDOR_ID: u16 = 0x0200;
 const PRODUCT_ID: u16 = 0x1000;
 
+// Keys are 100x100 and mostly flat color and text; the encoder's default (75)
+// spends bytes the small screen doesn't show, and every byte goes through the
+// 512-byte interrupt packets.
+const DEFAULT_JPEG_QUALITY: u8 = 70;
+// Qualities compared by measure_jpeg_quality
+const JPEG_QUALITY_STEPS: [u8; 6] = [50, 60, 70, 80, 90, 100];
+
 // Global flag to signal refresh needed
 static REFRESH_NEEDED: AtomicBool = AtomicBool::new(false);
 
+// Brightness waiting to be sent without a page reload (NO_BRIGHTNESS = none)
+const NO_BRIGHTNESS: u8 = u8::MAX;
+static PENDING_BRIGHTNESS: AtomicU8 = AtomicU8::new(NO_BRIGHTNESS);
+
 // Global timer state (timestamp when timer started, 0 = not running)
 static TIMER_START: AtomicU64 = AtomicU64::new(0);
 static TIMER_DURATION: AtomicU64 = AtomicU64::new(0); // Duration in seconds
+// Bumped on every start and stop, so a superseded timer never finishes
+static TIMER_GENERATION: AtomicU64 = AtomicU64::new(0);
 
 // ============================================================================
 // Global Hotkey System
@@ -44,6 +134,8 @@ lazy_static::lazy_static! {
     static ref RECORDED_HOTKEY: RwLock<Vec<Key>> = RwLock::new(Vec::new());
     static ref GLOBAL_CONFIG_PATH: RwLock<Option<PathBuf>> = RwLock::new(None);
     static ref GLOBAL_ICONS_PATH: RwLock<Option<PathBuf>> = RwLock::new(None);
+    // (page, key) pairs to redraw without reloading the whole page
+    static ref KEY_REFRESH: Mutex<HashSet<(usize, u8)>> = Mutex::new(HashSet::new());
 }
 
 // Convert rdev::Key to a readable string
@@ -209,7 +301,7 @@ fn start_keyboard_listener(config_path: PathBuf, icons_path: PathBuf) {
     }
 
     thread::spawn(move || {
-        eprintln!("DEBUG: Global keyboard listener started");
+        debug!("Global keyboard listener started");
 
         if let Err(e) = listen(move |event: Event| {
             match event.event_type {
@@ -233,7 +325,7 @@ fn start_keyboard_listener(config_path: PathBuf, icons_path: PathBuf) {
                             if !hotkey_str.is_empty() {
                                 if let Ok(hotkeys) = REGISTERED_HOTKEYS.read() {
                                     if let Some((page, button_id)) = hotkeys.get(&hotkey_str) {
-                                        eprintln!("DEBUG: Hotkey triggered: {} -> page {}, button {}", hotkey_str, page, button_id);
+                                        debug!("Hotkey triggered: {} -> page {}, button {}", hotkey_str, page, button_id);
                                         // Execute the button action
                                         if let Ok(cfg_path) = GLOBAL_CONFIG_PATH.read() {
                                             if let Ok(icn_path) = GLOBAL_ICONS_PATH.read() {
@@ -257,118 +349,42 @@ fn start_keyboard_listener(config_path: PathBuf, icons_path: PathBuf) {
                 _ => {}
             }
         }) {
-            eprintln!("ERROR: Keyboard listener failed: {:?}", e);
+            error!("Keyboard listener failed: {:?}", e);
         }
     });
 }
 
 // Trigger action for a hotkey-activated button
-fn trigger_hotkey_action(page: usize, button_id: u8, config_path: &PathBuf, icons_path: &PathBuf) {
-    // Read config to get the button command
-    let config: Config = match fs::read_to_string(config_path) {
-        Ok(content) => match serde_json::from_str(&content) {
-            Ok(c) => c,
-            Err(_) => return,
-        },
-        Err(_) => return,
-    };
-
+fn trigger_hotkey_action(page: usize, button_id: u8, config_path: &Path, icons_path: &Path) {
     // Get the specific page and button
-    if let Some(target_page) = config.pages.get(page) {
-        if let Some(button) = target_page.buttons.get(&button_id.to_string()) {
-            if !button.command.is_empty() {
-                // Extract the actual command (remove __HOTKEY_ prefix if present)
-                let cmd = if button.command.starts_with("__HOTKEY_") {
-                    // Find the command after the hotkey definition
-                    // Format: __HOTKEY_Ctrl+F1__command_here or just __HOTKEY_Ctrl+F1__
-                    if let Some(idx) = button.command[9..].find("__") {
-                        let after_hotkey = &button.command[9 + idx + 2..];
-                        if after_hotkey.is_empty() {
-                            return; // No command after hotkey
-                        }
-                        after_hotkey.to_string()
-                    } else {
-                        return; // Malformed hotkey command
-                    }
-                } else {
-                    button.command.clone()
-                };
-
-                eprintln!("DEBUG: Executing hotkey command: {}", cmd);
-
-                // Execute the command in a new thread
-                let config_path_clone = config_path.clone();
-                let icons_path_clone = icons_path.clone();
-                thread::spawn(move || {
-                    execute_hotkey_command(&cmd, &config_path_clone, &icons_path_clone);
-                });
-            }
+    let button = config_store::read(|config| {
+        config.pages.get(page).and_then(|p| p.buttons.get(&button_id.to_string()).cloned())
+    });
+    if let Some(button) = button.flatten() {
+        // The __HOTKEY_<combo>__ binding itself is dropped by the action parser
+        let action = button.resolved_action();
+        if action == Action::None {
+            return;
         }
-    }
-}
-
-// Execute a command from hotkey (reuses existing command logic)
-fn execute_hotkey_command(cmd: &str, config_path: &PathBuf, icons_path: &PathBuf) {
-    // Handle __URL_ command
-    if cmd.starts_with("__URL_") {
-        let url = &cmd[6..];
-        Command::new("xdg-open").arg(url).spawn().ok();
-        return;
-    }
-
-    // Handle __TYPE_ command
-    if cmd.starts_with("__TYPE_") {
-        let text = &cmd[7..];
-        Command::new("ydotool")
-            .args(["type", "--clearmodifiers", text])
-            .spawn()
-            .ok();
-        return;
-    }
 
-    // Handle __KEY_ command
-    if cmd.starts_with("__KEY_") {
-        let keys = &cmd[6..];
-        execute_hotkey(keys);
-        return;
-    }
+        debug!("Executing hotkey action: {:?}", action);
 
-    // Handle page navigation
-    if cmd == "__NEXT_PAGE__" || cmd == "__PREV_PAGE__" || cmd.starts_with("__PAGE_") {
-        // Read config to get page count
-        if let Ok(content) = fs::read_to_string(config_path) {
-            if let Ok(config) = serde_json::from_str::<Config>(&content) {
-                let new_page = if cmd == "__NEXT_PAGE__" {
-                    (config.current_page + 1) % config.pages.len()
-                } else if cmd == "__PREV_PAGE__" {
-                    if config.current_page == 0 { config.pages.len() - 1 } else { config.current_page - 1 }
-                } else if cmd.starts_with("__PAGE_") && cmd.ends_with("__") {
-                    cmd[7..cmd.len()-2].parse::<usize>().unwrap_or(config.current_page)
-                } else {
-                    return;
-                };
-                change_page(new_page, config_path, icons_path);
+        // Execute the action in a new thread
+        let config_path_clone = config_path.to_path_buf();
+        let icons_path_clone = icons_path.to_path_buf();
+        thread::spawn(move || {
+            if let Err(e) = execute_action(&action, &config_path_clone, &icons_path_clone) {
+                warn!("Hotkey action failed: {}", e);
             }
-        }
-        return;
+        });
     }
-
-    // Normal shell command
-    Command::new("sh")
-        .arg("-c")
-        .arg(cmd)
-        .spawn()
-        .ok();
 }
 
 // Load registered hotkeys from config
-fn load_hotkeys_from_config(config_path: &PathBuf) {
-    let config: Config = match fs::read_to_string(config_path) {
-        Ok(content) => match serde_json::from_str(&content) {
-            Ok(c) => c,
-            Err(_) => return,
-        },
-        Err(_) => return,
+fn load_hotkeys_from_config() {
+    let config = match config_store::read(|c| c.clone()) {
+        Some(c) => c,
+        None => return,
     };
 
     if let Ok(mut hotkeys) = REGISTERED_HOTKEYS.write() {
@@ -382,7 +398,7 @@ fn load_hotkeys_from_config(config_path: &PathBuf) {
                     if let Some(end_idx) = hotkey_part.find("__") {
                         let hotkey_str = &hotkey_part[..end_idx];
                         if let Ok(button_id) = button_id_str.parse::<u8>() {
-                            eprintln!("DEBUG: Registered hotkey '{}' for page {} button {}", hotkey_str, page_idx, button_id);
+                            debug!("Registered hotkey '{}' for page {} button {}", hotkey_str, page_idx, button_id);
                             hotkeys.insert(hotkey_str.to_string(), (page_idx, button_id));
                         }
                     }
@@ -402,12 +418,66 @@ pub struct ButtonConfig {
     pub command: String,
     pub color: String,
     pub icon: String,
+    // Structured form of `command` (see action.rs)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<Action>,
+    // Only run after the key is held for HOLD_CONFIRM_DURATION
+    #[serde(default, rename = "holdConfirm", skip_serializing_if = "std::ops::Not::not")]
+    pub hold_confirm: bool,
+    // Only run on a second press within CONFIRM_WINDOW ("Sure?" on the key)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub confirm: bool,
+    // "beep" or an audio file played on press (sound.rs)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sound: Option<String>,
+}
+
+impl ButtonConfig {
+    // The action to run; `command` wins over a stale `action`, while a
+    // matching `action` keeps settings the string can't hold (shell options)
+    pub fn resolved_action(&self) -> Action {
+        match &self.action {
+            Some(action) if self.command.is_empty() || action.to_command() == self.command => action.clone(),
+            _ => Action::parse(&self.command),
+        }
+    }
+
+    // Keep `command` and `action` in sync (migrates legacy buttons)
+    pub fn normalize_action(&mut self) {
+        let action = self.resolved_action();
+        if self.command.is_empty() {
+            self.command = action.to_command();
+        }
+        self.action = match action {
+            Action::None => None,
+            action => Some(action),
+        };
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Page {
     pub name: String,
     pub buttons: HashMap<String, ButtonConfig>,
+    // Overrides the config brightness while the page is shown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub brightness: Option<u8>,
+    // Color for the page's buttons that keep the stock color
+    #[serde(default, rename = "defaultColor", skip_serializing_if = "Option::is_none")]
+    pub default_color: Option<String>,
+}
+
+impl Page {
+    // Paint stock-colored buttons with the page's default color
+    pub fn apply_default_color(&mut self) {
+        if let Some(color) = &self.default_color {
+            for button in self.buttons.values_mut() {
+                if button.color == "#1a1a2e" {
+                    button.color = color.clone();
+                }
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -416,11 +486,97 @@ pub struct Config {
     #[serde(rename = "currentPage")]
     pub current_page: usize,
     pub pages: Vec<Page>,
+    #[serde(default, rename = "inputBackend")]
+    pub input_backend: input::BackendKind,
+    // Page shown while the camera or microphone is in use (privacy.rs)
+    #[serde(default, rename = "privacyPage", skip_serializing_if = "Option::is_none")]
+    pub privacy_page: Option<usize>,
+    // Phone/tablet virtual deck server (remote.rs)
+    #[serde(default, rename = "remoteDeck", skip_serializing_if = "Option::is_none")]
+    pub remote_deck: Option<remote::RemoteDeck>,
+    // Name of the active profile; the others are kept in `profiles` (profiles.rs)
+    #[serde(default = "profiles::default_name")]
+    pub profile: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub profiles: Vec<profiles::Profile>,
+    #[serde(default)]
+    pub usb: UsbSettings,
+    // error, warn, info, debug or trace (logging.rs)
+    #[serde(default = "logging::default_level", rename = "logLevel")]
+    pub log_level: String,
+    // Quality of the key images sent to the device (10-100)
+    #[serde(default = "default_jpeg_quality", rename = "jpegQuality")]
+    pub jpeg_quality: u8,
+    // What the deck shows while the session is locked (lock_screen.rs)
+    #[serde(default, rename = "lockScreen")]
+    pub lock_screen: lock_screen::LockScreen,
+    // Record key presses for get_usage_stats (usage_stats.rs)
+    #[serde(default = "usage_stats::default_enabled", rename = "usageStats")]
+    pub usage_stats: bool,
+    // Commands run at set times or intervals (scheduler.rs)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub schedule: Vec<scheduler::ScheduledJob>,
+    // Streaming events that flash the whole deck (alerts.rs)
+    #[serde(default)]
+    pub alerts: alerts::AlertSettings,
+    // Timer and alert sounds, volume and mute (sound.rs)
+    #[serde(default)]
+    pub sounds: sound::SoundSettings,
+    // Desktop notifications for app events (notify.rs)
+    #[serde(default)]
+    pub notifications: notify::NotificationSettings,
+    // Idle minutes before the deck shows a clock; 0 = never (screensaver.rs)
+    #[serde(default, rename = "screensaverMinutes", skip_serializing_if = "is_zero")]
+    pub screensaver_minutes: u32,
+    // Page shown for a break when a timer ends (timer_alarm.rs)
+    #[serde(default, rename = "timerBreak", skip_serializing_if = "Option::is_none")]
+    pub timer_break: Option<timer_alarm::TimerBreak>,
+    // Refuse shell commands, scripts and command widgets (shared/kiosk machines)
+    #[serde(default, rename = "safeMode", skip_serializing_if = "std::ops::Not::not")]
+    pub safe_mode: bool,
+    // Dropped frame and bitrate thresholds of __OBS_HEALTH__
+    #[serde(default, rename = "obsHealth")]
+    pub obs_health: ObsHealthSettings,
+    // Starting OBS from the deck (__OBS_LAUNCH__, autoLaunch)
+    #[serde(default, rename = "obsLaunch")]
+    pub obs_launch: ObsLaunchSettings,
+    // Device key code -> key 1-15 from a calibration (calibration.rs)
+    #[serde(default, rename = "keyMap", skip_serializing_if = "BTreeMap::is_empty")]
+    pub key_map: BTreeMap<u8, u8>,
+    // Brightness fades and staggered page loads (transitions.rs)
+    #[serde(default)]
+    pub transitions: transitions::Transitions,
+    // Rotation and mirroring of the key images
+    #[serde(default)]
+    pub orientation: Orientation,
+    // Language of widget texts and presets (see i18n.rs)
+    #[serde(default)]
+    pub language: i18n::Language,
+}
+
+fn default_jpeg_quality() -> u8 {
+    DEFAULT_JPEG_QUALITY
+}
+
+fn is_zero(value: &u32) -> bool {
+    *value == 0
+}
+
+impl Config {
+    // Migrate legacy command strings and fill commands of action-only buttons
+    pub fn normalize_actions(&mut self) {
+        for page in &mut self.pages {
+            for button in page.buttons.values_mut() {
+                button.normalize_action();
+            }
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
 pub struct StatusResponse {
     pub connected: bool,
+    pub simulator: bool,
 }
 
 // ============================================================================
@@ -428,7 +584,9 @@ pub struct StatusResponse {
 // ============================================================================
 
 pub struct AppState {
-    pub config: Mutex<Config>,
+    pub config: Arc<RwLock<Config>>,
+    // Snapshots for undo_config / redo_config (history.rs)
+    pub history: Mutex<history::ConfigHistory>,
     pub device_connected: Mutex<bool>,
     pub config_path: PathBuf,
     pub icons_path: PathBuf,
@@ -441,19 +599,33 @@ impl AppState {
 
         fs::create_dir_all(&icons_path).ok();
 
-        let config = if config_path.exists() {
-            let content = fs::read_to_string(&config_path).unwrap_or_default();
-            serde_json::from_str(&content).unwrap_or_else(|_| Self::default_config())
-        } else {
-            let config = Self::default_config();
-            if let Ok(content) = serde_json::to_string_pretty(&config) {
-                fs::write(&config_path, content).ok();
+        let loaded = if config_path.exists() { config_store::load(&config_path) } else { None };
+        let config = match loaded {
+            Some(mut config) => {
+                let content = fs::read_to_string(&config_path).unwrap_or_default();
+                config.normalize_actions();
+                // Saved with a backup, so a bad migration can be rolled back
+                if let Ok(migrated) = serde_json::to_string_pretty(&config) {
+                    if migrated != content {
+                        if let Err(e) = config_store::save(&config_path, &config) {
+                            warn!("Failed to save migrated config: {}", e);
+                        }
+                    }
+                }
+                config
+            }
+            None => {
+                // New install, or an unreadable file that was moved aside
+                // (the UI offers its backups, see config_store.rs)
+                let config = Self::default_config();
+                config_store::save(&config_path, &config).ok();
+                config
             }
-            config
         };
 
         Self {
-            config: Mutex::new(config),
+            history: Mutex::new(history::ConfigHistory::new(&config)),
+            config: config_store::share(config),
             device_connected: Mutex::new(false),
             config_path,
             icons_path,
@@ -470,6 +642,10 @@ impl AppState {
                     command: String::new(),
                     color: "#1a1a2e".to_string(),
                     icon: String::new(),
+                    action: None,
+                    hold_confirm: false,
+                    confirm: false,
+                    sound: None,
                 },
             );
         }
@@ -480,6 +656,10 @@ impl AppState {
                 command: "__NEXT_PAGE__".to_string(),
                 color: "#e94560".to_string(),
                 icon: String::new(),
+                action: None,
+                hold_confirm: false,
+                confirm: false,
+                sound: None,
             },
         );
 
@@ -489,16 +669,46 @@ impl AppState {
             pages: vec![Page {
                 name: "Principal".to_string(),
                 buttons,
+                brightness: None,
+                default_color: None,
             }],
+            input_backend: input::BackendKind::Auto,
+            privacy_page: None,
+            remote_deck: None,
+            profile: profiles::default_name(),
+            profiles: Vec::new(),
+            usb: UsbSettings::default(),
+            log_level: logging::default_level(),
+            jpeg_quality: DEFAULT_JPEG_QUALITY,
+            lock_screen: lock_screen::LockScreen::default(),
+            usage_stats: usage_stats::default_enabled(),
+            schedule: Vec::new(),
+            alerts: alerts::AlertSettings::default(),
+            sounds: sound::SoundSettings::default(),
+            notifications: notify::NotificationSettings::default(),
+            screensaver_minutes: 0,
+            timer_break: None,
+            safe_mode: false,
+            obs_health: ObsHealthSettings::default(),
+            obs_launch: ObsLaunchSettings::default(),
+            key_map: BTreeMap::new(),
+            transitions: transitions::Transitions::default(),
+            orientation: Orientation::default(),
+            language: i18n::Language::default(),
         }
     }
 
     pub fn save_config(&self) {
-        if let Ok(config) = self.config.lock() {
-            if let Ok(content) = serde_json::to_string_pretty(&*config) {
-                fs::write(&self.config_path, content).ok();
+        if let Ok(config) = self.config.read() {
+            if let Ok(mut history) = self.history.lock() {
+                history.record(&config);
+            }
+            if let Err(e) = config_store::save(&self.config_path, &config) {
+                warn!("Failed to save config: {}", e);
             }
         }
+        // Page names and brightness in the tray menu
+        tray::update();
     }
 }
 
@@ -521,10 +731,84 @@ const CMD_BAT: [u8; 3] = [0x42, 0x41, 0x54];
 
 const PACKET_SIZE: usize = 512;
 const BUTTON_SIZE: u32 = 100;
+// Label font, also used by whole-deck drawings (screensaver.rs)
+const FONT_DATA: &[u8] = include_bytes!("/usr/share/fonts/TTF/DejaVuSans.ttf");
+// How long hold_confirm keys must be held
+const HOLD_CONFIRM_DURATION: Duration = Duration::from_secs(2);
+// How long a confirm key waits for its second press
+const CONFIRM_WINDOW: Duration = Duration::from_secs(3);
+const CONFIRM_COLOR: &str = "#e67e22";
+// How long the self-test waits for the device to answer DIS
+const SELF_TEST_READ_TIMEOUT: Duration = Duration::from_millis(500);
+// First wait before retrying a failed write; doubles on each retry
+const USB_RETRY_BACKOFF: Duration = Duration::from_millis(20);
+
+// Set when a transfer finds the device gone; the listener then reconnects
+static DEVICE_LOST: AtomicBool = AtomicBool::new(false);
+// Whether the listener has a deck open (shown in the tray)
+static DECK_CONNECTED: AtomicBool = AtomicBool::new(false);
+
+fn deck_connected() -> bool {
+    DECK_CONNECTED.load(Ordering::SeqCst)
+}
+
+// Make the listener drop the device and open it again
+fn device_lost() {
+    DEVICE_LOST.store(true, Ordering::SeqCst);
+}
+
+// USB timeouts and retries, "usb" in config.json
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsbSettings {
+    #[serde(rename = "writeTimeoutMs")]
+    pub write_timeout_ms: u64,
+    // Also how often the listener loop runs when no key is pressed
+    #[serde(rename = "readTimeoutMs")]
+    pub read_timeout_ms: u64,
+    // Extra attempts for a write that failed with a transient error
+    pub retries: u32,
+    // Key image frames sent per second at most (frame_scheduler.rs)
+    #[serde(default = "default_max_fps", rename = "maxFps")]
+    pub max_fps: u32,
+}
+
+fn default_max_fps() -> u32 {
+    20
+}
+
+impl Default for UsbSettings {
+    fn default() -> Self {
+        UsbSettings {
+            write_timeout_ms: 1000,
+            read_timeout_ms: 100,
+            retries: 3,
+            max_fps: default_max_fps(),
+        }
+    }
+}
+
+fn usb_settings() -> UsbSettings {
+    config_store::read(|c| c.usb.clone()).unwrap_or_default()
+}
+
+// Errors after which the handle is useless; anything else may pass on retry
+fn is_fatal_usb_error(e: &rusb::Error) -> bool {
+    matches!(
+        e,
+        rusb::Error::NoDevice | rusb::Error::NotFound | rusb::Error::Access | rusb::Error::InvalidParam | rusb::Error::NotSupported
+    )
+}
 
 // Key mapping: physical position -> logical key ID (1-15)
-// Used when receiving key presses from the device
+// Used when receiving key presses from the device. A calibrated "keyMap"
+// replaces the built-in map; calibration itself needs the raw codes.
 fn map_physical_to_logical(physical: u8) -> u8 {
+    if calibration::active() {
+        return physical;
+    }
+    if let Some(key_id) = config_store::read(|c| c.key_map.get(&physical).copied()).flatten() {
+        return key_id;
+    }
     match physical {
         0x0b => 1,
         0x0c => 2,
@@ -551,24 +835,24 @@ fn find_device() -> Option<DeviceHandle<Context>> {
     for device in context.devices().ok()?.iter() {
         let desc = device.device_descriptor().ok()?;
         if desc.vendor_id() == VENDOR_ID && desc.product_id() == PRODUCT_ID {
-            eprintln!("DEBUG: Found device VID={:04x} PID={:04x}", desc.vendor_id(), desc.product_id());
+            debug!("Found device VID={:04x} PID={:04x}", desc.vendor_id(), desc.product_id());
 
             #[allow(unused_mut)]
             let mut handle = match device.open() {
                 Ok(h) => {
-                    eprintln!("DEBUG: Device opened successfully");
+                    debug!("Device opened successfully");
                     h
                 }
                 Err(e) => {
-                    eprintln!("DEBUG: Failed to open device: {:?}", e);
+                    warn!("Failed to open device: {:?}", e);
                     return None;
                 }
             };
 
             // Set configuration (required for some devices)
             match handle.set_active_configuration(1) {
-                Ok(_) => eprintln!("DEBUG: Configuration 1 set"),
-                Err(e) => eprintln!("DEBUG: Could not set configuration (may already be set): {:?}", e),
+                Ok(_) => debug!("Configuration 1 set"),
+                Err(e) => warn!("Could not set configuration (may already be set): {:?}", e),
             }
 
             // Detach kernel driver if attached (Linux)
@@ -576,22 +860,22 @@ fn find_device() -> Option<DeviceHandle<Context>> {
             {
                 match handle.kernel_driver_active(0) {
                     Ok(true) => {
-                        eprintln!("DEBUG: Kernel driver active, detaching...");
+                        debug!("Kernel driver active, detaching...");
                         match handle.detach_kernel_driver(0) {
-                            Ok(_) => eprintln!("DEBUG: Kernel driver detached"),
-                            Err(e) => eprintln!("DEBUG: Failed to detach kernel driver: {:?}", e),
+                            Ok(_) => debug!("Kernel driver detached"),
+                            Err(e) => warn!("Failed to detach kernel driver: {:?}", e),
                         }
                     }
-                    Ok(false) => eprintln!("DEBUG: No kernel driver active"),
-                    Err(e) => eprintln!("DEBUG: Error checking kernel driver: {:?}", e),
+                    Ok(false) => debug!("No kernel driver active"),
+                    Err(e) => warn!("Error checking kernel driver: {:?}", e),
                 }
             }
 
             // Claim the interface
             match handle.claim_interface(0) {
-                Ok(_) => eprintln!("DEBUG: Interface 0 claimed successfully"),
+                Ok(_) => debug!("Interface 0 claimed successfully"),
                 Err(e) => {
-                    eprintln!("DEBUG: Failed to claim interface 0: {:?}", e);
+                    warn!("Failed to claim interface 0: {:?}", e);
                     return None;
                 }
             }
@@ -599,7 +883,7 @@ fn find_device() -> Option<DeviceHandle<Context>> {
             return Some(handle);
         }
     }
-    eprintln!("DEBUG: Device not found");
+    debug!("Device not found");
     None
 }
 
@@ -619,19 +903,44 @@ fn send_to_device(handle: &DeviceHandle<Context>, data: &[u8], use_prefix: bool)
         packet.push(0x00);
     }
 
-    eprintln!("DEBUG: Sending {} bytes to endpoint 0x01", packet.len());
-    eprintln!("DEBUG: First 20 bytes: {:02x?}", &packet[..20.min(packet.len())]);
+    write_out(handle, &packet)
+}
 
-    // Endpoint 0x01 is the OUT endpoint for this device
-    match handle.write_interrupt(0x01, &packet, Duration::from_millis(1000)) {
-        Ok(bytes_written) => {
-            eprintln!("DEBUG: Successfully wrote {} bytes", bytes_written);
-            Ok(())
+// One interrupt transfer to endpoint 0x01 (the OUT endpoint for this device),
+// retried on transient errors. A short write is an error: the rest would be
+// taken as the start of the next packet.
+fn write_out(handle: &DeviceHandle<Context>, data: &[u8]) -> Result<(), String> {
+    trace!("Sending {} bytes to endpoint 0x01", data.len());
+    trace!("First 20 bytes: {:02x?}", &data[..20.min(data.len())]);
+
+    let settings = usb_settings();
+    let timeout = Duration::from_millis(settings.write_timeout_ms);
+    let mut attempt = 0;
+    loop {
+        let e = match handle.write_interrupt(0x01, data, timeout) {
+            Ok(bytes_written) if bytes_written == data.len() => {
+                trace!("Successfully wrote {} bytes", bytes_written);
+                return Ok(());
+            }
+            Ok(bytes_written) => {
+                return Err(format!("USB short write: {} of {} bytes", bytes_written, data.len()));
+            }
+            Err(e) => e,
+        };
+        warn!("USB write error: {:?}", e);
+        if is_fatal_usb_error(&e) {
+            DEVICE_LOST.store(true, Ordering::SeqCst);
+            return Err(format!("USB device lost: {}", e));
         }
-        Err(e) => {
-            eprintln!("DEBUG: USB write error: {:?}", e);
-            Err(format!("USB write error: {}", e))
+        if attempt >= settings.retries {
+            return Err(format!("USB write error: {}", e));
+        }
+        // A stalled endpoint stays stalled until cleared
+        if e == rusb::Error::Pipe {
+            handle.clear_halt(0x01).ok();
         }
+        thread::sleep(USB_RETRY_BACKOFF * 2u32.pow(attempt));
+        attempt += 1;
     }
 }
 
@@ -666,14 +975,16 @@ fn refresh_screen(handle: &DeviceHandle<Context>) -> Result<(), String> {
     send_to_device(handle, &CMD_STP, true)
 }
 
-// Send raw bytes in 512-byte chunks (without prefix)
+// Send raw bytes as 512-byte packets (without prefix), the last one padded.
+// One transfer per packet: a multi-packet transfer that times out does not say
+// how many packets the device already took, so retrying it would resend some
+// of them and corrupt the image. Batching was not kept since no throughput gain
+// was measured on the device to weigh against that.
 fn send_bytes(handle: &DeviceHandle<Context>, data: &[u8]) -> Result<(), String> {
-    let mut offset = 0;
-    while offset < data.len() {
-        let end = std::cmp::min(offset + PACKET_SIZE, data.len());
-        let chunk = &data[offset..end];
-        send_to_device(handle, chunk, false)?;
-        offset += PACKET_SIZE;
+    let mut padded = data.to_vec();
+    padded.resize(data.len().div_ceil(PACKET_SIZE) * PACKET_SIZE, 0x00);
+    for packet in padded.chunks(PACKET_SIZE) {
+        write_out(handle, packet)?;
     }
     Ok(())
 }
@@ -701,12 +1012,39 @@ fn parse_hex_color(color: &str) -> (u8, u8, u8) {
     }
 }
 
-// Generate a button image from config
-fn generate_button_image(button: &ButtonConfig, icons_path: &PathBuf) -> Result<Vec<u8>, String> {
-    let (r, g, b) = parse_hex_color(&button.color);
+// Perceived brightness above the middle
+fn is_light(r: u8, g: u8, b: u8) -> bool {
+    (299 * r as u32 + 587 * g as u32 + 114 * b as u32) / 1000 > 150
+}
+
+// Draw a button (upright, before the device rotation)
+fn render_button_image(button: &ButtonConfig, icons_path: &Path) -> RgbImage {
+    // Status widgets may override the background color (e.g. CI pass/fail)
+    let widget_color = if is_widget_command(&button.command) {
+        get_widget_color(&button.command)
+    } else {
+        None
+    };
+    // Keys whose command just failed flash red
+    let flashing = action_log::is_flashing(&button.command);
+    let background = if flashing {
+        action_log::FLASH_COLOR
+    } else {
+        widget_color.as_deref().unwrap_or(&button.color)
+    };
+    let (r, g, b) = parse_hex_color(background);
+
+    // Plugins can push their own key image
+    let plugin_image = plugins::get_key_image(&button.command)
+        .or_else(|| elgato::get_key_image(&button.command))
+        .and_then(|data| image::load_from_memory(&data).ok());
 
     // Try to load icon if specified
-    let mut img: RgbImage = if !button.icon.is_empty() {
+    let mut img: RgbImage = if flashing {
+        ImageBuffer::from_pixel(BUTTON_SIZE, BUTTON_SIZE, Rgb([r, g, b]))
+    } else if let Some(icon) = plugin_image {
+        icon.resize_exact(BUTTON_SIZE, BUTTON_SIZE, imageops::FilterType::Lanczos3).to_rgb8()
+    } else if !button.icon.is_empty() {
         let icon_path = icons_path.join(&button.icon);
         if icon_path.exists() {
             match image::open(&icon_path) {
@@ -722,6 +1060,9 @@ fn generate_button_image(button: &ButtonConfig, icons_path: &PathBuf) -> Result<
         } else {
             ImageBuffer::from_pixel(BUTTON_SIZE, BUTTON_SIZE, Rgb([r, g, b]))
         }
+    } else if let Some(icon) = apps::icon_path(&button.command).and_then(|p| image::open(p).ok()) {
+        // App launchers show the app's icon
+        icon.resize_exact(BUTTON_SIZE, BUTTON_SIZE, imageops::FilterType::Lanczos3).to_rgb8()
     } else {
         // No icon, create solid color background
         ImageBuffer::from_pixel(BUTTON_SIZE, BUTTON_SIZE, Rgb([r, g, b]))
@@ -729,16 +1070,16 @@ fn generate_button_image(button: &ButtonConfig, icons_path: &PathBuf) -> Result<
 
     // Determine text to display
     // If command is a widget, show dynamic text; otherwise show label
+    let label = variables::interpolate(&button.label);
     let display_text = if is_widget_command(&button.command) {
-        get_widget_text(&button.command).unwrap_or_else(|| button.label.clone())
+        get_widget_text(&button.command).unwrap_or(label)
     } else {
-        button.label.clone()
+        label
     };
 
     // Draw text if specified
     if !display_text.is_empty() {
-        let font_data = include_bytes!("/usr/share/fonts/TTF/DejaVuSans.ttf");
-        if let Ok(font) = FontRef::try_from_slice(font_data) {
+        if let Ok(font) = FontRef::try_from_slice(FONT_DATA) {
             let scale = if display_text.len() > 8 {
                 PxScale::from(16.0)
             } else if display_text.len() > 5 {
@@ -764,22 +1105,85 @@ fn generate_button_image(button: &ButtonConfig, icons_path: &PathBuf) -> Result<
                 }
             }
 
-            draw_text_mut(&mut img, Rgb([255, 255, 255]), x, y, scale, &font, &display_text);
+            // Light widget colors (e.g. a picked color) need dark text
+            let text_color = if widget_color.is_some() && !flashing && is_light(r, g, b) {
+                Rgb([0, 0, 0])
+            } else {
+                Rgb([255, 255, 255])
+            };
+            draw_text_mut(&mut img, text_color, x, y, scale, &font, &display_text);
         }
     }
 
-    // Rotate 180 degrees (required by the device)
-    let rotated = imageops::rotate180(&img);
+    img
+}
 
-    // Convert to JPEG
-    let mut jpeg_data = Vec::new();
-    let mut cursor = Cursor::new(&mut jpeg_data);
+// Hash of what render_button_image draws a key from (icon files aside), so a
+// cached image can be reused while it stays the same
+pub(crate) fn key_fingerprint(button: &ButtonConfig) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    serde_json::to_string(button).unwrap_or_default().hash(&mut hasher);
+    variables::interpolate(&button.label).hash(&mut hasher);
+    action_log::is_flashing(&button.command).hash(&mut hasher);
+    if is_widget_command(&button.command) {
+        get_widget_text(&button.command).hash(&mut hasher);
+        get_widget_color(&button.command).hash(&mut hasher);
+    }
+    plugins::get_key_image(&button.command)
+        .or_else(|| elgato::get_key_image(&button.command))
+        .hash(&mut hasher);
+    hasher.finish()
+}
 
-    let dynamic_img = DynamicImage::ImageRgb8(rotated);
-    dynamic_img.write_to(&mut cursor, image::ImageFormat::Jpeg)
+fn encode_jpeg(img: &RgbImage, quality: u8) -> Result<Vec<u8>, String> {
+    let mut jpeg_data = Vec::new();
+    JpegEncoder::new_with_quality(&mut jpeg_data, quality.clamp(10, 100))
+        .encode_image(img)
         .map_err(|e| format!("Failed to encode JPEG: {}", e))?;
+    Ok(jpeg_data)
+}
+
+// How key images are turned before they are sent, "orientation" in
+// config.json. The SS-550 shows them upside down, hence 180 by default; a
+// deck mounted upside down or a clone with another panel needs another value.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Orientation {
+    // Clockwise degrees: 0, 90, 180 or 270
+    pub rotation: u16,
+    // Flip left-right before rotating
+    #[serde(default)]
+    pub mirror: bool,
+}
+
+impl Default for Orientation {
+    fn default() -> Self {
+        Orientation {
+            rotation: 180,
+            mirror: false,
+        }
+    }
+}
+
+fn orient(img: &RgbImage, orientation: Orientation) -> RgbImage {
+    let img = if orientation.mirror { imageops::flip_horizontal(img) } else { img.clone() };
+    match orientation.rotation {
+        0 => img,
+        90 => imageops::rotate90(&img),
+        270 => imageops::rotate270(&img),
+        _ => imageops::rotate180(&img),
+    }
+}
 
-    eprintln!("DEBUG: Generated button image, {} bytes JPEG", jpeg_data.len());
+// Rotate and encode a key image as the device expects
+fn encode_key_image(img: &RgbImage) -> Result<Vec<u8>, String> {
+    let orientation = config_store::read(|c| c.orientation).unwrap_or_default();
+    let rotated = orient(img, orientation);
+
+    // Convert to JPEG
+    let quality = config_store::read(|c| c.jpeg_quality).unwrap_or(DEFAULT_JPEG_QUALITY);
+    let jpeg_data = encode_jpeg(&rotated, quality)?;
+
+    trace!("Generated button image, {} bytes JPEG", jpeg_data.len());
     Ok(jpeg_data)
 }
 
@@ -794,7 +1198,7 @@ fn set_key_image(handle: &DeviceHandle<Context>, key_id: u8, jpeg_data: &[u8]) -
     cmd_data.extend_from_slice(&size_bytes);
     cmd_data.push(key_id);
 
-    eprintln!("DEBUG: Setting key {} with {} bytes image", key_id, jpeg_data.len());
+    trace!("Setting key {} with {} bytes image", key_id, jpeg_data.len());
 
     // Send BAT command
     send_to_device(handle, &cmd_data, true)?;
@@ -808,45 +1212,159 @@ fn set_key_image(handle: &DeviceHandle<Context>, key_id: u8, jpeg_data: &[u8]) -
     Ok(())
 }
 
+// The SS-550 over USB
+struct UsbDeck {
+    handle: DeviceHandle<Context>,
+}
+
+impl Deck for UsbDeck {
+    fn wake(&self) -> Result<(), String> {
+        wake_screen(&self.handle)
+    }
+
+    fn clear(&self) -> Result<(), String> {
+        clear_screen(&self.handle)
+    }
+
+    fn set_brightness(&self, brightness: u8) -> Result<(), String> {
+        set_device_brightness(&self.handle, brightness)
+    }
+
+    fn set_key_image(&self, key_id: u8, image: &RgbImage) -> Result<(), String> {
+        set_key_image(&self.handle, key_id, &render_cache::jpeg(image)?)
+    }
+
+    fn self_test(&self) -> Result<String, String> {
+        wake_screen(&self.handle)?;
+        let mut buf = [0u8; PACKET_SIZE];
+        match self.handle.read_interrupt(0x82, &mut buf, SELF_TEST_READ_TIMEOUT) {
+            Ok(len) => Ok(format!("DIS acknowledged: {:02x?}", &buf[..len.min(16)])),
+            Err(rusb::Error::Timeout) => Ok("DIS sent, no reply".to_string()),
+            Err(e) => Err(format!("USB read error: {}", e)),
+        }
+    }
+
+    fn send_raw(&self, data: &[u8], use_prefix: bool) -> Result<(), String> {
+        send_to_device(&self.handle, data, use_prefix)
+    }
+
+    fn read_raw(&self, timeout: Duration) -> Result<Option<Vec<u8>>, String> {
+        let mut buf = [0u8; PACKET_SIZE];
+        match self.handle.read_interrupt(0x82, &mut buf, timeout) {
+            Ok(len) => Ok(Some(buf[..len].to_vec())),
+            Err(rusb::Error::Timeout) => Ok(None),
+            Err(e) => Err(format!("USB read error: {}", e)),
+        }
+    }
+
+    fn read_event(&self) -> Result<Option<DeckEvent>, String> {
+        match read_key_press(&self.handle) {
+            Ok((key_id, 1)) => Ok(Some(DeckEvent::KeyDown(key_id))),
+            Ok((key_id, 0)) => Ok(Some(DeckEvent::KeyUp(key_id))),
+            Ok(_) => Ok(None),
+            Err(e) if e == "timeout" => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+// The simulated deck in simulator mode, else the USB device if connected
+fn open_deck() -> Option<Box<dyn Deck + Send + Sync>> {
+    if deck::simulator_enabled() {
+        return Some(Box::new(deck::Mirrored(deck::simulator())));
+    }
+    find_device().map(|handle| Box::new(deck::Mirrored(UsbDeck { handle })) as Box<dyn Deck + Send + Sync>)
+}
+
+// The image for a key, None for keys left dark (no label, icon or color)
+fn key_image(key_id: u8, button: &ButtonConfig, icons_path: &Path) -> Option<RgbImage> {
+    if !(1..=15).contains(&key_id) {
+        return None;
+    }
+    // Scripts can replace a key's text until the page changes
+    let scripted;
+    let button = match scripting::key_text(key_id) {
+        Some(label) => {
+            scripted = ButtonConfig { label, ..button.clone() };
+            &scripted
+        }
+        None => button,
+    };
+
+    // Only send if button has content
+    if !button.label.is_empty() || !button.icon.is_empty() || button.color != "#1a1a2e" {
+        Some(render_button_image(button, icons_path))
+    } else {
+        None
+    }
+}
+
 // Load all buttons for a page to the device
-fn load_page_to_device(handle: &DeviceHandle<Context>, page: &Page, brightness: u8, icons_path: &PathBuf) -> Result<(), String> {
-    eprintln!("DEBUG: Loading page '{}' to device", page.name);
+fn load_page_to_device(deck: &dyn Deck, page: &Page, brightness: u8, icons_path: &Path) -> Result<(), String> {
+    debug!("Loading page '{}' to device", page.name);
+    let mut page = page.clone();
+    page.apply_default_color();
 
-    // Wake and clear screen first
-    wake_screen(handle)?;
-    clear_screen(handle)?;
-    set_device_brightness(handle, brightness)?;
+    // Re-render when a variable shown in a label changes
+    variables::set_displayed(page.buttons.values().map(|b| b.label.as_str()));
 
-    // Send each button image
-    for (key_id_str, button) in &page.buttons {
-        if let Ok(key_id) = key_id_str.parse::<u8>() {
-            if key_id >= 1 && key_id <= 15 {
-                // Only send if button has content
-                if !button.label.is_empty() || !button.icon.is_empty() || button.color != "#1a1a2e" {
-                    match generate_button_image(button, icons_path) {
-                        Ok(jpeg_data) => {
-                            if let Err(e) = set_key_image(handle, key_id, &jpeg_data) {
-                                eprintln!("DEBUG: Failed to set key {}: {}", key_id, e);
-                            }
-                        }
-                        Err(e) => {
-                            eprintln!("DEBUG: Failed to generate image for key {}: {}", key_id, e);
-                        }
-                    }
+    // Stream Deck plugins get willAppear/willDisappear for their keys
+    elgato::page_shown(page.buttons.iter().filter_map(|(k, b)| Some((k.parse().ok()?, b.command.as_str()))));
+
+    // Render every key before touching the device; toasts stay on top
+    let mut images = render_cache::render_page(&page, icons_path);
+    overlay::cover(&mut images, icons_path);
+
+    // A staggered load replaces the old keys column by column instead of
+    // clearing them (see transitions.rs)
+    let stagger = transitions::settings().stagger_pages;
+
+    // Wake and clear screen first
+    deck.wake()?;
+    if !stagger {
+        deck.clear()?;
+    }
+    deck.set_brightness(page.brightness.unwrap_or(brightness))?;
+
+    // Widgets are scheduled again from this full draw
+    widget_schedule::reset();
+
+    if stagger {
+        for column in transitions::columns() {
+            for key_id in (1..=15).filter(|k| transitions::column(*k) == column) {
+                // Keys without content go dark, as after a clear
+                let image = images
+                    .iter()
+                    .find(|(id, _)| *id == key_id)
+                    .map(|(_, image)| image.clone())
+                    .unwrap_or_else(|| ImageBuffer::from_pixel(BUTTON_SIZE, BUTTON_SIZE, Rgb([0, 0, 0])));
+                if let Err(e) = deck.set_key_image(key_id, &image) {
+                    warn!("Failed to set key {}: {}", key_id, e);
                 }
             }
+            deck.flush();
+            thread::sleep(transitions::STAGGER_STEP);
+        }
+        debug!("Page loaded successfully");
+        return Ok(());
+    }
+
+    // Send each button image
+    for (key_id, image) in &images {
+        if let Err(e) = deck.set_key_image(*key_id, image) {
+            warn!("Failed to set key {}: {}", key_id, e);
         }
     }
 
-    eprintln!("DEBUG: Page loaded successfully");
+    debug!("Page loaded successfully");
     Ok(())
 }
 
 // ============================================================================
-// Hotkey Functions (ydotool for Wayland)
+// Hotkey Functions (backends in input.rs)
 // ============================================================================
 
-// Map key names to ydotool key codes
+// Map key names to Linux input event codes (ydotool codes, X11 keycode - 8)
 fn key_name_to_code(key: &str) -> Option<&'static str> {
     match key.to_lowercase().as_str() {
         // Modifiers
@@ -993,35 +1511,7 @@ fn execute_hotkey(keys: &str) {
 // Execute hotkey synchronously
 fn execute_hotkey_sync(keys: &str) {
     // Parse keys like "ctrl+shift+a" or "alt+tab"
-    let key_parts: Vec<&str> = keys.split('+').collect();
-
-    // Build ydotool key sequence
-    // Format: key codes with :1 for press, :0 for release
-    let mut key_codes: Vec<String> = Vec::new();
-
-    for key in &key_parts {
-        if let Some(code) = key_name_to_code(key.trim()) {
-            key_codes.push(format!("{}:1", code)); // Press
-        }
-    }
-
-    // Release in reverse order
-    for key in key_parts.iter().rev() {
-        if let Some(code) = key_name_to_code(key.trim()) {
-            key_codes.push(format!("{}:0", code)); // Release
-        }
-    }
-
-    if !key_codes.is_empty() {
-        let key_sequence = key_codes.join(" ");
-        eprintln!("DEBUG: ydotool key {}", key_sequence);
-
-        Command::new("ydotool")
-            .arg("key")
-            .args(key_codes)
-            .status()
-            .ok();
-    }
+    input::press_keys(keys);
 }
 
 // ============================================================================
@@ -1038,21 +1528,37 @@ fn get_widget_clock_seconds() -> String {
     Local::now().format("%H:%M:%S").to_string()
 }
 
+// Custom strftime clock, e.g. __CLOCK_FMT_%I:%M %p__ or __CLOCK_FMT_%Y-%m-%d__
+pub(crate) const CLOCK_FMT_PREFIX: &str = "__CLOCK_FMT_";
+
+fn clock_format(cmd: &str) -> Option<&str> {
+    cmd.strip_prefix(CLOCK_FMT_PREFIX)?.strip_suffix("__")
+}
+
+// Format the current time; an invalid format shows "FMT?" instead of panicking
+fn get_widget_clock_format(format: &str) -> String {
+    use std::fmt::Write;
+    let mut text = String::new();
+    if write!(text, "{}", Local::now().format(format)).is_err() {
+        return "FMT?".to_string();
+    }
+    text
+}
+
 // Get current date as string
 fn get_widget_date() -> String {
-    Local::now().format("%d/%m").to_string()
+    Local::now().format(i18n::date_formats().0).to_string()
 }
 
 // Get current date with year
 fn get_widget_date_full() -> String {
-    Local::now().format("%d/%m/%Y").to_string()
+    Local::now().format(i18n::date_formats().1).to_string()
 }
 
 // Get day of week
 fn get_widget_weekday() -> String {
-    let weekdays = ["Dom", "Lun", "Mar", "Mié", "Jue", "Vie", "Sáb"];
     let day = Local::now().weekday().num_days_from_sunday() as usize;
-    weekdays[day].to_string()
+    i18n::weekday(day).to_string()
 }
 
 // Get CPU usage percentage
@@ -1095,8 +1601,29 @@ fn get_widget_temp() -> String {
     "N/A".to_string()
 }
 
+// Time since boot: 3d 4h, 5h 12m, 42m
+fn get_widget_uptime() -> String {
+    let minutes = System::uptime() / 60;
+    let (days, hours, minutes) = (minutes / 1440, minutes / 60 % 24, minutes % 60);
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+// One-minute load average (/proc/loadavg)
+fn get_widget_load() -> String {
+    format!("{:.2}", System::load_average().one)
+}
+
 // Get timer remaining time
 fn get_widget_timer() -> String {
+    if timer_alarm::ringing() {
+        return i18n::tr("timer.done").to_string();
+    }
     let start = TIMER_START.load(Ordering::Relaxed);
     let duration = TIMER_DURATION.load(Ordering::Relaxed);
 
@@ -1116,7 +1643,7 @@ fn get_widget_timer() -> String {
         // Timer finished
         TIMER_START.store(0, Ordering::Relaxed);
         TIMER_DURATION.store(0, Ordering::Relaxed);
-        return "DONE!".to_string();
+        return i18n::tr("timer.done").to_string();
     }
 
     let mins = remaining / 60;
@@ -1132,12 +1659,33 @@ fn start_timer(duration_secs: u64) {
         .unwrap_or(0);
     TIMER_START.store(now, Ordering::Relaxed);
     TIMER_DURATION.store(duration_secs, Ordering::Relaxed);
+    timer_alarm::silence();
+
+    // Rings even when no timer key is on the deck
+    let generation = TIMER_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    thread::spawn(move || {
+        thread::sleep(Duration::from_secs(duration_secs));
+        if TIMER_GENERATION.load(Ordering::SeqCst) == generation {
+            timer_done();
+        }
+    });
 }
 
 // Stop/reset timer
 fn stop_timer() {
     TIMER_START.store(0, Ordering::Relaxed);
     TIMER_DURATION.store(0, Ordering::Relaxed);
+    TIMER_GENERATION.fetch_add(1, Ordering::SeqCst);
+    timer_alarm::silence();
+}
+
+fn timer_done() {
+    debug!("Timer finished");
+    TIMER_START.store(0, Ordering::Relaxed);
+    TIMER_DURATION.store(0, Ordering::Relaxed);
+    timer_alarm::ring();
+    sound::play_event(sound::Event::Timer);
+    notify::event(notify::Event::TimerDone);
 }
 
 // Check if a command is a widget that needs dynamic updates
@@ -1148,15 +1696,44 @@ fn is_widget_command(cmd: &str) -> bool {
     cmd.starts_with("__CPU") ||
     cmd.starts_with("__RAM") ||
     cmd.starts_with("__TEMP") ||
+    cmd == "__UPTIME__" ||
+    cmd == "__LOAD__" ||
+    cmd == updates::COMMAND ||
+    cmd == journal::COMMAND ||
     cmd.starts_with("__TIMER") ||
     cmd == "__OBS_STATUS__" ||
+    cmd == OBS_HEALTH_COMMAND ||
     cmd == "__TWITCH_VIEWERS__" ||
-    cmd == "__TWITCH_FOLLOWERS__"
+    cmd == "__TWITCH_FOLLOWERS__" ||
+    systemd::is_systemd_command(cmd) ||
+    docker::is_docker_widget(cmd) ||
+    ci::is_ci_command(cmd) ||
+    github::is_github_command(cmd) ||
+    kubernetes::is_kube_widget(cmd) ||
+    plugins::is_plugin_widget(cmd) ||
+    elgato::is_sd_widget(cmd) ||
+    command_widget::is_command_widget(cmd) ||
+    cmd == screen::RECORD_COMMAND ||
+    cmd == color_picker::COMMAND ||
+    display::is_display_widget(cmd) ||
+    network::is_network_widget(cmd) ||
+    audio::is_audio_widget(cmd) ||
+    cmd == privacy::COMMAND ||
+    cmd == privacy::SCREEN_COMMAND ||
+    cmd == sound::MUTE_COMMAND ||
+    vlc::is_vlc_widget(cmd) ||
+    mpv::is_mpv_widget(cmd) ||
+    mpris::is_mpris_widget(cmd) ||
+    window::is_window_widget(cmd) ||
+    workspaces::is_workspace_widget(cmd) ||
+    process_monitor::is_process_command(cmd)
 }
 
 // Get the display text for a widget command
 fn get_widget_text(cmd: &str) -> Option<String> {
-    if cmd == "__CLOCK__" {
+    if let Some(format) = clock_format(cmd) {
+        Some(get_widget_clock_format(format))
+    } else if cmd == "__CLOCK__" {
         Some(get_widget_clock())
     } else if cmd == "__CLOCK_S__" {
         Some(get_widget_clock_seconds())
@@ -1172,15 +1749,104 @@ fn get_widget_text(cmd: &str) -> Option<String> {
         Some(get_widget_ram())
     } else if cmd == "__TEMP__" {
         Some(get_widget_temp())
+    } else if cmd == "__UPTIME__" {
+        Some(get_widget_uptime())
+    } else if cmd == "__LOAD__" {
+        Some(get_widget_load())
+    } else if cmd == updates::COMMAND {
+        Some(updates::get_widget_text())
+    } else if cmd == journal::COMMAND {
+        Some(journal::get_widget_text())
     } else if cmd.starts_with("__TIMER_") && cmd.ends_with("__") {
         // __TIMER_5__ means 5 minute timer, show remaining time
         Some(get_widget_timer())
     } else if cmd == "__OBS_STATUS__" {
         Some(get_obs_status_text())
+    } else if cmd == OBS_HEALTH_COMMAND {
+        Some(get_obs_health_text())
     } else if cmd == "__TWITCH_VIEWERS__" {
         Some(get_twitch_viewers_text())
     } else if cmd == "__TWITCH_FOLLOWERS__" {
         Some(get_twitch_followers_text())
+    } else if systemd::is_systemd_command(cmd) {
+        Some(systemd::get_widget_text(cmd))
+    } else if docker::is_docker_widget(cmd) {
+        Some(docker::get_widget_text(cmd))
+    } else if ci::is_ci_command(cmd) {
+        Some(ci::get_widget_text(cmd))
+    } else if github::is_github_command(cmd) {
+        Some(github::get_widget_text(cmd))
+    } else if kubernetes::is_kube_widget(cmd) {
+        Some(kubernetes::get_widget_text(cmd))
+    } else if plugins::is_plugin_command(cmd) {
+        plugins::get_widget_text(cmd)
+    } else if elgato::is_sd_command(cmd) {
+        elgato::get_widget_text(cmd)
+    } else if command_widget::is_command_widget(cmd) {
+        Some(command_widget::get_widget_text(cmd))
+    } else if cmd == screen::RECORD_COMMAND {
+        Some(screen::get_widget_text())
+    } else if cmd == color_picker::COMMAND {
+        color_picker::get_widget_text()
+    } else if display::is_display_widget(cmd) {
+        Some(display::get_widget_text(cmd))
+    } else if network::is_network_widget(cmd) {
+        Some(network::get_widget_text())
+    } else if audio::is_audio_widget(cmd) {
+        Some(audio::get_widget_text())
+    } else if cmd == privacy::COMMAND {
+        Some(privacy::get_widget_text())
+    } else if cmd == privacy::SCREEN_COMMAND {
+        Some(privacy::get_screen_text())
+    } else if cmd == sound::MUTE_COMMAND {
+        Some(sound::get_widget_text())
+    } else if vlc::is_vlc_widget(cmd) {
+        Some(vlc::get_widget_text(cmd))
+    } else if mpv::is_mpv_widget(cmd) {
+        Some(mpv::get_widget_text(cmd))
+    } else if mpris::is_mpris_widget(cmd) {
+        Some(mpris::get_widget_text(cmd))
+    } else if window::is_window_widget(cmd) {
+        Some(window::get_widget_text(cmd))
+    } else if workspaces::is_workspace_widget(cmd) {
+        Some(workspaces::get_widget_text(cmd))
+    } else if process_monitor::is_process_command(cmd) {
+        Some(process_monitor::get_widget_text(cmd))
+    } else {
+        None
+    }
+}
+
+// Get the background color override for a status widget, if any
+fn get_widget_color(cmd: &str) -> Option<String> {
+    if ci::is_ci_command(cmd) {
+        ci::get_widget_color(cmd)
+    } else if github::is_github_command(cmd) {
+        github::get_widget_color(cmd)
+    } else if command_widget::is_command_widget(cmd) {
+        command_widget::get_widget_color(cmd)
+    } else if cmd == screen::RECORD_COMMAND {
+        screen::get_widget_color()
+    } else if cmd == color_picker::COMMAND {
+        color_picker::get_widget_color()
+    } else if network::is_network_widget(cmd) {
+        network::get_widget_color()
+    } else if cmd == privacy::COMMAND {
+        privacy::get_widget_color()
+    } else if cmd == privacy::SCREEN_COMMAND {
+        privacy::get_screen_color()
+    } else if cmd == sound::MUTE_COMMAND {
+        sound::get_widget_color()
+    } else if cmd.starts_with("__TIMER") {
+        timer_alarm::widget_color()
+    } else if cmd == OBS_HEALTH_COMMAND {
+        get_obs_health_color()
+    } else if workspaces::is_workspace_widget(cmd) {
+        workspaces::get_widget_color(cmd)
+    } else if process_monitor::is_process_command(cmd) {
+        process_monitor::get_widget_color(cmd)
+    } else if cmd == journal::COMMAND {
+        journal::get_widget_color()
     } else {
         None
     }
@@ -1216,7 +1882,7 @@ struct ObsMessage {
 // Connect to OBS WebSocket and authenticate
 fn obs_connect(host: &str, port: u16, password: Option<&str>) -> Result<(), String> {
     let url = format!("ws://{}:{}", host, port);
-    eprintln!("DEBUG: OBS connecting to {}", url);
+    debug!("OBS connecting to {}", url);
 
     let (mut socket, _response) = connect(&url)
         .map_err(|e| format!("OBS connection failed: {}", e))?;
@@ -1235,7 +1901,7 @@ fn obs_connect(host: &str, port: u16, password: Option<&str>) -> Result<(), Stri
         return Err(format!("Expected Hello (op=0), got op={}", hello.op));
     }
 
-    eprintln!("DEBUG: OBS Hello received");
+    debug!("OBS Hello received");
 
     // Check if authentication is required
     let auth_data = hello.d.get("authentication");
@@ -1285,7 +1951,7 @@ fn obs_connect(host: &str, port: u16, password: Option<&str>) -> Result<(), Stri
         return Err(format!("Authentication failed (op={})", identified.op));
     }
 
-    eprintln!("DEBUG: OBS authenticated successfully");
+    debug!("OBS authenticated successfully");
 
     // Update state
     if let Ok(mut state) = OBS_STATE.write() {
@@ -1299,6 +1965,18 @@ fn obs_connect(host: &str, port: u16, password: Option<&str>) -> Result<(), Stri
     Ok(())
 }
 
+// OBS stopped answering; tell the user once per lost connection
+fn obs_unreachable() {
+    let was_connected = OBS_STATE
+        .write()
+        .map(|mut state| std::mem::replace(&mut state.connected, false))
+        .unwrap_or(false);
+    if was_connected {
+        warn!("OBS connection lost");
+        notify::event(notify::Event::ObsDisconnected);
+    }
+}
+
 // Generate OBS authentication string (SHA256)
 fn generate_obs_auth(password: &str, challenge: &str, salt: &str) -> String {
     // base64(SHA256(base64(SHA256(password + salt)) + challenge))
@@ -1316,8 +1994,16 @@ fn obs_request(request_type: &str, request_data: Option<serde_json::Value>) -> R
     let url = get_obs_websocket_url();
     let password = get_obs_password();
 
-    let (mut socket, _) = connect(&url)
-        .map_err(|e| format!("OBS connection failed: {}", e))?;
+    let (mut socket, _) = match connect(&url) {
+        Ok(s) => s,
+        Err(e) => {
+            obs_unreachable();
+            return Err(format!("OBS connection failed: {}", e));
+        }
+    };
+    if let Ok(mut state) = OBS_STATE.write() {
+        state.connected = true;
+    }
 
     // Read Hello
     let hello_msg = socket.read().map_err(|e| format!("Read error: {}", e))?;
@@ -1467,35 +2153,150 @@ fn get_obs_password() -> String {
     std::env::var("OBS_WEBSOCKET_PASSWORD").unwrap_or_default()
 }
 
+// __OBS_LAUNCH__ starts OBS when it is not running and waits until its
+// WebSocket answers. With "obsLaunch": { "autoLaunch": true } the OBS keys
+// (stream, record, mute, scenes) do the same first, so OBS Stream with OBS
+// closed opens OBS and then starts the stream.
+const OBS_LAUNCH_COMMAND: &str = "__OBS_LAUNCH__";
+// Time between two connection attempts while OBS starts
+const OBS_LAUNCH_POLL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObsLaunchSettings {
+    #[serde(default, rename = "autoLaunch")]
+    pub auto_launch: bool,
+    // Program and arguments split on whitespace (no shell), e.g.
+    // "flatpak run com.obsproject.Studio". Only the default is run in safe mode.
+    #[serde(default = "default_obs_launch_command")]
+    pub command: String,
+    #[serde(default = "default_obs_launch_timeout", rename = "timeoutSecs")]
+    pub timeout_secs: u64,
+}
+
+fn default_obs_launch_command() -> String {
+    "obs".to_string()
+}
+
+fn default_obs_launch_timeout() -> u64 {
+    30
+}
+
+impl Default for ObsLaunchSettings {
+    fn default() -> Self {
+        ObsLaunchSettings {
+            auto_launch: false,
+            command: default_obs_launch_command(),
+            timeout_secs: default_obs_launch_timeout(),
+        }
+    }
+}
+
+// Set while a launch waits for the WebSocket, so repeated presses don't start OBS twice
+static OBS_LAUNCHING: AtomicBool = AtomicBool::new(false);
+
+fn obs_process_running() -> bool {
+    sandbox::host_command("pgrep")
+        .args(["-x", "obs"])
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+// Start OBS if needed and wait until its WebSocket accepts requests
+fn obs_launch() -> Result<(), String> {
+    if obs_request("GetVersion", None).is_ok() {
+        return Ok(());
+    }
+    if OBS_LAUNCHING.swap(true, Ordering::SeqCst) {
+        return Err("OBS is already starting".to_string());
+    }
+    let result = obs_launch_and_wait();
+    OBS_LAUNCHING.store(false, Ordering::SeqCst);
+    result
+}
+
+fn obs_launch_and_wait() -> Result<(), String> {
+    let settings = config_store::read(|c| c.obs_launch.clone()).unwrap_or_default();
+    if !obs_process_running() {
+        let custom = settings.command.trim() != default_obs_launch_command();
+        if custom && config_store::read(|c| c.safe_mode).unwrap_or(false) {
+            return Err(format!("Safe mode: not running {}", settings.command));
+        }
+        let mut parts = settings.command.split_whitespace();
+        let program = parts.next().ok_or("obsLaunch.command is empty")?;
+        debug!("Starting OBS: {}", settings.command);
+        let mut child = sandbox::host_command(program)
+            .args(parts)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Failed to start OBS: {}", e))?;
+        // Reaped whenever OBS exits so it leaves no zombie
+        thread::spawn(move || child.wait());
+    }
+    let deadline = Instant::now() + Duration::from_secs(settings.timeout_secs);
+    while Instant::now() < deadline {
+        thread::sleep(OBS_LAUNCH_POLL);
+        if obs_request("GetVersion", None).is_ok() {
+            debug!("OBS WebSocket is up");
+            request_refresh();
+            return Ok(());
+        }
+    }
+    Err(format!("OBS WebSocket did not answer within {} s", settings.timeout_secs))
+}
+
+// With autoLaunch, make sure OBS is up before a request
+fn obs_auto_launch() -> Result<(), String> {
+    if config_store::read(|c| c.obs_launch.auto_launch).unwrap_or(false) {
+        obs_launch()
+    } else {
+        Ok(())
+    }
+}
+
 // OBS Commands for button presses
 fn obs_toggle_stream() {
     thread::spawn(|| {
+        if let Err(e) = obs_auto_launch() {
+            warn!("OBS launch error: {}", e);
+            return;
+        }
         match obs_request("ToggleStream", None) {
-            Ok(_) => eprintln!("DEBUG: OBS stream toggled"),
-            Err(e) => eprintln!("DEBUG: OBS toggle stream error: {}", e),
+            Ok(_) => debug!("OBS stream toggled"),
+            Err(e) => warn!("OBS toggle stream error: {}", e),
         }
     });
 }
 
 fn obs_toggle_record() {
     thread::spawn(|| {
+        if let Err(e) = obs_auto_launch() {
+            warn!("OBS launch error: {}", e);
+            return;
+        }
         match obs_request("ToggleRecord", None) {
-            Ok(_) => eprintln!("DEBUG: OBS record toggled"),
-            Err(e) => eprintln!("DEBUG: OBS toggle record error: {}", e),
+            Ok(_) => debug!("OBS record toggled"),
+            Err(e) => warn!("OBS toggle record error: {}", e),
         }
     });
 }
 
 fn obs_toggle_mute() {
     thread::spawn(|| {
+        if let Err(e) = obs_auto_launch() {
+            warn!("OBS launch error: {}", e);
+            return;
+        }
         // Toggle mute for default audio input
         match obs_request("ToggleInputMute", Some(serde_json::json!({"inputName": "Mic/Aux"}))) {
-            Ok(_) => eprintln!("DEBUG: OBS mic mute toggled"),
+            Ok(_) => debug!("OBS mic mute toggled"),
             Err(e) => {
                 // Try alternative input name
                 match obs_request("ToggleInputMute", Some(serde_json::json!({"inputName": "Desktop Audio"}))) {
-                    Ok(_) => eprintln!("DEBUG: OBS desktop audio mute toggled"),
-                    Err(e2) => eprintln!("DEBUG: OBS toggle mute error: {} / {}", e, e2),
+                    Ok(_) => debug!("OBS desktop audio mute toggled"),
+                    Err(e2) => warn!("OBS toggle mute error: {} / {}", e, e2),
                 }
             }
         }
@@ -1505,13 +2306,117 @@ fn obs_toggle_mute() {
 fn obs_set_scene(scene_name: &str) {
     let scene = scene_name.to_string();
     thread::spawn(move || {
+        if let Err(e) = obs_auto_launch() {
+            warn!("OBS launch error: {}", e);
+            return;
+        }
         match obs_request("SetCurrentProgramScene", Some(serde_json::json!({"sceneName": scene}))) {
-            Ok(_) => eprintln!("DEBUG: OBS scene changed to: {}", scene),
-            Err(e) => eprintln!("DEBUG: OBS set scene error: {}", e),
+            Ok(_) => debug!("OBS scene changed to: {}", scene),
+            Err(e) => warn!("OBS set scene error: {}", e),
         }
     });
 }
 
+// Scene names in OBS order, and the current program scene
+fn obs_scene_list() -> Result<(Vec<String>, String), String> {
+    let data = obs_request("GetSceneList", None)?;
+    let mut scenes: Vec<String> = data
+        .get("scenes")
+        .and_then(|v| v.as_array())
+        .map(|list| {
+            list.iter()
+                .filter_map(|s| s.get("sceneName").and_then(|n| n.as_str()))
+                .map(|n| n.to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    // OBS lists them bottom to top
+    scenes.reverse();
+    let current = data.get("currentProgramSceneName").and_then(|v| v.as_str()).unwrap_or_default();
+    Ok((scenes, current.to_string()))
+}
+
+// __OBS_CHAPTER__: mark a chapter in the running recording. OBS only has
+// CreateRecordChapter from obs-websocket 5.5 and with Hybrid MP4 output; with
+// anything else the recording time goes to obs-chapters.txt in the recording
+// directory instead.
+const OBS_CHAPTER_COMMAND: &str = "__OBS_CHAPTER__";
+const OBS_CHAPTER_FILE: &str = "obs-chapters.txt";
+
+fn obs_create_chapter() -> Result<(), String> {
+    let status = obs_request("GetRecordStatus", None)?;
+    if !status.get("outputActive").and_then(|v| v.as_bool()).unwrap_or(false) {
+        return Err("OBS is not recording".to_string());
+    }
+    let error = match obs_request("CreateRecordChapter", None) {
+        Ok(_) => {
+            debug!("OBS chapter marker added");
+            return Ok(());
+        }
+        Err(e) => e,
+    };
+    debug!("CreateRecordChapter failed ({}), writing {}", error, OBS_CHAPTER_FILE);
+
+    let timecode = status.get("outputTimecode").and_then(|v| v.as_str()).unwrap_or("?");
+    let directory = obs_request("GetRecordDirectory", None)?;
+    let directory = directory
+        .get("recordDirectory")
+        .and_then(|v| v.as_str())
+        .ok_or("OBS did not report its recording directory")?;
+    let path = Path::new(directory).join(OBS_CHAPTER_FILE);
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let now = Local::now().format("%Y-%m-%d %H:%M:%S");
+    writeln!(file, "{}\t{}", now, timecode).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+// __OBS_SCENES__: one key per scene, the live one highlighted
+const OBS_SCENES_COMMAND: &str = "__OBS_SCENES__";
+const OBS_SCENE_COLOR: &str = "#302b63";
+const OBS_LIVE_SCENE_COLOR: &str = "#c0392b";
+
+struct ObsScenePage;
+
+impl virtual_page::PageProvider for ObsScenePage {
+    fn title(&self) -> String {
+        "OBS Scenes".to_string()
+    }
+
+    fn entries(&mut self) -> Vec<virtual_page::ListEntry> {
+        let (scenes, current) = match obs_scene_list() {
+            Ok(list) => list,
+            Err(e) => {
+                warn!("OBS scene list: {}", e);
+                return Vec::new();
+            }
+        };
+        scenes
+            .iter()
+            .map(|scene| virtual_page::ListEntry {
+                color: if *scene == current { OBS_LIVE_SCENE_COLOR.to_string() } else { String::new() },
+                ..virtual_page::ListEntry::new(scene)
+            })
+            .collect()
+    }
+
+    fn color(&self) -> String {
+        OBS_SCENE_COLOR.to_string()
+    }
+
+    fn pressed(&mut self, _index: usize, entry: &virtual_page::ListEntry) -> Result<virtual_page::AfterPress, String> {
+        obs_request("SetCurrentProgramScene", Some(serde_json::json!({ "sceneName": entry.label })))?;
+        Ok(virtual_page::AfterPress::Refresh)
+    }
+
+    // Follow scene changes made in OBS itself
+    fn refresh_every(&self) -> Option<Duration> {
+        Some(Duration::from_secs(2))
+    }
+}
+
 // Get OBS status text for widget display
 fn get_obs_status_text() -> String {
     // Try to update status first (non-blocking)
@@ -1534,7 +2439,7 @@ fn get_obs_status_text() -> String {
     // Return current cached state
     if let Ok(state) = OBS_STATE.read() {
         if !state.connected {
-            return "OBS OFF".to_string();
+            return i18n::tr("obs.off").to_string();
         }
         let s = if state.streaming { "LIVE" } else { "---" };
         let r = if state.recording { "REC" } else { "---" };
@@ -1544,6 +2449,163 @@ fn get_obs_status_text() -> String {
     }
 }
 
+// __OBS_HEALTH__: dropped frames and bitrate of the running stream, yellow or
+// red past the "obsHealth" thresholds. Both are measured between two polls so
+// a dying connection shows within seconds instead of being averaged away.
+pub(crate) const OBS_HEALTH_COMMAND: &str = "__OBS_HEALTH__";
+const OBS_HEALTH_POLL_SECS: u64 = 2;
+const OBS_HEALTH_WARN_COLOR: &str = "#f39c12";
+const OBS_HEALTH_ALERT_COLOR: &str = "#c0392b";
+
+// Thresholds for __OBS_HEALTH__; a bitrate threshold of 0 is off
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObsHealthSettings {
+    #[serde(default = "default_obs_warn_drop", rename = "warnDropPercent")]
+    pub warn_drop_percent: f64,
+    #[serde(default = "default_obs_alert_drop", rename = "alertDropPercent")]
+    pub alert_drop_percent: f64,
+    #[serde(default, rename = "warnBitrateKbps")]
+    pub warn_bitrate_kbps: u64,
+    #[serde(default, rename = "alertBitrateKbps")]
+    pub alert_bitrate_kbps: u64,
+}
+
+fn default_obs_warn_drop() -> f64 {
+    1.0
+}
+
+fn default_obs_alert_drop() -> f64 {
+    5.0
+}
+
+impl Default for ObsHealthSettings {
+    fn default() -> Self {
+        Self {
+            warn_drop_percent: default_obs_warn_drop(),
+            alert_drop_percent: default_obs_alert_drop(),
+            warn_bitrate_kbps: 0,
+            alert_bitrate_kbps: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ObsHealth {
+    Offline,
+    NotLive,
+    // Live, waiting for a second sample to measure against
+    Starting,
+    Live { drop_percent: f64, kbps: u64 },
+    Error,
+}
+
+// Counters of the previous poll
+#[derive(Debug, Clone, Copy)]
+struct ObsStreamSample {
+    at: Instant,
+    bytes: u64,
+    // Frames dropped by the network (GetStreamStatus)
+    network_skipped: u64,
+    network_total: u64,
+    // Frames skipped by an overloaded encoder (GetStats)
+    encoder_skipped: u64,
+    encoder_total: u64,
+}
+
+lazy_static::lazy_static! {
+    static ref OBS_HEALTH_SAMPLE: Mutex<Option<ObsStreamSample>> = Mutex::new(None);
+}
+
+fn drop_percent(skipped: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        skipped as f64 * 100.0 / total as f64
+    }
+}
+
+fn obs_health() -> ObsHealth {
+    let stream = match obs_request("GetStreamStatus", None) {
+        Ok(data) => data,
+        Err(_) if !OBS_STATE.read().map(|s| s.connected).unwrap_or(false) => return ObsHealth::Offline,
+        Err(e) => {
+            debug!("OBS health: {}", e);
+            return ObsHealth::Error;
+        }
+    };
+    let field = |data: &serde_json::Value, name: &str| data.get(name).and_then(|v| v.as_u64()).unwrap_or(0);
+    if !stream.get("outputActive").and_then(|v| v.as_bool()).unwrap_or(false) {
+        if let Ok(mut sample) = OBS_HEALTH_SAMPLE.lock() {
+            *sample = None;
+        }
+        return ObsHealth::NotLive;
+    }
+    let stats = obs_request("GetStats", None).unwrap_or(serde_json::Value::Null);
+    let sample = ObsStreamSample {
+        at: Instant::now(),
+        bytes: field(&stream, "outputBytes"),
+        network_skipped: field(&stream, "outputSkippedFrames"),
+        network_total: field(&stream, "outputTotalFrames"),
+        encoder_skipped: field(&stats, "outputSkippedFrames"),
+        encoder_total: field(&stats, "outputTotalFrames"),
+    };
+
+    let previous = match OBS_HEALTH_SAMPLE.lock() {
+        Ok(mut last) => last.replace(sample),
+        Err(_) => return ObsHealth::Error,
+    };
+    // A restarted stream starts its counters over
+    let previous = match previous.filter(|p| p.bytes <= sample.bytes && p.network_total <= sample.network_total) {
+        Some(p) => p,
+        None => return ObsHealth::Starting,
+    };
+    let secs = sample.at.duration_since(previous.at).as_secs_f64();
+    if secs <= 0.0 {
+        return ObsHealth::Starting;
+    }
+    let network = drop_percent(
+        sample.network_skipped.saturating_sub(previous.network_skipped),
+        sample.network_total - previous.network_total,
+    );
+    let encoder = drop_percent(
+        sample.encoder_skipped.saturating_sub(previous.encoder_skipped),
+        sample.encoder_total.saturating_sub(previous.encoder_total),
+    );
+    ObsHealth::Live {
+        drop_percent: network.max(encoder),
+        kbps: ((sample.bytes - previous.bytes) as f64 * 8.0 / 1000.0 / secs) as u64,
+    }
+}
+
+fn get_obs_health_text() -> String {
+    match poller::poll(OBS_HEALTH_COMMAND, OBS_HEALTH_POLL_SECS, obs_health) {
+        Some(ObsHealth::Offline) => i18n::tr("obs.off").to_string(),
+        Some(ObsHealth::NotLive) => i18n::tr("obs.not_live").to_string(),
+        Some(ObsHealth::Live { drop_percent, kbps }) => {
+            format!("{:.1}% {:.1}M", drop_percent, kbps as f64 / 1000.0)
+        }
+        Some(ObsHealth::Error) => "ERR".to_string(),
+        Some(ObsHealth::Starting) | None => "...".to_string(),
+    }
+}
+
+fn get_obs_health_color() -> Option<String> {
+    let (drop_percent, kbps) = match poller::peek::<ObsHealth>(OBS_HEALTH_COMMAND)? {
+        ObsHealth::Live { drop_percent, kbps } => (drop_percent, kbps),
+        ObsHealth::Error => return Some(OBS_HEALTH_ALERT_COLOR.to_string()),
+        _ => return None,
+    };
+    let limits = config_store::read(|c| c.obs_health.clone()).unwrap_or_default();
+    let below = |threshold: u64| threshold > 0 && kbps < threshold;
+    if drop_percent >= limits.alert_drop_percent || below(limits.alert_bitrate_kbps) {
+        Some(OBS_HEALTH_ALERT_COLOR.to_string())
+    } else if drop_percent >= limits.warn_drop_percent || below(limits.warn_bitrate_kbps) {
+        Some(OBS_HEALTH_WARN_COLOR.to_string())
+    } else {
+        None
+    }
+}
+
 // ============================================================================
 // Twitch API Integration
 // ============================================================================
@@ -1693,7 +2755,7 @@ fn twitch_send_chat(message: &str) {
         };
 
         if broadcaster_id.is_empty() {
-            eprintln!("DEBUG: Twitch not connected");
+            debug!("Twitch not connected");
             return;
         }
 
@@ -1710,7 +2772,7 @@ fn twitch_send_chat(message: &str) {
             }))
             .send();
 
-        eprintln!("DEBUG: Twitch chat message sent: {}", msg);
+        debug!("Twitch chat message sent: {}", msg);
     });
 }
 
@@ -1726,7 +2788,7 @@ fn twitch_create_clip() {
         };
 
         if broadcaster_id.is_empty() {
-            eprintln!("DEBUG: Twitch not connected");
+            debug!("Twitch not connected");
             return;
         }
 
@@ -1740,11 +2802,11 @@ fn twitch_create_clip() {
             Ok(resp) => {
                 if let Ok(data) = resp.json::<serde_json::Value>() {
                     if let Some(clip_id) = data["data"][0]["id"].as_str() {
-                        eprintln!("DEBUG: Twitch clip created: {}", clip_id);
+                        debug!("Twitch clip created: {}", clip_id);
                     }
                 }
             }
-            Err(e) => eprintln!("DEBUG: Twitch create clip error: {}", e),
+            Err(e) => warn!("Twitch create clip error: {}", e),
         }
     });
 }
@@ -1761,7 +2823,7 @@ fn twitch_run_commercial(length: u32) {
         };
 
         if broadcaster_id.is_empty() {
-            eprintln!("DEBUG: Twitch not connected");
+            debug!("Twitch not connected");
             return;
         }
 
@@ -1777,7 +2839,7 @@ fn twitch_run_commercial(length: u32) {
             }))
             .send();
 
-        eprintln!("DEBUG: Twitch commercial started: {}s", length);
+        debug!("Twitch commercial started: {}s", length);
     });
 }
 
@@ -1845,6 +2907,344 @@ fn get_twitch_followers_text() -> String {
     }
 }
 
+// ============================================================================
+// Action Dispatch
+// ============================================================================
+
+// Safe mode ("safeMode" in config.json) lets only structured actions run:
+// no shell commands, scripts, program launches or ssh sessions, also not
+// inside a multi-action. Plugins are not started either (start_plugins).
+fn check_safe_mode(action: &Action) -> Result<(), String> {
+    if action.runs_code() && config_store::read(|c| c.safe_mode).unwrap_or(false) {
+        return Err(format!("Safe mode: not running {}", action.to_command()));
+    }
+    Ok(())
+}
+
+// Execute a button action. Slow work runs in background threads.
+fn execute_action(action: &Action, config_path: &Path, icons_path: &Path) -> Result<(), String> {
+    check_safe_mode(action)?;
+    match action {
+        Action::None | Action::Delay { .. } => Ok(()),
+        Action::Shell { command, options } => {
+            debug!("Executing command: {}", command);
+            // Waited on in the background so failures reach the action log
+            let process = shell_process(command, options);
+            let command = command.clone();
+            thread::spawn(move || action_log::run_logged(&command, process));
+            Ok(())
+        }
+        Action::Url { url } => {
+            debug!("Opening URL: {}", url);
+            sandbox::open_uri(url)
+        }
+        Action::Hotkey { keys } => {
+            debug!("Pressing keys: {}", keys);
+            execute_hotkey(keys);
+            Ok(())
+        }
+        Action::Type { text } => {
+            let text = variables::interpolate(text);
+            debug!("Typing text: {}", text);
+            thread::spawn(move || input::type_text(&text));
+            Ok(())
+        }
+        Action::Mouse { action } => {
+            let action = *action;
+            thread::spawn(move || input::mouse(action));
+            Ok(())
+        }
+        Action::Multi { steps } => {
+            let steps = steps.clone();
+            let config_path = config_path.to_path_buf();
+            let icons_path = icons_path.to_path_buf();
+            thread::spawn(move || run_action_steps(&steps, &config_path, &icons_path));
+            Ok(())
+        }
+        Action::PageNav { target } => {
+            navigate_page(*target, config_path, icons_path);
+            Ok(())
+        }
+        Action::Timer { minutes } => {
+            toggle_timer(*minutes);
+            Ok(())
+        }
+        Action::ObsStream => {
+            debug!("OBS toggle stream");
+            obs_toggle_stream();
+            Ok(())
+        }
+        Action::ObsRecord => {
+            debug!("OBS toggle record");
+            alerts::expect_recording_change();
+            obs_toggle_record();
+            Ok(())
+        }
+        Action::ObsMute => {
+            debug!("OBS toggle mute");
+            obs_toggle_mute();
+            Ok(())
+        }
+        Action::ObsScene { scene } => {
+            debug!("OBS set scene: {}", scene);
+            obs_set_scene(scene);
+            Ok(())
+        }
+        Action::TwitchChat { message } => {
+            debug!("Twitch chat: {}", message);
+            twitch_send_chat(message);
+            Ok(())
+        }
+        Action::TwitchClip => {
+            debug!("Twitch create clip");
+            twitch_create_clip();
+            Ok(())
+        }
+        Action::TwitchAd { length } => {
+            debug!("Twitch commercial: {}s", length);
+            twitch_run_commercial(*length);
+            Ok(())
+        }
+        Action::Widget { .. } => {
+            // Widgets don't execute anything when pressed, they just display info
+            // But we can request a refresh to show updated value
+            request_refresh();
+            Ok(())
+        }
+        Action::Script { name } => {
+            let name = name.clone();
+            let config_path = config_path.to_path_buf();
+            let icons_path = icons_path.to_path_buf();
+            thread::spawn(move || {
+                if let Err(e) = scripting::run(&name, &config_path, &icons_path) {
+                    warn!("{}", e);
+                }
+            });
+            Ok(())
+        }
+        Action::SetVar { name, value } => {
+            debug!("Setting variable {}", name);
+            variables::set(name, &variables::interpolate(value))
+        }
+        Action::Integration { command } => execute_integration(command, config_path),
+    }
+}
+
+// Process for a shell action: `sh -c`, or the user's login shell, with the
+// button's working directory and environment. ${var} references are expanded.
+fn shell_process(command: &str, options: &ShellOptions) -> Command {
+    let command = variables::interpolate(command);
+    let dir = options.working_dir();
+    let env = options.env.iter().map(|(name, value)| (name, variables::interpolate(value)));
+    // On the host in Flatpak (see sandbox.rs)
+    let mut process = if options.login_shell {
+        let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string());
+        let mut process = sandbox::host_command_with(shell, dir.as_deref(), env);
+        process.arg("-l");
+        process
+    } else {
+        sandbox::host_command_with("sh", dir.as_deref(), env)
+    };
+    process.arg("-c").arg(command);
+    process
+}
+
+// Run __MULTI_ steps in order, waiting for each one to finish (blocking)
+fn run_action_steps(steps: &[Action], config_path: &PathBuf, icons_path: &PathBuf) {
+    for step in steps {
+        debug!("Multi-action step: {:?}", step);
+        let result = match step {
+            Action::Shell { command, options } => action_log::run_logged(command, shell_process(command, options)),
+            Action::Hotkey { keys } => {
+                execute_hotkey_sync(keys);
+                Ok(())
+            }
+            Action::Type { text } => {
+                input::type_text(&variables::interpolate(text));
+                Ok(())
+            }
+            Action::Mouse { action } => {
+                input::mouse(*action);
+                Ok(())
+            }
+            Action::Delay { ms } => {
+                thread::sleep(Duration::from_millis(*ms));
+                Ok(())
+            }
+            Action::Multi { steps } => {
+                run_action_steps(steps, config_path, icons_path);
+                Ok(())
+            }
+            Action::Script { name } => scripting::run(name, config_path, icons_path).map(|_| ()),
+            other => execute_action(other, config_path, icons_path),
+        };
+        if let Err(e) = result {
+            warn!("Multi-action step failed: {}", e);
+        }
+        // Small delay between actions
+        thread::sleep(Duration::from_millis(100));
+    }
+}
+
+// Commands owned by the integration modules
+fn execute_integration(cmd: &str, config_path: &Path) -> Result<(), String> {
+    if systemd::is_systemd_command(cmd) {
+        debug!("systemd toggle: {}", cmd);
+        systemd::toggle_from_command(cmd);
+    } else if apps::is_app_command(cmd) {
+        return apps::execute_command(cmd);
+    } else if cmd == color_picker::COMMAND {
+        color_picker::execute_command();
+    } else if display::is_display_command(cmd) {
+        return display::execute_command(cmd);
+    } else if docker::is_docker_command(cmd) {
+        debug!("Docker action: {}", cmd);
+        docker::execute_command(cmd);
+    } else if ci::is_ci_command(cmd) {
+        ci::open_run(cmd);
+    } else if github::is_github_command(cmd) {
+        github::open_page(cmd);
+    } else if snippets::is_snippet_command(cmd) {
+        return snippets::execute_command(cmd, config_path);
+    } else if clipboard::is_clipboard_command(cmd) {
+        return clipboard::execute_command(cmd);
+    } else if ssh::is_ssh_command(cmd) {
+        return ssh::execute_command(cmd);
+    } else if cmd == window_switcher::COMMAND {
+        debug!("Window switcher");
+        window_switcher::open_page();
+    } else if workspaces::is_workspace_widget(cmd) {
+        return workspaces::execute_command(cmd);
+    } else if process_monitor::is_process_command(cmd) {
+        process_monitor::execute_command(cmd);
+    } else if cmd == updates::COMMAND {
+        return updates::execute_command();
+    } else if cmd == journal::COMMAND {
+        return journal::execute_command();
+    } else if kubernetes::is_kube_command(cmd) {
+        debug!("Kube action: {}", cmd);
+        kubernetes::execute_command(cmd);
+    } else if midi::is_midi_command(cmd) {
+        return midi::execute_command(cmd);
+    } else if mpv::is_mpv_command(cmd) {
+        return mpv::execute_command(cmd);
+    } else if mpris::is_mpris_command(cmd) {
+        return mpris::execute_command(cmd);
+    } else if network::is_network_command(cmd) {
+        network::execute_command(cmd);
+    } else if audio::is_audio_command(cmd) {
+        audio::execute_command(cmd);
+    } else if notify::is_notify_command(cmd) {
+        return notify::execute_command(cmd);
+    } else if numpad::is_numpad_command(cmd) {
+        return numpad::execute_command(cmd, config_path);
+    } else if plugins::is_plugin_command(cmd) {
+        return plugins::execute_command(cmd);
+    } else if elgato::is_sd_command(cmd) {
+        return elgato::execute_command(cmd);
+    } else if command_widget::is_command_widget(cmd) {
+        command_widget::execute_command(cmd);
+    } else if power::is_power_command(cmd) {
+        return power::execute_command(cmd);
+    } else if cmd == privacy::COMMAND || cmd == privacy::SCREEN_COMMAND {
+        privacy::execute_command();
+    } else if profiles::is_profile_command(cmd) {
+        return profiles::execute_command(cmd, config_path);
+    } else if scheduler::is_schedule_command(cmd) {
+        return scheduler::execute_command(cmd, config_path);
+    } else if screen::is_screen_command(cmd) {
+        return screen::execute_command(cmd);
+    } else if sound::is_sound_command(cmd) {
+        return sound::execute_command(config_path);
+    } else if vlc::is_vlc_command(cmd) {
+        return vlc::execute_command(cmd);
+    } else if virtual_page::is_list_command(cmd) {
+        return virtual_page::execute_list_command(cmd);
+    } else if cmd == OBS_SCENES_COMMAND {
+        virtual_page::open_provider(Box::new(ObsScenePage));
+    } else if cmd == OBS_LAUNCH_COMMAND {
+        thread::spawn(|| {
+            if let Err(e) = obs_launch() {
+                action_log::record_error(OBS_LAUNCH_COMMAND, &format!("OBS launch: {}", e));
+            }
+        });
+    } else if cmd == OBS_CHAPTER_COMMAND {
+        thread::spawn(|| {
+            if let Err(e) = obs_create_chapter() {
+                action_log::record_error(OBS_CHAPTER_COMMAND, &format!("OBS chapter: {}", e));
+            }
+        });
+    } else {
+        return Err(format!("Unknown integration command: {}", cmd));
+    }
+    Ok(())
+}
+
+fn navigate_page(target: PageTarget, config_path: &Path, icons_path: &Path) {
+    // Leave a generated page and return to the config page
+    if target == PageTarget::Close {
+        virtual_page::close();
+        return;
+    }
+
+    let (current_page, page_count) = match config_store::read(|c| (c.current_page, c.pages.len())) {
+        Some(p) => p,
+        None => return,
+    };
+    if page_count == 0 {
+        return;
+    }
+
+    let page_index = match target {
+        PageTarget::Next => (current_page + 1) % page_count,
+        PageTarget::Prev => {
+            if current_page == 0 {
+                page_count - 1
+            } else {
+                current_page - 1
+            }
+        }
+        PageTarget::Index(index) => index,
+        PageTarget::Close => return,
+    };
+    // change_page ignores indexes past the last page
+    change_page(page_index, config_path, icons_path);
+}
+
+// App state exposed to scripts through get_state(name)
+fn get_app_state_value(name: &str) -> serde_json::Value {
+    let config: Option<Config> = config_store::read(|c| c.clone());
+    let obs = OBS_STATE.read().ok();
+    match name {
+        "page" => config.map(|c| c.current_page.into()).unwrap_or_default(),
+        "page_name" => config
+            .and_then(|c| c.pages.get(c.current_page).map(|p| p.name.clone().into()))
+            .unwrap_or_default(),
+        "brightness" => config.map(|c| c.brightness.into()).unwrap_or_default(),
+        "obs_connected" => obs.map(|s| s.connected).unwrap_or(false).into(),
+        "obs_streaming" => obs.map(|s| s.streaming).unwrap_or(false).into(),
+        "obs_recording" => obs.map(|s| s.recording).unwrap_or(false).into(),
+        "timer_running" => (TIMER_START.load(Ordering::Relaxed) > 0).into(),
+        _ => serde_json::Value::Null,
+    }
+}
+
+// __TIMER_N__: start an N minute timer, or stop the running one
+fn toggle_timer(minutes: u64) {
+    let current_start = TIMER_START.load(Ordering::Relaxed);
+    if current_start > 0 {
+        // Timer is running, stop it
+        stop_timer();
+        debug!("Timer stopped");
+    } else {
+        // Start timer with N minutes
+        start_timer(minutes * 60);
+        debug!("Timer started for {} minutes", minutes);
+    }
+    // Request refresh to update display
+    request_refresh();
+}
+
 // ============================================================================
 // Button Listener Functions
 // ============================================================================
@@ -1855,8 +3255,12 @@ fn read_key_press(handle: &DeviceHandle<Context>) -> Result<(u8, u8), String> {
     let mut buf = [0u8; 512];
 
     // Read from endpoint 0x82 (IN endpoint)
-    match handle.read_interrupt(0x82, &mut buf, Duration::from_millis(100)) {
+    let timeout = Duration::from_millis(usb_settings().read_timeout_ms);
+    match handle.read_interrupt(0x82, &mut buf, timeout) {
         Ok(len) => {
+            if protocol_console::developer_enabled() {
+                protocol_console::dump("IN ", &buf[..len]);
+            }
             if len >= 11 {
                 let physical_key = buf[9];
                 let state = buf[10];
@@ -1869,280 +3273,259 @@ fn read_key_press(handle: &DeviceHandle<Context>) -> Result<(u8, u8), String> {
         Err(rusb::Error::Timeout) => {
             Err("timeout".to_string())
         }
+        // A hiccup on the IN endpoint is not worth a reconnect
+        Err(e) if !is_fatal_usb_error(&e) => {
+            warn!("USB read error (ignored): {:?}", e);
+            if e == rusb::Error::Pipe {
+                handle.clear_halt(0x82).ok();
+            }
+            Err("timeout".to_string())
+        }
         Err(e) => {
             Err(format!("USB read error: {}", e))
         }
     }
 }
 
-// Handle a button press - execute the associated command
-fn handle_button_press(key_id: u8, config_path: &PathBuf, icons_path: &PathBuf) {
-    // Read current config from file
-    let config: Config = match fs::read_to_string(config_path) {
-        Ok(content) => match serde_json::from_str(&content) {
-            Ok(c) => c,
-            Err(_) => return,
-        },
-        Err(_) => return,
-    };
-
-    let page = match config.pages.get(config.current_page) {
-        Some(p) => p,
-        None => return,
-    };
-
-    let button = match page.buttons.get(&key_id.to_string()) {
-        Some(b) => b,
-        None => return,
-    };
-
-    if button.command.is_empty() {
-        return;
-    }
+// The button on the page shown on the deck
+fn button_for_key(key_id: u8) -> Option<ButtonConfig> {
+    // A generated page (e.g. the SSH host picker) replaces the config page
+    let mut page = virtual_page::active()
+        .or_else(|| config_store::read(|c| c.pages.get(c.current_page).cloned()).flatten())?;
+    page.buttons.remove(&key_id.to_string())
+}
 
-    let cmd = &button.command;
-    eprintln!("DEBUG: Button {} pressed, command: {}", key_id, cmd);
+// Seek and volume nudges run again while their key is held
+const REPEAT_DELAY: Duration = Duration::from_millis(400);
+const REPEAT_INTERVAL: Duration = Duration::from_millis(200);
 
-    // Handle special page navigation commands
-    if cmd == "__NEXT_PAGE__" {
-        let next_page = (config.current_page + 1) % config.pages.len();
-        change_page(next_page, config_path, icons_path);
-        return;
-    }
+fn repeats_while_held(cmd: &str) -> bool {
+    [mpris::PREFIX, vlc::PREFIX, mpv::PREFIX]
+        .iter()
+        .any(|prefix| player::parse(cmd, prefix).map(|c| c.repeats()).unwrap_or(false))
+}
 
-    if cmd == "__PREV_PAGE__" {
-        let prev_page = if config.current_page == 0 {
-            config.pages.len() - 1
-        } else {
-            config.current_page - 1
-        };
-        change_page(prev_page, config_path, icons_path);
-        return;
-    }
+// A held key and when its action runs next
+struct HeldRepeat {
+    key_id: u8,
+    next: Instant,
+}
 
-    // Check for __PAGE_N__ pattern
-    if cmd.starts_with("__PAGE_") && cmd.ends_with("__") {
-        let page_str = &cmd[7..cmd.len()-2];
-        if let Ok(target_page) = page_str.parse::<usize>() {
-            if target_page < config.pages.len() {
-                change_page(target_page, config_path, icons_path);
-            }
-        }
+// Run a held key's action again (no sound, not counted in the usage stats)
+fn repeat_button_press(key_id: u8, config_path: &Path, icons_path: &Path) {
+    if lock_screen::locked() {
         return;
     }
-
-    // Handle __TIMER_N__ - start/stop timer (N = minutes)
-    if cmd.starts_with("__TIMER_") && cmd.ends_with("__") {
-        let timer_str = &cmd[8..cmd.len()-2];
-        if let Ok(minutes) = timer_str.parse::<u64>() {
-            // Toggle timer: if running, stop; if stopped, start
-            let current_start = TIMER_START.load(Ordering::Relaxed);
-            if current_start > 0 {
-                // Timer is running, stop it
-                stop_timer();
-                eprintln!("DEBUG: Timer stopped");
-            } else {
-                // Start timer with N minutes
-                start_timer(minutes * 60);
-                eprintln!("DEBUG: Timer started for {} minutes", minutes);
-            }
-            // Request refresh to update display
-            request_refresh();
+    if let Some(button) = button_for_key(key_id) {
+        if let Err(e) = execute_action(&button.resolved_action(), config_path, icons_path) {
+            warn!("Button {} repeat failed: {}", key_id, e);
         }
-        return;
-    }
-
-    // Handle widget display commands (they don't execute anything, just display)
-    if cmd == "__CLOCK__" || cmd == "__CLOCK_S__" ||
-       cmd == "__DATE__" || cmd == "__DATE_FULL__" ||
-       cmd == "__WEEKDAY__" ||
-       cmd == "__CPU__" || cmd == "__RAM__" || cmd == "__TEMP__" ||
-       cmd == "__OBS_STATUS__" || cmd == "__TWITCH_VIEWERS__" || cmd == "__TWITCH_FOLLOWERS__" {
-        // Widgets don't execute anything when pressed, they just display info
-        // But we can request a refresh to show updated value
-        request_refresh();
-        return;
     }
+}
 
-    // Handle OBS commands
-    if cmd == "__OBS_STREAM__" {
-        eprintln!("DEBUG: OBS toggle stream");
-        obs_toggle_stream();
-        return;
-    }
-    if cmd == "__OBS_RECORD__" {
-        eprintln!("DEBUG: OBS toggle record");
-        obs_toggle_record();
-        return;
-    }
-    if cmd == "__OBS_MUTE__" {
-        eprintln!("DEBUG: OBS toggle mute");
-        obs_toggle_mute();
+// Handle a button press - execute the associated command
+fn handle_button_press(key_id: u8, config_path: &Path, icons_path: &Path) {
+    // Nothing runs while the screen is locked
+    if lock_screen::locked() {
+        debug!("Session locked, ignoring key {}", key_id);
         return;
     }
-    // Handle __OBS_SCENE_scenename pattern
-    if cmd.starts_with("__OBS_SCENE_") {
-        let scene_name = &cmd[12..];
-        eprintln!("DEBUG: OBS set scene: {}", scene_name);
-        obs_set_scene(scene_name);
-        return;
+    let button = match button_for_key(key_id) {
+        Some(b) => b,
+        None => return,
+    };
+    usage_stats::record(key_id, &button.command);
+    if let Some(sound) = &button.sound {
+        sound::play(sound);
     }
 
-    // Handle Twitch commands
-    // __TWITCH_CHAT_message - send chat message
-    if cmd.starts_with("__TWITCH_CHAT_") {
-        let message = &cmd[14..];
-        eprintln!("DEBUG: Twitch chat: {}", message);
-        twitch_send_chat(message);
-        return;
-    }
-    // __TWITCH_CLIP__ - create clip
-    if cmd == "__TWITCH_CLIP__" {
-        eprintln!("DEBUG: Twitch create clip");
-        twitch_create_clip();
-        return;
-    }
-    // __TWITCH_AD_30__, __TWITCH_AD_60__, etc. - run commercial
-    if cmd.starts_with("__TWITCH_AD_") && cmd.ends_with("__") {
-        let length_str = &cmd[12..cmd.len()-2];
-        if let Ok(length) = length_str.parse::<u32>() {
-            eprintln!("DEBUG: Twitch commercial: {}s", length);
-            twitch_run_commercial(length);
+    // The only action that depends on which key was pressed
+    if button.command == midi::KEY_COMMAND {
+        if let Err(e) = midi::send_key(key_id) {
+            warn!("Button {} action failed: {}", key_id, e);
         }
         return;
     }
 
-    // Handle __URL_ command - open URL in default browser
-    if cmd.starts_with("__URL_") {
-        let url = &cmd[6..];
-        eprintln!("DEBUG: Opening URL: {}", url);
-        let url_clone = url.to_string();
-        thread::spawn(move || {
-            Command::new("xdg-open")
-                .arg(&url_clone)
-                .spawn()
-                .ok();
-        });
-        return;
-    }
-
-    // Handle __TYPE_ command - type text using ydotool
-    if cmd.starts_with("__TYPE_") {
-        let text = &cmd[7..];
-        eprintln!("DEBUG: Typing text: {}", text);
-        let text_clone = text.to_string();
-        thread::spawn(move || {
-            Command::new("ydotool")
-                .args(["type", "--clearmodifiers", &text_clone])
-                .spawn()
-                .ok();
-        });
-        return;
-    }
-
-    // Handle __KEY_ command - simulate key press using ydotool
-    if cmd.starts_with("__KEY_") {
-        let keys = &cmd[6..];
-        eprintln!("DEBUG: Pressing keys: {}", keys);
-        execute_hotkey(keys);
+    let action = button.resolved_action();
+    if action == Action::None {
         return;
     }
 
-    // Handle __MULTI_ command - execute multiple commands in sequence
-    if cmd.starts_with("__MULTI_") {
-        let commands = &cmd[8..];
-        eprintln!("DEBUG: Executing multi-action: {}", commands);
-        let commands_clone = commands.to_string();
-        thread::spawn(move || {
-            for single_cmd in commands_clone.split(";;") {
-                let trimmed = single_cmd.trim();
-                if !trimmed.is_empty() {
-                    eprintln!("DEBUG: Multi-action step: {}", trimmed);
-                    // Handle special commands within multi-action
-                    if trimmed.starts_with("__URL_") {
-                        let url = &trimmed[6..];
-                        Command::new("xdg-open").arg(url).spawn().ok();
-                    } else if trimmed.starts_with("__TYPE_") {
-                        let text = &trimmed[7..];
-                        Command::new("ydotool")
-                            .args(["type", "--clearmodifiers", text])
-                            .status()
-                            .ok();
-                    } else if trimmed.starts_with("__KEY_") {
-                        let keys = &trimmed[6..];
-                        execute_hotkey_sync(keys);
-                    } else if trimmed.starts_with("__DELAY_") {
-                        if let Ok(ms) = trimmed[8..].parse::<u64>() {
-                            thread::sleep(Duration::from_millis(ms));
-                        }
-                    } else {
-                        // Normal shell command
-                        Command::new("sh")
-                            .arg("-c")
-                            .arg(trimmed)
-                            .status()
-                            .ok();
-                    }
-                    // Small delay between actions
-                    thread::sleep(Duration::from_millis(100));
-                }
+    debug!("Button {} pressed, action: {:?}", key_id, action);
+    match execute_action(&action, config_path, icons_path) {
+        Ok(()) => {
+            if action == Action::TwitchClip {
+                overlay::show(key_id, "Clip!", overlay::DEFAULT_DURATION);
             }
-        });
-        return;
+        }
+        Err(e) => {
+            warn!("Button {} action failed: {}", key_id, e);
+            overlay::show_with_color(key_id, "Error", overlay::ERROR_COLOR, overlay::DEFAULT_DURATION);
+        }
     }
-
-    // Execute normal command
-    eprintln!("DEBUG: Executing command: {}", cmd);
-    let cmd_clone = cmd.clone();
-    thread::spawn(move || {
-        Command::new("sh")
-            .arg("-c")
-            .arg(&cmd_clone)
-            .spawn()
-            .ok();
-    });
 }
 
 // Change to a different page and update the device
-fn change_page(page_index: usize, config_path: &PathBuf, icons_path: &PathBuf) {
-    // Read and update config
-    let mut config: Config = match fs::read_to_string(config_path) {
-        Ok(content) => match serde_json::from_str(&content) {
-            Ok(c) => c,
-            Err(_) => return,
-        },
-        Err(_) => return,
+fn change_page(page_index: usize, config_path: &Path, icons_path: &Path) {
+    // Update the shared config (saved to disk as a side effect)
+    let config = config_store::update(config_path, |config| {
+        if page_index >= config.pages.len() {
+            return None;
+        }
+        config.current_page = page_index;
+        Some(config.clone())
+    }).flatten();
+    let config = match config {
+        Some(c) => c,
+        None => return,
     };
 
-    if page_index >= config.pages.len() {
-        return;
-    }
-
-    config.current_page = page_index;
-
-    // Save updated config
-    if let Ok(content) = serde_json::to_string_pretty(&config) {
-        fs::write(config_path, content).ok();
-    }
+    virtual_page::close();
+    scripting::clear_key_text();
 
     // Load the new page to device
-    if let Some(handle) = find_device() {
+    if let Some(deck) = open_deck() {
         let page = &config.pages[page_index];
-        if let Err(e) = load_page_to_device(&handle, page, config.brightness, icons_path) {
-            eprintln!("DEBUG: Failed to load page: {}", e);
+        if let Err(e) = load_page_to_device(deck.as_ref(), page, config.brightness, icons_path) {
+            warn!("Failed to load page: {}", e);
         }
     }
+    render_cache::prerender_around(&config.pages, page_index, icons_path);
 }
 
-// Start the button listener in a background thread
-fn start_button_listener(config_path: PathBuf, icons_path: PathBuf) {
-    thread::spawn(move || {
-        eprintln!("DEBUG: Button listener started");
-
+// Switch pages through the listener (it owns the device); false for a bad index
+fn switch_page(config_path: &Path, index: usize) -> bool {
+    let pages = config_store::update(config_path, |config| {
+        if index >= config.pages.len() {
+            return None;
+        }
+        config.current_page = index;
+        Some(config.pages.clone())
+    })
+    .flatten();
+    let pages = match pages {
+        Some(p) => p,
+        None => return false,
+    };
+    virtual_page::close();
+    scripting::clear_key_text();
+    request_refresh();
+    if let Some(icons_path) = config_path.parent().map(|dir| dir.join("icons")) {
+        render_cache::prerender_around(&pages, index, &icons_path);
+    }
+    true
+}
+
+// Set the config brightness; a page with its own brightness keeps it
+fn set_base_brightness(config_path: &Path, brightness: u8) {
+    let shown = config_store::update(config_path, |config| {
+        config.brightness = brightness;
+        virtual_page::active()
+            .or_else(|| config.pages.get(config.current_page).cloned())
+            .and_then(|p| p.brightness)
+            .unwrap_or(brightness)
+    });
+    if let Some(shown) = shown {
+        request_brightness(shown);
+    }
+}
+
+// Draw the part of a ring around the key that shows hold progress (0.0-1.0)
+fn draw_progress_ring(img: &mut RgbImage, progress: f32) {
+    let center = BUTTON_SIZE as f32 / 2.0;
+    let (inner, outer) = (center - 8.0, center - 2.0);
+    for (x, y, pixel) in img.enumerate_pixels_mut() {
+        let (dx, dy) = (x as f32 + 0.5 - center, y as f32 + 0.5 - center);
+        let distance = (dx * dx + dy * dy).sqrt();
+        if distance < inner || distance > outer {
+            continue;
+        }
+        // Clockwise from the top
+        let angle = dx.atan2(-dy).rem_euclid(std::f32::consts::TAU);
+        if angle / std::f32::consts::TAU <= progress {
+            *pixel = Rgb([231, 76, 60]);
+        }
+    }
+}
+
+// Wait while a hold_confirm key is held, filling a ring on it. Returns true
+// once it was held for HOLD_CONFIRM_DURATION, false if released earlier.
+fn confirm_hold(deck: &dyn Deck, key_id: u8, button: &ButtonConfig, icons_path: &Path) -> bool {
+    let image = render_button_image(button, icons_path);
+    let started = Instant::now();
+
+    let confirmed = loop {
+        let progress = started.elapsed().as_secs_f32() / HOLD_CONFIRM_DURATION.as_secs_f32();
+        if progress >= 1.0 {
+            break true;
+        }
+        let mut frame = image.clone();
+        draw_progress_ring(&mut frame, progress);
+        deck.set_key_image(key_id, &frame).ok();
+
+        match deck.read_event() {
+            Ok(Some(DeckEvent::KeyUp(id))) if id == key_id => break false,
+            Err(_) => break false,
+            _ => {}
+        }
+    };
+
+    // Back to the normal image
+    deck.set_key_image(key_id, &image).ok();
+    widget_schedule::forget(key_id);
+    debug!("Hold on button {} {}", key_id, if confirmed { "confirmed" } else { "cancelled" });
+    confirmed
+}
+
+// A confirm key showing "Sure?" until its second press or CONFIRM_WINDOW
+struct PendingConfirm {
+    key_id: u8,
+    since: Instant,
+    // What the key showed before
+    image: RgbImage,
+}
+
+impl PendingConfirm {
+    fn ask(deck: &dyn Deck, key_id: u8, button: &ButtonConfig, icons_path: &Path) -> PendingConfirm {
+        let prompt = ButtonConfig {
+            label: "Sure?".to_string(),
+            icon: String::new(),
+            color: CONFIRM_COLOR.to_string(),
+            ..button.clone()
+        };
+        deck.set_key_image(key_id, &render_button_image(&prompt, icons_path)).ok();
+        debug!("Button {} waiting for confirmation", key_id);
+        PendingConfirm {
+            key_id,
+            since: Instant::now(),
+            image: render_button_image(button, icons_path),
+        }
+    }
+
+    fn expired(&self) -> bool {
+        self.since.elapsed() >= CONFIRM_WINDOW
+    }
+
+    // Back to the normal image
+    fn restore(&self, deck: &dyn Deck) {
+        deck.set_key_image(self.key_id, &self.image).ok();
+        widget_schedule::forget(self.key_id);
+    }
+}
+
+// Start the button listener in a background thread
+fn start_button_listener(config_path: PathBuf, icons_path: PathBuf) {
+    // Widgets render off this thread so a slow one never delays a press
+    let widget_images = widget_schedule::start_worker(icons_path.clone());
+
+    thread::spawn(move || {
+        debug!("Button listener started");
+
         loop {
-            // Try to find and open device
-            let handle = match find_device() {
-                Some(h) => h,
+            // Try to find and open device (or the simulator)
+            let deck = match open_deck() {
+                Some(d) => d,
                 None => {
                     // Device not found, wait and retry
                     thread::sleep(Duration::from_secs(2));
@@ -2150,47 +3533,170 @@ fn start_button_listener(config_path: PathBuf, icons_path: PathBuf) {
                 }
             };
 
-            eprintln!("DEBUG: Button listener connected to device");
+            // Key events come from a reader thread (see deck_reader.rs), key
+            // images go out in frames (see frame_scheduler.rs)
+            let deck = frame_scheduler::FrameScheduler::new(Box::new(deck_reader::ThreadedReader::new(deck)));
 
-            // Load initial page on connect
-            load_current_page_internal(&handle, &config_path, &icons_path);
+            debug!("Button listener connected to device");
+            DEVICE_LOST.store(false, Ordering::SeqCst);
+            DECK_CONNECTED.store(true, Ordering::SeqCst);
+            tray::update();
 
-            // Widget update counter (update every ~10 loop iterations = ~1 second)
-            let mut widget_counter: u32 = 0;
-            let widget_update_interval: u32 = 10;
+            // Load initial page on connect
+            load_current_page_internal(&deck, &icons_path);
+            let mut confirming: Option<PendingConfirm> = None;
+            let mut alert_player = alerts::AlertPlayer::default();
+            let mut screensaver = screensaver::Screensaver::default();
+            let mut repeating: Option<HeldRepeat> = None;
 
             // Listen for button presses
             loop {
+                // Calibration takes the whole deck (see calibration.rs)
+                if calibration::step(&deck, &icons_path) {
+                    confirming = None;
+                    repeating = None;
+                    match deck.read_event() {
+                        Ok(Some(DeckEvent::KeyDown(code))) => {
+                            screensaver::activity();
+                            calibration::pressed(code, &config_path);
+                        }
+                        Ok(_) => {}
+                        Err(e) => {
+                            warn!("Button listener error: {}", e);
+                            break;
+                        }
+                    }
+                    continue;
+                }
+
+                // A deck-wide alert holds every key until it ends (see alerts.rs)
+                let alerting = alert_player.step(&deck, &icons_path);
+                // So does the idle clock (see screensaver.rs)
+                let saving = if alerting {
+                    screensaver.invalidate();
+                    false
+                } else {
+                    screensaver.step(&deck)
+                };
+
                 // Check if refresh is requested
-                if REFRESH_NEEDED.swap(false, Ordering::SeqCst) {
-                    eprintln!("DEBUG: Refresh requested, reloading page");
-                    load_current_page_internal(&handle, &config_path, &icons_path);
-                    widget_counter = 0; // Reset counter after full refresh
+                if alerting || saving {
+                    // The page is redrawn when the alert or screensaver ends
+                    repeating = None;
+                } else if REFRESH_NEEDED.swap(false, Ordering::SeqCst) {
+                    debug!("Refresh requested, reloading page");
+                    KEY_REFRESH.lock().map(|mut keys| keys.clear()).ok();
+                    PENDING_BRIGHTNESS.store(NO_BRIGHTNESS, Ordering::SeqCst);
+                    // The prompt and a held key are gone with the old page
+                    confirming = None;
+                    repeating = None;
+                    load_current_page_internal(&deck, &icons_path);
+                } else {
+                    refresh_keys(&deck, &icons_path);
                 }
 
-                // Periodically update widgets (every ~1 second)
-                widget_counter += 1;
-                if widget_counter >= widget_update_interval {
-                    widget_counter = 0;
-                    update_widget_buttons(&handle, &config_path, &icons_path);
+                // Brightness alone only needs the LIG command
+                let brightness = PENDING_BRIGHTNESS.swap(NO_BRIGHTNESS, Ordering::SeqCst);
+                if brightness != NO_BRIGHTNESS {
+                    if let Err(e) = deck.set_brightness(brightness) {
+                        warn!("Failed to set brightness: {}", e);
+                    }
                 }
 
-                match read_key_press(&handle) {
-                    Ok((key_id, state)) => {
-                        if state == 1 {
-                            // Key pressed
+                // Widget images rendered by the worker (see widget_schedule.rs)
+                while let Ok(update) = widget_images.try_recv() {
+                    let prompting = confirming.as_ref().map(|c| c.key_id == update.key_id).unwrap_or(false);
+                    if !update.is_current() || prompting || alerting || saving {
+                        continue;
+                    }
+                    if let Err(e) = deck.set_key_image(update.key_id, &update.image) {
+                        warn!("Failed to update widget button {}: {}", update.key_id, e);
+                    }
+                }
+
+                if !alerting && !saving {
+                    redraw_overlays(&deck, &icons_path);
+                }
+
+                if let Some(pending) = confirming.take_if(|c| c.expired() && !alerting && !saving) {
+                    debug!("Button {} not confirmed", pending.key_id);
+                    pending.restore(&deck);
+                }
+
+                if let Some(repeat) = repeating.as_mut().filter(|r| Instant::now() >= r.next) {
+                    repeat.next = Instant::now() + REPEAT_INTERVAL;
+                    repeat_button_press(repeat.key_id, &config_path, &icons_path);
+                }
+
+                diagnostics::run_pending_self_tests(&deck);
+                protocol_console::run_pending(&deck);
+
+                // A write found the device gone
+                if DEVICE_LOST.load(Ordering::SeqCst) {
+                    warn!("Device lost, reconnecting");
+                    break;
+                }
+
+                match deck.read_event() {
+                    Ok(Some(DeckEvent::KeyDown(_))) if alert_player.active() => {
+                        // A press only dismisses the alert
+                        screensaver::activity();
+                        alert_player.dismiss();
+                    }
+                    Ok(Some(DeckEvent::KeyDown(_))) if screensaver.active() => {
+                        // ...or wakes the page
+                        screensaver.wake();
+                    }
+                    Ok(Some(DeckEvent::KeyDown(key_id))) => {
+                        screensaver::activity();
+                        // Any press clears a "Sure?"; only its own key confirms it
+                        let confirmed = match confirming.take() {
+                            Some(pending) => {
+                                pending.restore(&deck);
+                                pending.key_id == key_id
+                            }
+                            None => false,
+                        };
+                        let button = button_for_key(key_id);
+                        let held = match &button {
+                            Some(button) if button.confirm && !confirmed => {
+                                confirming = Some(PendingConfirm::ask(&deck, key_id, button, &icons_path));
+                                false
+                            }
+                            Some(button) if button.hold_confirm && !confirmed => {
+                                confirm_hold(&deck, key_id, button, &icons_path)
+                            }
+                            _ => true,
+                        };
+                        repeating = None;
+                        if held {
                             handle_button_press(key_id, &config_path, &icons_path);
+                            if button.map(|b| repeats_while_held(&b.command)).unwrap_or(false) {
+                                repeating = Some(HeldRepeat {
+                                    key_id,
+                                    next: Instant::now() + REPEAT_DELAY,
+                                });
+                            }
                         }
                     }
-                    Err(e) => {
-                        if e != "timeout" {
-                            eprintln!("DEBUG: Button listener error: {}", e);
-                            break; // Reconnect
+                    Ok(Some(DeckEvent::KeyUp(key_id))) => {
+                        if repeating.as_ref().map(|r| r.key_id) == Some(key_id) {
+                            repeating = None;
                         }
                     }
+                    Ok(_) => {}
+                    Err(e) => {
+                        warn!("Button listener error: {}", e);
+                        break; // Reconnect
+                    }
                 }
             }
 
+            DECK_CONNECTED.store(false, Ordering::SeqCst);
+            calibration::cancel();
+            tray::update();
+            notify::event(notify::Event::DeviceDisconnected);
+
             // Wait before reconnecting
             thread::sleep(Duration::from_secs(1));
         }
@@ -2198,61 +3704,130 @@ fn start_button_listener(config_path: PathBuf, icons_path: PathBuf) {
 }
 
 // Update only buttons that have widget commands
-fn update_widget_buttons(handle: &DeviceHandle<Context>, config_path: &PathBuf, icons_path: &PathBuf) {
-    let config: Config = match fs::read_to_string(config_path) {
-        Ok(content) => match serde_json::from_str(&content) {
-            Ok(c) => c,
-            Err(_) => return,
-        },
-        Err(_) => return,
-    };
-
-    let page = match config.pages.get(config.current_page) {
+// Every widget key of the shown page, with a new image for the due ones whose
+// image changed. Runs on the widget worker thread; the listener sends them.
+fn render_widget_updates(icons_path: &Path) -> Vec<(u8, Option<RgbImage>)> {
+    // A generated page (e.g. the SSH host picker) replaces the config page
+    let page = virtual_page::active()
+        .or_else(|| config_store::read(|c| c.pages.get(c.current_page).cloned()).flatten());
+    let mut page = match page {
         Some(p) => p,
-        None => return,
+        None => return Vec::new(),
     };
+    page.apply_default_color();
 
-    // Find buttons with widget commands and update them
+    // Find buttons with widget commands and render the due ones
+    let mut updates = Vec::new();
     for (key_str, button) in &page.buttons {
         if is_widget_command(&button.command) {
-            if let Ok(key_id) = key_str.parse::<u8>() {
-                // Generate new image for this widget button
-                match generate_button_image(button, icons_path) {
-                    Ok(jpeg_data) => {
-                        if let Err(e) = set_key_image(handle, key_id, &jpeg_data) {
-                            eprintln!("DEBUG: Failed to update widget button {}: {}", key_id, e);
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("DEBUG: Failed to generate widget image for button {}: {}", key_id, e);
-                    }
-                }
+            // A toast covers the key; it is drawn again when the toast ends
+            if let Some(key_id) = key_str.parse::<u8>().ok().filter(|k| !overlay::active(*k)) {
+                let image = if widget_schedule::due(key_id, &button.command) {
+                    // Generate new image for this widget button, send it if it changed
+                    Some(render_button_image(button, icons_path)).filter(|image| widget_schedule::changed(key_id, image))
+                } else {
+                    None
+                };
+                updates.push((key_id, image));
             }
         }
     }
+    updates
 }
 
 // Internal function to load current page (used by button listener)
-fn load_current_page_internal(handle: &DeviceHandle<Context>, config_path: &PathBuf, icons_path: &PathBuf) {
-    let config: Config = match fs::read_to_string(config_path) {
-        Ok(content) => match serde_json::from_str(&content) {
-            Ok(c) => c,
-            Err(_) => return,
-        },
-        Err(_) => return,
+fn load_current_page_internal(deck: &dyn Deck, icons_path: &Path) {
+    let config = match config_store::read(|c| c.clone()) {
+        Some(c) => c,
+        None => return,
     };
 
-    if config.current_page < config.pages.len() {
+    if let Some(page) = virtual_page::active() {
+        if let Err(e) = load_page_to_device(deck, &page, config.brightness, icons_path) {
+            warn!("Failed to load virtual page: {}", e);
+        }
+    } else if config.current_page < config.pages.len() {
         let page = &config.pages[config.current_page];
-        if let Err(e) = load_page_to_device(handle, page, config.brightness, icons_path) {
-            eprintln!("DEBUG: Failed to load page: {}", e);
+        if let Err(e) = load_page_to_device(deck, page, config.brightness, icons_path) {
+            warn!("Failed to load page: {}", e);
         }
+        render_cache::prerender_around(&config.pages, config.current_page, icons_path);
     }
 }
 
 // Signal that a refresh is needed (called from UI)
 fn request_refresh() {
     REFRESH_NEEDED.store(true, Ordering::SeqCst);
+    deck_reader::wake();
+}
+
+// Change the deck brightness without redrawing the keys
+fn request_brightness(brightness: u8) {
+    PENDING_BRIGHTNESS.store(brightness.min(100), Ordering::SeqCst);
+    deck_reader::wake();
+}
+
+// Redraw one key of a page, if that page is on the deck
+fn request_key_refresh(page: usize, key_id: u8) {
+    if let Ok(mut keys) = KEY_REFRESH.lock() {
+        keys.insert((page, key_id));
+    }
+    deck_reader::wake();
+}
+
+// Draw the keys queued by request_key_refresh
+fn refresh_keys(deck: &dyn Deck, icons_path: &Path) {
+    let keys: Vec<(usize, u8)> = match KEY_REFRESH.lock() {
+        Ok(mut keys) if !keys.is_empty() => keys.drain().collect(),
+        _ => return,
+    };
+    // A generated page covers the config page
+    if let Some(mut page) = virtual_page::active() {
+        page.apply_default_color();
+        for (_, key_id) in keys.into_iter().filter(|(p, _)| *p == virtual_page::PAGE_INDEX) {
+            draw_key(deck, &page, key_id, icons_path);
+        }
+        return;
+    }
+    let page = config_store::read(|c| (c.current_page, c.pages.get(c.current_page).cloned()));
+    let (current, mut page) = match page {
+        Some((current, Some(page))) => (current, page),
+        _ => return,
+    };
+    page.apply_default_color();
+
+    for (_, key_id) in keys.into_iter().filter(|(p, _)| *p == current) {
+        draw_key(deck, &page, key_id, icons_path);
+    }
+}
+
+// Draw one key of the shown page (default colors applied), under any overlay
+fn draw_key(deck: &dyn Deck, page: &Page, key_id: u8, icons_path: &Path) {
+    let image = overlay::image(key_id, icons_path)
+        .or_else(|| page.buttons.get(&key_id.to_string()).and_then(|button| key_image(key_id, button, icons_path)))
+        // Keys without content are dark after a page load
+        .unwrap_or_else(|| ImageBuffer::from_pixel(BUTTON_SIZE, BUTTON_SIZE, Rgb([0, 0, 0])));
+    if let Err(e) = deck.set_key_image(key_id, &image) {
+        warn!("Failed to refresh key {}: {}", key_id, e);
+    }
+    widget_schedule::forget(key_id);
+}
+
+// Draw the keys whose overlay appeared or ran out
+fn redraw_overlays(deck: &dyn Deck, icons_path: &Path) {
+    let keys = overlay::take_changed();
+    if keys.is_empty() {
+        return;
+    }
+    let page = virtual_page::active().or_else(|| config_store::read(|c| c.pages.get(c.current_page).cloned()).flatten());
+    let mut page = match page {
+        Some(p) => p,
+        None => return,
+    };
+    page.apply_default_color();
+    for key_id in keys {
+        draw_key(deck, &page, key_id, icons_path);
+    }
 }
 
 // ============================================================================
@@ -2261,23 +3836,119 @@ fn request_refresh() {
 
 #[tauri::command]
 fn get_config(state: State<AppState>) -> Result<Config, String> {
-    let config = state.config.lock().map_err(|e| e.to_string())?;
+    let config = state.config.read().map_err(|e| e.to_string())?;
     Ok(config.clone())
 }
 
 #[tauri::command]
-fn save_full_config(state: State<AppState>, config: Config) -> Result<(), String> {
-    let mut current = state.config.lock().map_err(|e| e.to_string())?;
+fn save_full_config(state: State<AppState>, mut config: Config) -> Result<(), String> {
+    config.normalize_actions();
+    let mut current = state.config.write().map_err(|e| e.to_string())?;
+    input::set_backend(config.input_backend);
     *current = config;
     drop(current);
     state.save_config();
     Ok(())
 }
 
+// Input backend setting and the backend it currently resolves to
+#[tauri::command]
+fn get_input_backend(state: State<AppState>) -> Result<(String, String, String), String> {
+    let config = state.config.read().map_err(|e| e.to_string())?;
+    Ok((
+        config.input_backend.as_str().to_string(),
+        input::active_backend().as_str().to_string(),
+        session::session_type().as_str().to_string(),
+    ))
+}
+
+#[tauri::command]
+fn set_input_backend(state: State<AppState>, backend: String) -> Result<(), String> {
+    let kind = input::BackendKind::parse(&backend)
+        .ok_or_else(|| format!("Unknown input backend: {}", backend))?;
+    let mut config = state.config.write().map_err(|e| e.to_string())?;
+    config.input_backend = kind;
+    drop(config);
+    input::set_backend(kind);
+    state.save_config();
+    Ok(())
+}
+
+// Page to switch to while the camera/mic is live (None disables it)
+#[tauri::command]
+fn set_privacy_page(state: State<AppState>, page: Option<usize>) -> Result<(), String> {
+    let mut config = state.config.write().map_err(|e| e.to_string())?;
+    if let Some(index) = page.filter(|i| *i >= config.pages.len()) {
+        return Err(format!("Page {} does not exist", index));
+    }
+    config.privacy_page = page;
+    drop(config);
+    state.save_config();
+    Ok(())
+}
+
+// Enable the virtual deck server on `port` (None disables it); returns the URL
+// to open. It listens on 127.0.0.1 unless `lan` is true.
+#[tauri::command]
+fn set_remote_deck(state: State<AppState>, port: Option<u16>, lan: Option<bool>) -> Result<Option<String>, String> {
+    let mut config = state.config.write().map_err(|e| e.to_string())?;
+    // Keep the token so paired phones keep working
+    let token = config.remote_deck.as_ref().map(|r| r.token.clone()).filter(|t| !t.is_empty());
+    config.remote_deck = match port {
+        Some(port) => Some(remote::RemoteDeck {
+            port,
+            token: match token {
+                Some(token) => token,
+                None => remote::generate_token()?,
+            },
+            lan: lan.unwrap_or(false),
+        }),
+        None => None,
+    };
+    let remote_deck = config.remote_deck.clone();
+    drop(config);
+    state.save_config();
+
+    match remote_deck {
+        Some(remote_deck) => {
+            remote::start(state.config_path.clone(), state.icons_path.clone())?;
+            Ok(Some(remote::url(&remote_deck)))
+        }
+        None => Ok(None),
+    }
+}
+
 #[tauri::command]
 fn get_status(state: State<AppState>) -> StatusResponse {
     let connected = state.device_connected.lock().map(|c| *c).unwrap_or(false);
-    StatusResponse { connected }
+    StatusResponse {
+        connected: connected || deck::simulator_enabled(),
+        simulator: deck::simulator_enabled(),
+    }
+}
+
+// ============================================================================
+// Simulator / Mirror Commands
+// ============================================================================
+
+// Brightness and key images of the simulated deck (empty outside simulator mode)
+#[tauri::command]
+fn get_simulator_keys() -> deck::KeyImages {
+    deck::simulator_state()
+}
+
+// What the deck shows right now; updates arrive as "deck-mirror" events
+#[tauri::command]
+fn get_deck_mirror() -> deck::KeyImages {
+    deck::mirror_state()
+}
+
+#[tauri::command]
+fn simulate_key(key_id: u8, pressed: bool) -> Result<(), String> {
+    if !deck::simulator_enabled() {
+        return Err("Simulator mode is not enabled".to_string());
+    }
+    deck::send_key_event(key_id, pressed)
 }
 
 #[tauri::command]
@@ -2317,18 +3988,20 @@ fn connect_device(state: State<AppState>) -> Result<bool, String> {
 
 #[tauri::command]
 fn set_page(state: State<AppState>, index: usize) -> Result<(), String> {
-    let mut config = state.config.lock().map_err(|e| e.to_string())?;
+    let mut config = state.config.write().map_err(|e| e.to_string())?;
     if index < config.pages.len() {
         config.current_page = index;
     }
     drop(config);
+    virtual_page::close();
+    scripting::clear_key_text();
     state.save_config();
     Ok(())
 }
 
 #[tauri::command]
 fn add_page(state: State<AppState>, name: String) -> Result<usize, String> {
-    let mut config = state.config.lock().map_err(|e| e.to_string())?;
+    let mut config = state.config.write().map_err(|e| e.to_string())?;
 
     let mut buttons = HashMap::new();
     for i in 1..=15 {
@@ -2339,11 +4012,20 @@ fn add_page(state: State<AppState>, name: String) -> Result<usize, String> {
                 command: String::new(),
                 color: "#1a1a2e".to_string(),
                 icon: String::new(),
+                action: None,
+                hold_confirm: false,
+                confirm: false,
+                sound: None,
             },
         );
     }
 
-    config.pages.push(Page { name, buttons });
+    config.pages.push(Page {
+        name,
+        buttons,
+        brightness: None,
+        default_color: None,
+    });
     let new_index = config.pages.len() - 1;
     drop(config);
     state.save_config();
@@ -2353,7 +4035,7 @@ fn add_page(state: State<AppState>, name: String) -> Result<usize, String> {
 
 #[tauri::command]
 fn delete_page(state: State<AppState>, index: usize) -> Result<(), String> {
-    let mut config = state.config.lock().map_err(|e| e.to_string())?;
+    let mut config = state.config.write().map_err(|e| e.to_string())?;
 
     if config.pages.len() <= 1 {
         return Err("Cannot delete the last page".to_string());
@@ -2373,7 +4055,7 @@ fn delete_page(state: State<AppState>, index: usize) -> Result<(), String> {
 
 #[tauri::command]
 fn update_page_name(state: State<AppState>, index: usize, name: String) -> Result<(), String> {
-    let mut config = state.config.lock().map_err(|e| e.to_string())?;
+    let mut config = state.config.write().map_err(|e| e.to_string())?;
 
     if index < config.pages.len() {
         config.pages[index].name = name;
@@ -2384,16 +4066,60 @@ fn update_page_name(state: State<AppState>, index: usize, name: String) -> Resul
     Ok(())
 }
 
+// Per-page brightness and default button color (None = use the config's)
+#[tauri::command]
+fn set_page_style(
+    state: State<AppState>,
+    index: usize,
+    brightness: Option<u8>,
+    default_color: Option<String>,
+) -> Result<(), String> {
+    let mut config = state.config.write().map_err(|e| e.to_string())?;
+    let page = config.pages.get_mut(index).ok_or("Invalid page index")?;
+    page.brightness = brightness.map(|b| b.min(100));
+    page.default_color = default_color.filter(|c| !c.is_empty());
+    drop(config);
+    state.save_config();
+    request_refresh();
+
+    Ok(())
+}
+
+// The key image exactly as the deck would show it (before rotation), as a
+// PNG data URL; with `page_index` the page's default color applies
+#[tauri::command]
+fn preview_button(
+    state: State<AppState>,
+    mut button_config: ButtonConfig,
+    page_index: Option<usize>,
+) -> Result<String, String> {
+    button_config.normalize_action();
+    if let Some(index) = page_index {
+        let config = state.config.read().map_err(|e| e.to_string())?;
+        if let Some(color) = config.pages.get(index).and_then(|p| p.default_color.clone()) {
+            if button_config.color == "#1a1a2e" {
+                button_config.color = color;
+            }
+        }
+    }
+    let image = render_button_image(&button_config, &state.icons_path);
+    deck::png_data_url(image).ok_or_else(|| "Failed to encode preview".to_string())
+}
+
 #[tauri::command]
 fn update_button(
     state: State<AppState>,
     page_index: usize,
     button_id: String,
-    button_config: ButtonConfig,
+    mut button_config: ButtonConfig,
 ) -> Result<(), String> {
-    let mut config = state.config.lock().map_err(|e| e.to_string())?;
+    button_config.normalize_action();
+    let mut config = state.config.write().map_err(|e| e.to_string())?;
 
     if page_index < config.pages.len() {
+        if let Ok(key_id) = button_id.parse::<u8>() {
+            request_key_refresh(page_index, key_id);
+        }
         config.pages[page_index].buttons.insert(button_id, button_config);
     }
     drop(config);
@@ -2404,21 +4130,255 @@ fn update_button(
 
 #[tauri::command]
 fn set_brightness_level(state: State<AppState>, brightness: u8) -> Result<(), String> {
-    let mut config = state.config.lock().map_err(|e| e.to_string())?;
+    let mut config = state.config.write().map_err(|e| e.to_string())?;
     config.brightness = brightness;
+    // A page with its own brightness keeps it
+    let shown = virtual_page::active()
+        .or_else(|| config.pages.get(config.current_page).cloned())
+        .and_then(|p| p.brightness)
+        .unwrap_or(brightness);
+    drop(config);
+    state.save_config();
+
+    request_brightness(shown);
+    debug!("Brightness set to {}", brightness);
+
+    Ok(())
+}
+
+#[tauri::command]
+fn set_jpeg_quality(state: State<AppState>, quality: u8) -> Result<(), String> {
+    let mut config = state.config.write().map_err(|e| e.to_string())?;
+    config.jpeg_quality = quality.clamp(10, 100);
+    drop(config);
+    state.save_config();
+
+    // Send every key again with the new quality
+    request_refresh();
+    Ok(())
+}
+
+#[tauri::command]
+fn set_orientation(state: State<AppState>, rotation: u16, mirror: bool) -> Result<(), String> {
+    if ![0, 90, 180, 270].contains(&rotation) {
+        return Err("Rotation must be 0, 90, 180 or 270".to_string());
+    }
+    let mut config = state.config.write().map_err(|e| e.to_string())?;
+    config.orientation = Orientation { rotation, mirror };
     drop(config);
     state.save_config();
 
-    // Signal the button listener to refresh (which will apply new brightness)
+    // Send every key again turned the new way
+    request_refresh();
+    Ok(())
+}
+
+#[tauri::command]
+fn set_language(state: State<AppState>, language: i18n::Language) -> Result<(), String> {
+    let mut config = state.config.write().map_err(|e| e.to_string())?;
+    config.language = language;
+    drop(config);
+    state.save_config();
+
+    // Redraw weekday, date and the other translated widgets
+    request_refresh();
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+struct JpegSample {
+    quality: u8,
+    // Average size of the current page's keys
+    bytes: usize,
+}
+
+// Encoded size of the current page at each quality step
+#[tauri::command]
+fn measure_jpeg_quality(state: State<AppState>) -> Result<Vec<JpegSample>, String> {
+    let config = state.config.read().map_err(|e| e.to_string())?;
+    let mut page = config.pages.get(config.current_page).cloned().ok_or("No current page")?;
+    drop(config);
+    page.apply_default_color();
+
+    let images = render_cache::render_page(&page, &state.icons_path);
+    if images.is_empty() {
+        return Ok(Vec::new());
+    }
+    JPEG_QUALITY_STEPS
+        .iter()
+        .map(|&quality| {
+            let total = images
+                .iter()
+                .map(|(_, img)| encode_jpeg(img, quality).map(|jpeg| jpeg.len()))
+                .sum::<Result<usize, String>>()?;
+            Ok(JpegSample {
+                quality,
+                bytes: total / images.len(),
+            })
+        })
+        .collect()
+}
+
+// USB descriptors of the deck and a DIS round trip (waits for the listener)
+#[tauri::command]
+async fn get_device_info() -> Result<diagnostics::DeviceInfo, String> {
+    tauri::async_runtime::spawn_blocking(diagnostics::device_info)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// udev, input, fonts, OBS and Twitch checks (slow: contacts OBS and Twitch)
+#[tauri::command]
+async fn get_diagnostics() -> Vec<diagnostics::Check> {
+    tauri::async_runtime::spawn_blocking(diagnostics::report).await.unwrap_or_default()
+}
+
+// Session type, compositor, portal and the backends picked for them (see session.rs)
+#[tauri::command]
+async fn get_environment() -> Result<session::Environment, String> {
+    tauri::async_runtime::spawn_blocking(session::environment)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Which external tools the actions rely on are available (see dependencies.rs)
+#[tauri::command]
+async fn check_dependencies() -> Vec<dependencies::Dependency> {
+    tauri::async_runtime::spawn_blocking(dependencies::check).await.unwrap_or_default()
+}
+
+// Developer mode only: send a hex payload, return what the device answered
+#[tauri::command]
+async fn send_raw_command(payload: String, prefix: Option<bool>, read_ms: Option<u64>) -> Result<protocol_console::RawReply, String> {
+    tauri::async_runtime::spawn_blocking(move || protocol_console::send(&payload, prefix.unwrap_or(true), read_ms))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+// Light each key in turn and record the code it sends (see calibration.rs)
+#[tauri::command]
+fn start_calibration() -> Result<(), String> {
+    if !DECK_CONNECTED.load(Ordering::SeqCst) {
+        return Err("Deck not connected".to_string());
+    }
+    calibration::start();
+    Ok(())
+}
+
+#[tauri::command]
+fn cancel_calibration() {
+    calibration::cancel();
+}
+
+#[tauri::command]
+fn get_calibration_status() -> calibration::CalibrationStatus {
+    calibration::status()
+}
+
+// Back to the built-in key map
+#[tauri::command]
+fn reset_key_map(state: State<AppState>) -> Result<(), String> {
+    let mut config = state.config.write().map_err(|e| e.to_string())?;
+    config.key_map.clear();
+    drop(config);
+    state.save_config();
+    Ok(())
+}
+
+#[tauri::command]
+fn is_developer_mode() -> bool {
+    protocol_console::developer_enabled()
+}
+
+// The last lines of the log files, for bug reports
+#[tauri::command]
+fn get_recent_logs(lines: Option<usize>) -> Result<String, String> {
+    logging::recent(lines.unwrap_or(logging::DEFAULT_RECENT_LINES))
+}
+
+#[tauri::command]
+fn set_log_level(state: State<AppState>, level: String) -> Result<(), String> {
+    logging::set_level(&level)?;
+    let mut config = state.config.write().map_err(|e| e.to_string())?;
+    config.log_level = level.trim().to_lowercase();
+    drop(config);
+    state.save_config();
+    Ok(())
+}
+
+// Press counts per key, most used first; `days` limits it to recent presses
+#[tauri::command]
+fn get_usage_stats(days: Option<u64>) -> Result<usage_stats::UsageStats, String> {
+    usage_stats::stats(days)
+}
+
+#[tauri::command]
+fn set_usage_stats(state: State<AppState>, enabled: bool) -> Result<(), String> {
+    let mut config = state.config.write().map_err(|e| e.to_string())?;
+    config.usage_stats = enabled;
+    drop(config);
+    state.save_config();
+    Ok(())
+}
+
+#[tauri::command]
+fn set_notifications(state: State<AppState>, enabled: bool) -> Result<(), String> {
+    let mut config = state.config.write().map_err(|e| e.to_string())?;
+    config.notifications.enabled = enabled;
+    drop(config);
+    state.save_config();
+    Ok(())
+}
+
+#[tauri::command]
+fn set_safe_mode(state: State<AppState>, enabled: bool) -> Result<(), String> {
+    let mut config = state.config.write().map_err(|e| e.to_string())?;
+    config.safe_mode = enabled;
+    drop(config);
+    state.save_config();
+    // Plugins run code, so they only run outside safe mode
+    plugins::stop_all();
+    elgato::stop_all();
+    start_plugins(&state.config_path);
     request_refresh();
-    eprintln!("DEBUG: Brightness set to {}, refresh requested", brightness);
+    Ok(())
+}
+
+#[tauri::command]
+fn clear_usage_stats() -> Result<(), String> {
+    usage_stats::clear()
+}
+
+#[tauri::command]
+fn set_scheduled_job_enabled(state: State<AppState>, name: String, enabled: bool) -> Result<(), String> {
+    scheduler::enable(&state.config_path, &name, enabled)
+}
 
+#[tauri::command]
+fn run_scheduled_job(state: State<AppState>, name: String) -> Result<(), String> {
+    scheduler::run_now(&state.config_path, &state.icons_path, &name)
+}
+
+// Show a message on a key for a few seconds, then the key again
+#[tauri::command]
+fn show_key_message(key: u8, text: String, duration_ms: Option<u64>) -> Result<(), String> {
+    if !(1..=15).contains(&key) {
+        return Err("Keys are 1-15".to_string());
+    }
+    let duration = duration_ms.map(Duration::from_millis).unwrap_or(overlay::DEFAULT_DURATION);
+    overlay::show(key, &text, duration);
     Ok(())
 }
 
+// Show a brightness while the slider moves; set_brightness_level saves it
+#[tauri::command]
+fn preview_brightness(brightness: u8) {
+    request_brightness(brightness);
+}
+
 #[tauri::command]
 fn clear_page_buttons(state: State<AppState>, page_index: usize) -> Result<(), String> {
-    let mut config = state.config.lock().map_err(|e| e.to_string())?;
+    let mut config = state.config.write().map_err(|e| e.to_string())?;
 
     if page_index >= config.pages.len() {
         return Err("Invalid page index".to_string());
@@ -2433,6 +4393,10 @@ fn clear_page_buttons(state: State<AppState>, page_index: usize) -> Result<(), S
                 command: String::new(),
                 color: "#1a1a2e".to_string(),
                 icon: String::new(),
+                action: None,
+                hold_confirm: false,
+                confirm: false,
+                sound: None,
             },
         );
     }
@@ -2443,95 +4407,308 @@ fn clear_page_buttons(state: State<AppState>, page_index: usize) -> Result<(), S
     Ok(())
 }
 
+// How move_button treats a button already on the target key
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+enum MoveMode {
+    // The target button goes to the source key
+    Swap,
+    // The target button is dropped and the source key left empty
+    Overwrite,
+}
+
+// Drag and drop in the editor, also between pages
 #[tauri::command]
-fn run_command(command: String) -> Result<(), String> {
-    if command.is_empty() {
+fn move_button(
+    state: State<AppState>,
+    src_page: usize,
+    src_key: u8,
+    dst_page: usize,
+    dst_key: u8,
+    mode: MoveMode,
+) -> Result<(), String> {
+    if !(1..=15).contains(&src_key) || !(1..=15).contains(&dst_key) {
+        return Err("Invalid key".to_string());
+    }
+    let mut config = state.config.write().map_err(|e| e.to_string())?;
+    if src_page >= config.pages.len() || dst_page >= config.pages.len() {
+        return Err("Invalid page index".to_string());
+    }
+    if src_page == dst_page && src_key == dst_key {
         return Ok(());
     }
 
-    // Handle special commands (same logic as handle_button_press)
-    let cmd = command.clone();
+    let empty = ButtonConfig {
+        label: String::new(),
+        command: String::new(),
+        color: "#1a1a2e".to_string(),
+        icon: String::new(),
+        action: None,
+        hold_confirm: false,
+        confirm: false,
+        sound: None,
+    };
+    let moved = config.pages[src_page]
+        .buttons
+        .remove(&src_key.to_string())
+        .unwrap_or_else(|| empty.clone());
+    let replaced = config.pages[dst_page].buttons.insert(dst_key.to_string(), moved);
+
+    let dropped_icon = match (mode, replaced) {
+        (MoveMode::Swap, Some(replaced)) => {
+            config.pages[src_page].buttons.insert(src_key.to_string(), replaced);
+            None
+        }
+        (MoveMode::Overwrite, Some(replaced)) => {
+            config.pages[src_page].buttons.insert(src_key.to_string(), empty);
+            Some(replaced.icon).filter(|icon| !icon.is_empty())
+        }
+        (_, None) => {
+            config.pages[src_page].buttons.insert(src_key.to_string(), empty);
+            None
+        }
+    };
 
-    // Handle __URL_ command
-    if cmd.starts_with("__URL_") {
-        let url = cmd[6..].to_string();
-        std::thread::spawn(move || {
-            Command::new("xdg-open")
-                .arg(&url)
-                .spawn()
-                .ok();
-        });
-        return Ok(());
+    // An overwritten button's icon file goes once nothing shows it
+    if let Some(icon) = dropped_icon {
+        if !icons::in_use(&config, &icon) {
+            fs::remove_file(state.icons_path.join(&icon)).ok();
+            debug!("Removed unused icon {}", icon);
+        }
     }
+    drop(config);
+    state.save_config();
+    request_key_refresh(src_page, src_key);
+    request_key_refresh(dst_page, dst_key);
 
-    // Handle __TYPE_ command
-    if cmd.starts_with("__TYPE_") {
-        let text = cmd[7..].to_string();
-        std::thread::spawn(move || {
-            Command::new("ydotool")
-                .args(["type", "--clearmodifiers", &text])
-                .spawn()
-                .ok();
-        });
-        return Ok(());
-    }
+    Ok(())
+}
 
-    // Handle __KEY_ command
-    if cmd.starts_with("__KEY_") {
-        let keys = cmd[6..].to_string();
-        std::thread::spawn(move || {
-            execute_hotkey_sync(&keys);
-        });
-        return Ok(());
+#[tauri::command]
+fn run_command(state: State<AppState>, command: String) -> Result<(), String> {
+    let action = Action::parse(&command);
+    execute_action(&action, &state.config_path, &state.icons_path)
+}
+
+// Action from the editor: the command string, or the action when it is empty
+fn editor_action(command: &str, action: Option<Action>) -> Action {
+    ButtonConfig {
+        label: String::new(),
+        command: command.to_string(),
+        color: String::new(),
+        icon: String::new(),
+        action,
+        hold_confirm: false,
+        confirm: false,
+        sound: None,
     }
+    .resolved_action()
+}
+
+// Syntax problems plus checks against the config, OBS and script files
+fn action_problems(command: &str, action: &Action, config: &Config, config_path: &Path) -> Vec<String> {
+    let mut problems = if command.is_empty() {
+        action.validate()
+    } else {
+        action::validate_command(command)
+    };
 
-    // Handle __MULTI_ command
-    if cmd.starts_with("__MULTI_") {
-        let commands = cmd[8..].to_string();
-        std::thread::spawn(move || {
-            for single_cmd in commands.split(";;") {
-                let trimmed = single_cmd.trim();
-                if !trimmed.is_empty() {
-                    if trimmed.starts_with("__URL_") {
-                        let url = &trimmed[6..];
-                        Command::new("xdg-open").arg(url).spawn().ok();
-                    } else if trimmed.starts_with("__TYPE_") {
-                        let text = &trimmed[7..];
-                        Command::new("ydotool")
-                            .args(["type", "--clearmodifiers", text])
-                            .status()
-                            .ok();
-                    } else if trimmed.starts_with("__KEY_") {
-                        let keys = &trimmed[6..];
-                        execute_hotkey_sync(keys);
-                    } else if trimmed.starts_with("__DELAY_") {
-                        if let Ok(ms) = trimmed[8..].parse::<u64>() {
-                            std::thread::sleep(Duration::from_millis(ms));
+    let mut obs_scenes: Option<Option<Vec<String>>> = None;
+    for step in action.flatten() {
+        match step {
+            Action::PageNav { target: PageTarget::Index(index) } if *index >= config.pages.len() => {
+                problems.push(format!("Page {} does not exist ({} pages)", index, config.pages.len()));
+            }
+            Action::ObsScene { scene } if !scene.trim().is_empty() => {
+                // Only checked when OBS is reachable
+                let scenes = obs_scenes.get_or_insert_with(|| obs_scene_list().ok().map(|(scenes, _)| scenes));
+                if let Some(scenes) = scenes {
+                    if !scenes.contains(scene) {
+                        problems.push(format!("OBS scene '{}' not found", scene));
+                    }
+                }
+            }
+            Action::Integration { command } if apps::is_app_command(command) => {
+                if let Err(e) = apps::check(command) {
+                    problems.push(e);
+                }
+            }
+            Action::Script { name } if !name.is_empty() => {
+                match scripting::read(config_path, name) {
+                    Ok(source) => {
+                        if let Err(e) = scripting::check(&source) {
+                            problems.push(format!("Script {}: {}", name, e));
                         }
-                    } else {
-                        Command::new("sh")
-                            .arg("-c")
-                            .arg(trimmed)
-                            .status()
-                            .ok();
                     }
-                    std::thread::sleep(Duration::from_millis(100));
+                    Err(e) => problems.push(e),
+                }
+            }
+            _ => {}
+        }
+    }
+    problems
+}
+
+#[tauri::command]
+fn validate_action(state: State<AppState>, command: String, action: Option<Action>) -> Result<Vec<String>, String> {
+    let action = editor_action(&command, action);
+    let config = state.config.read().map_err(|e| e.to_string())?;
+    Ok(action_problems(&command, &action, &config, &state.config_path))
+}
+
+// Recent shell action runs with exit status and stderr, newest first
+#[tauri::command]
+fn get_action_log() -> Vec<action_log::ActionLogEntry> {
+    action_log::entries()
+}
+
+// Longest a shell command may run during test_action
+const TEST_COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Serialize)]
+pub struct ActionTestResult {
+    pub action: Action,
+    pub problems: Vec<String>,
+    pub executed: bool,
+    pub success: bool,
+    pub output: String,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+// Run a shell process capturing stdout and stderr, killing it after `timeout`
+fn run_shell_captured(mut process: Command, timeout: Duration) -> Result<String, String> {
+    let mut child = process
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run command: {}", e))?;
+
+    // Read both pipes in the background so a chatty command can't block
+    let readers: Vec<thread::JoinHandle<String>> = [
+        child.stdout.take().map(|p| Box::new(p) as Box<dyn IoRead + Send>),
+        child.stderr.take().map(|p| Box::new(p) as Box<dyn IoRead + Send>),
+    ]
+    .into_iter()
+    .flatten()
+    .map(|mut pipe| {
+        thread::spawn(move || {
+            let mut text = String::new();
+            pipe.read_to_string(&mut text).ok();
+            text
+        })
+    })
+    .collect();
+
+    let started = SystemTime::now();
+    let status = loop {
+        if let Some(status) = child.try_wait().map_err(|e| e.to_string())? {
+            break Some(status);
+        }
+        if started.elapsed().unwrap_or_default() > timeout {
+            child.kill().ok();
+            child.wait().ok();
+            break None;
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+
+    let output: String = readers.into_iter().filter_map(|r| r.join().ok()).collect();
+    match status {
+        Some(status) if status.success() => Ok(output),
+        Some(status) => Err(format!("{}{}", output, status)),
+        None => Err(format!("{}Timed out after {}s", output, timeout.as_secs())),
+    }
+}
+
+// Execute an action once, waiting for it and collecting shell output
+fn run_action_captured(action: &Action, config_path: &PathBuf, icons_path: &PathBuf, output: &mut String) -> Result<(), String> {
+    check_safe_mode(action)?;
+    match action {
+        Action::Shell { command, options } => {
+            run_shell_captured(shell_process(command, options), TEST_COMMAND_TIMEOUT)
+                .map(|text| output.push_str(&text))
+        }
+        Action::Script { name } => {
+            scripting::run(name, config_path, icons_path).map(|text| output.push_str(&text))
+        }
+        Action::Hotkey { keys } => {
+            execute_hotkey_sync(keys);
+            Ok(())
+        }
+        Action::Type { text } => {
+            input::type_text(&variables::interpolate(text));
+            Ok(())
+        }
+        Action::Mouse { action } => {
+            input::mouse(*action);
+            Ok(())
+        }
+        Action::Delay { ms } => {
+            thread::sleep(Duration::from_millis(*ms));
+            Ok(())
+        }
+        Action::Multi { steps } => {
+            let mut failed = 0;
+            for (i, step) in steps.iter().enumerate() {
+                output.push_str(&format!("[step {}] {}\n", i + 1, step.to_command()));
+                if let Err(e) = run_action_captured(step, config_path, icons_path, output) {
+                    output.push_str(&format!("[step {} failed] {}\n", i + 1, e));
+                    failed += 1;
                 }
+                thread::sleep(Duration::from_millis(100));
             }
-        });
-        return Ok(());
+            if failed > 0 {
+                Err(format!("{} of {} steps failed", failed, steps.len()))
+            } else {
+                Ok(())
+            }
+        }
+        other => execute_action(other, config_path, icons_path),
     }
+}
 
-    // Execute normal shell command
-    std::thread::spawn(move || {
-        Command::new("sh")
-            .arg("-c")
-            .arg(&command)
-            .spawn()
-            .ok();
-    });
+// Execute an action once from the editor and report what happened
+#[tauri::command]
+async fn test_action(
+    state: State<'_, AppState>,
+    command: String,
+    action: Option<Action>,
+) -> Result<ActionTestResult, String> {
+    let action = editor_action(&command, action);
+    let problems = {
+        let config = state.config.read().map_err(|e| e.to_string())?;
+        action_problems(&command, &action, &config, &state.config_path)
+    };
+    if !problems.is_empty() {
+        return Ok(ActionTestResult {
+            action,
+            problems,
+            executed: false,
+            success: false,
+            output: String::new(),
+            error: None,
+            duration_ms: 0,
+        });
+    }
 
-    Ok(())
+    let config_path = state.config_path.clone();
+    let icons_path = state.icons_path.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let started = SystemTime::now();
+        let mut output = String::new();
+        let result = run_action_captured(&action, &config_path, &icons_path, &mut output);
+        ActionTestResult {
+            action,
+            problems,
+            executed: true,
+            success: result.is_ok(),
+            output,
+            error: result.err(),
+            duration_ms: started.elapsed().map(|d| d.as_millis() as u64).unwrap_or(0),
+        }
+    })
+    .await
+    .map_err(|e| format!("Action test failed: {}", e))
 }
 
 #[tauri::command]
@@ -2564,7 +4741,7 @@ fn setup_udev_rules() -> Result<bool, String> {
     }
 
     // Try to create rules using pkexec
-    let result = Command::new("pkexec")
+    let result = sandbox::host_command("pkexec")
         .args(["bash", "-c", &format!(
             "echo '{}' > {} && udevadm control --reload-rules && udevadm trigger",
             rules_content,
@@ -2583,6 +4760,36 @@ fn check_udev_rules() -> bool {
     std::path::Path::new("/etc/udev/rules.d/99-redragon.rules").exists()
 }
 
+// Allow the logged-in user to create the uinput virtual keyboard
+#[tauri::command]
+fn setup_uinput_rules() -> Result<bool, String> {
+    let rules_path = "/etc/udev/rules.d/99-redragon-uinput.rules";
+    let rules_content = r#"KERNEL=="uinput", SUBSYSTEM=="misc", MODE="0660", GROUP="input", TAG+="uaccess""#;
+
+    if input::uinput_accessible() {
+        return Ok(true);
+    }
+
+    // Load uinput now and on every boot, then apply the rule
+    let result = sandbox::host_command("pkexec")
+        .args(["bash", "-c", &format!(
+            "echo '{}' > {} && echo uinput > /etc/modules-load.d/redragon-uinput.conf && modprobe uinput && udevadm control --reload-rules && udevadm trigger --sysname-match=uinput",
+            rules_content,
+            rules_path
+        )])
+        .status();
+
+    match result {
+        Ok(status) => Ok(status.success()),
+        Err(e) => Err(format!("Failed to setup uinput rules: {}", e)),
+    }
+}
+
+#[tauri::command]
+fn check_uinput_access() -> bool {
+    input::uinput_accessible()
+}
+
 #[tauri::command]
 fn save_icon(state: State<AppState>, source_path: String, icon_name: String) -> Result<String, String> {
     let source = PathBuf::from(&source_path);
@@ -2606,6 +4813,18 @@ fn save_icon(state: State<AppState>, source_path: String, icon_name: String) ->
     Ok(final_name)
 }
 
+// Upload from the UI (drag and drop, paste) without a file path
+#[tauri::command]
+fn save_icon_data(state: State<AppState>, name: String, data: String) -> Result<String, String> {
+    icons::save_data(&state.icons_path, &name, &data)
+}
+
+// Icon from an emoji (color emoji font) on the given background color
+#[tauri::command]
+fn save_emoji_icon(state: State<AppState>, emoji: String, background: Option<String>) -> Result<String, String> {
+    icons::render_emoji(&state.icons_path, &emoji, background.as_deref().unwrap_or("#1a1a2e"))
+}
+
 fn chrono_lite() -> u64 {
     use std::time::{SystemTime, UNIX_EPOCH};
     SystemTime::now()
@@ -2619,7 +4838,7 @@ fn reset_config(state: State<AppState>) -> Result<(), String> {
     // Reset to default config
     let default_config = AppState::default_config();
 
-    let mut config = state.config.lock().map_err(|e| e.to_string())?;
+    let mut config = state.config.write().map_err(|e| e.to_string())?;
     *config = default_config;
     drop(config);
 
@@ -2634,20 +4853,217 @@ fn reset_config(state: State<AppState>) -> Result<(), String> {
     Ok(())
 }
 
+// ============================================================================
+// Profile Bundle Commands
+// ============================================================================
+
+// Zip the config (or one page) with its icons
+#[tauri::command]
+fn export_profile(state: State<AppState>, path: String, page: Option<usize>) -> Result<(), String> {
+    let config = state.config.read().map_err(|e| e.to_string())?.clone();
+    profile_bundle::export(&config, &state.icons_path, page, Path::new(&path))
+}
+
+// Shell commands and URLs in a bundle, shown before import_profile
+#[tauri::command]
+fn review_profile(path: String) -> Result<Vec<profile_bundle::ImportedAction>, String> {
+    profile_bundle::review(Path::new(&path))
+}
+
+// Append the pages of a bundle; returns how many were added. `approved`
+// confirms the user saw what review_profile listed.
+#[tauri::command]
+fn import_profile(state: State<AppState>, path: String, approved: bool) -> Result<usize, String> {
+    let mut config = state.config.write().map_err(|e| e.to_string())?;
+    let count = profile_bundle::import(&mut config, &state.icons_path, Path::new(&path), approved)?;
+    drop(config);
+    state.save_config();
+    request_refresh();
+    Ok(count)
+}
+
+// Commands and URLs approved on import, oldest first
+#[tauri::command]
+fn get_import_audit(state: State<AppState>) -> Vec<profile_bundle::AuditEntry> {
+    let app_dir = state.config_path.parent().unwrap_or(&state.config_path);
+    profile_bundle::audit_entries(app_dir)
+}
+
+// ============================================================================
+// Profile Commands
+// ============================================================================
+
+#[tauri::command]
+fn get_profiles(state: State<AppState>) -> Result<profiles::ProfileList, String> {
+    let config = state.config.read().map_err(|e| e.to_string())?;
+    Ok(profiles::list(&config))
+}
+
+// New profile with a single default page
+#[tauri::command]
+fn create_profile(state: State<AppState>, name: String) -> Result<(), String> {
+    let mut config = state.config.write().map_err(|e| e.to_string())?;
+    profiles::create(&mut config, &name, AppState::default_config().pages)?;
+    drop(config);
+    state.save_config();
+    Ok(())
+}
+
+#[tauri::command]
+fn clone_profile(state: State<AppState>, source: String, name: String) -> Result<(), String> {
+    let mut config = state.config.write().map_err(|e| e.to_string())?;
+    profiles::clone(&mut config, &source, &name)?;
+    drop(config);
+    state.save_config();
+    Ok(())
+}
+
+#[tauri::command]
+fn delete_profile(state: State<AppState>, name: String) -> Result<(), String> {
+    let mut config = state.config.write().map_err(|e| e.to_string())?;
+    profiles::delete(&mut config, &name)?;
+    drop(config);
+    state.save_config();
+    Ok(())
+}
+
+#[tauri::command]
+fn switch_profile(state: State<AppState>, name: String) -> Result<(), String> {
+    let mut config = state.config.write().map_err(|e| e.to_string())?;
+    profiles::switch(&mut config, &name)?;
+    drop(config);
+    state.save_config();
+    profiles::activated();
+    Ok(())
+}
+
+// ============================================================================
+// Config Backup Commands
+// ============================================================================
+
+// Set when config.json was unreadable at startup, with the backup to offer
+#[tauri::command]
+fn get_config_recovery() -> Option<config_store::Recovery> {
+    config_store::recovery()
+}
+
+#[tauri::command]
+fn list_config_backups(state: State<AppState>) -> Vec<config_store::BackupInfo> {
+    config_store::list_backups(&state.config_path)
+}
+
+#[tauri::command]
+fn restore_config_backup(state: State<AppState>, name: String) -> Result<Config, String> {
+    let mut restored = config_store::read_backup(&state.config_path, &name)?;
+    restored.normalize_actions();
+    let mut config = state.config.write().map_err(|e| e.to_string())?;
+    *config = restored.clone();
+    drop(config);
+    state.save_config();
+    config_store::clear_recovery();
+    virtual_page::close();
+    request_refresh();
+    debug!("Restored config backup {}", name);
+    Ok(restored)
+}
+
+// ============================================================================
+// Undo/Redo Commands
+// ============================================================================
+
+// Step back over the last config edit; returns the restored config
+#[tauri::command]
+fn undo_config(state: State<AppState>) -> Result<Config, String> {
+    let mut config = state.config.write().map_err(|e| e.to_string())?;
+    let previous = state
+        .history
+        .lock()
+        .map_err(|e| e.to_string())?
+        .undo(&config)
+        .ok_or("Nothing to undo")?;
+    *config = previous.clone();
+    drop(config);
+    state.save_config();
+    virtual_page::close();
+    request_refresh();
+    Ok(previous)
+}
+
+#[tauri::command]
+fn redo_config(state: State<AppState>) -> Result<Config, String> {
+    let mut config = state.config.write().map_err(|e| e.to_string())?;
+    let next = state
+        .history
+        .lock()
+        .map_err(|e| e.to_string())?
+        .redo(&config)
+        .ok_or("Nothing to redo")?;
+    *config = next.clone();
+    drop(config);
+    state.save_config();
+    virtual_page::close();
+    request_refresh();
+    Ok(next)
+}
+
+// How many steps can be undone and redone
+#[tauri::command]
+fn get_config_history(state: State<AppState>) -> Result<history::HistoryState, String> {
+    Ok(state.history.lock().map_err(|e| e.to_string())?.state())
+}
+
 #[tauri::command]
 fn list_icons(state: State<AppState>) -> Vec<String> {
-    let mut icons = Vec::new();
-    if let Ok(entries) = fs::read_dir(&state.icons_path) {
-        for entry in entries.flatten() {
-            if let Some(name) = entry.file_name().to_str() {
-                if name.ends_with(".png") || name.ends_with(".jpg") || name.ends_with(".jpeg") {
-                    icons.push(name.to_string());
-                }
-            }
-        }
+    icons::list(&state.icons_path)
+}
+
+// Buttons (in any profile) that show the icon
+#[tauri::command]
+fn get_icon_usage(state: State<AppState>, name: String) -> Result<Vec<icons::IconUsage>, String> {
+    let config = state.config.read().map_err(|e| e.to_string())?;
+    Ok(icons::usage(&config, &name))
+}
+
+// Returns how many buttons were pointed at the new name
+#[tauri::command]
+fn rename_icon(state: State<AppState>, old_name: String, new_name: String) -> Result<usize, String> {
+    let mut config = state.config.write().map_err(|e| e.to_string())?;
+    let changed = icons::rename(&mut config, &state.icons_path, &old_name, &new_name)?;
+    drop(config);
+    state.save_config();
+    request_refresh();
+    Ok(changed)
+}
+
+// Fails while buttons use the icon unless `force`; returns the buttons that lost it
+#[tauri::command]
+fn delete_icon(state: State<AppState>, name: String, force: bool) -> Result<Vec<icons::IconUsage>, String> {
+    let mut config = state.config.write().map_err(|e| e.to_string())?;
+    let users = icons::delete(&mut config, &state.icons_path, &name, force)?;
+    drop(config);
+    if !users.is_empty() {
+        state.save_config();
+        request_refresh();
+    }
+    Ok(users)
+}
+
+#[tauri::command]
+fn dedupe_icons(state: State<AppState>) -> Result<icons::DedupeResult, String> {
+    let mut config = state.config.write().map_err(|e| e.to_string())?;
+    let result = icons::dedupe(&mut config, &state.icons_path);
+    drop(config);
+    if !result.merged.is_empty() {
+        state.save_config();
     }
-    icons.sort();
-    icons
+    Ok(result)
+}
+
+// Icon files no button uses
+#[tauri::command]
+fn get_orphan_icons(state: State<AppState>) -> Result<Vec<String>, String> {
+    let config = state.config.read().map_err(|e| e.to_string())?;
+    Ok(icons::orphans(&config, &state.icons_path))
 }
 
 #[tauri::command]
@@ -2685,6 +5101,8 @@ fn get_preset_commands() -> Vec<(String, String, String)> {
         ("Vol +".to_string(), "wpctl set-volume @DEFAULT_AUDIO_SINK@ 5%+".to_string(), "Subir volumen".to_string()),
         ("Vol -".to_string(), "wpctl set-volume @DEFAULT_AUDIO_SINK@ 5%-".to_string(), "Bajar volumen".to_string()),
         ("Mute".to_string(), "wpctl set-mute @DEFAULT_AUDIO_SINK@ toggle".to_string(), "Silenciar/Activar audio".to_string()),
+        ("Salida".to_string(), "__AUDIO_OUT__".to_string(), "Widget: salida de audio; presionar cambia a la siguiente".to_string()),
+        ("HDMI".to_string(), "__AUDIO_OUT_HDMI__".to_string(), "Sacar el audio por HDMI".to_string()),
         ("Play/Pause".to_string(), "playerctl play-pause".to_string(), "Reproducir/Pausar media".to_string()),
         ("Next".to_string(), "playerctl next".to_string(), "Siguiente pista".to_string()),
         ("Prev".to_string(), "playerctl previous".to_string(), "Pista anterior".to_string()),
@@ -2734,6 +5152,9 @@ fn get_preset_commands() -> Vec<(String, String, String)> {
         // Widgets - Fecha/Hora
         ("Reloj".to_string(), "__CLOCK__".to_string(), "Muestra hora actual (HH:MM)".to_string()),
         ("Reloj+Seg".to_string(), "__CLOCK_S__".to_string(), "Muestra hora con segundos".to_string()),
+        ("Reloj 12h".to_string(), "__CLOCK_FMT_%I:%M %p__".to_string(), "Hora en formato 12 horas (editar formato strftime)".to_string()),
+        ("Fecha ISO".to_string(), "__CLOCK_FMT_%Y-%m-%d__".to_string(), "Fecha ISO 8601 (editar formato strftime)".to_string()),
+        ("Semana".to_string(), "__CLOCK_FMT_S%V__".to_string(), "Número de semana ISO".to_string()),
         ("Fecha".to_string(), "__DATE__".to_string(), "Muestra fecha (DD/MM)".to_string()),
         ("Fecha completa".to_string(), "__DATE_FULL__".to_string(), "Muestra fecha completa".to_string()),
         ("Día semana".to_string(), "__WEEKDAY__".to_string(), "Muestra día de la semana".to_string()),
@@ -2742,6 +5163,11 @@ fn get_preset_commands() -> Vec<(String, String, String)> {
         ("CPU %".to_string(), "__CPU__".to_string(), "Muestra uso de CPU".to_string()),
         ("RAM %".to_string(), "__RAM__".to_string(), "Muestra uso de RAM".to_string()),
         ("Temp CPU".to_string(), "__TEMP__".to_string(), "Muestra temperatura CPU".to_string()),
+        ("Uptime".to_string(), "__UPTIME__".to_string(), "Muestra el tiempo desde el arranque".to_string()),
+        ("Load".to_string(), "__LOAD__".to_string(), "Muestra la carga media del último minuto".to_string()),
+        ("Updates".to_string(), "__UPDATES__".to_string(), "Actualizaciones pendientes; presionar abre la terminal para actualizar".to_string()),
+        ("Journal".to_string(), "__JOURNAL_ERRORS__".to_string(), "Errores del journal en la última hora; presionar los muestra en la terminal".to_string()),
+        ("Proceso".to_string(), "__PROC_syncthing__".to_string(), "Widget: proceso en marcha (verde, con CPU/RAM) o caído (rojo); presionar lo detiene o lo inicia (editar nombre)".to_string()),
 
         // Widgets - Timer
         ("Timer 1m".to_string(), "__TIMER_1__".to_string(), "Temporizador 1 minuto".to_string()),
@@ -2749,12 +5175,21 @@ fn get_preset_commands() -> Vec<(String, String, String)> {
         ("Timer 10m".to_string(), "__TIMER_10__".to_string(), "Temporizador 10 minutos".to_string()),
         ("Timer 15m".to_string(), "__TIMER_15__".to_string(), "Temporizador 15 minutos".to_string()),
         ("Timer 30m".to_string(), "__TIMER_30__".to_string(), "Temporizador 30 minutos".to_string()),
+        ("Timer ?".to_string(), "__INPUT_TIMER__".to_string(), "Temporizador: escribir los minutos en el deck".to_string()),
+        ("Brillo ?".to_string(), "__INPUT_BRIGHTNESS__".to_string(), "Brillo: escribir el valor (0-100) en el deck".to_string()),
+        ("Transición ?".to_string(), "__INPUT_OBS_TRANSITION__".to_string(), "OBS: escribir la duración de la transición (ms) en el deck".to_string()),
+        ("Sonido".to_string(), "__SOUND_MUTE__".to_string(), "Silenciar/activar los sonidos".to_string()),
+        ("Aviso".to_string(), "__NOTIFY_Título|Texto__".to_string(), "Mostrar una notificación del escritorio".to_string()),
 
         // OBS Studio - WebSocket Control
         ("OBS Stream".to_string(), "__OBS_STREAM__".to_string(), "Iniciar/Detener streaming".to_string()),
+        ("Escenas".to_string(), "__OBS_SCENES__".to_string(), "Página con una tecla por escena de OBS".to_string()),
+        ("Capítulo".to_string(), "__OBS_CHAPTER__".to_string(), "Marcar un capítulo en la grabación".to_string()),
         ("OBS Record".to_string(), "__OBS_RECORD__".to_string(), "Iniciar/Detener grabación".to_string()),
+        ("OBS Abrir".to_string(), "__OBS_LAUNCH__".to_string(), "Abrir OBS y esperar a su WebSocket".to_string()),
         ("OBS Mute".to_string(), "__OBS_MUTE__".to_string(), "Mutear/Desmutear micrófono".to_string()),
         ("OBS Status".to_string(), "__OBS_STATUS__".to_string(), "Widget: muestra LIVE/REC".to_string()),
+        ("OBS Salud".to_string(), "__OBS_HEALTH__".to_string(), "Widget: frames perdidos y bitrate del stream".to_string()),
         ("Escena 1".to_string(), "__OBS_SCENE_Scene".to_string(), "Cambiar a escena (editar nombre)".to_string()),
         ("Escena Gaming".to_string(), "__OBS_SCENE_Gaming".to_string(), "Cambiar a escena Gaming".to_string()),
         ("Escena Webcam".to_string(), "__OBS_SCENE_Webcam".to_string(), "Cambiar a escena Webcam".to_string()),
@@ -2770,12 +5205,85 @@ fn get_preset_commands() -> Vec<(String, String, String)> {
         ("Chat Hola".to_string(), "__TWITCH_CHAT_¡Hola chat!".to_string(), "Enviar mensaje al chat".to_string()),
         ("Chat BRB".to_string(), "__TWITCH_CHAT_BRB - Vuelvo en un momento".to_string(), "Enviar BRB al chat".to_string()),
 
-        // Hyprland/Sway workspaces
-        ("WS 1".to_string(), "hyprctl dispatch workspace 1".to_string(), "Ir a workspace 1".to_string()),
-        ("WS 2".to_string(), "hyprctl dispatch workspace 2".to_string(), "Ir a workspace 2".to_string()),
-        ("WS 3".to_string(), "hyprctl dispatch workspace 3".to_string(), "Ir a workspace 3".to_string()),
-        ("WS 4".to_string(), "hyprctl dispatch workspace 4".to_string(), "Ir a workspace 4".to_string()),
-        ("WS 5".to_string(), "hyprctl dispatch workspace 5".to_string(), "Ir a workspace 5".to_string()),
+        // Systemd - Servicios
+        ("Servicio".to_string(), "__SYSTEMD_nombre__".to_string(), "Widget: estado de servicio del sistema, toggle start/stop (editar nombre)".to_string()),
+        ("Servicio usuario".to_string(), "__SYSTEMD_USER_nombre__".to_string(), "Widget: estado de servicio de usuario, toggle start/stop (editar nombre)".to_string()),
+
+        // Docker
+        ("Contenedor".to_string(), "__DOCKER_nombre__".to_string(), "Widget: estado del contenedor, toggle start/stop (editar nombre)".to_string()),
+        ("Contenedor CPU".to_string(), "__DOCKER_CPU_nombre__".to_string(), "Widget: uso de CPU del contenedor".to_string()),
+        ("Reiniciar contenedor".to_string(), "__DOCKER_RESTART_nombre__".to_string(), "Reiniciar contenedor (editar nombre)".to_string()),
+        ("Compose restart".to_string(), "__COMPOSE_RESTART_proyecto__".to_string(), "Reiniciar proyecto docker compose".to_string()),
+
+        // CI
+        ("CI GitHub".to_string(), "__CI_GITHUB_usuario/repo__".to_string(), "Widget: estado del último workflow (editar repo)".to_string()),
+        ("CI GitLab".to_string(), "__CI_GITLAB_grupo/proyecto__".to_string(), "Widget: estado del último pipeline (editar proyecto)".to_string()),
+
+        // GitHub
+        ("GitHub Notifs".to_string(), "__GITHUB_NOTIFS__".to_string(), "Widget: notificaciones sin leer".to_string()),
+        ("GitHub Reviews".to_string(), "__GITHUB_REVIEWS__".to_string(), "Widget: PRs esperando tu review".to_string()),
+
+        // Mouse
+        ("Click".to_string(), "__MOUSE_CLICK_left__".to_string(), "Click izquierdo (left/right/middle)".to_string()),
+        ("Mover mouse".to_string(), "__MOUSE_MOVE_100,200__".to_string(), "Mover el puntero a x,y (editar posición)".to_string()),
+        ("Scroll arriba".to_string(), "__SCROLL_up__".to_string(), "Rueda hacia arriba".to_string()),
+        ("Scroll abajo".to_string(), "__SCROLL_down_3__".to_string(), "Rueda hacia abajo 3 pasos".to_string()),
+
+        // Snippets
+        ("Snippet".to_string(), "__SNIPPET_nombre__".to_string(), "Insertar snippet de la biblioteca (editar nombre)".to_string()),
+
+        // Clipboard
+        ("Portapapeles".to_string(), "__CLIPBOARD_PAGE__".to_string(), "Página con el historial del portapapeles, presionar pega".to_string()),
+
+        // SSH
+        ("SSH hosts".to_string(), "__SSH_PAGE__".to_string(), "Página con los hosts de ~/.ssh/config".to_string()),
+        ("Ventanas".to_string(), "__WINDOWS_PAGE__".to_string(), "Página con las ventanas abiertas; pulsar una la enfoca".to_string()),
+        ("SSH".to_string(), "__SSH_servidor__".to_string(), "Abrir terminal con ssh al host (editar nombre)".to_string()),
+
+        // Kubernetes
+        ("Kube contexto".to_string(), "__KUBE_CONTEXT__".to_string(), "Widget: contexto actual, presionar cambia al siguiente".to_string()),
+        ("Kube cambiar".to_string(), "__KUBE_CTX_contexto__".to_string(), "Cambiar a un contexto (editar nombre)".to_string()),
+        ("Kube pods".to_string(), "__KUBE_PODS_default__".to_string(), "Widget: pods que no están Running (editar namespace)".to_string()),
+
+        // Shell command widget
+        ("Salida comando".to_string(), "__CMD_30_uptime -p__".to_string(), "Widget: salida de un comando cada N segundos (editar)".to_string()),
+        ("Firefox".to_string(), "__APP_firefox__".to_string(), "Abrir Firefox o enfocar su ventana".to_string()),
+        ("Captura".to_string(), "__SCREENSHOT_region__".to_string(), "Captura de una región".to_string()),
+        ("Pantalla".to_string(), "__SCREENSHOT_full__".to_string(), "Captura de pantalla completa".to_string()),
+        ("Grabar".to_string(), "__SCREENREC_TOGGLE__".to_string(), "Iniciar/detener grabación de pantalla".to_string()),
+        ("Color".to_string(), "__COLORPICK__".to_string(), "Tomar un color de la pantalla y copiar su código".to_string()),
+        ("Bloquear".to_string(), "__POWER_lock__".to_string(), "Bloquear la sesión".to_string()),
+        ("Suspender".to_string(), "__POWER_suspend__".to_string(), "Suspender el equipo".to_string()),
+        ("Apagar".to_string(), "__POWER_shutdown__".to_string(), "Apagar el equipo (activar mantener para confirmar)".to_string()),
+        ("Reiniciar".to_string(), "__POWER_reboot__".to_string(), "Reiniciar el equipo (activar mantener para confirmar)".to_string()),
+        ("Monitor".to_string(), "__MONITOR_BRIGHT__".to_string(), "Widget: brillo del monitor".to_string()),
+        ("Brillo +".to_string(), "__MONITOR_BRIGHT_+10__".to_string(), "Subir brillo del monitor".to_string()),
+        ("Brillo -".to_string(), "__MONITOR_BRIGHT_-10__".to_string(), "Bajar brillo del monitor".to_string()),
+        ("Noche".to_string(), "__NIGHTLIGHT__".to_string(), "Activar/desactivar luz nocturna".to_string()),
+        ("Wi-Fi".to_string(), "__WIFI__".to_string(), "Widget: red Wi-Fi y señal".to_string()),
+        ("Wi-Fi On/Off".to_string(), "__WIFI_TOGGLE__".to_string(), "Activar/desactivar Wi-Fi".to_string()),
+        ("On Air".to_string(), "__ONAIR__".to_string(), "Widget: cámara/micrófono en uso".to_string()),
+        ("Pantalla".to_string(), "__SCREENSHARE__".to_string(), "Widget: pantalla compartida (screencast del portal)".to_string()),
+        ("Ventana".to_string(), "__WINDOW__".to_string(), "Widget: título de la ventana activa".to_string()),
+        ("VLC Play".to_string(), "__VLC_PLAY__".to_string(), "VLC: reproducir/pausar".to_string()),
+        ("VLC".to_string(), "__VLC_POSITION__".to_string(), "Widget: posición en VLC".to_string()),
+        ("mpv Play".to_string(), "__MPV_PLAY__".to_string(), "mpv: reproducir/pausar".to_string()),
+        ("mpv +10s".to_string(), "__MPV_SEEK_+10__".to_string(), "mpv: adelantar 10 s".to_string()),
+        ("mpv".to_string(), "__MPV_POSITION__".to_string(), "Widget: posición en mpv".to_string()),
+        ("+10s".to_string(), "__MEDIA_SEEK_+10__".to_string(), "Spotify/MPRIS: adelantar 10 s (mantener para seguir)".to_string()),
+        ("-10s".to_string(), "__MEDIA_SEEK_-10__".to_string(), "Spotify/MPRIS: retroceder 10 s (mantener para seguir)".to_string()),
+        ("Vol +".to_string(), "__MEDIA_VOL_+5__".to_string(), "Spotify/MPRIS: subir volumen 5 %".to_string()),
+        ("MIDI".to_string(), "__MIDI_KEY__".to_string(), "MIDI: nota fija de la tecla (para MIDI learn)".to_string()),
+        ("MIDI CC".to_string(), "__MIDI_CC_7_127__".to_string(), "MIDI: control change 7 = 127 (editar)".to_string()),
+        ("Perfil".to_string(), "__PROFILE_Principal__".to_string(), "Cambiar al perfil Principal (editar)".to_string()),
+        ("Tarea".to_string(), "__SCHEDULE_TOGGLE_backup__".to_string(), "Activar/pausar la tarea programada backup (editar)".to_string()),
+
+        // Workspaces (Hyprland, Sway, X11): widget with occupancy, press switches
+        ("WS 1".to_string(), "__WS_1__".to_string(), "Ir a workspace 1".to_string()),
+        ("WS 2".to_string(), "__WS_2__".to_string(), "Ir a workspace 2".to_string()),
+        ("WS 3".to_string(), "__WS_3__".to_string(), "Ir a workspace 3".to_string()),
+        ("WS 4".to_string(), "__WS_4__".to_string(), "Ir a workspace 4".to_string()),
+        ("WS 5".to_string(), "__WS_5__".to_string(), "Ir a workspace 5".to_string()),
 
         // Sistema
         ("Screenshot".to_string(), "grim -g \"$(slurp)\" - | wl-copy".to_string(), "Captura de pantalla".to_string()),
@@ -2792,6 +5300,117 @@ fn get_preset_commands() -> Vec<(String, String, String)> {
         ("Hotkey Ctrl+F1".to_string(), "__HOTKEY_Ctrl+F1__".to_string(), "Activar con Ctrl+F1".to_string()),
         ("Hotkey Ctrl+Shift+1".to_string(), "__HOTKEY_Ctrl+Shift+1__".to_string(), "Activar con Ctrl+Shift+1".to_string()),
     ]
+    .into_iter()
+    .map(|(label, command, description)| (i18n::preset(label), command, i18n::preset(description)))
+    .collect()
+}
+
+// Recent clipboard entries, newest first
+#[tauri::command]
+fn get_clipboard_history() -> Vec<String> {
+    clipboard::get_history()
+}
+
+// Hosts from ~/.ssh/config, for the __SSH_host__ picker in the UI
+#[tauri::command]
+fn get_ssh_hosts() -> Vec<String> {
+    ssh::list_hosts()
+}
+
+// ============================================================================
+// Snippet Library Commands
+// ============================================================================
+
+#[tauri::command]
+fn get_snippets(state: State<AppState>) -> Vec<snippets::Snippet> {
+    snippets::load(&state.config_path)
+}
+
+// Create or update a snippet (matched by name)
+#[tauri::command]
+fn save_snippet(state: State<AppState>, name: String, text: String) -> Result<(), String> {
+    snippets::upsert(&state.config_path, snippets::Snippet { name, text })
+}
+
+#[tauri::command]
+fn delete_snippet(state: State<AppState>, name: String) -> Result<(), String> {
+    snippets::delete(&state.config_path, &name)
+}
+
+// Expanded text of a snippet, for previews in the UI
+#[tauri::command]
+fn preview_snippet(text: String) -> String {
+    snippets::expand(&text).0
+}
+
+// ============================================================================
+// Variable Commands
+// ============================================================================
+
+#[tauri::command]
+fn get_variables() -> HashMap<String, String> {
+    variables::get_all()
+}
+
+#[tauri::command]
+fn set_variable(name: String, value: String) -> Result<(), String> {
+    variables::set(&name, &value)
+}
+
+// ============================================================================
+// Script Commands
+// ============================================================================
+
+#[tauri::command]
+fn get_scripts(state: State<AppState>) -> Vec<String> {
+    scripting::list(&state.config_path)
+}
+
+#[tauri::command]
+fn get_script(state: State<AppState>, name: String) -> Result<String, String> {
+    scripting::read(&state.config_path, &name)
+}
+
+// Save a script after checking its syntax
+#[tauri::command]
+fn save_script(state: State<AppState>, name: String, source: String) -> Result<(), String> {
+    scripting::save(&state.config_path, &name, &source)
+}
+
+// ============================================================================
+// Plugin Commands
+// ============================================================================
+
+#[tauri::command]
+fn get_plugins() -> Vec<plugins::PluginInfo> {
+    plugins::list()
+}
+
+// Launch plugins from <app data>/plugins and Elgato plugins from
+// <app data>/sdplugins. Both run executables or node scripts, so safe mode
+// starts neither.
+fn start_plugins(config_path: &Path) {
+    if config_store::read(|c| c.safe_mode).unwrap_or(false) {
+        debug!("Safe mode: not starting plugins");
+        return;
+    }
+    plugins::start_all(&plugins::plugins_dir(config_path));
+    elgato::start_all(&elgato::plugins_dir(config_path));
+}
+
+// Restart all plugins (after adding or updating one)
+#[tauri::command]
+fn reload_plugins(state: State<AppState>) -> Vec<plugins::PluginInfo> {
+    plugins::stop_all();
+    elgato::stop_all();
+    start_plugins(&state.config_path);
+    request_refresh();
+    plugins::list()
+}
+
+#[tauri::command]
+fn get_sd_plugins() -> Vec<elgato::SdPluginInfo> {
+    elgato::list()
 }
 
 // ============================================================================
@@ -2800,7 +5419,7 @@ fn get_preset_commands() -> Vec<(String, String, String)> {
 
 #[tauri::command]
 fn start_hotkey_recording() -> Result<(), String> {
-    eprintln!("DEBUG: Starting hotkey recording");
+    debug!("Starting hotkey recording");
     // Clear previous recorded keys
     if let Ok(mut recorded) = RECORDED_HOTKEY.write() {
         recorded.clear();
@@ -2812,7 +5431,7 @@ fn start_hotkey_recording() -> Result<(), String> {
 
 #[tauri::command]
 fn stop_hotkey_recording() -> Result<String, String> {
-    eprintln!("DEBUG: Stopping hotkey recording");
+    debug!("Stopping hotkey recording");
     HOTKEY_RECORDING.store(false, Ordering::Relaxed);
 
     // Get the recorded keys
@@ -2832,7 +5451,7 @@ fn stop_hotkey_recording() -> Result<String, String> {
         current.clear();
     }
 
-    eprintln!("DEBUG: Recorded hotkey: {}", hotkey_str);
+    debug!("Recorded hotkey: {}", hotkey_str);
     Ok(hotkey_str)
 }
 
@@ -2845,9 +5464,59 @@ fn get_current_recording() -> Result<String, String> {
     }
 }
 
+// ============================================================================
+// Macro Recording Commands
+// ============================================================================
+
+#[tauri::command]
+fn start_macro_recording() -> Result<(), String> {
+    debug!("Starting macro recording");
+    macro_recorder::start()
+}
+
+// Stop recording and return the __MULTI_ command. If a page and button are
+// given the macro is also saved as that button's command.
+#[tauri::command]
+fn stop_macro_recording(
+    state: State<AppState>,
+    page_index: Option<usize>,
+    button_id: Option<String>,
+) -> Result<String, String> {
+    let command = macro_recorder::stop()?;
+    debug!("Recorded macro: {}", command);
+
+    if let (Some(page_index), Some(button_id)) = (page_index, button_id) {
+        let mut config = state.config.write().map_err(|e| e.to_string())?;
+        let page = config.pages.get_mut(page_index)
+            .ok_or_else(|| format!("Page {} does not exist", page_index))?;
+        let button = page.buttons.entry(button_id.clone()).or_insert_with(|| ButtonConfig {
+            label: "Macro".to_string(),
+            command: String::new(),
+            color: "#1a1a2e".to_string(),
+            icon: String::new(),
+            action: None,
+            hold_confirm: false,
+            confirm: false,
+            sound: None,
+        });
+        button.command = command.clone();
+        button.normalize_action();
+        drop(config);
+        state.save_config();
+        request_refresh();
+    }
+
+    Ok(command)
+}
+
+#[tauri::command]
+fn is_macro_recording() -> bool {
+    macro_recorder::is_recording()
+}
+
 #[tauri::command]
 fn register_hotkey(hotkey: String, page: usize, button_id: u8) -> Result<(), String> {
-    eprintln!("DEBUG: Registering hotkey '{}' for page {} button {}", hotkey, page, button_id);
+    debug!("Registering hotkey '{}' for page {} button {}", hotkey, page, button_id);
     if let Ok(mut hotkeys) = REGISTERED_HOTKEYS.write() {
         hotkeys.insert(hotkey, (page, button_id));
         Ok(())
@@ -2858,7 +5527,7 @@ fn register_hotkey(hotkey: String, page: usize, button_id: u8) -> Result<(), Str
 
 #[tauri::command]
 fn unregister_hotkey(hotkey: String) -> Result<(), String> {
-    eprintln!("DEBUG: Unregistering hotkey '{}'", hotkey);
+    debug!("Unregistering hotkey '{}'", hotkey);
     if let Ok(mut hotkeys) = REGISTERED_HOTKEYS.write() {
         hotkeys.remove(&hotkey);
         Ok(())
@@ -2877,8 +5546,8 @@ fn get_registered_hotkeys() -> Result<Vec<(String, usize, u8)>, String> {
 }
 
 #[tauri::command]
-fn reload_hotkeys(state: State<AppState>) -> Result<(), String> {
-    load_hotkeys_from_config(&state.config_path);
+fn reload_hotkeys() -> Result<(), String> {
+    load_hotkeys_from_config();
     Ok(())
 }
 
@@ -2911,7 +5580,7 @@ pub struct CommitInfo {
 
 #[tauri::command]
 async fn check_for_updates() -> Result<UpdateInfo, String> {
-    eprintln!("DEBUG: Checking for updates...");
+    debug!("Checking for updates...");
 
     // Get latest commits from GitHub API
     let url = format!(
@@ -3004,7 +5673,7 @@ async fn check_for_updates() -> Result<UpdateInfo, String> {
         String::new()
     };
 
-    eprintln!("DEBUG: Update available: {}, changes: {}", available, changes.len());
+    debug!("Update available: {}, changes: {}", available, changes.len());
 
     Ok(UpdateInfo {
         available,
@@ -3019,7 +5688,7 @@ async fn check_for_updates() -> Result<UpdateInfo, String> {
 
 #[tauri::command]
 async fn install_update() -> Result<String, String> {
-    eprintln!("DEBUG: Starting update installation...");
+    debug!("Starting update installation...");
 
     // Get the directory where the app is located
     let exe_path = std::env::current_exe()
@@ -3110,7 +5779,7 @@ echo "Reinicia la aplicación para aplicar los cambios."
 
         if result.is_ok() {
             success = true;
-            eprintln!("DEBUG: Update started in {}", terminal);
+            debug!("Update started in {}", terminal);
             break;
         }
     }
@@ -3137,6 +5806,20 @@ fn get_current_version() -> (String, String) {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // `--developer raw <hex>` talks to the device and exits without a window
+    let cli = protocol_console::run_cli(|| find_device().map(|handle| Box::new(UsbDeck { handle }) as Box<dyn Deck>));
+    if let Some(code) = cli {
+        std::process::exit(code);
+    }
+    // `page 2`, `press 7`... (or the binary linked as deckctl) go to the running app
+    if let Some(code) = cli::run_cli() {
+        std::process::exit(code);
+    }
+    // One instance owns the device; a second launch only shows its window
+    if !cli::claim_instance() {
+        std::process::exit(cli::hand_off());
+    }
+
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
@@ -3144,9 +5827,41 @@ pub fn run() {
         .setup(|app| {
             let app_dir = app.path().app_data_dir().unwrap_or_else(|_| PathBuf::from("."));
             fs::create_dir_all(&app_dir).ok();
+            logging::init(&app_dir);
+            usage_stats::init(&app_dir);
+
+            // Flatpak stops apps without windows unless the portal allows it
+            if sandbox::is_flatpak() {
+                debug!("Running in Flatpak");
+                thread::spawn(sandbox::request_background);
+            }
 
             let state = AppState::new(app_dir.clone());
 
+            // Live mirror of the deck in the window
+            let handle = app.handle().clone();
+            deck::set_mirror_sink(move |event| {
+                handle.emit("deck-mirror", event).ok();
+            });
+
+            // Apply the configured input backend for __KEY_ / __TYPE_
+            if let Ok(config) = state.config.read() {
+                input::set_backend(config.input_backend);
+                if let Err(e) = logging::set_level(&config.log_level) {
+                    warn!("{}", e);
+                }
+
+                // Create the MIDI port up front so the DAW can see it
+                let uses_midi = config.pages.iter()
+                    .flat_map(|p| p.buttons.values())
+                    .any(|b| midi::is_midi_command(&b.command));
+                if uses_midi {
+                    if let Err(e) = midi::open_port() {
+                        warn!("{}", e);
+                    }
+                }
+            }
+
             // Start the button listener in background
             let config_path = app_dir.join("config.json");
             let icons_path = app_dir.join("icons");
@@ -3156,39 +5871,166 @@ pub fn run() {
             start_keyboard_listener(config_path.clone(), icons_path.clone());
 
             // Load registered hotkeys from config
-            load_hotkeys_from_config(&config_path);
+            load_hotkeys_from_config();
+
+            // Record clipboard history for __CLIPBOARD_PAGE__
+            clipboard::start_watcher();
+
+            // Launch plugins and Elgato Stream Deck plugins (not in safe mode)
+            start_plugins(&config_path);
+
+            // Switch to the privacy page while the camera/mic is live
+            privacy::start_monitor(config_path.clone());
+
+            // Redraw the deck after suspend (logind PrepareForSleep)
+            power::start_sleep_monitor();
+
+            // Blank the deck while the screen is locked
+            lock_screen::start_monitor();
+
+            // Jobs from "schedule" in config.json
+            scheduler::start(config_path.clone(), icons_path.clone());
+
+            // Blink the timer keys and take the break page when a timer ends
+            timer_alarm::start(config_path.clone());
+
+            // Followers, raids and stopped recordings flash the deck
+            alerts::start_monitors();
+
+            // Phone/tablet virtual deck, when enabled in config.json
+            if let Err(e) = remote::start(config_path.clone(), icons_path.clone()) {
+                debug!("Remote deck not started: {}", e);
+            }
+
+            // deckctl commands from scripts and keybindings
+            cli::start_server(app.handle(), config_path.clone(), icons_path.clone());
 
             app.manage(state);
 
+            // Tray icon; the window then hides on close instead of quitting
+            tray::setup(app.handle(), config_path);
+
             Ok(())
         })
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                if tray::active() {
+                    window.hide().ok();
+                    api.prevent_close();
+                }
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             get_config,
             save_full_config,
+            get_input_backend,
+            set_input_backend,
+            set_privacy_page,
+            set_remote_deck,
+            get_simulator_keys,
+            get_deck_mirror,
+            export_profile,
+            review_profile,
+            import_profile,
+            get_import_audit,
+            get_profiles,
+            create_profile,
+            clone_profile,
+            delete_profile,
+            switch_profile,
+            get_config_recovery,
+            list_config_backups,
+            restore_config_backup,
+            undo_config,
+            redo_config,
+            get_config_history,
+            simulate_key,
             get_status,
             connect_device,
             set_page,
             add_page,
             delete_page,
             update_page_name,
+            set_page_style,
             update_button,
+            preview_button,
             set_brightness_level,
+            preview_brightness,
+            set_jpeg_quality,
+            measure_jpeg_quality,
+            set_orientation,
+            set_language,
+            get_recent_logs,
+            get_device_info,
+            get_diagnostics,
+            check_dependencies,
+            get_environment,
+            send_raw_command,
+            start_calibration,
+            cancel_calibration,
+            get_calibration_status,
+            reset_key_map,
+            is_developer_mode,
+            set_log_level,
+            get_usage_stats,
+            set_usage_stats,
+            clear_usage_stats,
+            set_scheduled_job_enabled,
+            run_scheduled_job,
+            show_key_message,
+            set_notifications,
+            set_safe_mode,
             run_command,
+            validate_action,
+            test_action,
+            get_action_log,
             refresh_device,
             load_current_page,
             get_icons_path,
             setup_udev_rules,
             check_udev_rules,
+            setup_uinput_rules,
+            check_uinput_access,
             save_icon,
             reset_config,
             list_icons,
+            save_icon_data,
+            save_emoji_icon,
+            get_icon_usage,
+            rename_icon,
+            delete_icon,
+            dedupe_icons,
+            get_orphan_icons,
             get_icon_data,
             get_preset_commands,
             clear_page_buttons,
+            move_button,
+            get_ssh_hosts,
+            get_clipboard_history,
+            // Snippet commands
+            get_snippets,
+            save_snippet,
+            delete_snippet,
+            preview_snippet,
+            // Variable commands
+            get_variables,
+            set_variable,
+            // Script commands
+            get_scripts,
+            get_script,
+            save_script,
+            // Plugin commands
+            get_plugins,
+            reload_plugins,
+            get_sd_plugins,
             // Hotkey commands
             start_hotkey_recording,
             stop_hotkey_recording,
             get_current_recording,
+            // Macro recorder commands
+            start_macro_recording,
+            stop_macro_recording,
+            is_macro_recording,
             register_hotkey,
             unregister_hotkey,
             get_registered_hotkeys,
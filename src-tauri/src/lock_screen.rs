@@ -0,0 +1,98 @@
+// ============================================================================
+// Lock Screen Blanking
+// ============================================================================
+//
+// While the session is locked the deck must not show widget contents (mail
+// counts, CI status, clipboard...) nor run commands on a stray press. The
+// lock state comes from logind's LockedHint, which GNOME, KDE and the usual
+// Wayland lockers (swaylock, hyprlock via ext-session-lock) set.
+//
+// "lockScreen" in config.json picks what the deck shows meanwhile:
+//   "blank"  - all keys dark, backlight off (default)
+//   "page"   - a minimal page with a lock on the center key
+//   "off"    - keep the page as it is (presses are still ignored)
+//
+// A generated page that was open when the screen locked comes back on unlock.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::{config_store, power, virtual_page, Page};
+
+const LOCK_PAGE_NAME: &str = "Locked";
+const LOCK_KEY: u8 = 8;
+const LOCK_COLOR: &str = "#1a1a2e";
+
+static LOCKED: AtomicBool = AtomicBool::new(false);
+
+lazy_static::lazy_static! {
+    // Generated page open before locking, if any
+    static ref PREVIOUS: Mutex<Option<Page>> = Mutex::new(None);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LockScreen {
+    #[default]
+    Blank,
+    Page,
+    Off,
+}
+
+// Whether key presses should be ignored
+pub fn locked() -> bool {
+    LOCKED.load(Ordering::SeqCst)
+}
+
+fn lock_page(mode: LockScreen) -> Option<Page> {
+    let mut buttons = HashMap::new();
+    let brightness = match mode {
+        LockScreen::Off => return None,
+        LockScreen::Blank => Some(0),
+        LockScreen::Page => {
+            buttons.insert(LOCK_KEY.to_string(), virtual_page::button("🔒", "", LOCK_COLOR));
+            None
+        }
+    };
+    Some(Page {
+        name: LOCK_PAGE_NAME.to_string(),
+        buttons,
+        brightness,
+        default_color: None,
+    })
+}
+
+fn on_lock_change(locked: bool) {
+    if LOCKED.swap(locked, Ordering::SeqCst) == locked {
+        return;
+    }
+    let mode = config_store::read(|c| c.lock_screen).unwrap_or_default();
+    debug!("Session {}, lock screen mode {:?}", if locked { "locked" } else { "unlocked" }, mode);
+
+    if locked {
+        if let Some(page) = lock_page(mode) {
+            if let Ok(mut previous) = PREVIOUS.lock() {
+                *previous = virtual_page::active();
+            }
+            virtual_page::open(page);
+        }
+        return;
+    }
+    // Back to what was shown before (the mode may have changed meanwhile)
+    let is_lock_page = virtual_page::active().map(|p| p.name == LOCK_PAGE_NAME).unwrap_or(false);
+    let previous = PREVIOUS.lock().ok().and_then(|mut p| p.take());
+    match previous {
+        Some(page) if is_lock_page => virtual_page::open(page),
+        _ if is_lock_page => virtual_page::close(),
+        _ => {}
+    }
+}
+
+// Follow the session lock state; called once at startup
+pub fn start_monitor() {
+    power::watch_session_lock(on_lock_change);
+}
@@ -0,0 +1,111 @@
+// ============================================================================
+// Logging
+// ============================================================================
+//
+// Log lines go to stderr (as before, when started from a terminal) and to
+// <app data>/logs/redragon.<date>.log, one file per day, the last MAX_FILES
+// kept, so a bug report can include them without a terminal (get_recent_logs).
+// The level for this app is "logLevel" in config.json (error, warn, info,
+// debug, trace) and can be changed at runtime with set_log_level; other
+// crates only log warnings. USB packet dumps are at trace level.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::{LevelFilter, Targets};
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{fmt, reload, Registry};
+
+pub const DEFAULT_LEVEL: &str = "debug";
+const FILE_PREFIX: &str = "redragon";
+const FILE_SUFFIX: &str = "log";
+const MAX_FILES: usize = 7;
+// Lines returned by get_recent_logs when not asked for a number
+pub const DEFAULT_RECENT_LINES: usize = 500;
+
+static FILTER: OnceLock<reload::Handle<Targets, Registry>> = OnceLock::new();
+static LOG_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+pub fn default_level() -> String {
+    DEFAULT_LEVEL.to_string()
+}
+
+fn targets(level: LevelFilter) -> Targets {
+    Targets::new()
+        .with_target(env!("CARGO_CRATE_NAME"), level)
+        .with_default(LevelFilter::WARN)
+}
+
+fn parse_level(level: &str) -> Result<LevelFilter, String> {
+    level.trim().parse::<LevelFilter>().map_err(|_| format!("Unknown log level: {}", level))
+}
+
+// Install the subscriber; called once at startup
+pub fn init(app_dir: &Path) {
+    let log_dir = app_dir.join("logs");
+    fs::create_dir_all(&log_dir).ok();
+
+    let (filter, handle) = reload::Layer::new(targets(LevelFilter::DEBUG));
+    let stderr = fmt::layer().with_writer(std::io::stderr);
+    let file = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(FILE_PREFIX)
+        .filename_suffix(FILE_SUFFIX)
+        .max_log_files(MAX_FILES)
+        .build(&log_dir)
+        .map(|appender| fmt::layer().with_ansi(false).with_writer(appender))
+        .map_err(|e| eprintln!("Failed to open the log file in {}: {}", log_dir.display(), e))
+        .ok();
+
+    if tracing_subscriber::registry().with(filter).with(stderr).with(file).try_init().is_err() {
+        return;
+    }
+    FILTER.set(handle).ok();
+    LOG_DIR.set(log_dir).ok();
+}
+
+// <app data>/logs, once init ran
+pub fn log_dir() -> Option<PathBuf> {
+    LOG_DIR.get().cloned()
+}
+
+pub fn set_level(level: &str) -> Result<(), String> {
+    let level = parse_level(level)?;
+    let handle = FILTER.get().ok_or("Logging is not initialized")?;
+    handle.reload(targets(level)).map_err(|e| format!("Failed to set log level: {}", e))?;
+    tracing::info!("Log level set to {}", level);
+    Ok(())
+}
+
+// The last `lines` lines, across the daily files
+pub fn recent(lines: usize) -> Result<String, String> {
+    let log_dir = LOG_DIR.get().ok_or("Logging is not initialized")?;
+    let mut files: Vec<PathBuf> = fs::read_dir(log_dir)
+        .map_err(|e| format!("Failed to read {}: {}", log_dir.display(), e))?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .map(|n| n.starts_with(FILE_PREFIX) && n.ends_with(FILE_SUFFIX))
+                .unwrap_or(false)
+        })
+        .collect();
+    // Dated names sort oldest first
+    files.sort();
+
+    let mut collected: Vec<String> = Vec::new();
+    for file in files.iter().rev() {
+        let content = fs::read_to_string(file).unwrap_or_default();
+        let mut older: Vec<String> = content.lines().map(str::to_string).collect();
+        older.append(&mut collected);
+        collected = older;
+        if collected.len() >= lines {
+            break;
+        }
+    }
+    let skip = collected.len().saturating_sub(lines);
+    Ok(collected[skip..].join("\n"))
+}
@@ -0,0 +1,184 @@
+// ============================================================================
+// Macro Recorder
+// ============================================================================
+//
+// Records key presses from every readable keyboard (evdev) and turns them
+// into a __MULTI_ action: each key press becomes a __KEY_ step (with the
+// modifiers held at that moment) and pauses become __DELAY_ steps.
+//
+// Reading /dev/input/event* requires the user to be in the `input` group.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use evdev::{EventType, KeyCode};
+use tracing::{debug, warn};
+
+use crate::key_name_to_code;
+
+// Pauses shorter than this are not recorded
+const MIN_DELAY_MS: u128 = 150;
+// __MULTI_ already waits this long between steps
+const MULTI_STEP_DELAY_MS: u128 = 100;
+
+// Name prefix of our own uinput devices, never recorded
+const OWN_DEVICE_PREFIX: &str = "Redragon Stream Deck";
+
+// Names used when converting key codes back to __KEY_ names
+const KEY_NAMES: &[&str] = &[
+    "ctrl", "shift", "alt", "super", "rctrl", "rshift", "ralt", "rsuper",
+    "f1", "f2", "f3", "f4", "f5", "f6", "f7", "f8", "f9", "f10", "f11", "f12",
+    "esc", "tab", "enter", "space", "backspace", "delete", "insert", "home", "end",
+    "pageup", "pagedown", "up", "down", "left", "right", "printscreen", "pause",
+    "capslock", "numlock", "scrolllock", "menu",
+    "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k", "l", "m",
+    "n", "o", "p", "q", "r", "s", "t", "u", "v", "w", "x", "y", "z",
+    "0", "1", "2", "3", "4", "5", "6", "7", "8", "9",
+    "minus", "equal", "leftbracket", "rightbracket", "backslash", "semicolon",
+    "apostrophe", "grave", "comma", "period", "slash",
+    "volumeup", "volumedown", "mute", "playpause", "stop", "next", "prev",
+    "kp0", "kp1", "kp2", "kp3", "kp4", "kp5", "kp6", "kp7", "kp8", "kp9",
+    "kpenter", "kpplus", "kpminus", "kpmultiply", "kpdivide", "kpdot",
+];
+
+const MODIFIERS: &[&str] = &["ctrl", "shift", "alt", "super", "rctrl", "rshift", "ralt", "rsuper"];
+
+static RECORDING: AtomicBool = AtomicBool::new(false);
+
+lazy_static::lazy_static! {
+    // (timestamp, key code, pressed)
+    static ref EVENTS: Mutex<Vec<(SystemTime, u16, bool)>> = Mutex::new(Vec::new());
+}
+
+fn code_to_name(code: u16) -> Option<&'static str> {
+    let code = code.to_string();
+    KEY_NAMES.iter().copied().find(|name| key_name_to_code(name) == Some(code.as_str()))
+}
+
+pub fn is_recording() -> bool {
+    RECORDING.load(Ordering::SeqCst)
+}
+
+// Start listening on all keyboards
+pub fn start() -> Result<(), String> {
+    if RECORDING.swap(true, Ordering::SeqCst) {
+        return Err("Macro recording already in progress".to_string());
+    }
+    if let Ok(mut events) = EVENTS.lock() {
+        events.clear();
+    }
+
+    let keyboards: Vec<evdev::Device> = evdev::enumerate()
+        .map(|(_, device)| device)
+        .filter(|device| {
+            let is_keyboard = device
+                .supported_keys()
+                .map(|keys| keys.contains(KeyCode::KEY_A))
+                .unwrap_or(false);
+            let is_own = device.name().unwrap_or("").starts_with(OWN_DEVICE_PREFIX);
+            is_keyboard && !is_own
+        })
+        .collect();
+
+    if keyboards.is_empty() {
+        RECORDING.store(false, Ordering::SeqCst);
+        return Err("No readable keyboards found (is the user in the 'input' group?)".to_string());
+    }
+
+    for mut device in keyboards {
+        debug!("Recording macro from {}", device.name().unwrap_or("unknown"));
+        thread::spawn(move || {
+            if device.set_nonblocking(true).is_err() {
+                return;
+            }
+            while RECORDING.load(Ordering::SeqCst) {
+                match device.fetch_events() {
+                    Ok(events) => {
+                        // value 2 is autorepeat
+                        let keys: Vec<(SystemTime, u16, bool)> = events
+                            .filter(|e| e.event_type() == EventType::KEY && e.value() != 2)
+                            .map(|e| (e.timestamp(), e.code(), e.value() == 1))
+                            .collect();
+                        if let Ok(mut recorded) = EVENTS.lock() {
+                            recorded.extend(keys);
+                        }
+                    }
+                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                        thread::sleep(Duration::from_millis(10));
+                    }
+                    Err(e) => {
+                        warn!("Macro recorder read error: {}", e);
+                        break;
+                    }
+                }
+            }
+        });
+    }
+
+    Ok(())
+}
+
+// Stop recording and return the recorded __MULTI_ command
+pub fn stop() -> Result<String, String> {
+    if !RECORDING.swap(false, Ordering::SeqCst) {
+        return Err("Macro recording is not running".to_string());
+    }
+    // Let the reader threads drain their last events
+    thread::sleep(Duration::from_millis(50));
+
+    let mut events = EVENTS.lock().map_err(|e| e.to_string())?.clone();
+    events.sort_by_key(|(time, _, _)| *time);
+
+    let steps = events_to_steps(&events);
+    if steps.is_empty() {
+        return Err("No keys were recorded".to_string());
+    }
+    Ok(format!("__MULTI_{}", steps.join(";;")))
+}
+
+fn events_to_steps(events: &[(SystemTime, u16, bool)]) -> Vec<String> {
+    let mut steps = Vec::new();
+    let mut held_modifiers: Vec<&str> = Vec::new();
+    let mut last_step: Option<SystemTime> = None;
+
+    for (time, code, pressed) in events {
+        let name = match code_to_name(*code) {
+            Some(n) => n,
+            None => continue,
+        };
+
+        if MODIFIERS.contains(&name) {
+            if *pressed {
+                if !held_modifiers.contains(&name) {
+                    held_modifiers.push(name);
+                }
+            } else {
+                held_modifiers.retain(|m| *m != name);
+            }
+            continue;
+        }
+        if !pressed {
+            continue;
+        }
+
+        if let Some(last) = last_step {
+            let gap = time.duration_since(last).map(|d| d.as_millis()).unwrap_or(0);
+            if gap >= MIN_DELAY_MS {
+                // Round to 10ms so the command stays readable
+                let delay = (gap - MULTI_STEP_DELAY_MS) / 10 * 10;
+                if delay > 0 {
+                    steps.push(format!("__DELAY_{}", delay));
+                }
+            }
+        }
+        last_step = Some(*time);
+
+        let mut combo: Vec<&str> = held_modifiers.clone();
+        combo.push(name);
+        steps.push(format!("__KEY_{}", combo.join("+")));
+    }
+
+    steps
+}
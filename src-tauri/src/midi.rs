@@ -0,0 +1,131 @@
+// ============================================================================
+// MIDI Output (virtual ALSA port via midir)
+// ============================================================================
+//
+// A virtual output port named "Redragon StreamDeck" appears once a MIDI
+// command is used (or at startup when the config has any). Connect it to the
+// DAW as a MIDI input.
+//
+// Command formats (channel 1 unless CH<n>_ is given):
+//   __MIDI_NOTE_60__          - note on (velocity 127), note off 100ms later
+//   __MIDI_NOTE_60_100__      - with velocity 100
+//   __MIDI_CC_7_127__         - control change: controller 7, value 127
+//   __MIDI_CH10_NOTE_36__     - note on channel 10
+//   __MIDI_KEY__              - the note fixed to this key: key 1 = 36 (C2)
+//                               up to key 15 = 50, for MIDI learn in the DAW
+
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use midir::os::unix::VirtualOutput;
+use midir::{MidiOutput, MidiOutputConnection};
+use tracing::debug;
+
+pub const KEY_COMMAND: &str = "__MIDI_KEY__";
+
+const PORT_NAME: &str = "Redragon StreamDeck";
+// Note of key 1 for __MIDI_KEY__
+const FIRST_KEY_NOTE: u8 = 36;
+const NOTE_LENGTH: Duration = Duration::from_millis(100);
+
+lazy_static::lazy_static! {
+    static ref PORT: Mutex<Option<MidiOutputConnection>> = Mutex::new(None);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MidiMessage {
+    Note { channel: u8, note: u8, velocity: u8 },
+    ControlChange { channel: u8, controller: u8, value: u8 },
+}
+
+// 7-bit data byte
+fn data_byte(text: &str) -> Option<u8> {
+    text.parse::<u8>().ok().filter(|v| *v <= 127)
+}
+
+fn parse_command(cmd: &str) -> Option<MidiMessage> {
+    let inner = cmd.strip_prefix("__MIDI_")?.strip_suffix("__")?;
+
+    // Optional CH<1-16>_ prefix
+    let (channel, rest) = match inner.strip_prefix("CH") {
+        Some(after) => {
+            let (number, rest) = after.split_once('_')?;
+            let channel = number.parse::<u8>().ok().filter(|c| (1..=16).contains(c))?;
+            (channel - 1, rest)
+        }
+        None => (0, inner),
+    };
+
+    let parts: Vec<&str> = rest.split('_').collect();
+    match parts[..] {
+        ["NOTE", note] => Some(MidiMessage::Note { channel, note: data_byte(note)?, velocity: 127 }),
+        ["NOTE", note, velocity] => Some(MidiMessage::Note {
+            channel,
+            note: data_byte(note)?,
+            velocity: data_byte(velocity).filter(|v| *v > 0)?,
+        }),
+        ["CC", controller, value] => Some(MidiMessage::ControlChange {
+            channel,
+            controller: data_byte(controller)?,
+            value: data_byte(value)?,
+        }),
+        _ => None,
+    }
+}
+
+pub fn is_midi_command(cmd: &str) -> bool {
+    cmd == KEY_COMMAND || parse_command(cmd).is_some()
+}
+
+// Create the virtual port if it doesn't exist yet
+pub fn open_port() -> Result<(), String> {
+    let mut port = PORT.lock().map_err(|e| e.to_string())?;
+    if port.is_some() {
+        return Ok(());
+    }
+    let output = MidiOutput::new(PORT_NAME).map_err(|e| format!("MIDI init failed: {}", e))?;
+    let connection = output
+        .create_virtual(PORT_NAME)
+        .map_err(|e| format!("Failed to create MIDI port: {}", e))?;
+    debug!("MIDI port '{}' created", PORT_NAME);
+    *port = Some(connection);
+    Ok(())
+}
+
+fn send_raw(bytes: &[u8]) -> Result<(), String> {
+    open_port()?;
+    let mut port = PORT.lock().map_err(|e| e.to_string())?;
+    let connection = port.as_mut().ok_or("MIDI port not open")?;
+    connection.send(bytes).map_err(|e| format!("MIDI send failed: {}", e))
+}
+
+fn send(message: MidiMessage) -> Result<(), String> {
+    match message {
+        MidiMessage::Note { channel, note, velocity } => {
+            send_raw(&[0x90 | channel, note, velocity])?;
+            thread::spawn(move || {
+                thread::sleep(NOTE_LENGTH);
+                send_raw(&[0x80 | channel, note, 0]).ok();
+            });
+            Ok(())
+        }
+        MidiMessage::ControlChange { channel, controller, value } => send_raw(&[0xB0 | channel, controller, value]),
+    }
+}
+
+pub fn execute_command(cmd: &str) -> Result<(), String> {
+    if cmd == KEY_COMMAND {
+        return Err("__MIDI_KEY__ only works when pressed on the deck".to_string());
+    }
+    let message = parse_command(cmd).ok_or_else(|| format!("Invalid MIDI command: {}", cmd))?;
+    debug!("MIDI {:?}", message);
+    send(message)
+}
+
+// The fixed note of a deck key (1-15)
+pub fn send_key(key_id: u8) -> Result<(), String> {
+    let note = FIRST_KEY_NOTE + key_id.saturating_sub(1);
+    debug!("MIDI key {} -> note {}", key_id, note);
+    send(MidiMessage::Note { channel: 0, note, velocity: 127 })
+}
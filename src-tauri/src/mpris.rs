@@ -0,0 +1,143 @@
+// ============================================================================
+// MPRIS Media Control (Spotify and any other D-Bus media player)
+// ============================================================================
+//
+// Commands: __MEDIA_PLAY__, __MEDIA_SEEK_+10__, __MEDIA_VOL_+5__,
+// __MEDIA_POSITION__, __MEDIA_TITLE__ (see player.rs).
+//
+// The player is the first org.mpris.MediaPlayer2.* name on the session bus
+// that is playing, else the first one found. MPRIS_PLAYER picks one by name
+// instead (e.g. MPRIS_PLAYER=spotify).
+//
+// Seek and volume keys repeat while held, so holding __MEDIA_SEEK_+10__
+// scrubs through a podcast.
+
+use std::collections::HashMap;
+
+use zbus::blocking::fdo::DBusProxy;
+use zbus::blocking::Connection;
+use zbus::proxy::CacheProperties;
+use zbus::zvariant::{OwnedValue, Value};
+
+use crate::player::{self, PlayerCommand, PlayerStatus};
+
+pub const PREFIX: &str = "__MEDIA_";
+const BUS_PREFIX: &str = "org.mpris.MediaPlayer2.";
+const MICROS: f64 = 1_000_000.0;
+
+#[zbus::proxy(interface = "org.mpris.MediaPlayer2.Player", default_path = "/org/mpris/MediaPlayer2")]
+trait MprisPlayer {
+    fn play_pause(&self) -> zbus::Result<()>;
+
+    // Relative, in microseconds
+    fn seek(&self, offset: i64) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn playback_status(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn position(&self) -> zbus::Result<i64>;
+
+    #[zbus(property)]
+    fn volume(&self) -> zbus::Result<f64>;
+
+    #[zbus(property)]
+    fn set_volume(&self, volume: f64) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn metadata(&self) -> zbus::Result<HashMap<String, OwnedValue>>;
+}
+
+pub fn is_mpris_command(cmd: &str) -> bool {
+    player::parse(cmd, PREFIX).is_some()
+}
+
+pub fn is_mpris_widget(cmd: &str) -> bool {
+    player::parse(cmd, PREFIX).map(|c| c.is_widget()).unwrap_or(false)
+}
+
+fn session_bus() -> Result<Connection, String> {
+    Connection::session().map_err(|e| format!("D-Bus session bus: {}", e))
+}
+
+fn proxy<'a>(conn: &'a Connection, name: &str) -> zbus::Result<MprisPlayerProxyBlocking<'a>> {
+    MprisPlayerProxyBlocking::builder(conn)
+        .destination(name.to_string())?
+        .cache_properties(CacheProperties::No)
+        .build()
+}
+
+// The player to control (see the header)
+fn find_player(conn: &Connection) -> Result<MprisPlayerProxyBlocking<'_>, String> {
+    let dbus = DBusProxy::new(conn).map_err(|e| e.to_string())?;
+    let names = dbus.list_names().map_err(|e| format!("ListNames failed: {}", e))?;
+    let players: Vec<String> = names
+        .iter()
+        .map(|name| name.as_str())
+        .filter(|name| name.starts_with(BUS_PREFIX))
+        .map(str::to_string)
+        .collect();
+
+    if let Ok(wanted) = std::env::var("MPRIS_PLAYER") {
+        let name = players
+            .iter()
+            .find(|name| name[BUS_PREFIX.len()..].starts_with(&wanted))
+            .ok_or_else(|| format!("MPRIS player '{}' is not running", wanted))?;
+        return proxy(conn, name).map_err(|e| e.to_string());
+    }
+
+    let playing = players.iter().find(|name| {
+        proxy(conn, name)
+            .and_then(|p| p.playback_status())
+            .map(|status| status == "Playing")
+            .unwrap_or(false)
+    });
+    let name = playing.or(players.first()).ok_or("No MPRIS media player running")?;
+    proxy(conn, name).map_err(|e| e.to_string())
+}
+
+fn status() -> Result<PlayerStatus, String> {
+    let conn = session_bus()?;
+    let player = find_player(&conn)?;
+    let title = player
+        .metadata()
+        .ok()
+        .and_then(|metadata| match metadata.get("xesam:title").map(|v| &**v) {
+            Some(Value::Str(title)) => Some(title.to_string()),
+            _ => None,
+        })
+        .unwrap_or_default();
+    Ok(PlayerStatus {
+        playing: player.playback_status().map(|s| s == "Playing").unwrap_or(false),
+        position: player.position().unwrap_or(0) as f64 / MICROS,
+        title,
+    })
+}
+
+pub fn execute_command(cmd: &str) -> Result<(), String> {
+    let command = player::parse(cmd, PREFIX).ok_or_else(|| format!("Invalid media command: {}", cmd))?;
+    let conn = session_bus()?;
+    let player = find_player(&conn)?;
+    match command {
+        PlayerCommand::PlayPause => player.play_pause().map_err(|e| format!("PlayPause failed: {}", e))?,
+        PlayerCommand::Seek(secs) => player
+            .seek((secs as f64 * MICROS) as i64)
+            .map_err(|e| format!("Seek failed: {}", e))?,
+        PlayerCommand::Volume(percent) => {
+            let volume = player.volume().map_err(|e| format!("Volume not available: {}", e))?;
+            let volume = (volume + percent as f64 / 100.0).clamp(0.0, 1.0);
+            player.set_volume(volume).map_err(|e| format!("Failed to set the volume: {}", e))?;
+        }
+        PlayerCommand::Position | PlayerCommand::Title => {}
+    }
+    player::action_done(PREFIX);
+    Ok(())
+}
+
+pub fn get_widget_text(cmd: &str) -> String {
+    match player::parse(cmd, PREFIX) {
+        // One status query serves every media widget
+        Some(command) => player::widget_text("__MEDIA_STATUS", command, status),
+        None => String::new(),
+    }
+}
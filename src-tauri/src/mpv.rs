@@ -0,0 +1,107 @@
+// ============================================================================
+// mpv Remote Control (JSON IPC)
+// ============================================================================
+//
+// Commands: __MPV_PLAY__, __MPV_SEEK_+10__, __MPV_VOL_+5__, __MPV_POSITION__,
+// __MPV_TITLE__ (see player.rs).
+//
+// Start mpv with an IPC socket, e.g. `input-ipc-server=/tmp/mpvsocket` in
+// ~/.config/mpv/mpv.conf. MPV_SOCKET overrides the socket path.
+
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use crate::player::{self, PlayerCommand, PlayerStatus};
+
+pub const PREFIX: &str = "__MPV_";
+const DEFAULT_SOCKET: &str = "/tmp/mpvsocket";
+
+pub fn is_mpv_command(cmd: &str) -> bool {
+    player::parse(cmd, PREFIX).is_some()
+}
+
+pub fn is_mpv_widget(cmd: &str) -> bool {
+    player::parse(cmd, PREFIX).map(|c| c.is_widget()).unwrap_or(false)
+}
+
+fn socket_path() -> String {
+    std::env::var("MPV_SOCKET").unwrap_or_else(|_| DEFAULT_SOCKET.to_string())
+}
+
+// Send commands over one connection and return their "data" fields in order.
+// mpv also writes event lines, so replies are matched by request_id.
+fn send(commands: &[Value]) -> Result<Vec<Value>, String> {
+    let path = socket_path();
+    let mut stream = UnixStream::connect(&path).map_err(|e| format!("mpv not reachable at {}: {}", path, e))?;
+    stream.set_read_timeout(Some(Duration::from_secs(2))).ok();
+
+    for (id, command) in commands.iter().enumerate() {
+        let line = json!({ "command": command, "request_id": id }).to_string();
+        writeln!(stream, "{}", line).map_err(|e| format!("mpv write failed: {}", e))?;
+    }
+
+    let mut replies = vec![Value::Null; commands.len()];
+    let mut pending = commands.len();
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    while pending > 0 {
+        line.clear();
+        let read = reader.read_line(&mut line).map_err(|e| format!("mpv read failed: {}", e))?;
+        if read == 0 {
+            return Err("mpv closed the connection".to_string());
+        }
+        let reply: Value = match serde_json::from_str(&line) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        let id = match reply.get("request_id").and_then(|i| i.as_u64()) {
+            Some(id) if (id as usize) < commands.len() => id as usize,
+            _ => continue,
+        };
+        // Unset properties (e.g. no file loaded) answer with an error; keep null
+        if reply.get("error").and_then(|e| e.as_str()) == Some("success") {
+            replies[id] = reply.get("data").cloned().unwrap_or(Value::Null);
+        }
+        pending -= 1;
+    }
+    Ok(replies)
+}
+
+fn status() -> Result<PlayerStatus, String> {
+    let replies = send(&[
+        json!(["get_property", "pause"]),
+        json!(["get_property", "time-pos"]),
+        json!(["get_property", "media-title"]),
+    ])?;
+    Ok(PlayerStatus {
+        playing: replies[0].as_bool() == Some(false),
+        position: replies[1].as_f64().unwrap_or(0.0),
+        title: replies[2].as_str().unwrap_or_default().to_string(),
+    })
+}
+
+pub fn execute_command(cmd: &str) -> Result<(), String> {
+    let command = player::parse(cmd, PREFIX).ok_or_else(|| format!("Invalid mpv command: {}", cmd))?;
+    let request = match command {
+        PlayerCommand::PlayPause => json!(["cycle", "pause"]),
+        PlayerCommand::Seek(secs) => json!(["seek", secs, "relative"]),
+        PlayerCommand::Volume(percent) => json!(["add", "volume", percent]),
+        PlayerCommand::Position | PlayerCommand::Title => {
+            player::action_done(PREFIX);
+            return Ok(());
+        }
+    };
+    send(&[request])?;
+    player::action_done(PREFIX);
+    Ok(())
+}
+
+pub fn get_widget_text(cmd: &str) -> String {
+    match player::parse(cmd, PREFIX) {
+        Some(command) => player::widget_text("__MPV_STATUS", command, status),
+        None => String::new(),
+    }
+}
@@ -0,0 +1,249 @@
+// ============================================================================
+// Network (NetworkManager D-Bus via zbus)
+// ============================================================================
+//
+// Command formats:
+//   __WIFI__            - widget with the current SSID and signal strength
+//   __WIFI_TOGGLE__     - turn Wi-Fi on/off
+//   __NET_<profile>__   - activate a saved connection by name (e.g. __NET_Casa__)
+//
+// Failed actions flash the key red and are kept in the action log.
+
+use std::collections::HashMap;
+use std::thread;
+
+use zbus::blocking::Connection;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue};
+use tracing::debug;
+
+use crate::{action_log, poller, request_refresh};
+
+pub const WIFI_WIDGET: &str = "__WIFI__";
+pub const WIFI_TOGGLE: &str = "__WIFI_TOGGLE__";
+
+const WIFI_POLL_SECS: u64 = 5;
+const MAX_SSID_CHARS: usize = 7;
+// NM_DEVICE_TYPE_WIFI
+const DEVICE_TYPE_WIFI: u32 = 2;
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager",
+    default_service = "org.freedesktop.NetworkManager",
+    default_path = "/org/freedesktop/NetworkManager"
+)]
+trait NetworkManager {
+    fn get_devices(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+
+    fn activate_connection(
+        &self,
+        connection: &ObjectPath<'_>,
+        device: &ObjectPath<'_>,
+        specific_object: &ObjectPath<'_>,
+    ) -> zbus::Result<OwnedObjectPath>;
+
+    #[zbus(property)]
+    fn wireless_enabled(&self) -> zbus::Result<bool>;
+
+    #[zbus(property)]
+    fn set_wireless_enabled(&self, enabled: bool) -> zbus::Result<()>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager.Settings",
+    default_service = "org.freedesktop.NetworkManager",
+    default_path = "/org/freedesktop/NetworkManager/Settings"
+)]
+trait Settings {
+    fn list_connections(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager.Settings.Connection",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+trait SettingsConnection {
+    fn get_settings(&self) -> zbus::Result<HashMap<String, HashMap<String, OwnedValue>>>;
+}
+
+#[zbus::proxy(interface = "org.freedesktop.NetworkManager.Device", default_service = "org.freedesktop.NetworkManager")]
+trait Device {
+    #[zbus(property)]
+    fn device_type(&self) -> zbus::Result<u32>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager.Device.Wireless",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+trait WirelessDevice {
+    #[zbus(property)]
+    fn active_access_point(&self) -> zbus::Result<OwnedObjectPath>;
+}
+
+#[zbus::proxy(interface = "org.freedesktop.NetworkManager.AccessPoint", default_service = "org.freedesktop.NetworkManager")]
+trait AccessPoint {
+    #[zbus(property)]
+    fn ssid(&self) -> zbus::Result<Vec<u8>>;
+
+    #[zbus(property)]
+    fn strength(&self) -> zbus::Result<u8>;
+}
+
+#[derive(Debug, Clone)]
+enum WifiStatus {
+    Off,
+    Disconnected,
+    Connected { ssid: String, strength: u8 },
+    Error,
+}
+
+fn parse_profile(cmd: &str) -> Option<&str> {
+    let name = cmd.strip_prefix("__NET_")?.strip_suffix("__")?;
+    (!name.is_empty()).then_some(name)
+}
+
+pub fn is_network_command(cmd: &str) -> bool {
+    cmd == WIFI_WIDGET || cmd == WIFI_TOGGLE || parse_profile(cmd).is_some()
+}
+
+pub fn is_network_widget(cmd: &str) -> bool {
+    cmd == WIFI_WIDGET
+}
+
+fn system_bus() -> Result<Connection, String> {
+    Connection::system().map_err(|e| format!("D-Bus connection failed: {}", e))
+}
+
+fn manager(conn: &Connection) -> Result<NetworkManagerProxyBlocking<'_>, String> {
+    NetworkManagerProxyBlocking::new(conn).map_err(|e| format!("NetworkManager not available: {}", e))
+}
+
+// SSID and signal of the access point a Wi-Fi device is connected to
+fn active_access_point(conn: &Connection, device: OwnedObjectPath) -> zbus::Result<Option<(String, u8)>> {
+    let kind = DeviceProxyBlocking::builder(conn).path(device.clone())?.build()?.device_type()?;
+    if kind != DEVICE_TYPE_WIFI {
+        return Ok(None);
+    }
+    let ap_path = WirelessDeviceProxyBlocking::builder(conn).path(device)?.build()?.active_access_point()?;
+    // "/" when not connected
+    if ap_path.as_str() == "/" {
+        return Ok(None);
+    }
+    let ap = AccessPointProxyBlocking::builder(conn).path(ap_path)?.build()?;
+    Ok(Some((String::from_utf8_lossy(&ap.ssid()?).to_string(), ap.strength()?)))
+}
+
+fn query_wifi() -> Result<WifiStatus, String> {
+    let conn = system_bus()?;
+    let nm = manager(&conn)?;
+    if !nm.wireless_enabled().map_err(|e| format!("WirelessEnabled read failed: {}", e))? {
+        return Ok(WifiStatus::Off);
+    }
+    let devices = nm.get_devices().map_err(|e| format!("GetDevices failed: {}", e))?;
+    for device in devices {
+        if let Ok(Some((ssid, strength))) = active_access_point(&conn, device) {
+            return Ok(WifiStatus::Connected { ssid, strength });
+        }
+    }
+    Ok(WifiStatus::Disconnected)
+}
+
+fn toggle_wifi() -> Result<bool, String> {
+    let conn = system_bus()?;
+    let nm = manager(&conn)?;
+    let enabled = nm.wireless_enabled().map_err(|e| format!("WirelessEnabled read failed: {}", e))?;
+    nm.set_wireless_enabled(!enabled)
+        .map_err(|e| format!("Failed to turn Wi-Fi {}: {}", if enabled { "off" } else { "on" }, e))?;
+    Ok(!enabled)
+}
+
+// Saved connection whose connection.id is `name`
+fn find_profile(conn: &Connection, name: &str) -> Result<OwnedObjectPath, String> {
+    let settings = SettingsProxyBlocking::new(conn).map_err(|e| format!("NetworkManager not available: {}", e))?;
+    let connections = settings.list_connections().map_err(|e| format!("ListConnections failed: {}", e))?;
+    for path in connections {
+        let id = SettingsConnectionProxyBlocking::builder(conn)
+            .path(path.clone())
+            .and_then(|b| b.build())
+            .and_then(|c| c.get_settings())
+            .ok()
+            .and_then(|s| s.get("connection")?.get("id")?.try_clone().ok())
+            .and_then(|id| String::try_from(id).ok());
+        if id.as_deref() == Some(name) {
+            return Ok(path);
+        }
+    }
+    Err(format!("No saved connection named '{}'", name))
+}
+
+fn activate_profile(name: &str) -> Result<(), String> {
+    let conn = system_bus()?;
+    let profile = find_profile(&conn, name)?;
+    // "/" lets NetworkManager pick the device and access point
+    let any = ObjectPath::from_static_str_unchecked("/");
+    manager(&conn)?
+        .activate_connection(&profile.as_ref(), &any, &any)
+        .map_err(|e| format!("Failed to activate '{}': {}", name, e))?;
+    debug!("Activated connection {}", name);
+    Ok(())
+}
+
+pub fn execute_command(cmd: &str) {
+    if cmd == WIFI_WIDGET {
+        poller::invalidate_matching(|key| key == WIFI_WIDGET);
+        request_refresh();
+        return;
+    }
+    let cmd = cmd.to_string();
+    // Activation waits for NetworkManager; keep the button listener free
+    thread::spawn(move || {
+        let result = if cmd == WIFI_TOGGLE {
+            toggle_wifi().map(|enabled| debug!("Wi-Fi {}", if enabled { "on" } else { "off" }))
+        } else {
+            match parse_profile(&cmd) {
+                Some(name) => activate_profile(name),
+                None => Err(format!("Invalid network command: {}", cmd)),
+            }
+        };
+        if let Err(e) = result {
+            action_log::record_error(&cmd, &e);
+        }
+        poller::invalidate_matching(|key| key == WIFI_WIDGET);
+        request_refresh();
+    });
+}
+
+fn truncate(text: &str) -> String {
+    if text.chars().count() > MAX_SSID_CHARS {
+        let head: String = text.chars().take(MAX_SSID_CHARS - 1).collect();
+        format!("{}…", head)
+    } else {
+        text.to_string()
+    }
+}
+
+fn poll_wifi() -> Option<WifiStatus> {
+    poller::poll(WIFI_WIDGET, WIFI_POLL_SECS, || {
+        query_wifi().unwrap_or_else(|e| {
+            debug!("Wi-Fi status: {}", e);
+            WifiStatus::Error
+        })
+    })
+}
+
+pub fn get_widget_text() -> String {
+    match poll_wifi() {
+        Some(WifiStatus::Off) => "Wi-Fi OFF".to_string(),
+        Some(WifiStatus::Disconnected) => "No Wi-Fi".to_string(),
+        Some(WifiStatus::Connected { ssid, strength }) => format!("{} {}%", truncate(&ssid), strength),
+        Some(WifiStatus::Error) => "ERR".to_string(),
+        None => "...".to_string(),
+    }
+}
+
+pub fn get_widget_color() -> Option<String> {
+    match poller::peek::<WifiStatus>(WIFI_WIDGET)? {
+        WifiStatus::Error => Some("#c0392b".to_string()),
+        _ => None,
+    }
+}
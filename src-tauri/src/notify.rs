@@ -0,0 +1,151 @@
+// ============================================================================
+// Desktop Notifications (freedesktop Notifications D-Bus via zbus)
+// ============================================================================
+//
+// Command format:
+//   __NOTIFY_<title>|<body>__  - show a desktop notification ("|<body>" is optional)
+//
+// The app also notifies on its own when the deck is unplugged (and not back
+// within a few seconds), when the connection to OBS is lost and when a timer
+// finishes. "notifications" in config.json turns them off, all at once
+// ("enabled", also the "Avisos" checkbox) or one by one:
+//
+//   "notifications": { "enabled": true, "deviceDisconnected": true, "obsDisconnected": true, "timer": true }
+
+use std::collections::HashMap;
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+use zbus::blocking::Connection;
+use zbus::zvariant::Value;
+
+use crate::{config_store, deck_connected};
+
+const APP_NAME: &str = "Redragon Stream Deck";
+const APP_ICON: &str = "input-keyboard";
+// Default expiry chosen by the notification server
+const EXPIRE_DEFAULT: i32 = -1;
+// A replug or a resume reconnects within this; no need to tell anyone
+const RECONNECT_GRACE: Duration = Duration::from_secs(5);
+
+#[zbus::proxy(
+    interface = "org.freedesktop.Notifications",
+    default_service = "org.freedesktop.Notifications",
+    default_path = "/org/freedesktop/Notifications"
+)]
+trait Notifications {
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+        &self,
+        app_name: &str,
+        replaces_id: u32,
+        app_icon: &str,
+        summary: &str,
+        body: &str,
+        actions: &[&str],
+        hints: HashMap<&str, &Value<'_>>,
+        expire_timeout: i32,
+    ) -> zbus::Result<u32>;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationSettings {
+    #[serde(default = "enabled")]
+    pub enabled: bool,
+    #[serde(default = "enabled", rename = "deviceDisconnected")]
+    pub device_disconnected: bool,
+    #[serde(default = "enabled", rename = "obsDisconnected")]
+    pub obs_disconnected: bool,
+    #[serde(default = "enabled")]
+    pub timer: bool,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        NotificationSettings {
+            enabled: true,
+            device_disconnected: true,
+            obs_disconnected: true,
+            timer: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Event {
+    DeviceDisconnected,
+    ObsDisconnected,
+    TimerDone,
+}
+
+fn send_blocking(title: &str, body: &str) -> Result<(), String> {
+    let connection = Connection::session().map_err(|e| format!("D-Bus session bus: {}", e))?;
+    let proxy = NotificationsProxyBlocking::new(&connection).map_err(|e| e.to_string())?;
+    proxy
+        .notify(APP_NAME, 0, APP_ICON, title, body, &[], HashMap::new(), EXPIRE_DEFAULT)
+        .map_err(|e| format!("Notification failed: {}", e))?;
+    Ok(())
+}
+
+// Show a notification without holding up the caller
+pub fn send(title: &str, body: &str) {
+    debug!("Notification: {} - {}", title, body);
+    let (title, body) = (title.to_string(), body.to_string());
+    thread::spawn(move || {
+        if let Err(e) = send_blocking(&title, &body) {
+            warn!("{}", e);
+        }
+    });
+}
+
+fn wanted(event: Event) -> bool {
+    let settings = config_store::read(|c| c.notifications.clone()).unwrap_or_default();
+    settings.enabled
+        && match event {
+            Event::DeviceDisconnected => settings.device_disconnected,
+            Event::ObsDisconnected => settings.obs_disconnected,
+            Event::TimerDone => settings.timer,
+        }
+}
+
+// Notify about an app event, if enabled in config.json
+pub fn event(event: Event) {
+    if !wanted(event) {
+        return;
+    }
+    match event {
+        Event::DeviceDisconnected => {
+            thread::spawn(|| {
+                thread::sleep(RECONNECT_GRACE);
+                if !deck_connected() {
+                    send("Deck desconectado", "Volverá a funcionar al conectarlo de nuevo.");
+                }
+            });
+        }
+        Event::ObsDisconnected => send("Conexión con OBS perdida", "OBS está cerrado o su servidor WebSocket está apagado."),
+        Event::TimerDone => send("Temporizador terminado", "El temporizador del deck ha llegado a cero."),
+    }
+}
+
+fn parse_command(cmd: &str) -> Option<(&str, &str)> {
+    let rest = cmd.strip_prefix("__NOTIFY_")?.strip_suffix("__")?;
+    let (title, body) = rest.split_once('|').unwrap_or((rest, ""));
+    Some((title, body)).filter(|(title, _)| !title.is_empty())
+}
+
+pub fn is_notify_command(cmd: &str) -> bool {
+    parse_command(cmd).is_some()
+}
+
+// __NOTIFY_<title>|<body>__ waits for the server, so failures reach the key
+pub fn execute_command(cmd: &str) -> Result<(), String> {
+    let (title, body) = parse_command(cmd).ok_or_else(|| format!("Invalid notify command: {}", cmd))?;
+    debug!("Notification: {} - {}", title, body);
+    send_blocking(title, body)
+}
@@ -0,0 +1,205 @@
+// ============================================================================
+// Numeric Entry (numpad mode)
+// ============================================================================
+//
+// An action that needs a number turns the deck into a numpad until OK or
+// Cancel, so the value is typed on the device itself:
+//
+//    1    2    3    4    5
+//    6    7    8    9    0
+//   prompt value  <-  Cancel  OK
+//
+// Command formats:
+//   __INPUT_TIMER__           - start a timer of N minutes
+//   __INPUT_BRIGHTNESS__      - set the brightness (0-100)
+//   __INPUT_OBS_TRANSITION__  - set the OBS transition duration (ms)
+//
+// Other modules ask with numpad::ask(prompt, range, on_ok). The pad is a
+// generated page (virtual_page.rs): changing pages cancels it, and a value out
+// of range is refused on the value key.
+
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::sync::Mutex;
+
+use tracing::debug;
+
+use crate::{obs_request, overlay, set_base_brightness, start_timer, virtual_page, Page};
+
+const PAGE_NAME: &str = "Numpad";
+const MAX_DIGITS: usize = 6;
+const PROMPT_KEY: u8 = 11;
+const VALUE_KEY: u8 = 12;
+const BACK_KEY: u8 = 13;
+const CANCEL_KEY: u8 = 14;
+const OK_KEY: u8 = 15;
+const DIGIT_COLOR: &str = "#2c3e50";
+const VALUE_COLOR: &str = "#16213e";
+const CANCEL_COLOR: &str = "#e94560";
+const OK_COLOR: &str = "#27ae60";
+
+type OnOk = Box<dyn FnOnce(u32) -> Result<(), String> + Send>;
+
+struct Entry {
+    prompt: String,
+    range: RangeInclusive<u32>,
+    digits: String,
+    on_ok: OnOk,
+}
+
+lazy_static::lazy_static! {
+    static ref ENTRY: Mutex<Option<Entry>> = Mutex::new(None);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Key {
+    Digit(u8),
+    Back,
+    Ok,
+    Cancel,
+}
+
+fn parse_key(cmd: &str) -> Option<Key> {
+    match cmd.strip_prefix("__NUMPAD_")?.strip_suffix("__")? {
+        "BACK" => Some(Key::Back),
+        "OK" => Some(Key::Ok),
+        "CANCEL" => Some(Key::Cancel),
+        d => d.parse().ok().filter(|d| *d <= 9).map(Key::Digit),
+    }
+}
+
+fn parse_input(cmd: &str) -> Option<&str> {
+    cmd.strip_prefix("__INPUT_")?
+        .strip_suffix("__")
+        .filter(|target| ["TIMER", "BRIGHTNESS", "OBS_TRANSITION"].contains(target))
+}
+
+pub fn is_numpad_command(cmd: &str) -> bool {
+    parse_key(cmd).is_some() || parse_input(cmd).is_some()
+}
+
+fn value_label(entry: &Entry) -> String {
+    if entry.digits.is_empty() {
+        "_".to_string()
+    } else {
+        entry.digits.clone()
+    }
+}
+
+fn build_page(entry: &Entry) -> Page {
+    let mut buttons = HashMap::new();
+    // 1-9 on the first keys, 0 after 9
+    for key_id in 1..=10u8 {
+        let digit = key_id % 10;
+        let command = format!("__NUMPAD_{}__", digit);
+        buttons.insert(key_id.to_string(), virtual_page::button(&digit.to_string(), &command, DIGIT_COLOR));
+    }
+    buttons.insert(PROMPT_KEY.to_string(), virtual_page::button(&entry.prompt, "", VALUE_COLOR));
+    buttons.insert(VALUE_KEY.to_string(), virtual_page::button(&value_label(entry), "", VALUE_COLOR));
+    buttons.insert(BACK_KEY.to_string(), virtual_page::button("<-", "__NUMPAD_BACK__", DIGIT_COLOR));
+    buttons.insert(CANCEL_KEY.to_string(), virtual_page::button("Cancel", "__NUMPAD_CANCEL__", CANCEL_COLOR));
+    buttons.insert(OK_KEY.to_string(), virtual_page::button("OK", "__NUMPAD_OK__", OK_COLOR));
+    Page {
+        name: PAGE_NAME.to_string(),
+        buttons,
+        brightness: None,
+        default_color: None,
+    }
+}
+
+// Ask for a number on the deck; `on_ok` runs with it once OK is pressed
+pub fn ask(prompt: &str, range: RangeInclusive<u32>, on_ok: impl FnOnce(u32) -> Result<(), String> + Send + 'static) {
+    debug!("Numpad: {} ({}-{})", prompt, range.start(), range.end());
+    let entry = Entry {
+        prompt: prompt.to_string(),
+        range,
+        digits: String::new(),
+        on_ok: Box::new(on_ok),
+    };
+    let page = build_page(&entry);
+    if let Ok(mut current) = ENTRY.lock() {
+        *current = Some(entry);
+    }
+    virtual_page::open(page);
+}
+
+// The pad was closed some other way (page change, another generated page)
+fn shown() -> bool {
+    virtual_page::active().map(|p| p.name == PAGE_NAME).unwrap_or(false)
+}
+
+fn press(key: Key) -> Result<(), String> {
+    let mut current = ENTRY.lock().map_err(|e| e.to_string())?;
+    if !shown() {
+        *current = None;
+        return Ok(());
+    }
+    let entry = match current.as_mut() {
+        Some(e) => e,
+        None => return Ok(()),
+    };
+    match key {
+        Key::Digit(d) => {
+            if entry.digits.len() < MAX_DIGITS {
+                // No leading zeros
+                if entry.digits == "0" {
+                    entry.digits.clear();
+                }
+                entry.digits.push(char::from(b'0' + d));
+            }
+        }
+        Key::Back => {
+            entry.digits.pop();
+        }
+        Key::Cancel => {
+            debug!("Numpad cancelled");
+            *current = None;
+            virtual_page::close();
+            return Ok(());
+        }
+        Key::Ok => {
+            let value = entry.digits.parse::<u32>().ok().filter(|v| entry.range.contains(v));
+            let value = match value {
+                Some(v) => v,
+                None => {
+                    let hint = format!("{}-{}", entry.range.start(), entry.range.end());
+                    overlay::show_with_color(VALUE_KEY, &hint, overlay::ERROR_COLOR, overlay::DEFAULT_DURATION);
+                    return Ok(());
+                }
+            };
+            let entry = current.take().ok_or("Numpad closed")?;
+            drop(current);
+            debug!("Numpad value {} for {}", value, entry.prompt);
+            virtual_page::close();
+            return (entry.on_ok)(value);
+        }
+    }
+    virtual_page::set_button(VALUE_KEY, virtual_page::button(&value_label(entry), "", VALUE_COLOR));
+    Ok(())
+}
+
+pub fn execute_command(cmd: &str, config_path: &Path) -> Result<(), String> {
+    if let Some(key) = parse_key(cmd) {
+        return press(key);
+    }
+    match parse_input(cmd) {
+        Some("TIMER") => ask("Min?", 1..=999, |minutes| {
+            start_timer(minutes as u64 * 60);
+            Ok(())
+        }),
+        Some("BRIGHTNESS") => {
+            let config_path = config_path.to_path_buf();
+            ask("Bright?", 0..=100, move |level| {
+                set_base_brightness(&config_path, level as u8);
+                Ok(())
+            })
+        }
+        Some("OBS_TRANSITION") => ask("ms?", 50..=20_000, |ms| {
+            obs_request("SetCurrentSceneTransitionDuration", Some(serde_json::json!({ "transitionDuration": ms })))
+                .map(|_| ())
+        }),
+        _ => return Err(format!("Invalid numpad command: {}", cmd)),
+    }
+    Ok(())
+}
@@ -0,0 +1,133 @@
+// ============================================================================
+// Key Overlays (toast messages)
+// ============================================================================
+//
+// A message drawn over a key for a few seconds, after which the key shows
+// its normal image again: action results ("Clip!"), failures, plugin output.
+// Overlays sit above the page: a page load or key refresh draws the overlay
+// instead of the button while it lasts, and widget updates leave the key alone.
+//
+// show() only records the overlay; the listener (which owns the device) draws
+// the keys returned by take_changed() between reads.
+//
+// Also available as the show_key_message Tauri command, POST /message/<key>
+// on the remote deck, and {"event":"showMessage"} from plugins.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use image::RgbImage;
+use tracing::debug;
+
+use crate::{config_store, deck_reader, render_button_image, virtual_page};
+
+pub const DEFAULT_DURATION: Duration = Duration::from_secs(2);
+const MAX_DURATION: Duration = Duration::from_secs(60);
+const OVERLAY_COLOR: &str = "#2c3e50";
+pub const ERROR_COLOR: &str = "#c0392b";
+
+struct Overlay {
+    text: String,
+    color: String,
+    until: Instant,
+}
+
+lazy_static::lazy_static! {
+    static ref OVERLAYS: Mutex<HashMap<u8, Overlay>> = Mutex::new(HashMap::new());
+    // Keys whose overlay appeared since the listener last looked
+    static ref CHANGED: Mutex<HashSet<u8>> = Mutex::new(HashSet::new());
+}
+
+// Show `text` on a key for `duration`
+pub fn show(key_id: u8, text: &str, duration: Duration) {
+    show_with_color(key_id, text, OVERLAY_COLOR, duration);
+}
+
+pub fn show_with_color(key_id: u8, text: &str, color: &str, duration: Duration) {
+    if !(1..=15).contains(&key_id) {
+        return;
+    }
+    debug!("Overlay on key {}: {}", key_id, text);
+    if let Ok(mut overlays) = OVERLAYS.lock() {
+        overlays.insert(
+            key_id,
+            Overlay {
+                text: text.to_string(),
+                color: color.to_string(),
+                until: Instant::now() + duration.min(MAX_DURATION),
+            },
+        );
+    }
+    if let Ok(mut changed) = CHANGED.lock() {
+        changed.insert(key_id);
+    }
+    deck_reader::wake();
+}
+
+// Show `text` on every key of the shown page whose command is `command`
+pub fn show_on_command(command: &str, text: &str, duration: Duration) {
+    let page = virtual_page::active().or_else(|| config_store::read(|c| c.pages.get(c.current_page).cloned()).flatten());
+    let keys: Vec<u8> = page
+        .map(|p| {
+            p.buttons
+                .iter()
+                .filter(|(_, b)| b.command == command)
+                .filter_map(|(key, _)| key.parse().ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    for key_id in keys {
+        show(key_id, text, duration);
+    }
+}
+
+// Whether the key shows an overlay now
+pub fn active(key_id: u8) -> bool {
+    OVERLAYS
+        .lock()
+        .map(|overlays| overlays.get(&key_id).map(|o| Instant::now() < o.until).unwrap_or(false))
+        .unwrap_or(false)
+}
+
+// The overlay image for a key, while it lasts (never cached: render_cache
+// keys images by button)
+pub fn image(key_id: u8, icons_path: &Path) -> Option<RgbImage> {
+    let button = {
+        let overlays = OVERLAYS.lock().ok()?;
+        let overlay = overlays.get(&key_id).filter(|o| Instant::now() < o.until)?;
+        virtual_page::button(&overlay.text, "", &overlay.color)
+    };
+    Some(render_button_image(&button, icons_path))
+}
+
+// Put the active overlays over a rendered page
+pub fn cover(images: &mut Vec<(u8, RgbImage)>, icons_path: &Path) {
+    let keys: Vec<u8> = match OVERLAYS.lock() {
+        Ok(overlays) => overlays.keys().copied().collect(),
+        Err(_) => return,
+    };
+    for key_id in keys {
+        if let Some(image) = image(key_id, icons_path) {
+            images.retain(|(k, _)| *k != key_id);
+            images.push((key_id, image));
+        }
+    }
+}
+
+// Keys to redraw: overlays just shown and overlays that ran out
+pub fn take_changed() -> Vec<u8> {
+    let mut keys: HashSet<u8> = CHANGED.lock().map(|mut c| c.drain().collect()).unwrap_or_default();
+    if let Ok(mut overlays) = OVERLAYS.lock() {
+        let now = Instant::now();
+        overlays.retain(|key_id, overlay| {
+            let expired = now >= overlay.until;
+            if expired {
+                keys.insert(*key_id);
+            }
+            !expired
+        });
+    }
+    keys.into_iter().collect()
+}
@@ -0,0 +1,104 @@
+// ============================================================================
+// Media Player Remote Control (shared by vlc.rs and mpv.rs)
+// ============================================================================
+//
+// All players use the same command suffixes after their prefix (__VLC_,
+// __MPV_, __MEDIA_):
+//   PLAY           - play/pause
+//   SEEK_+10       - seek 10 seconds forward (-10 back)
+//   VOL_+5         - volume up 5% (-5 down)
+//   POSITION       - widget: ▶/⏸ and the playback position
+//   TITLE          - widget: title of the current media
+
+use crate::{poller, request_refresh};
+
+const STATUS_POLL_SECS: u64 = 1;
+const MAX_TITLE_CHARS: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlayerCommand {
+    PlayPause,
+    Seek(i64),
+    Volume(i64),
+    Position,
+    Title,
+}
+
+impl PlayerCommand {
+    pub fn is_widget(&self) -> bool {
+        matches!(self, PlayerCommand::Position | PlayerCommand::Title)
+    }
+
+    // Nudges that run again while their key is held
+    pub fn repeats(&self) -> bool {
+        matches!(self, PlayerCommand::Seek(_) | PlayerCommand::Volume(_))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PlayerStatus {
+    pub playing: bool,
+    // Seconds
+    pub position: f64,
+    pub title: String,
+}
+
+// "<prefix><suffix>__" -> command
+pub fn parse(cmd: &str, prefix: &str) -> Option<PlayerCommand> {
+    let inner = cmd.strip_prefix(prefix)?.strip_suffix("__")?;
+    match inner {
+        "PLAY" => Some(PlayerCommand::PlayPause),
+        "POSITION" => Some(PlayerCommand::Position),
+        "TITLE" => Some(PlayerCommand::Title),
+        _ => {
+            if let Some(secs) = inner.strip_prefix("SEEK_") {
+                secs.parse().ok().map(PlayerCommand::Seek)
+            } else if let Some(percent) = inner.strip_prefix("VOL_") {
+                percent.parse().ok().map(PlayerCommand::Volume)
+            } else {
+                None
+            }
+        }
+    }
+}
+
+fn format_time(secs: f64) -> String {
+    let secs = secs.max(0.0) as u64;
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+fn truncate(text: &str) -> String {
+    if text.chars().count() > MAX_TITLE_CHARS {
+        let head: String = text.chars().take(MAX_TITLE_CHARS - 1).collect();
+        format!("{}…", head)
+    } else {
+        text.to_string()
+    }
+}
+
+// Widget text from the player status, polled under `key`
+pub fn widget_text<F>(key: &str, command: PlayerCommand, fetch: F) -> String
+where
+    F: FnOnce() -> Result<PlayerStatus, String> + Send + 'static,
+{
+    match poller::poll(key, STATUS_POLL_SECS, fetch) {
+        Some(Ok(status)) => match command {
+            PlayerCommand::Title if status.title.is_empty() => "-".to_string(),
+            PlayerCommand::Title => truncate(&status.title),
+            _ => format!("{} {}", if status.playing { "▶" } else { "⏸" }, format_time(status.position)),
+        },
+        // Player not running or not reachable
+        Some(Err(_)) => "OFF".to_string(),
+        None => "...".to_string(),
+    }
+}
+
+// Refresh the player's widgets after an action
+pub fn action_done(key_prefix: &str) {
+    poller::invalidate_matching(|key| key.starts_with(key_prefix));
+    request_refresh();
+}
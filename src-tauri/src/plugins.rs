@@ -0,0 +1,415 @@
+// ============================================================================
+// Plugin Host
+// ============================================================================
+//
+// Plugins are external programs declared in <app data>/plugins/<dir>/plugin.json:
+//
+//   { "name": "weather", "exec": "./weather.py", "args": [], "transport": "stdio" }
+//
+// `exec` is relative to the plugin directory. Transports:
+//   stdio     - one JSON message per line on the plugin's stdin/stdout (default)
+//   websocket - the plugin is started with `--port <n> --plugin <name>` and
+//               connects to ws://127.0.0.1:<n>, one JSON message per frame
+//
+// Plugin -> host:
+//   {"event":"registerAction","action":"toggle","title":"Toggle light"}
+//   {"event":"registerWidget","widget":"temp"}
+//   {"event":"setTitle","context":"__PLUGIN_weather.temp__","title":"21°"}
+//   {"event":"setImage","context":"__PLUGIN_weather.temp__","image":"<base64 PNG/JPEG>"}
+//   {"event":"setVariable","name":"room_temp","value":"21°"}
+//   {"event":"showMessage","context":"__PLUGIN_weather.temp__","text":"Updated","duration":2000}
+//   {"event":"log","message":"..."}
+//
+// Host -> plugin:
+//   {"event":"keyDown","action":"toggle","param":"kitchen","context":"__PLUGIN_weather.toggle:kitchen__"}
+//
+// Buttons use __PLUGIN_<plugin>.<action>[:<param>]__; the whole command is
+// the context a plugin pushes titles and images to. Plugins should exit when
+// stdin (or the socket) is closed.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Mutex, RwLock};
+use std::thread;
+use std::time::Duration;
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use serde::{Deserialize, Serialize};
+use tungstenite::Message;
+use tracing::{debug, warn};
+
+use crate::{overlay, variables};
+
+const MANIFEST_FILE: &str = "plugin.json";
+// Poll interval of the WebSocket loop (between reads and queued writes)
+const WS_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Transport {
+    #[default]
+    Stdio,
+    Websocket,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Manifest {
+    name: String,
+    exec: String,
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default)]
+    transport: Transport,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RegisteredAction {
+    pub id: String,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginInfo {
+    pub name: String,
+    pub transport: String,
+    pub running: bool,
+    pub actions: Vec<RegisteredAction>,
+    pub widgets: Vec<String>,
+}
+
+struct Plugin {
+    info: PluginInfo,
+    child: Child,
+    // Outgoing messages, written by the transport thread
+    sender: Sender<String>,
+}
+
+// What a plugin pushed for one button context
+#[derive(Default)]
+struct KeyState {
+    title: Option<String>,
+    image: Option<Vec<u8>>,
+}
+
+lazy_static::lazy_static! {
+    static ref PLUGINS: Mutex<HashMap<String, Plugin>> = Mutex::new(HashMap::new());
+    static ref KEY_STATES: RwLock<HashMap<String, KeyState>> = RwLock::new(HashMap::new());
+}
+
+// __PLUGIN_<plugin>.<action>[:<param>]__ -> (plugin, action, param)
+fn parse_command(cmd: &str) -> Option<(&str, &str, &str)> {
+    let inner = cmd.strip_prefix("__PLUGIN_")?.strip_suffix("__")?;
+    let (plugin, rest) = inner.split_once('.')?;
+    let (action, param) = rest.split_once(':').unwrap_or((rest, ""));
+    if plugin.is_empty() || action.is_empty() {
+        None
+    } else {
+        Some((plugin, action, param))
+    }
+}
+
+pub fn is_plugin_command(cmd: &str) -> bool {
+    parse_command(cmd).is_some()
+}
+
+// Registered widgets and any key a plugin pushed a title or image to
+pub fn is_plugin_widget(cmd: &str) -> bool {
+    let (plugin, action, _) = match parse_command(cmd) {
+        Some(parts) => parts,
+        None => return false,
+    };
+    let pushed = KEY_STATES.read().map(|states| states.contains_key(cmd)).unwrap_or(false);
+    pushed
+        || PLUGINS
+            .lock()
+            .map(|plugins| {
+                plugins
+                    .get(plugin)
+                    .map(|p| p.info.widgets.iter().any(|w| w == action))
+                    .unwrap_or(false)
+            })
+            .unwrap_or(false)
+}
+
+pub fn get_widget_text(cmd: &str) -> Option<String> {
+    KEY_STATES.read().ok()?.get(cmd)?.title.clone()
+}
+
+// Encoded image pushed with setImage
+pub fn get_key_image(cmd: &str) -> Option<Vec<u8>> {
+    KEY_STATES.read().ok()?.get(cmd)?.image.clone()
+}
+
+fn handle_message(plugin: &str, line: &str) {
+    let msg: serde_json::Value = match serde_json::from_str(line) {
+        Ok(v) => v,
+        Err(e) => {
+            warn!("Plugin {} sent invalid JSON: {}", plugin, e);
+            return;
+        }
+    };
+    let field = |name: &str| msg.get(name).and_then(|v| v.as_str()).unwrap_or("").to_string();
+
+    match field("event").as_str() {
+        "registerAction" => {
+            let id = field("action");
+            let title = msg.get("title").and_then(|v| v.as_str()).unwrap_or(&id).to_string();
+            if let Ok(mut plugins) = PLUGINS.lock() {
+                if let Some(p) = plugins.get_mut(plugin) {
+                    p.info.actions.retain(|a| a.id != id);
+                    p.info.actions.push(RegisteredAction { id, title });
+                }
+            }
+        }
+        "registerWidget" => {
+            let widget = field("widget");
+            if let Ok(mut plugins) = PLUGINS.lock() {
+                if let Some(p) = plugins.get_mut(plugin) {
+                    if !p.info.widgets.contains(&widget) {
+                        p.info.widgets.push(widget);
+                    }
+                }
+            }
+        }
+        "setTitle" => {
+            if let Ok(mut states) = KEY_STATES.write() {
+                states.entry(field("context")).or_default().title = Some(field("title"));
+            }
+        }
+        "setImage" => {
+            let data = field("image");
+            // Accept data URLs as well as plain base64
+            let data = data.split_once("base64,").map(|(_, d)| d).unwrap_or(&data);
+            match STANDARD.decode(data.trim()) {
+                Ok(bytes) => {
+                    if let Ok(mut states) = KEY_STATES.write() {
+                        states.entry(field("context")).or_default().image = Some(bytes);
+                    }
+                }
+                Err(e) => warn!("Plugin {} sent an invalid image: {}", plugin, e),
+            }
+        }
+        "setVariable" => {
+            if let Err(e) = variables::set(&field("name"), &field("value")) {
+                debug!("Plugin {}: {}", plugin, e);
+            }
+        }
+        "showMessage" => {
+            let duration = msg
+                .get("duration")
+                .and_then(|v| v.as_u64())
+                .map(Duration::from_millis)
+                .unwrap_or(overlay::DEFAULT_DURATION);
+            overlay::show_on_command(&field("context"), &field("text"), duration);
+        }
+        "log" => debug!("[plugin {}] {}", plugin, field("message")),
+        other => debug!("Plugin {} sent unknown event '{}'", plugin, other),
+    }
+}
+
+fn mark_stopped(plugin: &str) {
+    debug!("Plugin {} disconnected", plugin);
+    if let Ok(mut plugins) = PLUGINS.lock() {
+        if let Some(p) = plugins.get_mut(plugin) {
+            p.info.running = false;
+        }
+    }
+}
+
+fn start_stdio(name: &str, mut stdin: ChildStdin, stdout: ChildStdout, outgoing: Receiver<String>) {
+    thread::spawn(move || {
+        for line in outgoing {
+            if writeln!(stdin, "{}", line).and_then(|_| stdin.flush()).is_err() {
+                break;
+            }
+        }
+    });
+
+    let name = name.to_string();
+    thread::spawn(move || {
+        for line in BufReader::new(stdout).lines() {
+            match line {
+                Ok(line) if line.trim().is_empty() => {}
+                Ok(line) => handle_message(&name, &line),
+                Err(_) => break,
+            }
+        }
+        mark_stopped(&name);
+    });
+}
+
+// Accept one plugin connection on `listener`, pass every text frame to
+// `on_message` and send what arrives on `outgoing`. Also used by elgato.rs.
+pub fn serve_websocket(
+    name: &str,
+    listener: TcpListener,
+    outgoing: Receiver<String>,
+    on_message: fn(&str, &str),
+    on_close: fn(&str),
+) {
+    let name = name.to_string();
+    thread::spawn(move || {
+        let stream = match listener.accept() {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                debug!("Plugin {} never connected: {}", name, e);
+                on_close(&name);
+                return;
+            }
+        };
+        let mut socket = match tungstenite::accept(stream) {
+            Ok(s) => s,
+            Err(e) => {
+                warn!("Plugin {} handshake failed: {}", name, e);
+                on_close(&name);
+                return;
+            }
+        };
+        // Short read timeout so queued messages get sent between reads
+        socket.get_ref().set_read_timeout(Some(WS_POLL_INTERVAL)).ok();
+
+        loop {
+            while let Ok(line) = outgoing.try_recv() {
+                if socket.send(Message::Text(line)).is_err() {
+                    on_close(&name);
+                    return;
+                }
+            }
+            match socket.read() {
+                Ok(Message::Text(text)) => on_message(&name, &text),
+                Ok(Message::Close(_)) => break,
+                Ok(_) => {}
+                Err(tungstenite::Error::Io(e))
+                    if e.kind() == std::io::ErrorKind::WouldBlock || e.kind() == std::io::ErrorKind::TimedOut => {}
+                Err(_) => break,
+            }
+        }
+        on_close(&name);
+    });
+}
+
+fn launch(dir: &Path, manifest: Manifest) -> Result<(), String> {
+    let exec = dir.join(&manifest.exec);
+    let mut command = Command::new(&exec);
+    command.args(&manifest.args).current_dir(dir);
+
+    let listener = match manifest.transport {
+        Transport::Stdio => {
+            command.stdin(Stdio::piped()).stdout(Stdio::piped());
+            None
+        }
+        Transport::Websocket => {
+            let listener = TcpListener::bind("127.0.0.1:0").map_err(|e| format!("Failed to listen: {}", e))?;
+            let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+            command.args(["--port", &port.to_string(), "--plugin", &manifest.name]);
+            Some(listener)
+        }
+    };
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", exec.display(), e))?;
+    let pipes = (child.stdin.take(), child.stdout.take());
+    debug!("Started plugin {} ({})", manifest.name, exec.display());
+
+    // Register before any message can arrive
+    let (sender, receiver) = mpsc::channel();
+    let info = PluginInfo {
+        name: manifest.name.clone(),
+        transport: match manifest.transport {
+            Transport::Stdio => "stdio".to_string(),
+            Transport::Websocket => "websocket".to_string(),
+        },
+        running: true,
+        actions: Vec::new(),
+        widgets: Vec::new(),
+    };
+    PLUGINS
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(manifest.name.clone(), Plugin { info, child, sender });
+
+    match (listener, pipes) {
+        (Some(listener), _) => serve_websocket(&manifest.name, listener, receiver, handle_message, mark_stopped),
+        (None, (Some(stdin), Some(stdout))) => start_stdio(&manifest.name, stdin, stdout, receiver),
+        (None, _) => return Err(format!("Plugin {} has no stdio pipes", manifest.name)),
+    }
+    Ok(())
+}
+
+pub fn plugins_dir(config_path: &Path) -> PathBuf {
+    config_path.with_file_name("plugins")
+}
+
+// Launch every plugin found in the plugins directory
+pub fn start_all(plugins_dir: &Path) {
+    let entries = match fs::read_dir(plugins_dir) {
+        Ok(e) => e,
+        Err(_) => return,
+    };
+    for dir in entries.filter_map(|e| e.ok()).map(|e| e.path()).filter(|p| p.is_dir()) {
+        let manifest: Manifest = match fs::read_to_string(dir.join(MANIFEST_FILE))
+            .map_err(|e| e.to_string())
+            .and_then(|content| serde_json::from_str(&content).map_err(|e| e.to_string()))
+        {
+            Ok(m) => m,
+            Err(e) => {
+                debug!("Skipping plugin in {}: {}", dir.display(), e);
+                continue;
+            }
+        };
+        if manifest.name.is_empty() || manifest.name.contains(['.', ':']) {
+            warn!("Invalid plugin name '{}' in {}", manifest.name, dir.display());
+            continue;
+        }
+        if let Err(e) = launch(&dir, manifest) {
+            warn!("Plugin error: {}", e);
+        }
+    }
+}
+
+// Kill every plugin process and forget what they pushed
+pub fn stop_all() {
+    if let Ok(mut plugins) = PLUGINS.lock() {
+        for (_, mut plugin) in plugins.drain() {
+            plugin.child.kill().ok();
+            plugin.child.wait().ok();
+        }
+    }
+    if let Ok(mut states) = KEY_STATES.write() {
+        states.clear();
+    }
+}
+
+pub fn list() -> Vec<PluginInfo> {
+    let mut list: Vec<PluginInfo> = PLUGINS
+        .lock()
+        .map(|plugins| plugins.values().map(|p| p.info.clone()).collect())
+        .unwrap_or_default();
+    list.sort_by(|a, b| a.name.cmp(&b.name));
+    list
+}
+
+// Send a keyDown event for a __PLUGIN_ command
+pub fn execute_command(cmd: &str) -> Result<(), String> {
+    let (plugin, action, param) = parse_command(cmd).ok_or_else(|| format!("Invalid plugin command: {}", cmd))?;
+    let plugins = PLUGINS.lock().map_err(|e| e.to_string())?;
+    let target = plugins.get(plugin).ok_or_else(|| format!("Plugin not loaded: {}", plugin))?;
+    if !target.info.running {
+        return Err(format!("Plugin {} is not running", plugin));
+    }
+    let event = serde_json::json!({
+        "event": "keyDown",
+        "action": action,
+        "param": param,
+        "context": cmd,
+    });
+    target
+        .sender
+        .send(event.to_string())
+        .map_err(|_| format!("Plugin {} is not running", plugin))
+}
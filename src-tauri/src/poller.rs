@@ -0,0 +1,107 @@
+// ============================================================================
+// Widget Polling Framework
+// ============================================================================
+//
+// Widgets backed by slow sources (D-Bus, sockets, HTTP APIs) must never block
+// the render loop. `poll` returns the last cached value for a key immediately
+// and, when that value is older than the widget's interval, refreshes it in a
+// background thread. Only one refresh per key runs at a time; a fetch that
+// panics is retried on the next poll.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::chrono_lite;
+
+struct PollEntry {
+    value: Option<Arc<dyn Any + Send + Sync>>,
+    updated: u64,
+    in_flight: bool,
+}
+
+lazy_static::lazy_static! {
+    static ref POLLS: Mutex<HashMap<String, PollEntry>> = Mutex::new(HashMap::new());
+}
+
+// Clears the key's in_flight when the fetch thread ends, also by a panic
+struct InFlight(String);
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        if let Ok(mut polls) = POLLS.lock() {
+            if let Some(entry) = polls.get_mut(&self.0) {
+                entry.in_flight = false;
+            }
+        }
+    }
+}
+
+// Get the cached value for `key`, scheduling `fetch` if it is missing or older
+// than `interval_secs`. Returns None until the first fetch completes.
+pub fn poll<T, F>(key: &str, interval_secs: u64, fetch: F) -> Option<T>
+where
+    T: Clone + Send + Sync + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let now = chrono_lite();
+    let (current, needs_fetch) = {
+        let mut polls = match POLLS.lock() {
+            Ok(p) => p,
+            Err(_) => return None,
+        };
+        let entry = polls.entry(key.to_string()).or_insert(PollEntry {
+            value: None,
+            updated: 0,
+            in_flight: false,
+        });
+
+        let current = entry.value.as_ref().and_then(|v| v.downcast_ref::<T>().cloned());
+        let stale = current.is_none() || now.saturating_sub(entry.updated) >= interval_secs;
+        let needs_fetch = stale && !entry.in_flight;
+        if needs_fetch {
+            entry.in_flight = true;
+        }
+        (current, needs_fetch)
+    };
+
+    if needs_fetch {
+        let in_flight = InFlight(key.to_string());
+        thread::spawn(move || {
+            let value = fetch();
+            store(&in_flight.0, value);
+        });
+    }
+
+    current
+}
+
+// Store a fresh value for `key` (used by fetches and by actions that already
+// know the new state)
+pub fn store<T: Send + Sync + 'static>(key: &str, value: T) {
+    if let Ok(mut polls) = POLLS.lock() {
+        polls.insert(key.to_string(), PollEntry {
+            value: Some(Arc::new(value)),
+            updated: chrono_lite(),
+            in_flight: false,
+        });
+    }
+}
+
+// Get the cached value for `key` without scheduling a refresh
+pub fn peek<T: Clone + 'static>(key: &str) -> Option<T> {
+    let polls = POLLS.lock().ok()?;
+    polls.get(key)?.value.as_ref()?.downcast_ref::<T>().cloned()
+}
+
+// Force the next `poll` of every matching key to refresh
+pub fn invalidate_matching(pred: impl Fn(&str) -> bool) {
+    if let Ok(mut polls) = POLLS.lock() {
+        for (key, entry) in polls.iter_mut() {
+            if pred(key) {
+                entry.updated = 0;
+            }
+        }
+    }
+}
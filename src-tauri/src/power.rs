@@ -0,0 +1,174 @@
+// ============================================================================
+// Power and Session Actions (logind D-Bus via zbus)
+// ============================================================================
+//
+// Command formats:
+//   __POWER_suspend__   - suspend the machine
+//   __POWER_shutdown__  - power off
+//   __POWER_reboot__    - reboot
+//   __POWER_lock__      - lock the current session
+//
+// Buttons with "holdConfirm": true only run after the key is held for two
+// seconds, which is recommended for shutdown and reboot.
+//
+// The deck loses its screen contents during suspend, so the sleep monitor
+// listens for logind's PrepareForSleep and, on resume, makes the listener
+// reopen the device and send the brightness and current page again.
+
+use std::thread;
+use std::time::Duration;
+
+use zbus::blocking::Connection;
+use zbus::zvariant::OwnedObjectPath;
+use tracing::{debug, warn};
+
+use crate::device_lost;
+
+// Give USB time to come back before reopening the device
+const RESUME_SETTLE: Duration = Duration::from_secs(2);
+
+#[zbus::proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1"
+)]
+trait LoginManager {
+    fn suspend(&self, interactive: bool) -> zbus::Result<()>;
+    fn power_off(&self, interactive: bool) -> zbus::Result<()>;
+    fn reboot(&self, interactive: bool) -> zbus::Result<()>;
+    fn get_session(&self, session_id: &str) -> zbus::Result<OwnedObjectPath>;
+    #[zbus(name = "GetSessionByPID")]
+    fn get_session_by_pid(&self, pid: u32) -> zbus::Result<OwnedObjectPath>;
+    // true before suspending, false after resuming
+    #[zbus(signal)]
+    fn prepare_for_sleep(&self, start: bool) -> zbus::Result<()>;
+}
+
+#[zbus::proxy(interface = "org.freedesktop.login1.Session", default_service = "org.freedesktop.login1")]
+trait LoginSession {
+    fn lock(&self) -> zbus::Result<()>;
+    fn set_brightness(&self, subsystem: &str, name: &str, brightness: u32) -> zbus::Result<()>;
+    #[zbus(property)]
+    fn locked_hint(&self) -> zbus::Result<bool>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PowerAction {
+    Suspend,
+    Shutdown,
+    Reboot,
+    Lock,
+}
+
+fn parse_command(cmd: &str) -> Option<PowerAction> {
+    match cmd.strip_prefix("__POWER_")?.strip_suffix("__")? {
+        "suspend" => Some(PowerAction::Suspend),
+        "shutdown" => Some(PowerAction::Shutdown),
+        "reboot" => Some(PowerAction::Reboot),
+        "lock" => Some(PowerAction::Lock),
+        _ => None,
+    }
+}
+
+pub fn is_power_command(cmd: &str) -> bool {
+    parse_command(cmd).is_some()
+}
+
+// Our logind session: $XDG_SESSION_ID, or the session this process belongs to
+fn session_path(manager: &LoginManagerProxyBlocking) -> zbus::Result<OwnedObjectPath> {
+    match std::env::var("XDG_SESSION_ID") {
+        Ok(id) if !id.is_empty() => manager.get_session(&id),
+        _ => manager.get_session_by_pid(std::process::id()),
+    }
+}
+
+fn session<'a>(conn: &'a Connection, manager: &LoginManagerProxyBlocking) -> zbus::Result<LoginSessionProxyBlocking<'a>> {
+    let path = session_path(manager)?;
+    LoginSessionProxyBlocking::builder(conn).path(path)?.build()
+}
+
+fn lock_session(conn: &Connection, manager: &LoginManagerProxyBlocking) -> zbus::Result<()> {
+    session(conn, manager)?.lock()
+}
+
+// Set a /sys/class/backlight device through logind, which needs no root
+pub fn set_backlight(name: &str, value: u32) -> Result<(), String> {
+    let conn = Connection::system().map_err(|e| format!("D-Bus connection failed: {}", e))?;
+    let manager = LoginManagerProxyBlocking::new(&conn).map_err(|e| format!("logind not available: {}", e))?;
+    session(&conn, &manager)
+        .and_then(|s| s.set_brightness("backlight", name, value))
+        .map_err(|e| format!("SetBrightness failed: {}", e))
+}
+
+pub fn execute_command(cmd: &str) -> Result<(), String> {
+    let action = parse_command(cmd).ok_or_else(|| format!("Invalid power command: {}", cmd))?;
+    let conn = Connection::system().map_err(|e| format!("D-Bus connection failed: {}", e))?;
+    let manager = LoginManagerProxyBlocking::new(&conn).map_err(|e| format!("logind not available: {}", e))?;
+
+    debug!("Power action {:?}", action);
+    // interactive = true lets polkit ask for a password when needed
+    let result = match action {
+        PowerAction::Suspend => manager.suspend(true),
+        PowerAction::Shutdown => manager.power_off(true),
+        PowerAction::Reboot => manager.reboot(true),
+        PowerAction::Lock => lock_session(&conn, &manager),
+    };
+    result.map_err(|e| format!("Power action failed: {}", e))
+}
+
+// Call `on_change` with the session's LockedHint now and whenever it changes
+pub fn watch_session_lock(on_change: fn(bool)) {
+    thread::spawn(move || {
+        let conn = match Connection::system() {
+            Ok(c) => c,
+            Err(e) => return warn!("Lock monitor: D-Bus connection failed: {}", e),
+        };
+        let manager = match LoginManagerProxyBlocking::new(&conn) {
+            Ok(m) => m,
+            Err(e) => return warn!("Lock monitor: logind not available: {}", e),
+        };
+        let session = match session(&conn, &manager) {
+            Ok(s) => s,
+            Err(e) => return warn!("Lock monitor: no logind session: {}", e),
+        };
+        debug!("Lock monitor started");
+        // The first change reports the current state
+        for change in session.receive_locked_hint_changed() {
+            if let Ok(locked) = change.get() {
+                on_change(locked);
+            }
+        }
+    });
+}
+
+// Re-initialize the deck after every resume; called once at startup
+pub fn start_sleep_monitor() {
+    thread::spawn(|| {
+        let conn = match Connection::system() {
+            Ok(c) => c,
+            Err(e) => return warn!("Sleep monitor: D-Bus connection failed: {}", e),
+        };
+        let manager = match LoginManagerProxyBlocking::new(&conn) {
+            Ok(m) => m,
+            Err(e) => return warn!("Sleep monitor: logind not available: {}", e),
+        };
+        let signals = match manager.receive_prepare_for_sleep() {
+            Ok(s) => s,
+            Err(e) => return warn!("Sleep monitor: failed to subscribe to PrepareForSleep: {}", e),
+        };
+        debug!("Sleep monitor started");
+        for signal in signals {
+            let start = match signal.args() {
+                Ok(args) => args.start,
+                Err(_) => continue,
+            };
+            if start {
+                debug!("System going to sleep");
+                continue;
+            }
+            debug!("System resumed, reinitializing the deck");
+            thread::sleep(RESUME_SETTLE);
+            device_lost();
+        }
+    });
+}
@@ -0,0 +1,181 @@
+// ============================================================================
+// Camera / Microphone / Screen Sharing Indicators
+// ============================================================================
+//
+// __ONAIR__ is a widget that turns red while any process has a webcam
+// (/dev/video*) open or a PipeWire capture stream is running. It shows
+// "CAM ON", "MIC ON", "ON AIR" when both are live, or "OFF AIR".
+//
+// __SCREENSHARE__ turns red ("SCREEN ON") while the screen is being shared.
+// Screencasts started through xdg-desktop-portal (browsers, Zoom, OBS on
+// Wayland) are PipeWire video sources published by the compositor or the
+// portal backend; a running Video/Source node that is not a device (no
+// device.id, unlike cameras) is one of them.
+//
+// With "privacyPage" set in config.json the deck switches to that page when
+// the camera or microphone goes live and back to the previous page afterwards.
+// The switch goes through the listener (switch_page), which owns the deck.
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::thread;
+use std::time::Duration;
+
+use tracing::debug;
+
+use crate::{config_store, poller, sandbox, switch_page, Config};
+
+pub const COMMAND: &str = "__ONAIR__";
+pub const SCREEN_COMMAND: &str = "__SCREENSHARE__";
+
+const POLL_SECS: u64 = 2;
+const ON_AIR_COLOR: &str = "#c0392b";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Capture {
+    camera: bool,
+    microphone: bool,
+    screen: bool,
+}
+
+impl Capture {
+    fn live(&self) -> bool {
+        self.camera || self.microphone
+    }
+}
+
+// Any process with a /dev/video* device open (only our own user's processes
+// are visible, which covers desktop apps)
+fn camera_in_use() -> bool {
+    let own_pid = std::process::id().to_string();
+    let processes = match fs::read_dir("/proc") {
+        Ok(entries) => entries,
+        Err(_) => return false,
+    };
+    processes
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_name().to_str().map(|n| n.bytes().all(|b| b.is_ascii_digit()) && n != own_pid).unwrap_or(false))
+        .filter_map(|e| fs::read_dir(e.path().join("fd")).ok())
+        .flat_map(|fds| fds.filter_map(|fd| fd.ok()))
+        .filter_map(|fd| fs::read_link(fd.path()).ok())
+        .any(|target| target.to_string_lossy().starts_with("/dev/video"))
+}
+
+fn pipewire_objects() -> Vec<serde_json::Value> {
+    match sandbox::host_command("pw-dump").stderr(Stdio::null()).output() {
+        Ok(o) if o.status.success() => serde_json::from_slice(&o.stdout).unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+// Running PipeWire nodes of a media class
+fn running_nodes<'a>(objects: &'a [serde_json::Value], class: &'a str) -> impl Iterator<Item = &'a serde_json::Value> {
+    objects.iter().filter_map(move |object| {
+        let info = object.get("info")?;
+        let props = info.get("props")?;
+        let running = info.get("state").and_then(|s| s.as_str()) == Some("running");
+        (running && props.get("media.class").and_then(|c| c.as_str()) == Some(class)).then_some(props)
+    })
+}
+
+// A running Stream/Input/Audio node means something records
+fn microphone_in_use(objects: &[serde_json::Value]) -> bool {
+    running_nodes(objects, "Stream/Input/Audio").next().is_some()
+}
+
+// A running video source that is not a camera device is a screencast
+fn screen_shared(objects: &[serde_json::Value]) -> bool {
+    running_nodes(objects, "Video/Source").any(|props| props.get("device.id").is_none())
+}
+
+fn detect() -> Capture {
+    let objects = pipewire_objects();
+    Capture {
+        camera: camera_in_use(),
+        microphone: microphone_in_use(&objects),
+        screen: screen_shared(&objects),
+    }
+}
+
+fn poll_capture() -> Option<Capture> {
+    poller::poll(COMMAND, POLL_SECS, detect)
+}
+
+pub fn get_widget_text() -> String {
+    match poll_capture() {
+        Some(Capture { camera: true, microphone: true, .. }) => "ON AIR".to_string(),
+        Some(Capture { camera: true, .. }) => "CAM ON".to_string(),
+        Some(Capture { microphone: true, .. }) => "MIC ON".to_string(),
+        Some(_) => "OFF AIR".to_string(),
+        None => "...".to_string(),
+    }
+}
+
+pub fn get_widget_color() -> Option<String> {
+    poller::peek::<Capture>(COMMAND)
+        .filter(|c| c.live())
+        .map(|_| ON_AIR_COLOR.to_string())
+}
+
+pub fn get_screen_text() -> String {
+    match poll_capture() {
+        Some(Capture { screen: true, .. }) => "SCREEN ON".to_string(),
+        Some(_) => "SCREEN OFF".to_string(),
+        None => "...".to_string(),
+    }
+}
+
+pub fn get_screen_color() -> Option<String> {
+    poller::peek::<Capture>(COMMAND)
+        .filter(|c| c.screen)
+        .map(|_| ON_AIR_COLOR.to_string())
+}
+
+pub fn execute_command() {
+    poller::invalidate_matching(|key| key == COMMAND);
+}
+
+// Switch to the privacy page while the camera or microphone is live
+pub fn start_monitor(config_path: PathBuf) {
+    thread::spawn(move || {
+        let mut live = false;
+        // Page to return to once nothing is captured any more
+        let mut previous_page: Option<usize> = None;
+
+        loop {
+            thread::sleep(Duration::from_secs(POLL_SECS));
+            let config = match config_store::read(Config::clone) {
+                Some(c) => c,
+                None => continue,
+            };
+            let privacy_page = match config.privacy_page {
+                Some(page) if page < config.pages.len() => page,
+                _ => {
+                    live = false;
+                    previous_page = None;
+                    continue;
+                }
+            };
+
+            let capture = detect();
+            poller::store(COMMAND, capture);
+            if capture.live() == live {
+                continue;
+            }
+            live = capture.live();
+
+            if live && config.current_page != privacy_page {
+                debug!("Camera/mic live, switching to privacy page {}", privacy_page);
+                previous_page = Some(config.current_page);
+                switch_page(&config_path, privacy_page);
+            } else if !live {
+                // Only go back if the user stayed on the privacy page
+                if let Some(page) = previous_page.take().filter(|_| config.current_page == privacy_page) {
+                    debug!("Camera/mic off, back to page {}", page);
+                    switch_page(&config_path, page);
+                }
+            }
+        }
+    });
+}
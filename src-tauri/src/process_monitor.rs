@@ -0,0 +1,156 @@
+// ============================================================================
+// Process Monitor Widget
+// ============================================================================
+//
+// Command format:
+//   __PROC_<name>__  - e.g. __PROC_restic__: green with the CPU % and memory
+//                      of the process while it runs, red "OFF" when it is not
+//                      running. Pressing stops it (SIGTERM) or starts `<name>`
+//                      from PATH. Starting runs code, so safe mode refuses
+//                      the press and profile imports list the key for approval.
+//
+// Processes are matched by exact name; several instances add up. The state is
+// sampled in the background every few seconds. In Flatpak the sandbox cannot
+// see host processes, so pgrep/pkill run on the host and no usage is shown.
+
+use std::ffi::OsStr;
+use std::process::Stdio;
+use std::thread;
+use std::time::Duration;
+
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, Signal, System};
+use tracing::debug;
+
+use crate::{action_log, poller, request_refresh, sandbox};
+
+const POLL_SECS: u64 = 2;
+// Two CPU samples this far apart give the current usage
+const CPU_SAMPLE: Duration = Duration::from_millis(200);
+// Let a started or stopped process settle before sampling again
+const SETTLE: Duration = Duration::from_millis(500);
+const RUNNING_COLOR: &str = "#27ae60";
+const STOPPED_COLOR: &str = "#c0392b";
+
+#[derive(Debug, Clone)]
+struct ProcessStatus {
+    running: bool,
+    // Sum over the matching processes; None when it cannot be measured
+    usage: Option<(f32, u64)>,
+}
+
+fn parse_command(cmd: &str) -> Option<&str> {
+    let name = cmd.strip_prefix("__PROC_")?.strip_suffix("__")?;
+    (!name.is_empty() && !name.contains('/')).then_some(name)
+}
+
+pub fn is_process_command(cmd: &str) -> bool {
+    parse_command(cmd).is_some()
+}
+
+fn host_succeeds(program: &str, args: &[&str]) -> bool {
+    sandbox::host_command(program)
+        .args(args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false)
+}
+
+fn processes(name: &str, cpu: bool) -> System {
+    let kind = if cpu { ProcessRefreshKind::new().with_cpu().with_memory() } else { ProcessRefreshKind::new() };
+    let mut sys = System::new();
+    sys.refresh_processes_specifics(ProcessesToUpdate::All, true, kind);
+    if cpu && sys.processes_by_exact_name(OsStr::new(name)).next().is_some() {
+        thread::sleep(CPU_SAMPLE);
+        sys.refresh_processes_specifics(ProcessesToUpdate::All, true, kind);
+    }
+    sys
+}
+
+fn sample(name: &str) -> ProcessStatus {
+    if sandbox::is_flatpak() {
+        return ProcessStatus { running: host_succeeds("pgrep", &["-x", name]), usage: None };
+    }
+    let sys = processes(name, true);
+    let (count, cpu, memory) = sys
+        .processes_by_exact_name(OsStr::new(name))
+        .fold((0, 0.0, 0), |(count, cpu, memory), p| (count + 1, cpu + p.cpu_usage(), memory + p.memory()));
+    ProcessStatus { running: count > 0, usage: (count > 0).then_some((cpu, memory)) }
+}
+
+fn stop(name: &str) -> Result<(), String> {
+    if sandbox::is_flatpak() {
+        return host_succeeds("pkill", &["-x", name])
+            .then_some(())
+            .ok_or_else(|| format!("Failed to stop {}", name));
+    }
+    let sys = processes(name, false);
+    let signalled = sys
+        .processes_by_exact_name(OsStr::new(name))
+        .filter(|p| p.kill_with(Signal::Term).unwrap_or(false))
+        .count();
+    if signalled == 0 {
+        return Err(format!("Failed to stop {}", name));
+    }
+    debug!("Sent SIGTERM to {} {} process(es)", signalled, name);
+    Ok(())
+}
+
+fn start(name: &str) -> Result<(), String> {
+    sandbox::host_command(name)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", name, e))?;
+    debug!("Started {}", name);
+    Ok(())
+}
+
+pub fn execute_command(cmd: &str) {
+    let name = match parse_command(cmd) {
+        Some(name) => name.to_string(),
+        None => return,
+    };
+    let cmd = cmd.to_string();
+    thread::spawn(move || {
+        let running = sample(&name).running;
+        let result = if running { stop(&name) } else { start(&name) };
+        if let Err(e) = result {
+            action_log::record_error(&cmd, &e);
+        }
+        thread::sleep(SETTLE);
+        poller::store(&cmd, sample(&name));
+        request_refresh();
+    });
+}
+
+fn poll_status(cmd: &str) -> Option<ProcessStatus> {
+    let name = parse_command(cmd)?.to_string();
+    poller::poll(cmd, POLL_SECS, move || sample(&name))
+}
+
+// 512M, 1.5G
+fn format_memory(bytes: u64) -> String {
+    let mib = bytes / (1024 * 1024);
+    if mib < 1024 {
+        format!("{}M", mib)
+    } else {
+        format!("{:.1}G", mib as f64 / 1024.0)
+    }
+}
+
+pub fn get_widget_text(cmd: &str) -> String {
+    match poll_status(cmd) {
+        Some(ProcessStatus { running: false, .. }) => "OFF".to_string(),
+        Some(ProcessStatus { usage: Some((cpu, memory)), .. }) => format!("{:.0}% {}", cpu, format_memory(memory)),
+        Some(_) => "ON".to_string(),
+        None => "...".to_string(),
+    }
+}
+
+pub fn get_widget_color(cmd: &str) -> Option<String> {
+    let status = poller::peek::<ProcessStatus>(cmd)?;
+    Some(if status.running { RUNNING_COLOR } else { STOPPED_COLOR }.to_string())
+}
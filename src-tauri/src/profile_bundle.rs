@@ -0,0 +1,281 @@
+// ============================================================================
+// Profile Bundles (zip export/import)
+// ============================================================================
+//
+// A bundle is a zip with the pages and the icons they use:
+//
+//   profile.json       - {"version": 1, "pages": [...]}
+//   icons/<file>       - every icon referenced by a button
+//
+// Importing appends the pages to the current config. Page names that already
+// exist get " (2)", " (3)", ...; an icon whose name is taken by a different
+// file is saved as <name>-2.<ext> and the buttons are pointed at it.
+//
+// A shared bundle can carry actions that run code and URLs. review() lists
+// every action that safe mode would refuse (Action::runs_code: shell commands,
+// scripts, __CMD_, __PROC_, __SSH_ and __APP_, also inside __MULTI_ steps) and
+// every URL, so the user can read them first; import() refuses a bundle that
+// has any unless they were approved, and every approved one is appended to
+// import-audit.jsonl next to config.json.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+use tracing::debug;
+
+use crate::action::Action;
+use crate::{apps, chrono_lite, process_monitor, ssh, Config, Page};
+
+const PROFILE_FILE: &str = "profile.json";
+const ICONS_DIR: &str = "icons/";
+const BUNDLE_VERSION: u32 = 1;
+const AUDIT_FILE: &str = "import-audit.jsonl";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Bundle {
+    version: u32,
+    pages: Vec<Page>,
+}
+
+// An action found in a bundle that runs code, or a URL
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportedAction {
+    pub page: String,
+    pub key: String,
+    pub label: String,
+    // "shell" (also __CMD_), "script", "process" (__PROC_, pressing starts the
+    // program), "ssh", "app" (__APP_) or "url"
+    pub kind: String,
+    pub command: String,
+}
+
+// One line of the audit log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: u64,
+    pub bundle: String,
+    #[serde(flatten)]
+    pub action: ImportedAction,
+}
+
+// Export all pages, or only `page`, with their icons
+pub fn export(config: &Config, icons_path: &Path, page: Option<usize>, target: &Path) -> Result<(), String> {
+    let pages = match page {
+        Some(index) => vec![config.pages.get(index).cloned().ok_or_else(|| format!("Page {} does not exist", index))?],
+        None => config.pages.clone(),
+    };
+    let bundle = Bundle {
+        version: BUNDLE_VERSION,
+        pages,
+    };
+
+    let file = File::create(target).map_err(|e| format!("Failed to create {}: {}", target.display(), e))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default();
+    let json = serde_json::to_string_pretty(&bundle).map_err(|e| e.to_string())?;
+    zip.start_file(PROFILE_FILE, options).map_err(|e| e.to_string())?;
+    zip.write_all(json.as_bytes()).map_err(|e| e.to_string())?;
+
+    let icons: HashSet<&str> = bundle
+        .pages
+        .iter()
+        .flat_map(|p| p.buttons.values())
+        .map(|b| b.icon.as_str())
+        .filter(|icon| !icon.is_empty())
+        .collect();
+    for icon in icons {
+        let data = match fs::read(icons_path.join(icon)) {
+            Ok(d) => d,
+            Err(e) => {
+                debug!("Skipping missing icon {}: {}", icon, e);
+                continue;
+            }
+        };
+        zip.start_file(format!("{}{}", ICONS_DIR, icon), options).map_err(|e| e.to_string())?;
+        zip.write_all(&data).map_err(|e| e.to_string())?;
+    }
+
+    zip.finish().map_err(|e| format!("Failed to write bundle: {}", e))?;
+    debug!("Exported {} page(s) to {}", bundle.pages.len(), target.display());
+    Ok(())
+}
+
+// Plain file name, nothing that could leave the icons directory
+fn is_safe_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(['/', '\\']) && !name.starts_with('.')
+}
+
+// Name for `data` in the icons directory: the same name if free or identical,
+// else <stem>-<n>.<ext>
+fn free_icon_name(icons_path: &Path, name: &str, data: &[u8]) -> String {
+    let path = Path::new(name);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or(name);
+    let extension = path.extension().and_then(|e| e.to_str()).map(|e| format!(".{}", e)).unwrap_or_default();
+
+    let mut candidate = name.to_string();
+    let mut n = 2;
+    loop {
+        match fs::read(icons_path.join(&candidate)) {
+            Err(_) => return candidate,
+            Ok(existing) if existing == data => return candidate,
+            Ok(_) => {
+                candidate = format!("{}-{}{}", stem, n, extension);
+                n += 1;
+            }
+        }
+    }
+}
+
+fn free_page_name(config: &Config, name: &str) -> String {
+    let taken = |candidate: &str| config.pages.iter().any(|p| p.name == candidate);
+    if !taken(name) {
+        return name.to_string();
+    }
+    (2..)
+        .map(|n| format!("{} ({})", name, n))
+        .find(|candidate| !taken(candidate))
+        .unwrap_or_else(|| name.to_string())
+}
+
+fn open_bundle(source: &Path) -> Result<(ZipArchive<File>, Bundle), String> {
+    let file = File::open(source).map_err(|e| format!("Failed to open {}: {}", source.display(), e))?;
+    let mut zip = ZipArchive::new(file).map_err(|e| format!("Not a profile bundle: {}", e))?;
+
+    let mut json = String::new();
+    zip.by_name(PROFILE_FILE)
+        .map_err(|_| format!("{} is missing from the bundle", PROFILE_FILE))?
+        .read_to_string(&mut json)
+        .map_err(|e| e.to_string())?;
+    let bundle: Bundle = serde_json::from_str(&json).map_err(|e| format!("Invalid {}: {}", PROFILE_FILE, e))?;
+    if bundle.version > BUNDLE_VERSION {
+        return Err(format!("Bundle version {} is newer than this app supports", bundle.version));
+    }
+    Ok((zip, bundle))
+}
+
+fn risky_actions(pages: &[Page]) -> Vec<ImportedAction> {
+    let mut found = Vec::new();
+    for page in pages {
+        let mut keys: Vec<_> = page.buttons.iter().collect();
+        keys.sort_by_key(|(key, _)| key.parse::<u8>().unwrap_or(u8::MAX));
+        for (key, button) in keys {
+            let action = button.resolved_action();
+            for step in action.flatten() {
+                // Everything safe mode refuses, plus URLs. The steps of a
+                // __MULTI_ are listed one by one.
+                let kind = match step {
+                    Action::Url { .. } => "url",
+                    Action::Multi { .. } => continue,
+                    _ if !step.runs_code() => continue,
+                    Action::Script { .. } => "script",
+                    Action::Widget { command } | Action::Integration { command } if process_monitor::is_process_command(command) => {
+                        "process"
+                    }
+                    Action::Widget { command } | Action::Integration { command } if ssh::is_ssh_command(command) => "ssh",
+                    Action::Widget { command } | Action::Integration { command } if apps::is_app_command(command) => "app",
+                    _ => "shell",
+                };
+                let command = match step {
+                    Action::Shell { command, .. } => command.clone(),
+                    Action::Url { url } => url.clone(),
+                    _ => step.to_command(),
+                };
+                found.push(ImportedAction {
+                    page: page.name.clone(),
+                    key: key.clone(),
+                    label: button.label.clone(),
+                    kind: kind.to_string(),
+                    command,
+                });
+            }
+        }
+    }
+    found
+}
+
+// Every action that runs code and every URL in the bundle, for the user to approve
+pub fn review(source: &Path) -> Result<Vec<ImportedAction>, String> {
+    let (_, bundle) = open_bundle(source)?;
+    Ok(risky_actions(&bundle.pages))
+}
+
+fn audit(app_dir: &Path, source: &Path, actions: &[ImportedAction]) -> Result<(), String> {
+    let path = app_dir.join(AUDIT_FILE);
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let timestamp = chrono_lite();
+    for action in actions {
+        let entry = AuditEntry {
+            timestamp,
+            bundle: source.display().to_string(),
+            action: action.clone(),
+        };
+        let line = serde_json::to_string(&entry).map_err(|e| e.to_string())?;
+        writeln!(file, "{}", line).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+// The audit log, oldest first
+pub fn audit_entries(app_dir: &Path) -> Vec<AuditEntry> {
+    fs::read_to_string(app_dir.join(AUDIT_FILE))
+        .map(|text| text.lines().filter_map(|line| serde_json::from_str(line).ok()).collect())
+        .unwrap_or_default()
+}
+
+// Append the bundle's pages to `config` and copy its icons; returns the number
+// of pages. A bundle with shell commands or URLs needs `approved`.
+pub fn import(config: &mut Config, icons_path: &Path, source: &Path, approved: bool) -> Result<usize, String> {
+    let (mut zip, bundle) = open_bundle(source)?;
+    let actions = risky_actions(&bundle.pages);
+    if !actions.is_empty() && !approved {
+        return Err(format!(
+            "The bundle contains {} shell command(s) or URL(s); review and approve them before importing",
+            actions.len()
+        ));
+    }
+    if !actions.is_empty() {
+        let app_dir = icons_path.parent().unwrap_or(icons_path);
+        audit(app_dir, source, &actions)?;
+    }
+
+    // Copy icons, remembering renames
+    fs::create_dir_all(icons_path).ok();
+    let mut renamed: HashMap<String, String> = HashMap::new();
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i).map_err(|e| e.to_string())?;
+        let name = match entry.name().strip_prefix(ICONS_DIR) {
+            Some(n) if is_safe_name(n) => n.to_string(),
+            _ => continue,
+        };
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data).map_err(|e| e.to_string())?;
+        let target = free_icon_name(icons_path, &name, &data);
+        fs::write(icons_path.join(&target), &data).map_err(|e| format!("Failed to save icon {}: {}", target, e))?;
+        if target != name {
+            renamed.insert(name, target);
+        }
+    }
+
+    let count = bundle.pages.len();
+    for mut page in bundle.pages {
+        for button in page.buttons.values_mut() {
+            if let Some(new_name) = renamed.get(&button.icon) {
+                button.icon = new_name.clone();
+            }
+        }
+        page.name = free_page_name(config, &page.name);
+        config.pages.push(page);
+    }
+    config.normalize_actions();
+    debug!("Imported {} page(s) from {}", count, source.display());
+    Ok(count)
+}
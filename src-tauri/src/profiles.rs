@@ -0,0 +1,161 @@
+// ============================================================================
+// Profiles (named sets of pages)
+// ============================================================================
+//
+// The active profile is the config itself ("pages", "brightness",
+// "currentPage"); the others are stored under "profiles" and swapped in on a
+// switch, so nothing else needs to know about profiles:
+//
+//   "profile": "Streaming",
+//   "profiles": [ { "name": "Trabajo", "brightness": 40, "currentPage": 0, "pages": [...] } ]
+//
+// Command: __PROFILE_<name>__ switches to that profile.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::{config_store, request_refresh, scripting, virtual_page, Config, Page};
+
+pub const DEFAULT_NAME: &str = "Principal";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    pub brightness: u8,
+    #[serde(rename = "currentPage")]
+    pub current_page: usize,
+    pub pages: Vec<Page>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileList {
+    pub active: String,
+    // All profiles, the active one included
+    pub names: Vec<String>,
+}
+
+pub fn default_name() -> String {
+    DEFAULT_NAME.to_string()
+}
+
+fn parse_command(cmd: &str) -> Option<&str> {
+    cmd.strip_prefix("__PROFILE_")?.strip_suffix("__").filter(|name| !name.is_empty())
+}
+
+pub fn is_profile_command(cmd: &str) -> bool {
+    parse_command(cmd).is_some()
+}
+
+pub fn list(config: &Config) -> ProfileList {
+    let mut names = vec![config.profile.clone()];
+    names.extend(config.profiles.iter().map(|p| p.name.clone()));
+    ProfileList {
+        active: config.profile.clone(),
+        names,
+    }
+}
+
+fn exists(config: &Config, name: &str) -> bool {
+    config.profile == name || config.profiles.iter().any(|p| p.name == name)
+}
+
+fn check_new_name(config: &Config, name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Profile name is empty".to_string());
+    }
+    if exists(config, name) {
+        return Err(format!("Profile '{}' already exists", name));
+    }
+    Ok(name.to_string())
+}
+
+// The active profile as a stored one
+fn active_profile(config: &Config) -> Profile {
+    Profile {
+        name: config.profile.clone(),
+        brightness: config.brightness,
+        current_page: config.current_page,
+        pages: config.pages.clone(),
+    }
+}
+
+pub fn switch(config: &mut Config, name: &str) -> Result<(), String> {
+    if config.profile == name {
+        return Ok(());
+    }
+    let index = config
+        .profiles
+        .iter()
+        .position(|p| p.name == name)
+        .ok_or_else(|| format!("Profile '{}' does not exist", name))?;
+    let target = config.profiles.remove(index);
+    let previous = active_profile(config);
+    config.profiles.insert(index, previous);
+
+    config.profile = target.name;
+    config.brightness = target.brightness;
+    config.current_page = target.current_page.min(target.pages.len().saturating_sub(1));
+    config.pages = target.pages;
+    // Page indexes of the old profile mean nothing here
+    config.privacy_page = None;
+    debug!("Switched to profile '{}'", config.profile);
+    Ok(())
+}
+
+// New profile with the given pages (not activated)
+pub fn create(config: &mut Config, name: &str, pages: Vec<Page>) -> Result<(), String> {
+    let name = check_new_name(config, name)?;
+    config.profiles.push(Profile {
+        name,
+        brightness: config.brightness,
+        current_page: 0,
+        pages,
+    });
+    Ok(())
+}
+
+pub fn clone(config: &mut Config, source: &str, name: &str) -> Result<(), String> {
+    let mut profile = if config.profile == source {
+        active_profile(config)
+    } else {
+        config
+            .profiles
+            .iter()
+            .find(|p| p.name == source)
+            .cloned()
+            .ok_or_else(|| format!("Profile '{}' does not exist", source))?
+    };
+    profile.name = check_new_name(config, name)?;
+    config.profiles.push(profile);
+    Ok(())
+}
+
+pub fn delete(config: &mut Config, name: &str) -> Result<(), String> {
+    if config.profile == name {
+        return Err("Switch to another profile before deleting this one".to_string());
+    }
+    let before = config.profiles.len();
+    config.profiles.retain(|p| p.name != name);
+    if config.profiles.len() == before {
+        return Err(format!("Profile '{}' does not exist", name));
+    }
+    Ok(())
+}
+
+// __PROFILE_<name>__ from a key or hotkey
+pub fn execute_command(cmd: &str, config_path: &Path) -> Result<(), String> {
+    let name = parse_command(cmd).ok_or_else(|| format!("Invalid profile command: {}", cmd))?;
+    config_store::update(config_path, |config| switch(config, name)).ok_or("Config is not loaded")??;
+    activated();
+    Ok(())
+}
+
+// After a switch: drop page-bound state and redraw the deck
+pub fn activated() {
+    virtual_page::close();
+    scripting::clear_key_text();
+    request_refresh();
+}
@@ -0,0 +1,214 @@
+// ============================================================================
+// Raw Protocol Console (developer mode)
+// ============================================================================
+//
+// For exploring the SS-550 protocol (other CLE targets, STP variants...):
+// send arbitrary hex payloads and see what comes back on the IN endpoint.
+// Only available with `--developer` or REDRAGON_DEVELOPER=1, since a wrong
+// payload can leave the screen in an odd state until the next page load.
+//
+// Payloads are hex bytes ("43 4c 45 00 00 00 ff", "434c45", "0x43,0x4c"),
+// sent as one 512-byte packet after the CRT\0\0 prefix unless `prefix` is
+// false. Both directions are appended to <app data>/logs/protocol.log; in
+// developer mode the listener also dumps every packet it reads there.
+//
+// In the app the listener owns the device, so send_raw_command is queued and
+// run between two reads. Without the app running it works from a terminal:
+//
+//   redragon-streamdeck --developer raw "43 4c 45 00 00 00 ff" [--no-prefix] [--read 500]
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::mpsc::{self, Sender};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::Local;
+use serde::Serialize;
+use tracing::debug;
+
+use crate::deck::Deck;
+use crate::{deck_reader, logging, PACKET_SIZE};
+
+// How long to collect replies when the caller doesn't say
+pub const DEFAULT_READ_MS: u64 = 500;
+// Longest a request may wait for the listener
+const QUEUE_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_READ_MS: u64 = 3000;
+
+lazy_static::lazy_static! {
+    static ref ENABLED: bool = std::env::args().any(|a| a == "--developer")
+        || std::env::var("REDRAGON_DEVELOPER").map(|v| v == "1").unwrap_or(false);
+    static ref PENDING: Mutex<Vec<(RawRequest, ReplySender)>> = Mutex::new(Vec::new());
+}
+
+type ReplySender = Sender<Result<RawReply, String>>;
+
+#[derive(Debug, Clone)]
+pub struct RawRequest {
+    pub payload: Vec<u8>,
+    pub prefix: bool,
+    pub read: Duration,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RawReply {
+    // Bytes sent, as hex
+    pub sent: String,
+    // Each packet read from the IN endpoint within the read time, as hex
+    pub received: Vec<String>,
+}
+
+pub fn developer_enabled() -> bool {
+    *ENABLED
+}
+
+fn hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(" ")
+}
+
+// Trailing zero padding says nothing; keep the log readable
+fn trimmed(data: &[u8]) -> &[u8] {
+    let end = data.iter().rposition(|b| *b != 0).map(|i| i + 1).unwrap_or(0);
+    &data[..end]
+}
+
+pub fn parse_hex(text: &str) -> Result<Vec<u8>, String> {
+    let digits: String = text
+        .split(|c: char| c.is_whitespace() || c == ',' || c == ':')
+        .map(|part| part.trim_start_matches("0x").trim_start_matches("0X"))
+        .collect();
+    if digits.is_empty() {
+        return Err("Empty payload".to_string());
+    }
+    if !digits.len().is_multiple_of(2) {
+        return Err(format!("Odd number of hex digits: {}", text));
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).map_err(|_| format!("Invalid hex: {}", &digits[i..i + 2])))
+        .collect()
+}
+
+// Append a packet to protocol.log (only once logging knows the app data dir)
+pub fn dump(direction: &str, data: &[u8]) {
+    let path = match logging::log_dir() {
+        Some(dir) => dir.join("protocol.log"),
+        None => return,
+    };
+    if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(path) {
+        writeln!(file, "{} {} {}", Local::now().format("%H:%M:%S%.3f"), direction, hex(trimmed(data))).ok();
+    }
+}
+
+// Send the payload and collect what the device answers
+pub fn exchange(deck: &dyn Deck, request: &RawRequest) -> Result<RawReply, String> {
+    if request.payload.len() > PACKET_SIZE {
+        return Err(format!("Payload longer than one packet ({} bytes)", PACKET_SIZE));
+    }
+    deck.send_raw(&request.payload, request.prefix)?;
+    dump("OUT", &request.payload);
+
+    let mut received = Vec::new();
+    let started = std::time::Instant::now();
+    while started.elapsed() < request.read {
+        match deck.read_raw(request.read.saturating_sub(started.elapsed()))? {
+            Some(packet) => {
+                dump("IN ", &packet);
+                received.push(hex(trimmed(&packet)));
+            }
+            None => break,
+        }
+    }
+    debug!("Raw command {} got {} packet(s)", hex(&request.payload), received.len());
+    Ok(RawReply {
+        sent: hex(&request.payload),
+        received,
+    })
+}
+
+// Queue a request for the listener and wait for its reply
+pub fn send(payload: &str, prefix: bool, read_ms: Option<u64>) -> Result<RawReply, String> {
+    if !developer_enabled() {
+        return Err("The raw protocol console needs developer mode (--developer)".to_string());
+    }
+    let request = RawRequest {
+        payload: parse_hex(payload)?,
+        prefix,
+        read: Duration::from_millis(read_ms.unwrap_or(DEFAULT_READ_MS).min(MAX_READ_MS)),
+    };
+    let (sender, receiver) = mpsc::channel();
+    PENDING.lock().map_err(|e| e.to_string())?.push((request, sender));
+    deck_reader::wake();
+    receiver
+        .recv_timeout(QUEUE_TIMEOUT + Duration::from_millis(MAX_READ_MS))
+        .map_err(|_| "The device is not open".to_string())?
+}
+
+// Called by the listener between reads
+pub fn run_pending(deck: &dyn Deck) {
+    let pending: Vec<_> = match PENDING.lock() {
+        Ok(mut pending) if !pending.is_empty() => pending.drain(..).collect(),
+        _ => return,
+    };
+    for (request, sender) in pending {
+        sender.send(exchange(deck, &request)).ok();
+    }
+}
+
+// `raw <hex> [--no-prefix] [--read <ms>]`; None when the arguments aren't a raw command
+pub fn run_cli(deck: impl FnOnce() -> Option<Box<dyn Deck>>) -> Option<i32> {
+    let args: Vec<String> = std::env::args().skip(1).filter(|a| a != "--developer").collect();
+    if args.first().map(|a| a.as_str()) != Some("raw") {
+        return None;
+    }
+    if !developer_enabled() {
+        eprintln!("raw needs --developer");
+        return Some(2);
+    }
+    let payload = match args.get(1) {
+        Some(p) => p,
+        None => {
+            eprintln!("usage: redragon-streamdeck --developer raw <hex> [--no-prefix] [--read <ms>]");
+            return Some(2);
+        }
+    };
+    let read_ms = args
+        .iter()
+        .position(|a| a == "--read")
+        .and_then(|i| args.get(i + 1))
+        .and_then(|ms| ms.parse().ok())
+        .unwrap_or(DEFAULT_READ_MS);
+    let request = match parse_hex(payload) {
+        Ok(bytes) => RawRequest {
+            payload: bytes,
+            prefix: !args.iter().any(|a| a == "--no-prefix"),
+            read: Duration::from_millis(read_ms.min(MAX_READ_MS)),
+        },
+        Err(e) => {
+            eprintln!("{}", e);
+            return Some(2);
+        }
+    };
+
+    let deck = match deck() {
+        Some(d) => d,
+        None => {
+            eprintln!("SS-550 not found or busy (close the app first)");
+            return Some(1);
+        }
+    };
+    match exchange(deck.as_ref(), &request) {
+        Ok(reply) => {
+            println!("OUT {}", reply.sent);
+            for packet in reply.received {
+                println!("IN  {}", packet);
+            }
+            Some(0)
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            Some(1)
+        }
+    }
+}
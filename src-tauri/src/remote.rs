@@ -0,0 +1,297 @@
+// ============================================================================
+// Remote Virtual Deck (HTTP)
+// ============================================================================
+//
+// Mirrors the current page on a phone or tablet and accepts presses, so the
+// layouts work without the hardware. Enabled with "remoteDeck" in config.json
+// (or the set_remote_deck command):
+//
+//   "remoteDeck": { "port": 8642, "token": "...", "lan": false }
+//
+// The server only listens on 127.0.0.1 unless "lan" is true; it is plain HTTP
+// and a press can run shell commands, so the LAN is opt-in. Open
+// http://<host>:<port>/?token=<token> in a browser. Every request needs the
+// token (128 random bits). Endpoints (also usable by other companion apps):
+//   GET  /              - the deck page
+//   GET  /state         - {"page", "name", "pages", "keys": {"1": "<image hash>", ...}}
+//   GET  /key/<1-15>    - JPEG of a key as rendered for the last /state
+//
+// Key images are kept between polls and only rendered again when something
+// they are drawn from changed (see key_fingerprint).
+//   POST /press/<1-15>  - press a key (add confirm=1 for hold-to-confirm keys)
+//   POST /page/<n>      - switch page
+//   POST /message/<1-15>?text=...[&ms=2000] - show a message on a key for a moment
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Cursor, Read, Write};
+use std::net::{TcpListener, TcpStream, UdpSocket};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+use std::thread;
+use std::time::Duration;
+
+use image::{DynamicImage, ImageBuffer, Rgb};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::{
+    button_for_key, config_store, handle_button_press, key_fingerprint, overlay, render_button_image, scripting, switch_page, virtual_page,
+    ButtonConfig, Config, BUTTON_SIZE,
+};
+
+const PAGE_HTML: &str = include_str!("remote.html");
+const MAX_REQUEST_BYTES: usize = 8192;
+const TOKEN_BYTES: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemoteDeck {
+    pub port: u16,
+    pub token: String,
+    // Listen on every interface instead of 127.0.0.1
+    #[serde(default)]
+    pub lan: bool,
+}
+
+lazy_static::lazy_static! {
+    // Fingerprint and JPEG per key from the last /state, served by /key/<n>
+    static ref IMAGES: RwLock<HashMap<u8, (u64, Vec<u8>)>> = RwLock::new(HashMap::new());
+    // Ports with a running listener, and whether it is on the LAN
+    static ref LISTENING: Mutex<Vec<(u16, bool)>> = Mutex::new(Vec::new());
+}
+
+struct Request {
+    method: String,
+    path: String,
+    query: HashMap<String, String>,
+}
+
+// New access token from the kernel's random source
+pub fn generate_token() -> Result<String, String> {
+    let mut bytes = [0u8; TOKEN_BYTES];
+    File::open("/dev/urandom")
+        .and_then(|mut random| random.read_exact(&mut bytes))
+        .map_err(|e| format!("Failed to read /dev/urandom: {}", e))?;
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+// Compare without returning early, so response timing does not leak how much
+// of a guessed token was right
+fn token_matches(given: &str, token: &str) -> bool {
+    given.len() == token.len() && given.bytes().zip(token.bytes()).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+// URL to open on the phone (LAN address of this machine), or on this machine
+// when the server is local only
+pub fn url(remote: &RemoteDeck) -> String {
+    if !remote.lan {
+        return format!("http://127.0.0.1:{}/?token={}", remote.port, remote.token);
+    }
+    // Connecting a UDP socket sends nothing but picks the outgoing interface
+    let host = UdpSocket::bind("0.0.0.0:0")
+        .and_then(|socket| socket.connect("8.8.8.8:80").map(|_| socket))
+        .and_then(|socket| socket.local_addr())
+        .map(|addr| addr.ip().to_string())
+        .unwrap_or_else(|_| "localhost".to_string());
+    format!("http://{}:{}/?token={}", host, remote.port, remote.token)
+}
+
+fn parse_request(stream: &mut TcpStream) -> Option<Request> {
+    let mut data = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while !data.windows(4).any(|w| w == b"\r\n\r\n") {
+        let read = stream.read(&mut buffer).ok()?;
+        if read == 0 || data.len() > MAX_REQUEST_BYTES {
+            return None;
+        }
+        data.extend_from_slice(&buffer[..read]);
+    }
+    let head = String::from_utf8_lossy(&data);
+    let mut parts = head.lines().next()?.split_whitespace();
+    let method = parts.next()?.to_string();
+    let target = url::Url::parse(&format!("http://localhost{}", parts.next()?)).ok()?;
+    Some(Request {
+        method,
+        path: target.path().to_string(),
+        query: target.query_pairs().into_owned().collect(),
+    })
+}
+
+fn respond(stream: &mut TcpStream, status: &str, content_type: &str, body: &[u8]) {
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    );
+    stream.write_all(head.as_bytes()).and_then(|_| stream.write_all(body)).ok();
+}
+
+fn respond_text(stream: &mut TcpStream, status: &str, text: &str) {
+    respond(stream, status, "text/plain; charset=utf-8", text.as_bytes());
+}
+
+// The page shown on the deck, with script texts applied
+fn current_page(config: &Config) -> HashMap<u8, ButtonConfig> {
+    let mut page = match virtual_page::active().or_else(|| config.pages.get(config.current_page).cloned()) {
+        Some(p) => p,
+        None => return HashMap::new(),
+    };
+    page.apply_default_color();
+    page.buttons
+        .into_iter()
+        .filter_map(|(key, button)| {
+            let key_id = key.parse::<u8>().ok().filter(|k| (1..=15).contains(k))?;
+            let label = scripting::key_text(key_id).unwrap_or_else(|| button.label.clone());
+            Some((key_id, ButtonConfig { label, ..button }))
+        })
+        .collect()
+}
+
+fn encode_jpeg(img: ImageBuffer<Rgb<u8>, Vec<u8>>) -> Vec<u8> {
+    let mut data = Vec::new();
+    DynamicImage::ImageRgb8(img)
+        .write_to(&mut Cursor::new(&mut data), image::ImageFormat::Jpeg)
+        .ok();
+    data
+}
+
+// Render the keys that changed since the last poll, keep the JPEGs and
+// describe the page
+fn state(config: &Config, icons_path: &Path) -> serde_json::Value {
+    let buttons = current_page(config);
+    let previous = IMAGES.read().map(|cache| cache.clone()).unwrap_or_default();
+    let mut images = HashMap::new();
+    let mut hashes = serde_json::Map::new();
+    for key_id in 1..=15u8 {
+        // 0 stands for an empty key
+        let fingerprint = buttons.get(&key_id).map(key_fingerprint).unwrap_or(0);
+        let jpeg = match previous.get(&key_id) {
+            Some((cached, jpeg)) if *cached == fingerprint => jpeg.clone(),
+            _ => encode_jpeg(match buttons.get(&key_id) {
+                Some(button) => render_button_image(button, icons_path),
+                None => ImageBuffer::from_pixel(BUTTON_SIZE, BUTTON_SIZE, Rgb([0, 0, 0])),
+            }),
+        };
+        hashes.insert(key_id.to_string(), format!("{:x}", fingerprint).into());
+        images.insert(key_id, (fingerprint, jpeg));
+    }
+    if let Ok(mut cache) = IMAGES.write() {
+        *cache = images;
+    }
+
+    let name = virtual_page::active()
+        .map(|p| p.name)
+        .or_else(|| config.pages.get(config.current_page).map(|p| p.name.clone()))
+        .unwrap_or_default();
+    serde_json::json!({
+        "page": config.current_page,
+        "name": name,
+        "pages": config.pages.iter().map(|p| p.name.clone()).collect::<Vec<_>>(),
+        "keys": hashes,
+    })
+}
+
+fn handle_connection(mut stream: TcpStream, port: u16, lan: bool, config_path: PathBuf, icons_path: PathBuf) {
+    stream.set_read_timeout(Some(Duration::from_secs(5))).ok();
+    let request = match parse_request(&mut stream) {
+        Some(r) => r,
+        None => return,
+    };
+    let config = match config_store::read(Config::clone) {
+        Some(c) => c,
+        None => return respond_text(&mut stream, "500 Internal Server Error", "config unavailable"),
+    };
+
+    // Disabled or moved to another port or address since this listener started
+    let remote = match config.remote_deck.clone().filter(|r| r.port == port && r.lan == lan) {
+        Some(r) => r,
+        None => return respond_text(&mut stream, "404 Not Found", "remote deck disabled"),
+    };
+    let given = request.query.get("token").map(|t| t.as_str()).unwrap_or_default();
+    if remote.token.is_empty() || !token_matches(given, &remote.token) {
+        return respond_text(&mut stream, "403 Forbidden", "invalid token");
+    }
+
+    let segments: Vec<&str> = request.path.trim_matches('/').split('/').collect();
+    match (request.method.as_str(), segments.as_slice()) {
+        ("GET", [""]) => respond(&mut stream, "200 OK", "text/html; charset=utf-8", PAGE_HTML.as_bytes()),
+        ("GET", ["state"]) => {
+            let body = state(&config, &icons_path).to_string();
+            respond(&mut stream, "200 OK", "application/json", body.as_bytes());
+        }
+        ("GET", ["key", key]) => {
+            let image = key.parse::<u8>().ok().and_then(|k| IMAGES.read().ok()?.get(&k).map(|(_, jpeg)| jpeg.clone()));
+            match image {
+                Some(jpeg) => respond(&mut stream, "200 OK", "image/jpeg", &jpeg),
+                None => respond_text(&mut stream, "404 Not Found", "no such key"),
+            }
+        }
+        ("POST", ["press", key]) => {
+            let key_id = match key.parse::<u8>().ok().filter(|k| (1..=15).contains(k)) {
+                Some(k) => k,
+                None => return respond_text(&mut stream, "400 Bad Request", "invalid key"),
+            };
+            // Keys that need a hold or a second press on the deck need an explicit confirmation here
+            let needs_confirm = button_for_key(key_id).map(|b| b.hold_confirm || b.confirm).unwrap_or(false);
+            if needs_confirm && request.query.get("confirm").map(|c| c.as_str()) != Some("1") {
+                return respond_text(&mut stream, "409 Conflict", "confirm");
+            }
+            debug!("Remote deck pressed key {}", key_id);
+            respond_text(&mut stream, "200 OK", "ok");
+            handle_button_press(key_id, &config_path, &icons_path);
+        }
+        ("POST", ["message", key]) => {
+            let key_id = match key.parse::<u8>().ok().filter(|k| (1..=15).contains(k)) {
+                Some(k) => k,
+                None => return respond_text(&mut stream, "400 Bad Request", "invalid key"),
+            };
+            let text = request.query.get("text").cloned().unwrap_or_default();
+            let duration = request
+                .query
+                .get("ms")
+                .and_then(|ms| ms.parse().ok())
+                .map(Duration::from_millis)
+                .unwrap_or(overlay::DEFAULT_DURATION);
+            overlay::show(key_id, &text, duration);
+            respond_text(&mut stream, "200 OK", "ok");
+        }
+        // The listener owns the deck and loads the page (switch_page)
+        ("POST", ["page", page]) => match page.parse::<usize>() {
+            Ok(index) if switch_page(&config_path, index) => respond_text(&mut stream, "200 OK", "ok"),
+            _ => respond_text(&mut stream, "400 Bad Request", "invalid page"),
+        },
+        _ => respond_text(&mut stream, "404 Not Found", "not found"),
+    }
+}
+
+// Start the server for the configured port (once per port)
+pub fn start(config_path: PathBuf, icons_path: PathBuf) -> Result<(), String> {
+    let remote = match config_store::read(Config::clone).and_then(|c| c.remote_deck) {
+        Some(r) => r,
+        None => return Ok(()),
+    };
+    if remote.token.is_empty() {
+        return Err("Remote deck needs a token".to_string());
+    }
+
+    let (port, lan) = (remote.port, remote.lan);
+    let mut listening = LISTENING.lock().map_err(|e| e.to_string())?;
+    if listening.contains(&(port, lan)) {
+        return Ok(());
+    }
+    // A listener on the other address keeps the port until the app restarts
+    let address = if lan { "0.0.0.0" } else { "127.0.0.1" };
+    let listener = TcpListener::bind((address, port))
+        .map_err(|e| format!("Failed to listen on {}:{}: {}", address, port, e))?;
+    listening.push((port, lan));
+    debug!("Remote deck listening on {}:{}", address, port);
+
+    thread::spawn(move || {
+        for stream in listener.incoming().filter_map(|s| s.ok()) {
+            let (config_path, icons_path) = (config_path.clone(), icons_path.clone());
+            thread::spawn(move || handle_connection(stream, port, lan, config_path, icons_path));
+        }
+    });
+    Ok(())
+}
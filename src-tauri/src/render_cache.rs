@@ -0,0 +1,168 @@
+// ============================================================================
+// Key Rendering (parallel, cached)
+// ============================================================================
+//
+// A page load renders all keys at once on scoped threads, before the first
+// USB write, instead of drawing and sending one key after the other. Keys
+// whose image only depends on the button (no widget, plugin, variable or
+// script text, not flashing) are cached by content, and after a page change
+// the neighbouring pages are rendered in the background so __NEXT_PAGE__ /
+// __PREV_PAGE__ only have to send the images.
+//
+// The same threads also rotate and JPEG-encode each key for the SS-550, so the
+// USB deck (jpeg) finds the bytes ready instead of encoding on the listener
+// thread. The JPEGs of static keys stay cached; those of the other keys are
+// used once.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::sync::Mutex;
+use std::thread;
+
+use image::RgbImage;
+
+use crate::{action_log, config_store, deck, elgato, encode_key_image, is_widget_command, key_image, plugins, scripting, ButtonConfig, Page};
+
+// Images kept; the cache starts over when full
+const MAX_ENTRIES: usize = 256;
+
+lazy_static::lazy_static! {
+    static ref CACHE: Mutex<HashMap<u64, Option<RgbImage>>> = Mutex::new(HashMap::new());
+    // Image hash -> (device JPEG, kept after use)
+    static ref JPEGS: Mutex<HashMap<u64, (Vec<u8>, bool)>> = Mutex::new(HashMap::new());
+}
+
+// Whether the key looks the same every time it is drawn
+fn is_static(key_id: u8, button: &ButtonConfig) -> bool {
+    !is_widget_command(&button.command)
+        && !plugins::is_plugin_command(&button.command)
+        && !elgato::is_sd_command(&button.command)
+        && !button.label.contains('{')
+        && !action_log::is_flashing(&button.command)
+        && scripting::key_text(key_id).is_none()
+}
+
+// Button content plus the icon file's age, so a replaced icon is redrawn
+fn cache_key(button: &ButtonConfig, icons_path: &Path) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    serde_json::to_string(button).unwrap_or_default().hash(&mut hasher);
+    if !button.icon.is_empty() {
+        fs::metadata(icons_path.join(&button.icon))
+            .and_then(|m| m.modified())
+            .ok()
+            .hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+// Pixels plus the settings encode_key_image uses
+fn jpeg_key(image: &RgbImage) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    image.as_raw().hash(&mut hasher);
+    config_store::read(|c| (c.orientation.rotation, c.orientation.mirror, c.jpeg_quality)).hash(&mut hasher);
+    hasher.finish()
+}
+
+// Encode ahead of the USB write; `keep` for static keys
+fn prepare_jpeg(image: &RgbImage, keep: bool) {
+    let key = jpeg_key(image);
+    if JPEGS.lock().map(|jpegs| jpegs.contains_key(&key)).unwrap_or(true) {
+        return;
+    }
+    if let Ok(jpeg) = encode_key_image(image) {
+        if let Ok(mut jpegs) = JPEGS.lock() {
+            if jpegs.len() >= MAX_ENTRIES {
+                jpegs.clear();
+            }
+            jpegs.insert(key, (jpeg, keep));
+        }
+    }
+}
+
+// The device JPEG of a key image, encoded now if no render prepared it
+pub fn jpeg(image: &RgbImage) -> Result<Vec<u8>, String> {
+    let key = jpeg_key(image);
+    if let Ok(mut jpegs) = JPEGS.lock() {
+        match jpegs.get(&key) {
+            Some((jpeg, true)) => return Ok(jpeg.clone()),
+            Some((_, false)) => return Ok(jpegs.remove(&key).map(|(jpeg, _)| jpeg).unwrap_or_default()),
+            None => {}
+        }
+    }
+    encode_key_image(image)
+}
+
+// `send`: the images go to the deck next, so dynamic keys are encoded too
+fn render(key_id: u8, button: &ButtonConfig, icons_path: &Path, send: bool) -> Option<RgbImage> {
+    // The simulator shows the RGB images
+    let encode = !deck::simulator_enabled();
+    if !is_static(key_id, button) {
+        let image = key_image(key_id, button, icons_path)?;
+        if encode && send {
+            prepare_jpeg(&image, false);
+        }
+        return Some(image);
+    }
+    let key = cache_key(button, icons_path);
+    let cached = CACHE.lock().ok().and_then(|cache| cache.get(&key).cloned());
+    let image = match cached {
+        Some(image) => image,
+        None => {
+            let image = key_image(key_id, button, icons_path);
+            if let Ok(mut cache) = CACHE.lock() {
+                if cache.len() >= MAX_ENTRIES {
+                    cache.clear();
+                }
+                cache.insert(key, image.clone());
+            }
+            image
+        }
+    };
+    if let (Some(image), true) = (&image, encode) {
+        prepare_jpeg(image, true);
+    }
+    image
+}
+
+fn render_all(page: &Page, icons_path: &Path, send: bool) -> Vec<(u8, RgbImage)> {
+    thread::scope(|scope| {
+        let handles: Vec<_> = page
+            .buttons
+            .iter()
+            .filter_map(|(key, button)| {
+                let key_id = key.parse::<u8>().ok()?;
+                Some(scope.spawn(move || Some((key_id, render(key_id, button, icons_path, send)?))))
+            })
+            .collect();
+        handles.into_iter().filter_map(|h| h.join().ok().flatten()).collect()
+    })
+}
+
+// All key images of a page (default color already applied), rendered and
+// encoded in parallel
+pub fn render_page(page: &Page, icons_path: &Path) -> Vec<(u8, RgbImage)> {
+    render_all(page, icons_path, true)
+}
+
+// Fill the cache for the pages before and after `current`
+pub fn prerender_around(pages: &[Page], current: usize, icons_path: &Path) {
+    if pages.len() < 2 {
+        return;
+    }
+    let next = (current + 1) % pages.len();
+    let prev = (current + pages.len() - 1) % pages.len();
+    let mut neighbours = vec![pages[next].clone()];
+    if prev != next {
+        neighbours.push(pages[prev].clone());
+    }
+    let icons_path = icons_path.to_path_buf();
+    thread::spawn(move || {
+        for mut page in neighbours {
+            page.apply_default_color();
+            render_all(&page, &icons_path, false);
+        }
+    });
+}
@@ -0,0 +1,251 @@
+// ============================================================================
+// Flatpak Sandbox Support
+// ============================================================================
+//
+// Inside Flatpak the app sees the runtime, not the host: shell actions, the
+// tools behind widgets (playerctl, pactl, ydotool...) and pkexec are not there,
+// and ydotoold's socket is out of reach. When /.flatpak-info exists:
+//   - host_command runs programs on the host through `flatpak-spawn --host`
+//     (needs --talk-name=org.freedesktop.Flatpak in the manifest)
+//   - URLs open through the OpenURI portal and screenshots through the
+//     Screenshot portal, which asks for the region itself
+//   - the Background portal is asked at startup so closing the window keeps
+//     the deck listening
+// Outside Flatpak host_command is a plain Command and nothing else changes.
+//
+// report() lists what works and what is degraded in the sandbox (USB access,
+// host commands, sensors...); the Diagnóstico dialog shows it.
+
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::OnceLock;
+
+use tracing::{debug, warn};
+use zbus::blocking::Connection;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, Value};
+
+use crate::diagnostics::{check, Check};
+
+#[zbus::proxy(
+    interface = "org.freedesktop.portal.OpenURI",
+    default_service = "org.freedesktop.portal.Desktop",
+    default_path = "/org/freedesktop/portal/desktop"
+)]
+trait OpenUri {
+    #[zbus(name = "OpenURI")]
+    fn open_uri(&self, parent_window: &str, uri: &str, options: HashMap<&str, Value<'_>>) -> zbus::Result<OwnedObjectPath>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.portal.Screenshot",
+    default_service = "org.freedesktop.portal.Desktop",
+    default_path = "/org/freedesktop/portal/desktop"
+)]
+pub trait Screenshot {
+    fn screenshot(&self, parent_window: &str, options: HashMap<&str, Value<'_>>) -> zbus::Result<OwnedObjectPath>;
+    fn pick_color(&self, parent_window: &str, options: HashMap<&str, Value<'_>>) -> zbus::Result<OwnedObjectPath>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.portal.Background",
+    default_service = "org.freedesktop.portal.Desktop",
+    default_path = "/org/freedesktop/portal/desktop"
+)]
+trait Background {
+    fn request_background(&self, parent_window: &str, options: HashMap<&str, Value<'_>>) -> zbus::Result<OwnedObjectPath>;
+}
+
+#[zbus::proxy(interface = "org.freedesktop.portal.Request", default_service = "org.freedesktop.portal.Desktop")]
+trait Request {
+    #[zbus(signal)]
+    fn response(&self, response: u32, results: HashMap<String, OwnedValue>) -> zbus::Result<()>;
+}
+
+// Makes each request's handle_token unique
+static NEXT_TOKEN: AtomicU32 = AtomicU32::new(0);
+
+pub fn is_flatpak() -> bool {
+    static FLATPAK: OnceLock<bool> = OnceLock::new();
+    *FLATPAK.get_or_init(|| Path::new("/.flatpak-info").exists())
+}
+
+// `flatpak-spawn --host` works (the manifest lets us talk to the Flatpak portal)
+fn host_available() -> bool {
+    static AVAILABLE: OnceLock<bool> = OnceLock::new();
+    *AVAILABLE.get_or_init(|| {
+        Command::new("flatpak-spawn")
+            .args(["--host", "true"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map(|s| s.success())
+            .unwrap_or(false)
+    })
+}
+
+// `program` on the host when sandboxed, otherwise a plain Command. Variables
+// and a directory set on the returned Command don't reach a host process; use
+// host_command_with for those.
+pub fn host_command(program: impl AsRef<OsStr>) -> Command {
+    host_command_with(program, None, std::iter::empty::<(&str, &str)>())
+}
+
+pub fn host_command_with<K, V>(
+    program: impl AsRef<OsStr>,
+    dir: Option<&Path>,
+    env: impl IntoIterator<Item = (K, V)>,
+) -> Command
+where
+    K: AsRef<str>,
+    V: AsRef<str>,
+{
+    if !is_flatpak() {
+        let mut command = Command::new(program);
+        if let Some(dir) = dir {
+            command.current_dir(dir);
+        }
+        command.envs(env.into_iter().map(|(name, value)| (name.as_ref().to_string(), value.as_ref().to_string())));
+        return command;
+    }
+    let mut command = Command::new("flatpak-spawn");
+    command.arg("--host");
+    if let Some(dir) = dir {
+        command.arg(format!("--directory={}", dir.display()));
+    }
+    for (name, value) in env {
+        command.arg(format!("--env={}={}", name.as_ref(), value.as_ref()));
+    }
+    command.arg(program);
+    command
+}
+
+// Call a portal method that answers through a Request object. The Response is
+// subscribed to before calling, as the portal docs advise. Ok(None) if the
+// user cancelled.
+pub fn portal_request<'a, F>(
+    conn: &Connection,
+    mut options: HashMap<&'a str, Value<'a>>,
+    call: F,
+) -> Result<Option<HashMap<String, OwnedValue>>, String>
+where
+    F: FnOnce(HashMap<&'a str, Value<'a>>) -> zbus::Result<OwnedObjectPath>,
+{
+    let token = format!("streamdeck{}_{}", std::process::id(), NEXT_TOKEN.fetch_add(1, Ordering::SeqCst));
+    let sender = conn
+        .unique_name()
+        .map(|n| n.trim_start_matches(':').replace('.', "_"))
+        .unwrap_or_default();
+    let request_path = format!("/org/freedesktop/portal/desktop/request/{}/{}", sender, token);
+    let request = RequestProxyBlocking::builder(conn)
+        .path(request_path)
+        .and_then(|b| b.build())
+        .map_err(|e| format!("Portal request failed: {}", e))?;
+    let mut responses = request
+        .receive_response()
+        .map_err(|e| format!("Portal request failed: {}", e))?;
+
+    options.insert("handle_token", Value::from(token));
+    call(options).map_err(|e| format!("Portal call failed: {}", e))?;
+
+    let signal = responses.next().ok_or("Portal closed without a response")?;
+    let args = signal.args().map_err(|e| format!("Invalid portal response: {}", e))?;
+    if args.response != 0 {
+        return Ok(None);
+    }
+    Ok(Some(args.results))
+}
+
+fn session() -> Result<Connection, String> {
+    Connection::session().map_err(|e| format!("D-Bus connection failed: {}", e))
+}
+
+// Open a URL in the default browser: the OpenURI portal in Flatpak, xdg-open
+// otherwise
+pub fn open_uri(url: &str) -> Result<(), String> {
+    if !is_flatpak() {
+        Command::new("xdg-open")
+            .arg(url)
+            .spawn()
+            .map_err(|e| format!("Failed to open URL: {}", e))?;
+        return Ok(());
+    }
+    let conn = session()?;
+    let portal = OpenUriProxyBlocking::new(&conn).map_err(|e| format!("Portal not available: {}", e))?;
+    portal
+        .open_uri("", url, HashMap::new())
+        .map_err(|e| format!("Failed to open URL: {}", e))?;
+    Ok(())
+}
+
+// Screenshot through the portal; `interactive` lets the user pick the area.
+// The file the portal saved, None if cancelled.
+pub fn portal_screenshot(interactive: bool) -> Result<Option<PathBuf>, String> {
+    let conn = session()?;
+    let portal = ScreenshotProxyBlocking::new(&conn).map_err(|e| format!("Portal not available: {}", e))?;
+    let options = HashMap::from([("interactive", Value::from(interactive))]);
+    let results = match portal_request(&conn, options, |options| portal.screenshot("", options))? {
+        Some(results) => results,
+        None => return Ok(None),
+    };
+    let uri = results
+        .get("uri")
+        .and_then(|v| String::try_from(v.try_clone().ok()?).ok())
+        .ok_or("Screenshot portal returned no file")?;
+    url::Url::parse(&uri)
+        .ok()
+        .and_then(|u| u.to_file_path().ok())
+        .map(Some)
+        .ok_or_else(|| format!("Screenshot portal returned {}", uri))
+}
+
+// Keep running with the window closed; called once at startup in Flatpak
+pub fn request_background() {
+    let result = session().and_then(|conn| {
+        let portal = BackgroundProxyBlocking::new(&conn).map_err(|e| format!("Portal not available: {}", e))?;
+        let options = HashMap::from([(
+            "reason",
+            Value::from("Keep listening to the stream deck while the window is closed"),
+        )]);
+        portal_request(&conn, options, |options| portal.request_background("", options))
+    });
+    match result {
+        Ok(Some(results)) => debug!("Background portal: {:?}", results.get("background")),
+        Ok(None) => warn!("Running in the background was not allowed"),
+        Err(e) => warn!("{}", e),
+    }
+}
+
+// What works in the sandbox; empty outside Flatpak
+pub fn report() -> Vec<Check> {
+    if !is_flatpak() {
+        return Vec::new();
+    }
+    let host = host_available();
+    vec![
+        check("flatpak", true, "Running in Flatpak"),
+        if host {
+            check("host commands", true, "Shell actions and tools run on the host (flatpak-spawn)")
+        } else {
+            check(
+                "host commands",
+                false,
+                "flatpak-spawn --host is not allowed (--talk-name=org.freedesktop.Flatpak); shell actions, ydotool and pkexec are unavailable",
+            )
+        },
+        if Path::new("/dev/bus/usb").exists() {
+            check("usb access", true, "/dev/bus/usb is visible")
+        } else {
+            check("usb access", false, "The deck cannot be opened without --device=all")
+        },
+        check("urls", true, "Opened through the OpenURI portal"),
+        check("screenshots", true, "Screenshot portal (the portal dialog picks the region)"),
+        if Path::new("/sys/class/thermal").exists() {
+            check("sensors", true, "/sys is readable")
+        } else {
+            check("sensors", false, "/sys is not visible; CPU temperature and backlight are unavailable")
+        },
+    ]
+}
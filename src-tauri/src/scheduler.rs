@@ -0,0 +1,219 @@
+// ============================================================================
+// Scheduled Actions
+// ============================================================================
+//
+// Jobs in "schedule" (config.json) run a command without a key press, through
+// the same dispatcher as the keys, either daily at a time or every N minutes:
+//
+//   "schedule": [
+//     { "name": "backup", "command": "~/bin/backup.sh", "at": "03:00" },
+//     { "name": "reminder", "command": "__TWITCH_CHAT_Follow!", "everyMinutes": 30, "whileStreaming": true },
+//     { "name": "standup", "command": "__URL_https://meet.example.com", "at": "09:55", "days": [1, 2, 3, 4, 5] }
+//   ]
+//
+// "days" limits a job to some weekdays (0 = Sunday ... 6 = Saturday),
+// "whileStreaming" to while OBS is streaming, and "enabled": false pauses it.
+// Interval jobs first run one interval after the app starts (or after being
+// enabled).
+//
+// Commands: __SCHEDULE_ON_<name>__, __SCHEDULE_OFF_<name>__ and
+// __SCHEDULE_TOGGLE_<name>__ enable and disable a job from a key.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::{Datelike, Local, NaiveDate, NaiveTime, Timelike};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::action::Action;
+use crate::{config_store, execute_action, obs_request, ButtonConfig};
+
+const TICK: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledJob {
+    pub name: String,
+    pub command: String,
+    // Structured form of `command`, as on keys
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<Action>,
+    // Daily at "HH:MM"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub at: Option<String>,
+    #[serde(default, rename = "everyMinutes", skip_serializing_if = "Option::is_none")]
+    pub every_minutes: Option<u32>,
+    // Weekdays it may run on (0 = Sunday); every day when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub days: Vec<u8>,
+    #[serde(default, rename = "whileStreaming", skip_serializing_if = "std::ops::Not::not")]
+    pub while_streaming: bool,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl ScheduledJob {
+    fn resolved_action(&self) -> Action {
+        ButtonConfig {
+            label: String::new(),
+            command: self.command.clone(),
+            color: String::new(),
+            icon: String::new(),
+            action: self.action.clone(),
+            hold_confirm: false,
+            confirm: false,
+            sound: None,
+        }
+        .resolved_action()
+    }
+
+    fn time(&self) -> Option<NaiveTime> {
+        self.at.as_deref().and_then(|at| NaiveTime::parse_from_str(at.trim(), "%H:%M").ok())
+    }
+}
+
+// When each job last ran, kept by the scheduler thread
+#[derive(Default)]
+struct JobState {
+    // Interval jobs: last run, or when the job was first seen
+    since: Option<Instant>,
+    // Daily jobs: the day they last ran
+    ran_on: Option<NaiveDate>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Toggle {
+    On,
+    Off,
+    Flip,
+}
+
+fn parse_command(cmd: &str) -> Option<(Toggle, &str)> {
+    let rest = cmd.strip_prefix("__SCHEDULE_")?.strip_suffix("__")?;
+    let (toggle, name) = if let Some(name) = rest.strip_prefix("ON_") {
+        (Toggle::On, name)
+    } else if let Some(name) = rest.strip_prefix("OFF_") {
+        (Toggle::Off, name)
+    } else {
+        (Toggle::Flip, rest.strip_prefix("TOGGLE_")?)
+    };
+    Some((toggle, name)).filter(|(_, name)| !name.is_empty())
+}
+
+pub fn is_schedule_command(cmd: &str) -> bool {
+    parse_command(cmd).is_some()
+}
+
+fn set_enabled(config_path: &Path, name: &str, toggle: Toggle) -> Result<bool, String> {
+    config_store::update(config_path, |config| {
+        let job = config
+            .schedule
+            .iter_mut()
+            .find(|j| j.name == name)
+            .ok_or_else(|| format!("No scheduled job named '{}'", name))?;
+        job.enabled = match toggle {
+            Toggle::On => true,
+            Toggle::Off => false,
+            Toggle::Flip => !job.enabled,
+        };
+        debug!("Scheduled job '{}' {}", name, if job.enabled { "enabled" } else { "disabled" });
+        Ok(job.enabled)
+    })
+    .ok_or("Config is not loaded")?
+}
+
+pub fn execute_command(cmd: &str, config_path: &Path) -> Result<(), String> {
+    let (toggle, name) = parse_command(cmd).ok_or_else(|| format!("Invalid schedule command: {}", cmd))?;
+    set_enabled(config_path, name, toggle).map(|_| ())
+}
+
+// Enable or disable a job (set_scheduled_job_enabled)
+pub fn enable(config_path: &Path, name: &str, enabled: bool) -> Result<(), String> {
+    set_enabled(config_path, name, if enabled { Toggle::On } else { Toggle::Off }).map(|_| ())
+}
+
+fn streaming() -> bool {
+    obs_request("GetStreamStatus", None)
+        .ok()
+        .and_then(|data| data.get("outputActive").and_then(|v| v.as_bool()))
+        .unwrap_or(false)
+}
+
+fn run(job: &ScheduledJob, config_path: &Path, icons_path: &Path) {
+    debug!("Running scheduled job '{}': {}", job.name, job.command);
+    let action = job.resolved_action();
+    let (name, config_path, icons_path) = (job.name.clone(), config_path.to_path_buf(), icons_path.to_path_buf());
+    // OBS and Twitch actions block; never hold up the other jobs
+    thread::spawn(move || {
+        if let Err(e) = execute_action(&action, &config_path, &icons_path) {
+            warn!("Scheduled job '{}' failed: {}", name, e);
+        }
+    });
+}
+
+// Run a job now, whatever its schedule (run_scheduled_job)
+pub fn run_now(config_path: &Path, icons_path: &Path, name: &str) -> Result<(), String> {
+    let job = config_store::read(|c| c.schedule.iter().find(|j| j.name == name).cloned())
+        .flatten()
+        .ok_or_else(|| format!("No scheduled job named '{}'", name))?;
+    run(&job, config_path, icons_path);
+    Ok(())
+}
+
+// Whether the job is due now; updates its state when it is
+fn due(job: &ScheduledJob, state: &mut JobState) -> bool {
+    let now = Local::now();
+    if !job.days.is_empty() && !job.days.contains(&(now.weekday().num_days_from_sunday() as u8)) {
+        return false;
+    }
+    if let Some(time) = job.time() {
+        let today = now.date_naive();
+        let reached = (now.hour(), now.minute()) >= (time.hour(), time.minute());
+        // Started after the time: wait for tomorrow instead of running late
+        let ran_on = *state.ran_on.get_or_insert(if reached { today } else { today.pred_opt().unwrap_or(today) });
+        if reached && ran_on != today {
+            state.ran_on = Some(today);
+            return true;
+        }
+        return false;
+    }
+    if let Some(minutes) = job.every_minutes.filter(|m| *m > 0) {
+        let since = *state.since.get_or_insert_with(Instant::now);
+        if since.elapsed() >= Duration::from_secs(minutes as u64 * 60) {
+            state.since = Some(Instant::now());
+            return true;
+        }
+    }
+    false
+}
+
+// Start the scheduler thread; called once at startup
+pub fn start(config_path: PathBuf, icons_path: PathBuf) {
+    thread::spawn(move || {
+        let mut states: HashMap<String, JobState> = HashMap::new();
+        loop {
+            thread::sleep(TICK);
+            let jobs = config_store::read(|c| c.schedule.clone()).unwrap_or_default();
+            // Jobs that were removed or disabled start over when they come back
+            states.retain(|name, _| jobs.iter().any(|j| &j.name == name && j.enabled));
+
+            for job in jobs.iter().filter(|j| j.enabled) {
+                let state = states.entry(job.name.clone()).or_default();
+                if !due(job, state) {
+                    continue;
+                }
+                if job.while_streaming && !streaming() {
+                    debug!("Skipping scheduled job '{}': not streaming", job.name);
+                    continue;
+                }
+                run(job, &config_path, &icons_path);
+            }
+        }
+    });
+}
@@ -0,0 +1,258 @@
+// ============================================================================
+// Screenshots and Screen Recording
+// ============================================================================
+//
+// Command formats:
+//   __SCREENSHOT_region__  - select a region and save it
+//   __SCREENSHOT_full__    - save the whole screen
+//   __SCREENREC_TOGGLE__   - start/stop recording the screen; as a widget it
+//                            shows the recording time on a red key
+//
+// Wayland (wlroots compositors) uses grim, slurp and wf-recorder; X11 uses maim
+// and ffmpeg; GNOME/KDE Wayland and Flatpak take screenshots through the
+// Screenshot portal (see session.rs).
+// Screenshots go to <Pictures>/Screenshots and recordings to
+// <Videos>/Screencasts, named by date and time.
+
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Instant;
+
+use chrono::Local;
+use tracing::{debug, warn};
+
+use crate::session::{self, Compositor};
+use crate::{request_refresh, sandbox};
+
+pub const RECORD_COMMAND: &str = "__SCREENREC_TOGGLE__";
+
+const RECORDING_COLOR: &str = "#c0392b";
+
+struct Recording {
+    child: Child,
+    started: Instant,
+    path: PathBuf,
+}
+
+lazy_static::lazy_static! {
+    static ref RECORDING: Mutex<Option<Recording>> = Mutex::new(None);
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ScreenshotMethod {
+    Portal,
+    Grim,
+    Maim,
+}
+
+impl ScreenshotMethod {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ScreenshotMethod::Portal => "portal",
+            ScreenshotMethod::Grim => "grim",
+            ScreenshotMethod::Maim => "maim",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Area {
+    Region,
+    Full,
+}
+
+fn parse_screenshot(cmd: &str) -> Option<Area> {
+    match cmd.strip_prefix("__SCREENSHOT_")?.strip_suffix("__")? {
+        "region" => Some(Area::Region),
+        "full" => Some(Area::Full),
+        _ => None,
+    }
+}
+
+pub fn is_screen_command(cmd: &str) -> bool {
+    cmd == RECORD_COMMAND || parse_screenshot(cmd).is_some()
+}
+
+pub fn is_recording() -> bool {
+    RECORDING.lock().map(|r| r.is_some()).unwrap_or(false)
+}
+
+// XDG user directory (PICTURES, VIDEOS), falling back to ~/<fallback>
+fn user_dir(name: &str, fallback: &str) -> PathBuf {
+    let configured = sandbox::host_command("xdg-user-dir")
+        .arg(name)
+        .output()
+        .ok()
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .filter(|dir| !dir.is_empty());
+    match configured {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(std::env::var("HOME").unwrap_or_default()).join(fallback),
+    }
+}
+
+// New timestamped file in <user dir>/<subdir>
+fn output_path(dir: PathBuf, prefix: &str, extension: &str) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let stamp = Local::now().format("%Y-%m-%d_%H-%M-%S");
+    Ok(dir.join(format!("{}_{}.{}", prefix, stamp, extension)))
+}
+
+fn run(program: &str, args: &[&str]) -> Result<std::process::Output, String> {
+    sandbox::host_command(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run {} (is it installed?): {}", program, e))
+}
+
+// grim only works on wlroots compositors; GNOME and KDE need the portal
+pub fn screenshot_method() -> ScreenshotMethod {
+    if sandbox::is_flatpak() {
+        return ScreenshotMethod::Portal;
+    }
+    if !session::is_wayland() {
+        return ScreenshotMethod::Maim;
+    }
+    match session::compositor() {
+        Compositor::Gnome | Compositor::Kde if session::portal_available() => ScreenshotMethod::Portal,
+        _ => ScreenshotMethod::Grim,
+    }
+}
+
+// Region picked with slurp, None if the selection was cancelled
+fn select_region() -> Result<Option<String>, String> {
+    let output = run("slurp", &[])?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+    Ok(Some(String::from_utf8_lossy(&output.stdout).trim().to_string()))
+}
+
+fn screenshot(area: Area) -> Result<(), String> {
+    let path = output_path(user_dir("PICTURES", "Pictures").join("Screenshots"), "Screenshot", "png")?;
+    let file = path.to_string_lossy().to_string();
+
+    let method = screenshot_method();
+    if method == ScreenshotMethod::Portal {
+        // The portal's own dialog picks the region
+        let saved = match sandbox::portal_screenshot(area == Area::Region)? {
+            Some(saved) => saved,
+            None => return Ok(()),
+        };
+        std::fs::rename(&saved, &path)
+            .or_else(|_| std::fs::copy(&saved, &path).map(|_| ()))
+            .map_err(|e| format!("Failed to save screenshot: {}", e))?;
+        debug!("Screenshot saved to {}", path.display());
+        return Ok(());
+    }
+
+    let output = if method == ScreenshotMethod::Grim {
+        match area {
+            Area::Full => run("grim", &[&file])?,
+            Area::Region => match select_region()? {
+                Some(geometry) => run("grim", &["-g", &geometry, &file])?,
+                None => return Ok(()),
+            },
+        }
+    } else {
+        match area {
+            Area::Full => run("maim", &[&file])?,
+            Area::Region => run("maim", &["-s", &file])?,
+        }
+    };
+
+    if !output.status.success() {
+        // maim -s exits non-zero when the selection is cancelled
+        if area == Area::Region && !path.exists() {
+            return Ok(());
+        }
+        return Err(format!("Screenshot failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    debug!("Screenshot saved to {}", path.display());
+    Ok(())
+}
+
+fn start_recording() -> Result<Recording, String> {
+    let path = output_path(user_dir("VIDEOS", "Videos").join("Screencasts"), "Screencast", "mp4")?;
+    let mut process = if session::is_wayland() {
+        let mut process = sandbox::host_command("wf-recorder");
+        process.arg("-f").arg(&path);
+        process
+    } else {
+        let display = std::env::var("DISPLAY").unwrap_or_else(|_| ":0".to_string());
+        let mut process = sandbox::host_command("ffmpeg");
+        process
+            .args(["-loglevel", "error", "-f", "x11grab", "-framerate", "30", "-i", &display])
+            .arg(&path);
+        process
+    };
+    let child = process
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to start screen recording (is wf-recorder/ffmpeg installed?): {}", e))?;
+    debug!("Recording screen to {}", path.display());
+    Ok(Recording { child, started: Instant::now(), path })
+}
+
+// SIGINT lets the recorder finish writing the file
+fn stop_recording(mut recording: Recording) {
+    Command::new("kill")
+        .args(["-INT", &recording.child.id().to_string()])
+        .status()
+        .ok();
+    thread::spawn(move || {
+        recording.child.wait().ok();
+        debug!("Recording saved to {}", recording.path.display());
+    });
+}
+
+fn toggle_recording() -> Result<(), String> {
+    let mut current = RECORDING.lock().map_err(|e| e.to_string())?;
+    // The recorder may have exited on its own (e.g. output unplugged)
+    if let Some(recording) = current.as_mut() {
+        if let Ok(Some(_)) = recording.child.try_wait() {
+            *current = None;
+        }
+    }
+    match current.take() {
+        Some(recording) => stop_recording(recording),
+        None => *current = Some(start_recording()?),
+    }
+    drop(current);
+    request_refresh();
+    Ok(())
+}
+
+pub fn execute_command(cmd: &str) -> Result<(), String> {
+    if cmd == RECORD_COMMAND {
+        return toggle_recording();
+    }
+    let area = parse_screenshot(cmd).ok_or_else(|| format!("Invalid screenshot command: {}", cmd))?;
+    // slurp/maim -s wait for the user, so don't block the button listener
+    thread::spawn(move || {
+        if let Err(e) = screenshot(area) {
+            warn!("{}", e);
+        }
+    });
+    Ok(())
+}
+
+// "● mm:ss" while recording, "REC" otherwise
+pub fn get_widget_text() -> String {
+    let elapsed = RECORDING
+        .lock()
+        .ok()
+        .and_then(|r| r.as_ref().map(|r| r.started.elapsed().as_secs()));
+    match elapsed {
+        Some(secs) => format!("● {:02}:{:02}", secs / 60, secs % 60),
+        None => "REC".to_string(),
+    }
+}
+
+pub fn get_widget_color() -> Option<String> {
+    is_recording().then(|| RECORDING_COLOR.to_string())
+}
@@ -0,0 +1,145 @@
+// ============================================================================
+// Screensaver Clock
+// ============================================================================
+//
+// After "screensaverMinutes" (config.json) without a key press the deck shows
+// one large clock across all 15 keys, with the date and CPU temperature in
+// the top corners. Any press wakes it back to the page (that press does
+// nothing else). 0 or missing turns it off.
+//
+// The picture is drawn on one canvas the size of the whole key grid and cut
+// into key images by slice(). The listener owns the Screensaver; while it is
+// shown, page refreshes wait and widget updates are dropped, as with alerts.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ab_glyph::{FontRef, PxScale};
+use image::{imageops, ImageBuffer, Rgb, RgbImage};
+use imageproc::drawing::{draw_text_mut, text_size};
+use tracing::debug;
+
+use crate::deck::Deck;
+use crate::{
+    config_store, get_widget_clock, get_widget_date, get_widget_temp, lock_screen, request_refresh, BUTTON_SIZE,
+    FONT_DATA,
+};
+
+const COLUMNS: u32 = 5;
+const ROWS: u32 = 3;
+const CLOCK_SCALE: f32 = 150.0;
+const CORNER_SCALE: f32 = 26.0;
+const CORNER_MARGIN: i32 = 10;
+const CLOCK_COLOR: Rgb<u8> = Rgb([220, 220, 220]);
+const CORNER_COLOR: Rgb<u8> = Rgb([120, 120, 120]);
+
+// Unix seconds of the last key press (or of startup)
+static LAST_ACTIVITY: AtomicU64 = AtomicU64::new(0);
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+// A key was pressed; restarts the idle countdown
+pub fn activity() {
+    LAST_ACTIVITY.store(now(), Ordering::SeqCst);
+}
+
+fn idle_secs() -> u64 {
+    let last = LAST_ACTIVITY.load(Ordering::SeqCst);
+    if last == 0 {
+        activity();
+        return 0;
+    }
+    now().saturating_sub(last)
+}
+
+// Cut a COLUMNS x ROWS key canvas into key images (key 1 top left)
+pub fn slice(canvas: &RgbImage) -> Vec<(u8, RgbImage)> {
+    let mut keys = Vec::new();
+    for row in 0..ROWS {
+        for column in 0..COLUMNS {
+            let key_id = (row * COLUMNS + column + 1) as u8;
+            let image = imageops::crop_imm(canvas, column * BUTTON_SIZE, row * BUTTON_SIZE, BUTTON_SIZE, BUTTON_SIZE);
+            keys.push((key_id, image.to_image()));
+        }
+    }
+    keys
+}
+
+fn draw(clock: &str) -> RgbImage {
+    let (width, height) = (COLUMNS * BUTTON_SIZE, ROWS * BUTTON_SIZE);
+    let mut canvas: RgbImage = ImageBuffer::from_pixel(width, height, Rgb([0, 0, 0]));
+    let font = match FontRef::try_from_slice(FONT_DATA) {
+        Ok(f) => f,
+        Err(_) => return canvas,
+    };
+
+    let scale = PxScale::from(CLOCK_SCALE);
+    let (text_width, text_height) = text_size(scale, &font, clock);
+    let x = (width as i32 - text_width as i32) / 2;
+    // A little below the middle, clear of the corners
+    let y = (height as i32 - text_height as i32) / 2 + CORNER_MARGIN * 2;
+    draw_text_mut(&mut canvas, CLOCK_COLOR, x, y, scale, &font, clock);
+
+    let corner = PxScale::from(CORNER_SCALE);
+    draw_text_mut(&mut canvas, CORNER_COLOR, CORNER_MARGIN, CORNER_MARGIN, corner, &font, &get_widget_date());
+    let temp = get_widget_temp();
+    let (temp_width, _) = text_size(corner, &font, &temp);
+    let temp_x = width as i32 - temp_width as i32 - CORNER_MARGIN;
+    draw_text_mut(&mut canvas, CORNER_COLOR, temp_x, CORNER_MARGIN, corner, &font, &temp);
+    canvas
+}
+
+// The idle clock; owned by the listener
+#[derive(Default)]
+pub struct Screensaver {
+    // Time shown, while the screensaver is on
+    shown: Option<String>,
+}
+
+impl Screensaver {
+    pub fn active(&self) -> bool {
+        self.shown.is_some()
+    }
+
+    // Start when idle long enough, redraw when the minute changes; true while on
+    pub fn step(&mut self, deck: &dyn Deck) -> bool {
+        // The lock screen takes over
+        if lock_screen::locked() {
+            self.wake();
+            return false;
+        }
+        if !self.active() {
+            let minutes = config_store::read(|c| c.screensaver_minutes).unwrap_or(0);
+            if minutes == 0 || idle_secs() < minutes as u64 * 60 {
+                return false;
+            }
+            debug!("Idle for {} minutes, starting the screensaver", minutes);
+        }
+        let clock = get_widget_clock();
+        if self.shown.as_deref() != Some(clock.as_str()) {
+            for (key_id, image) in slice(&draw(&clock)) {
+                deck.set_key_image(key_id, &image).ok();
+            }
+            self.shown = Some(clock);
+        }
+        true
+    }
+
+    // Draw everything again on the next step (something covered the clock)
+    pub fn invalidate(&mut self) {
+        if let Some(shown) = self.shown.as_mut() {
+            shown.clear();
+        }
+    }
+
+    // Back to the page
+    pub fn wake(&mut self) {
+        activity();
+        if self.shown.take().is_some() {
+            debug!("Screensaver woken");
+            request_refresh();
+        }
+    }
+}
@@ -0,0 +1,178 @@
+// ============================================================================
+// Button Scripts (Rhai)
+// ============================================================================
+//
+// __SCRIPT_<name>__ runs <app data>/scripts/<name>.rhai. Scripts can use:
+//   run(cmd)                 - run a shell command, returns its trimmed stdout
+//   action(cmd)              - run a deck command (e.g. "__OBS_SCENE_Game")
+//   obs::request(type[, data]) - OBS WebSocket request, returns the response data
+//   set_key_text(key, text)  - replace a key's text until the page changes
+//   get_state(name)          - "page", "page_name", "brightness", "obs_connected",
+//                              "obs_streaming", "obs_recording", "timer_running"
+//   set_var(name, value) / get_var(name) - global ${name} variables
+//   sleep(ms)
+//   print(value)             - appears in the log / test_action output
+//
+// Example:
+//   if get_state("obs_streaming") { action("__OBS_SCENE_BRB") } else { action("__OBS_STREAM__") }
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::sync::RwLock;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rhai::{Dynamic, Engine, EvalAltResult, Map, Module};
+use tracing::debug;
+
+use crate::action::Action;
+use crate::{execute_action, get_app_state_value, obs_request, request_refresh, sandbox, variables};
+
+const SCRIPT_EXTENSION: &str = "rhai";
+// Scripts running longer than this are stopped
+const SCRIPT_TIMEOUT: Duration = Duration::from_secs(60);
+
+lazy_static::lazy_static! {
+    // Text set with set_key_text, by key id
+    static ref KEY_TEXT: RwLock<HashMap<u8, String>> = RwLock::new(HashMap::new());
+}
+
+pub fn scripts_dir(config_path: &Path) -> PathBuf {
+    config_path.with_file_name("scripts")
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains("__") && name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+}
+
+pub fn script_path(config_path: &Path, name: &str) -> Result<PathBuf, String> {
+    if !is_valid_name(name) {
+        return Err(format!("Invalid script name: {}", name));
+    }
+    Ok(scripts_dir(config_path).join(format!("{}.{}", name, SCRIPT_EXTENSION)))
+}
+
+pub fn list(config_path: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(scripts_dir(config_path))
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.extension().and_then(|e| e.to_str()) == Some(SCRIPT_EXTENSION))
+                .filter_map(|p| p.file_stem().and_then(|s| s.to_str()).map(|s| s.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+pub fn read(config_path: &Path, name: &str) -> Result<String, String> {
+    let path = script_path(config_path, name)?;
+    fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+}
+
+// Syntax check without running anything
+pub fn check(source: &str) -> Result<(), String> {
+    Engine::new().compile(source).map(|_| ()).map_err(|e| e.to_string())
+}
+
+pub fn save(config_path: &Path, name: &str, source: &str) -> Result<(), String> {
+    check(source)?;
+    let path = script_path(config_path, name)?;
+    fs::create_dir_all(scripts_dir(config_path)).map_err(|e| e.to_string())?;
+    fs::write(&path, source).map_err(|e| format!("Failed to save script: {}", e))
+}
+
+pub fn key_text(key_id: u8) -> Option<String> {
+    KEY_TEXT.read().ok()?.get(&key_id).cloned()
+}
+
+// Called when the page changes
+pub fn clear_key_text() {
+    if let Ok(mut texts) = KEY_TEXT.write() {
+        texts.clear();
+    }
+}
+
+fn json_to_dynamic(value: serde_json::Value) -> Result<Dynamic, Box<EvalAltResult>> {
+    rhai::serde::to_dynamic(value)
+}
+
+fn build_engine(config_path: &Path, icons_path: &Path, output: Rc<RefCell<String>>) -> Engine {
+    let mut engine = Engine::new();
+
+    let started = Instant::now();
+    engine.on_progress(move |_| {
+        if started.elapsed() > SCRIPT_TIMEOUT {
+            Some(format!("Script timed out after {}s", SCRIPT_TIMEOUT.as_secs()).into())
+        } else {
+            None
+        }
+    });
+    engine.on_print(move |text| {
+        debug!("[script] {}", text);
+        let mut output = output.borrow_mut();
+        output.push_str(text);
+        output.push('\n');
+    });
+
+    engine.register_fn("run", |cmd: &str| -> Result<String, Box<EvalAltResult>> {
+        let out = sandbox::host_command("sh")
+            .arg("-c")
+            .arg(cmd)
+            .output()
+            .map_err(|e| format!("Failed to run command: {}", e))?;
+        Ok(String::from_utf8_lossy(&out.stdout).trim().to_string())
+    });
+
+    let (cfg, icons) = (config_path.to_path_buf(), icons_path.to_path_buf());
+    engine.register_fn("action", move |cmd: &str| -> Result<(), Box<EvalAltResult>> {
+        execute_action(&Action::parse(cmd), &cfg, &icons).map_err(|e| e.into())
+    });
+
+    engine.register_fn("set_key_text", |key: i64, text: &str| {
+        if let (Ok(key), Ok(mut texts)) = (u8::try_from(key), KEY_TEXT.write()) {
+            texts.insert(key, text.to_string());
+        }
+        request_refresh();
+    });
+
+    engine.register_fn("get_state", |name: &str| -> Result<Dynamic, Box<EvalAltResult>> {
+        json_to_dynamic(get_app_state_value(name))
+    });
+
+    engine.register_fn("set_var", |name: &str, value: Dynamic| -> Result<(), Box<EvalAltResult>> {
+        variables::set(name, &value.to_string()).map_err(|e| e.into())
+    });
+    engine.register_fn("get_var", |name: &str| variables::get(name).unwrap_or_default());
+
+    engine.register_fn("sleep", |ms: i64| thread::sleep(Duration::from_millis(ms.max(0) as u64)));
+
+    let mut obs = Module::new();
+    obs.set_native_fn("request", |request_type: &str| -> Result<Dynamic, Box<EvalAltResult>> {
+        json_to_dynamic(obs_request(request_type, None)?)
+    });
+    obs.set_native_fn("request", |request_type: &str, data: Map| -> Result<Dynamic, Box<EvalAltResult>> {
+        let data: serde_json::Value = rhai::serde::from_dynamic(&data.into())?;
+        json_to_dynamic(obs_request(request_type, Some(data))?)
+    });
+    engine.register_static_module("obs", obs.into());
+
+    engine
+}
+
+// Run a script by name (blocking), returning what it printed
+pub fn run(name: &str, config_path: &Path, icons_path: &Path) -> Result<String, String> {
+    let source = read(config_path, name)?;
+    let output = Rc::new(RefCell::new(String::new()));
+    let engine = build_engine(config_path, icons_path, output.clone());
+
+    debug!("Running script {}", name);
+    let result = engine.run(&source).map_err(|e| format!("Script {} failed: {}", name, e));
+    let printed = output.borrow().clone();
+    result.map(|_| printed)
+}
@@ -0,0 +1,140 @@
+// ============================================================================
+// Desktop Session Detection
+// ============================================================================
+//
+// Which session we run in decides how several features work:
+//   input backend   - `auto` is x11 on X11, uinput or ydotool on Wayland
+//   screenshots     - grim on wlroots compositors (Hyprland, Sway...), the
+//                     Screenshot portal on GNOME/KDE Wayland and in Flatpak,
+//                     maim on X11
+//   window watcher  - Hyprland's event socket, `swaymsg -t subscribe`, or
+//                     `xprop -spy` on X11
+// The session type comes from XDG_SESSION_TYPE (WAYLAND_DISPLAY/DISPLAY when
+// unset) and the compositor from its IPC variables or XDG_CURRENT_DESKTOP.
+//
+// get_environment reports all of it with the backends each feature picked;
+// the Diagnóstico dialog shows it.
+
+use serde::Serialize;
+use zbus::blocking::fdo::DBusProxy;
+use zbus::blocking::Connection;
+
+use crate::{input, sandbox, screen, window};
+
+const PORTAL_NAME: &str = "org.freedesktop.portal.Desktop";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionType {
+    Wayland,
+    X11,
+    Tty,
+    Unknown,
+}
+
+impl SessionType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SessionType::Wayland => "wayland",
+            SessionType::X11 => "x11",
+            SessionType::Tty => "tty",
+            SessionType::Unknown => "unknown",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compositor {
+    Hyprland,
+    Sway,
+    Gnome,
+    Kde,
+    Other,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Backends {
+    pub input: &'static str,
+    pub screenshot: &'static str,
+    #[serde(rename = "windowWatcher")]
+    pub window_watcher: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Environment {
+    #[serde(rename = "sessionType")]
+    pub session_type: SessionType,
+    pub compositor: Compositor,
+    // XDG_CURRENT_DESKTOP as set, e.g. "Hyprland" or "ubuntu:GNOME"
+    pub desktop: String,
+    // xdg-desktop-portal answers on the session bus
+    pub portal: bool,
+    pub flatpak: bool,
+    pub backends: Backends,
+}
+
+fn env_set(name: &str) -> bool {
+    std::env::var(name).map(|v| !v.is_empty()).unwrap_or(false)
+}
+
+pub fn session_type() -> SessionType {
+    match std::env::var("XDG_SESSION_TYPE").map(|t| t.to_lowercase()).as_deref() {
+        Ok("wayland") => SessionType::Wayland,
+        Ok("x11") => SessionType::X11,
+        Ok("tty") => SessionType::Tty,
+        _ if env_set("WAYLAND_DISPLAY") => SessionType::Wayland,
+        _ if env_set("DISPLAY") => SessionType::X11,
+        _ => SessionType::Unknown,
+    }
+}
+
+pub fn is_wayland() -> bool {
+    session_type() == SessionType::Wayland
+}
+
+fn desktop() -> String {
+    std::env::var("XDG_CURRENT_DESKTOP").unwrap_or_default()
+}
+
+pub fn compositor() -> Compositor {
+    if env_set("HYPRLAND_INSTANCE_SIGNATURE") {
+        return Compositor::Hyprland;
+    }
+    if env_set("SWAYSOCK") {
+        return Compositor::Sway;
+    }
+    let desktop = desktop().to_lowercase();
+    if desktop.contains("gnome") {
+        Compositor::Gnome
+    } else if desktop.contains("kde") {
+        Compositor::Kde
+    } else {
+        Compositor::Other
+    }
+}
+
+pub fn portal_available() -> bool {
+    Connection::session()
+        .ok()
+        .and_then(|conn| {
+            let dbus = DBusProxy::new(&conn).ok()?;
+            dbus.name_has_owner(PORTAL_NAME.try_into().ok()?).ok()
+        })
+        .unwrap_or(false)
+}
+
+pub fn environment() -> Environment {
+    Environment {
+        session_type: session_type(),
+        compositor: compositor(),
+        desktop: desktop(),
+        portal: portal_available(),
+        flatpak: sandbox::is_flatpak(),
+        backends: Backends {
+            input: input::active_backend().as_str(),
+            screenshot: screen::screenshot_method().as_str(),
+            window_watcher: window::watcher().as_str(),
+        },
+    }
+}
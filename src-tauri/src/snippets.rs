@@ -0,0 +1,132 @@
+// ============================================================================
+// Text Snippet Library
+// ============================================================================
+//
+// Snippets are stored in snippets.json next to config.json and inserted with
+// __SNIPPET_<name>__. Placeholders expanded on insert:
+//   {date}       - current date (dd/mm/yyyy)
+//   {time}       - current time (HH:MM)
+//   {clipboard}  - current clipboard text
+//   {cursor}     - where the cursor is left after typing (first one wins)
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::{clipboard, input};
+
+const CURSOR_MARK: &str = "{cursor}";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snippet {
+    pub name: String,
+    pub text: String,
+}
+
+pub fn is_snippet_command(cmd: &str) -> bool {
+    parse_name(cmd).is_some()
+}
+
+fn parse_name(cmd: &str) -> Option<&str> {
+    let name = cmd.strip_prefix("__SNIPPET_")?.strip_suffix("__")?;
+    if name.is_empty() {
+        None
+    } else {
+        Some(name)
+    }
+}
+
+fn snippets_path(config_path: &Path) -> PathBuf {
+    config_path.with_file_name("snippets.json")
+}
+
+pub fn load(config_path: &Path) -> Vec<Snippet> {
+    fs::read_to_string(snippets_path(config_path))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save(config_path: &Path, snippets: &[Snippet]) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(snippets).map_err(|e| e.to_string())?;
+    fs::write(snippets_path(config_path), content)
+        .map_err(|e| format!("Failed to save snippets: {}", e))
+}
+
+// Add a snippet or replace the one with the same name
+pub fn upsert(config_path: &Path, snippet: Snippet) -> Result<(), String> {
+    if snippet.name.trim().is_empty() {
+        return Err("Snippet name cannot be empty".to_string());
+    }
+    if snippet.name.contains("__") {
+        return Err("Snippet name cannot contain '__'".to_string());
+    }
+
+    let mut snippets = load(config_path);
+    match snippets.iter_mut().find(|s| s.name == snippet.name) {
+        Some(existing) => existing.text = snippet.text,
+        None => snippets.push(snippet),
+    }
+    save(config_path, &snippets)
+}
+
+pub fn delete(config_path: &Path, name: &str) -> Result<(), String> {
+    let mut snippets = load(config_path);
+    let before = snippets.len();
+    snippets.retain(|s| s.name != name);
+    if snippets.len() == before {
+        return Err(format!("Snippet not found: {}", name));
+    }
+    save(config_path, &snippets)
+}
+
+// Expand placeholders, returning the text and how many characters follow
+// the {cursor} mark
+pub fn expand(text: &str) -> (String, usize) {
+    let now = Local::now();
+    let mut expanded = text
+        .replace("{date}", &now.format("%d/%m/%Y").to_string())
+        .replace("{time}", &now.format("%H:%M").to_string());
+    if expanded.contains("{clipboard}") {
+        let clip = clipboard::read_clipboard().unwrap_or_default();
+        expanded = expanded.replace("{clipboard}", &clip);
+    }
+
+    match expanded.find(CURSOR_MARK) {
+        Some(pos) => {
+            let tail = expanded[pos + CURSOR_MARK.len()..].replace(CURSOR_MARK, "");
+            let head = &expanded[..pos];
+            let back = tail.chars().count();
+            (format!("{}{}", head, tail), back)
+        }
+        None => (expanded, 0),
+    }
+}
+
+// Type the snippet and move the cursor back to the {cursor} mark
+fn type_text(text: &str, cursor_back: usize) {
+    input::type_text(text);
+    for _ in 0..cursor_back {
+        input::press_keys("left");
+    }
+}
+
+// Insert a snippet by its __SNIPPET_<name>__ command (runs in background)
+pub fn execute_command(cmd: &str, config_path: &Path) -> Result<(), String> {
+    let name = parse_name(cmd).ok_or_else(|| format!("Invalid snippet command: {}", cmd))?;
+    let snippet = load(config_path)
+        .into_iter()
+        .find(|s| s.name == name)
+        .ok_or_else(|| format!("Snippet not found: {}", name))?;
+
+    debug!("Inserting snippet {}", snippet.name);
+    thread::spawn(move || {
+        let (text, cursor_back) = expand(&snippet.text);
+        type_text(&text, cursor_back);
+    });
+    Ok(())
+}
@@ -0,0 +1,179 @@
+// ============================================================================
+// Sound Playback
+// ============================================================================
+//
+// Optional sounds for a finished timer, a deck-wide alert (alerts.rs) and
+// key presses. Each is either "beep" (a built-in tone) or the path of an
+// audio file (wav, ogg, mp3, flac; "~/" is the home folder):
+//
+//   "sounds": { "volume": 80, "timer": "beep", "alert": "~/Sounds/raid.ogg" }
+//
+// and per key: { "label": "Clip", "command": "__TWITCH_CLIP__", "sound": "beep" }
+//
+// Nothing plays unless configured. __SOUND_MUTE__ mutes and unmutes every
+// sound from a key (a widget: "SOUND", or "MUTED" in red).
+//
+// Playback runs on its own thread, which opens the default output device on
+// the first sound and keeps it open.
+
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+use rodio::source::SineWave;
+use rodio::{Decoder, OutputStream, OutputStreamBuilder, Source};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::config_store;
+
+pub const MUTE_COMMAND: &str = "__SOUND_MUTE__";
+pub const BEEP: &str = "beep";
+
+const BEEP_FREQUENCY: f32 = 880.0;
+const BEEP_LENGTH: Duration = Duration::from_millis(150);
+const BEEP_GAP: Duration = Duration::from_millis(250);
+const MUTED_COLOR: &str = "#c0392b";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoundSettings {
+    // 0-100
+    #[serde(default = "default_volume")]
+    pub volume: u8,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub muted: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timer: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alert: Option<String>,
+}
+
+fn default_volume() -> u8 {
+    80
+}
+
+impl Default for SoundSettings {
+    fn default() -> Self {
+        SoundSettings {
+            volume: default_volume(),
+            muted: false,
+            timer: None,
+            alert: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Event {
+    Timer,
+    Alert,
+}
+
+static PLAYER: OnceLock<Mutex<Sender<(String, f32)>>> = OnceLock::new();
+
+fn settings() -> SoundSettings {
+    config_store::read(|c| c.sounds.clone()).unwrap_or_default()
+}
+
+fn resolve(sound: &str) -> PathBuf {
+    match (sound.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(sound),
+    }
+}
+
+fn play_on(stream: &OutputStream, sound: &str, volume: f32) -> Result<(), String> {
+    let mixer = stream.mixer();
+    if sound == BEEP {
+        // Two short tones
+        for delay in [Duration::ZERO, BEEP_GAP] {
+            mixer.add(SineWave::new(BEEP_FREQUENCY).take_duration(BEEP_LENGTH).amplify(volume).delay(delay));
+        }
+        return Ok(());
+    }
+    let path = resolve(sound);
+    let file = File::open(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let source = Decoder::try_from(file).map_err(|e| format!("{}: {}", path.display(), e))?;
+    mixer.add(source.amplify(volume));
+    Ok(())
+}
+
+fn start_player() -> Sender<(String, f32)> {
+    let (tx, rx) = mpsc::channel::<(String, f32)>();
+    thread::spawn(move || {
+        let mut stream: Option<OutputStream> = None;
+        for (sound, volume) in rx {
+            if stream.is_none() {
+                match OutputStreamBuilder::open_default_stream() {
+                    Ok(mut s) => {
+                        s.log_on_drop(false);
+                        stream = Some(s);
+                    }
+                    Err(e) => {
+                        warn!("No audio output for sounds: {}", e);
+                        continue;
+                    }
+                }
+            }
+            if let Some(stream) = &stream {
+                if let Err(e) = play_on(stream, &sound, volume) {
+                    warn!("Failed to play sound {}", e);
+                }
+            }
+        }
+    });
+    tx
+}
+
+// Play "beep" or an audio file, unless sounds are muted
+pub fn play(sound: &str) {
+    let sound = sound.trim();
+    let settings = settings();
+    if sound.is_empty() || settings.muted {
+        return;
+    }
+    debug!("Playing sound {}", sound);
+    let volume = settings.volume.min(100) as f32 / 100.0;
+    let player = PLAYER.get_or_init(|| Mutex::new(start_player()));
+    if let Ok(player) = player.lock() {
+        player.send((sound.to_string(), volume)).ok();
+    }
+}
+
+// Play the sound configured for an event, if any
+pub fn play_event(event: Event) {
+    let settings = settings();
+    let sound = match event {
+        Event::Timer => settings.timer,
+        Event::Alert => settings.alert,
+    };
+    if let Some(sound) = sound {
+        play(&sound);
+    }
+}
+
+pub fn is_sound_command(cmd: &str) -> bool {
+    cmd == MUTE_COMMAND
+}
+
+// Toggle the mute (config "sounds.muted")
+pub fn execute_command(config_path: &Path) -> Result<(), String> {
+    let muted = config_store::update(config_path, |c| {
+        c.sounds.muted = !c.sounds.muted;
+        c.sounds.muted
+    })
+    .ok_or("Config is not loaded")?;
+    debug!("Sounds {}", if muted { "muted" } else { "unmuted" });
+    Ok(())
+}
+
+pub fn get_widget_text() -> String {
+    if settings().muted { "MUTED" } else { "SOUND" }.to_string()
+}
+
+pub fn get_widget_color() -> Option<String> {
+    Some(MUTED_COLOR.to_string()).filter(|_| settings().muted)
+}
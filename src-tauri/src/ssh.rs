@@ -0,0 +1,240 @@
+// ============================================================================
+// SSH Quick-Connect
+// ============================================================================
+//
+// Command formats:
+//   __SSH_<host>__   - open a terminal running `ssh -- <host>`; hosts starting
+//                      with '-' are rejected
+//   __SSH_PAGE__     - show a virtual page with one key per host in ~/.ssh/config
+//
+// The terminal is taken from $TERMINAL, otherwise the first known terminal
+// found in PATH is used.
+
+use std::path::{Path, PathBuf};
+
+use tracing::debug;
+
+use crate::virtual_page::{self, AfterPress, ListEntry, PageProvider};
+use crate::sandbox;
+
+pub const PAGE_COMMAND: &str = "__SSH_PAGE__";
+
+const HOST_COLOR: &str = "#16213e";
+
+// Terminals tried in order when $TERMINAL is not set
+const KNOWN_TERMINALS: [&str; 8] = [
+    "kitty",
+    "alacritty",
+    "foot",
+    "wezterm",
+    "gnome-terminal",
+    "konsole",
+    "xfce4-terminal",
+    "xterm",
+];
+
+pub fn is_ssh_command(cmd: &str) -> bool {
+    cmd == PAGE_COMMAND || parse_host(cmd).is_some()
+}
+
+fn parse_host(cmd: &str) -> Option<&str> {
+    if cmd == PAGE_COMMAND {
+        return None;
+    }
+    let host = cmd.strip_prefix("__SSH_")?.strip_suffix("__")?;
+    // A leading '-' would reach ssh as an option (-oProxyCommand=...)
+    if host.is_empty() || host.starts_with('-') {
+        None
+    } else {
+        Some(host)
+    }
+}
+
+fn ssh_dir() -> Option<PathBuf> {
+    std::env::var("HOME").ok().map(|home| PathBuf::from(home).join(".ssh"))
+}
+
+fn expand_path(path: &str, ssh_dir: &Path) -> PathBuf {
+    if let Some(rest) = path.strip_prefix("~/") {
+        if let Ok(home) = std::env::var("HOME") {
+            return PathBuf::from(home).join(rest);
+        }
+    }
+    let path = PathBuf::from(path);
+    if path.is_absolute() {
+        path
+    } else {
+        // Relative Include paths are relative to ~/.ssh
+        ssh_dir.join(path)
+    }
+}
+
+// Resolve an Include pattern; only '*' wildcards in the file name are supported
+fn resolve_include(pattern: &str, ssh_dir: &Path) -> Vec<PathBuf> {
+    let path = expand_path(pattern, ssh_dir);
+    let file_name = match path.file_name().and_then(|n| n.to_str()) {
+        Some(n) => n.to_string(),
+        None => return Vec::new(),
+    };
+    if !file_name.contains('*') {
+        return vec![path];
+    }
+
+    let dir = match path.parent() {
+        Some(d) => d,
+        None => return Vec::new(),
+    };
+    let (prefix, suffix) = file_name.split_once('*').unwrap_or((&file_name, ""));
+    let mut matches: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| {
+                    p.file_name()
+                        .and_then(|n| n.to_str())
+                        .map(|n| n.starts_with(prefix) && n.ends_with(suffix) && n.len() >= prefix.len() + suffix.len())
+                        .unwrap_or(false)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    matches.sort();
+    matches
+}
+
+fn parse_config_file(path: &Path, ssh_dir: &Path, hosts: &mut Vec<String>, depth: u8) {
+    // Guard against Include loops
+    if depth > 4 {
+        return;
+    }
+    let content = match std::fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(_) => return,
+    };
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (keyword, args) = match line.split_once(|c: char| c.is_whitespace() || c == '=') {
+            Some((k, a)) => (k, a.trim_start_matches(|c: char| c.is_whitespace() || c == '=')),
+            None => continue,
+        };
+
+        if keyword.eq_ignore_ascii_case("Host") {
+            for name in args.split_whitespace() {
+                // Patterns and negations are not connectable hosts
+                if name.contains(['*', '?', '!']) || hosts.iter().any(|h| h == name) {
+                    continue;
+                }
+                hosts.push(name.to_string());
+            }
+        } else if keyword.eq_ignore_ascii_case("Include") {
+            for pattern in args.split_whitespace() {
+                for included in resolve_include(pattern, ssh_dir) {
+                    parse_config_file(&included, ssh_dir, hosts, depth + 1);
+                }
+            }
+        }
+    }
+}
+
+// Host aliases from ~/.ssh/config (including Include files), in file order
+pub fn list_hosts() -> Vec<String> {
+    let mut hosts = Vec::new();
+    if let Some(dir) = ssh_dir() {
+        parse_config_file(&dir.join("config"), &dir, &mut hosts, 0);
+    }
+    hosts
+}
+
+pub fn find_terminal() -> Option<String> {
+    if let Ok(terminal) = std::env::var("TERMINAL") {
+        if !terminal.is_empty() {
+            return Some(terminal);
+        }
+    }
+    KNOWN_TERMINALS
+        .iter()
+        .find(|t| {
+            sandbox::host_command("which")
+                .arg(t)
+                .output()
+                .map(|o| o.status.success())
+                .unwrap_or(false)
+        })
+        .map(|t| t.to_string())
+}
+
+// Arguments that make `terminal` run `program`
+pub fn terminal_args(terminal: &str, program: &[&str]) -> Vec<String> {
+    let name = Path::new(terminal)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or(terminal);
+    let mut args: Vec<String> = match name {
+        "gnome-terminal" | "kgx" => vec!["--".to_string()],
+        "wezterm" => vec!["start".to_string(), "--".to_string()],
+        "kitty" | "foot" => Vec::new(),
+        _ => vec!["-e".to_string()],
+    };
+    args.extend(program.iter().map(|p| p.to_string()));
+    args
+}
+
+pub fn connect(host: &str) -> Result<(), String> {
+    if host.starts_with('-') {
+        return Err(format!("Invalid ssh host: {}", host));
+    }
+    let terminal = find_terminal().ok_or("No terminal emulator found (set $TERMINAL)")?;
+    debug!("Opening ssh {} in {}", host, terminal);
+    sandbox::host_command(&terminal)
+        .args(terminal_args(&terminal, &["ssh", "--", host]))
+        .spawn()
+        .map_err(|e| format!("Failed to launch {}: {}", terminal, e))?;
+    Ok(())
+}
+
+struct HostPage;
+
+impl PageProvider for HostPage {
+    fn title(&self) -> String {
+        "SSH".to_string()
+    }
+
+    fn entries(&mut self) -> Vec<ListEntry> {
+        let hosts = list_hosts();
+        if hosts.is_empty() {
+            debug!("No hosts found in ~/.ssh/config");
+        }
+        hosts.iter().map(|host| ListEntry::new(host)).collect()
+    }
+
+    fn color(&self) -> String {
+        HOST_COLOR.to_string()
+    }
+
+    // Picking a host also leaves the page
+    fn pressed(&mut self, _index: usize, entry: &ListEntry) -> Result<AfterPress, String> {
+        connect(&entry.label)?;
+        Ok(AfterPress::Close)
+    }
+}
+
+pub fn open_host_page() {
+    virtual_page::open_provider(Box::new(HostPage));
+}
+
+// Execute an SSH command (host connect or host picker page)
+pub fn execute_command(cmd: &str) -> Result<(), String> {
+    if cmd == PAGE_COMMAND {
+        open_host_page();
+        return Ok(());
+    }
+    match parse_host(cmd) {
+        Some(host) => connect(host),
+        None => Err(format!("Invalid SSH command: {}", cmd)),
+    }
+}
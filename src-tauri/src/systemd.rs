@@ -0,0 +1,173 @@
+// ============================================================================
+// Systemd Unit Integration (D-Bus via zbus)
+// ============================================================================
+//
+// Command formats:
+//   __SYSTEMD_<unit>__       - system-level unit (e.g. __SYSTEMD_jellyfin__)
+//   __SYSTEMD_USER_<unit>__  - user-session unit (e.g. __SYSTEMD_USER_syncthing__)
+//
+// As a widget the key shows the unit's ActiveState; pressing it stops the
+// unit if it is active and starts it otherwise.
+
+use std::thread;
+use zbus::blocking::Connection;
+use zbus::zvariant::OwnedObjectPath;
+use tracing::{debug, warn};
+
+use crate::poller;
+
+// Seconds before a cached unit state is refreshed in the background
+const STATE_CACHE_SECS: u64 = 5;
+
+#[zbus::proxy(
+    interface = "org.freedesktop.systemd1.Manager",
+    default_service = "org.freedesktop.systemd1",
+    default_path = "/org/freedesktop/systemd1"
+)]
+trait SystemdManager {
+    fn load_unit(&self, name: &str) -> zbus::Result<OwnedObjectPath>;
+
+    #[zbus(allow_interactive_auth)]
+    fn start_unit(&self, name: &str, mode: &str) -> zbus::Result<OwnedObjectPath>;
+
+    #[zbus(allow_interactive_auth)]
+    fn stop_unit(&self, name: &str, mode: &str) -> zbus::Result<OwnedObjectPath>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.systemd1.Unit",
+    default_service = "org.freedesktop.systemd1"
+)]
+trait SystemdUnit {
+    #[zbus(property)]
+    fn active_state(&self) -> zbus::Result<String>;
+}
+
+#[derive(Debug, Clone)]
+struct UnitRef {
+    name: String,
+    user: bool,
+}
+
+pub fn is_systemd_command(cmd: &str) -> bool {
+    cmd.starts_with("__SYSTEMD_") && cmd.ends_with("__")
+}
+
+// Parse __SYSTEMD_<unit>__ / __SYSTEMD_USER_<unit>__ into a unit reference
+fn parse_command(cmd: &str) -> Option<UnitRef> {
+    let inner = cmd.strip_prefix("__SYSTEMD_")?.strip_suffix("__")?;
+    let (user, unit) = match inner.strip_prefix("USER_") {
+        Some(rest) => (true, rest),
+        None => (false, inner),
+    };
+    if unit.is_empty() {
+        return None;
+    }
+
+    // Default to .service like systemctl does
+    let name = if unit.contains('.') {
+        unit.to_string()
+    } else {
+        format!("{}.service", unit)
+    };
+
+    Some(UnitRef { name, user })
+}
+
+fn bus_for(unit: &UnitRef) -> Result<Connection, String> {
+    let conn = if unit.user { Connection::session() } else { Connection::system() };
+    conn.map_err(|e| format!("D-Bus connection failed: {}", e))
+}
+
+// Query the ActiveState of a unit (active, inactive, failed, activating, ...)
+fn query_active_state(unit: &UnitRef) -> Result<String, String> {
+    let conn = bus_for(unit)?;
+    let manager = SystemdManagerProxyBlocking::new(&conn)
+        .map_err(|e| format!("systemd proxy error: {}", e))?;
+    let path = manager.load_unit(&unit.name)
+        .map_err(|e| format!("LoadUnit failed: {}", e))?;
+
+    let unit_proxy = SystemdUnitProxyBlocking::builder(&conn)
+        .path(path)
+        .map_err(|e| format!("Invalid unit path: {}", e))?
+        .build()
+        .map_err(|e| format!("Unit proxy error: {}", e))?;
+
+    unit_proxy.active_state().map_err(|e| format!("ActiveState read failed: {}", e))
+}
+
+// Toggle a unit from its command string: stop if active, start otherwise
+pub fn toggle_from_command(cmd: &str) {
+    let unit = match parse_command(cmd) {
+        Some(u) => u,
+        None => {
+            debug!("Malformed systemd command: {}", cmd);
+            return;
+        }
+    };
+    let cmd = cmd.to_string();
+
+    thread::spawn(move || {
+        let result = (|| -> Result<(), String> {
+            let state = query_active_state(&unit)?;
+            let conn = bus_for(&unit)?;
+            let manager = SystemdManagerProxyBlocking::new(&conn)
+                .map_err(|e| format!("systemd proxy error: {}", e))?;
+
+            if state == "active" || state == "activating" || state == "reloading" {
+                debug!("Stopping unit {}", unit.name);
+                manager.stop_unit(&unit.name, "replace")
+                    .map_err(|e| format!("StopUnit failed: {}", e))?;
+            } else {
+                debug!("Starting unit {}", unit.name);
+                manager.start_unit(&unit.name, "replace")
+                    .map_err(|e| format!("StartUnit failed: {}", e))?;
+            }
+            Ok(())
+        })();
+
+        if let Err(e) = result {
+            warn!("systemd toggle error for {}: {}", unit.name, e);
+        }
+
+        // Give the job a moment to settle before re-reading the state
+        thread::sleep(std::time::Duration::from_millis(500));
+        match query_active_state(&unit) {
+            Ok(state) => poller::store(&cmd, state),
+            Err(e) => warn!("systemd state error for {}: {}", unit.name, e),
+        }
+    });
+}
+
+// Widget text for a systemd unit (cached, refreshed in the background)
+pub fn get_widget_text(cmd: &str) -> String {
+    let unit = match parse_command(cmd) {
+        Some(u) => u,
+        None => return "ERR".to_string(),
+    };
+
+    let state = poller::poll(cmd, STATE_CACHE_SECS, move || {
+        query_active_state(&unit).unwrap_or_else(|e| {
+            warn!("systemd state error for {}: {}", unit.name, e);
+            "error".to_string()
+        })
+    });
+
+    match state {
+        Some(state) => state_label(&state).to_string(),
+        None => "...".to_string(),
+    }
+}
+
+fn state_label(state: &str) -> &str {
+    match state {
+        "active" => "ON",
+        "inactive" => "OFF",
+        "failed" => "FAILED",
+        "activating" => "START..",
+        "deactivating" => "STOP..",
+        "reloading" => "RELOAD",
+        "error" => "ERR",
+        other => other,
+    }
+}
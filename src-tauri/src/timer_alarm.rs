@@ -0,0 +1,125 @@
+// ============================================================================
+// Timer Alarm (flash and break page)
+// ============================================================================
+//
+// When a timer reaches zero its keys show "DONE!" and blink red for a few
+// seconds, on top of the sound and desktop notification. With "timerBreak" in
+// config.json the deck also switches to a break page and, after the break,
+// back to the page it was on (only if the break page is still shown):
+//
+//   "timerBreak": { "page": 3, "minutes": 5 }
+//
+// The timer thread sends ring() to the alarm worker started from setup, which
+// tells the widget worker to redraw the timer keys right away instead of
+// waiting for their next one-second turn. Starting or stopping a timer
+// silences the alarm.
+
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::{config_store, switch_page, widget_schedule};
+
+const RING_DURATION: Duration = Duration::from_secs(10);
+const BLINK_COLOR: &str = "#e94560";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimerBreak {
+    pub page: usize,
+    #[serde(default = "default_break_minutes")]
+    pub minutes: u32,
+}
+
+fn default_break_minutes() -> u32 {
+    5
+}
+
+lazy_static::lazy_static! {
+    static ref ALARMS: Mutex<Option<Sender<()>>> = Mutex::new(None);
+    // When the alarm started, while the timer keys blink
+    static ref RINGING: Mutex<Option<Instant>> = Mutex::new(None);
+}
+
+fn is_timer_widget(cmd: &str) -> bool {
+    cmd.starts_with("__TIMER")
+}
+
+pub fn ringing() -> bool {
+    RINGING
+        .lock()
+        .map(|ringing| ringing.map(|started| started.elapsed() < RING_DURATION).unwrap_or(false))
+        .unwrap_or(false)
+}
+
+// Red on every other second while ringing
+pub fn widget_color() -> Option<String> {
+    let started = RINGING.lock().ok()?.filter(|started| started.elapsed() < RING_DURATION)?;
+    (started.elapsed().as_secs() % 2 == 0).then(|| BLINK_COLOR.to_string())
+}
+
+// A timer reached zero
+pub fn ring() {
+    if let Ok(alarms) = ALARMS.lock() {
+        if let Some(sender) = alarms.as_ref() {
+            sender.send(()).ok();
+        }
+    }
+}
+
+// Stop blinking (a timer was started or stopped)
+pub fn silence() {
+    let was_ringing = RINGING.lock().map(|mut ringing| ringing.take().is_some()).unwrap_or(false);
+    if was_ringing {
+        widget_schedule::refresh_now(is_timer_widget);
+    }
+}
+
+fn take_break(config_path: &Path) {
+    let (timer_break, current_page) = match config_store::read(|c| (c.timer_break.clone(), c.current_page)) {
+        Some((Some(timer_break), current_page)) => (timer_break, current_page),
+        _ => return,
+    };
+    if timer_break.page == current_page || !switch_page(config_path, timer_break.page) {
+        return;
+    }
+    debug!("Timer done, break page {} for {} minutes", timer_break.page, timer_break.minutes);
+    let config_path = config_path.to_path_buf();
+    thread::spawn(move || {
+        thread::sleep(Duration::from_secs(timer_break.minutes as u64 * 60));
+        let still_on_break = config_store::read(|c| c.current_page == timer_break.page).unwrap_or(false);
+        if still_on_break {
+            debug!("Break over, back to page {}", current_page);
+            switch_page(&config_path, current_page);
+        }
+    });
+}
+
+fn run(alarms: Receiver<()>, config_path: PathBuf) {
+    for () in alarms {
+        if let Ok(mut ringing) = RINGING.lock() {
+            *ringing = Some(Instant::now());
+        }
+        widget_schedule::refresh_now(is_timer_widget);
+        take_break(&config_path);
+
+        // Draw the keys in their normal color once the blinking is over
+        thread::spawn(|| {
+            thread::sleep(RING_DURATION);
+            widget_schedule::refresh_now(is_timer_widget);
+        });
+    }
+}
+
+// Start the alarm worker; ring() does nothing before this
+pub fn start(config_path: PathBuf) {
+    let (sender, receiver) = mpsc::channel();
+    if let Ok(mut alarms) = ALARMS.lock() {
+        *alarms = Some(sender);
+    }
+    thread::spawn(move || run(receiver, config_path));
+}
@@ -0,0 +1,69 @@
+// ============================================================================
+// Brightness Fades and Page Transitions
+// ============================================================================
+//
+// Brightness changes (locking and unlocking, resume, scheduled or tray
+// changes, pages with their own brightness) step the LIG level over
+// "brightnessFadeMs" instead of jumping; the frame scheduler does the steps,
+// since it sends every LIG command. 0 turns fades off.
+//
+// With "staggerPages" a page load keeps the old keys instead of clearing the
+// screen and replaces them one column at a time, left to right, so a page
+// switch sweeps across the deck rather than blinking:
+//
+//   "transitions": { "brightnessFadeMs": 300, "staggerPages": true }
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config_store;
+
+// Time between two LIG steps of a fade
+pub const FADE_STEP: Duration = Duration::from_millis(30);
+// Pause between two columns of a staggered page load
+pub const STAGGER_STEP: Duration = Duration::from_millis(40);
+const COLUMNS: u8 = 5;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transitions {
+    #[serde(default = "default_fade_ms", rename = "brightnessFadeMs")]
+    pub brightness_fade_ms: u64,
+    #[serde(default, rename = "staggerPages")]
+    pub stagger_pages: bool,
+}
+
+fn default_fade_ms() -> u64 {
+    300
+}
+
+impl Default for Transitions {
+    fn default() -> Self {
+        Transitions {
+            brightness_fade_ms: default_fade_ms(),
+            stagger_pages: false,
+        }
+    }
+}
+
+pub fn settings() -> Transitions {
+    config_store::read(|c| c.transitions.clone()).unwrap_or_default()
+}
+
+// Levels to send to get from `from` to `to`, the last one being `to`. Small
+// changes (a slider being dragged) take fewer steps.
+pub fn fade_levels(from: u8, to: u8) -> Vec<u8> {
+    let steps = (settings().brightness_fade_ms / FADE_STEP.as_millis() as u64).max(1) as i32;
+    let distance = to as i32 - from as i32;
+    let steps = steps.min(distance.abs()).max(1);
+    (1..=steps).map(|i| (from as i32 + distance * i / steps) as u8).collect()
+}
+
+// Column of a key (key 1 top left, 5 keys per row)
+pub fn column(key_id: u8) -> u8 {
+    (key_id.saturating_sub(1)) % COLUMNS
+}
+
+pub fn columns() -> std::ops::Range<u8> {
+    0..COLUMNS
+}
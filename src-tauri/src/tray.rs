@@ -0,0 +1,155 @@
+// ============================================================================
+// System Tray
+// ============================================================================
+//
+// The tray icon shows whether the deck is connected (tooltip and first menu
+// item) and offers page switching, brightness presets, reconnecting the
+// device, showing the window and quitting. With the tray in place, closing
+// the window only hides it: the listener keeps driving the deck until "Salir".
+//
+// The menu is rebuilt by update() when the connection changes or the config
+// is saved (new or renamed pages).
+
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use tauri::menu::{Menu, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Manager, Wry};
+use tracing::{debug, warn};
+
+use crate::{config_store, deck_connected, device_lost, set_base_brightness, switch_page};
+
+const TRAY_ID: &str = "main";
+const BRIGHTNESS_PRESETS: [u8; 4] = [25, 50, 75, 100];
+
+struct Tray {
+    app: AppHandle,
+    icon: TrayIcon,
+}
+
+static TRAY: OnceLock<Tray> = OnceLock::new();
+
+// Whether the window should hide instead of closing
+pub fn active() -> bool {
+    TRAY.get().is_some()
+}
+
+fn build_menu(app: &AppHandle) -> tauri::Result<Menu<Wry>> {
+    let (pages, current, brightness) = config_store::read(|c| {
+        (c.pages.iter().map(|p| p.name.clone()).collect::<Vec<_>>(), c.current_page, c.brightness)
+    })
+    .unwrap_or_default();
+
+    let status = if deck_connected() { "Deck conectado" } else { "Deck desconectado" };
+    let status = MenuItem::with_id(app, "status", status, false, None::<&str>)?;
+
+    let page_menu = Submenu::with_id(app, "pages", "Página", true)?;
+    for (index, name) in pages.iter().enumerate() {
+        let label = if index == current { format!("● {}", name) } else { name.clone() };
+        page_menu.append(&MenuItem::with_id(app, format!("page:{}", index), label, true, None::<&str>)?)?;
+    }
+
+    let brightness_menu = Submenu::with_id(app, "brightness", "Brillo", true)?;
+    for preset in BRIGHTNESS_PRESETS {
+        let label = if preset == brightness { format!("● {}%", preset) } else { format!("{}%", preset) };
+        brightness_menu.append(&MenuItem::with_id(app, format!("brightness:{}", preset), label, true, None::<&str>)?)?;
+    }
+
+    Menu::with_items(
+        app,
+        &[
+            &status,
+            &PredefinedMenuItem::separator(app)?,
+            &page_menu,
+            &brightness_menu,
+            &MenuItem::with_id(app, "reconnect", "Reconectar dispositivo", true, None::<&str>)?,
+            &PredefinedMenuItem::separator(app)?,
+            &MenuItem::with_id(app, "show", "Mostrar ventana", true, None::<&str>)?,
+            &MenuItem::with_id(app, "quit", "Salir", true, None::<&str>)?,
+        ],
+    )
+}
+
+pub fn show_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        window.show().ok();
+        window.unminimize().ok();
+        window.set_focus().ok();
+    }
+}
+
+fn on_menu(app: &AppHandle, id: &str, config_path: &Path) {
+    debug!("Tray menu: {}", id);
+    match id.split_once(':') {
+        Some(("page", index)) => {
+            if let Ok(index) = index.parse() {
+                switch_page(config_path, index);
+            }
+        }
+        Some(("brightness", level)) => {
+            if let Ok(level) = level.parse() {
+                set_base_brightness(config_path, level);
+            }
+        }
+        _ => match id {
+            "reconnect" => device_lost(),
+            "show" => show_window(app),
+            "quit" => app.exit(0),
+            _ => {}
+        },
+    }
+    update();
+}
+
+// Create the tray icon; without one (no tray on this desktop) the window closes normally
+pub fn setup(app: &AppHandle, config_path: PathBuf) {
+    let menu = match build_menu(app) {
+        Ok(m) => m,
+        Err(e) => return warn!("Failed to build the tray menu: {}", e),
+    };
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .menu(&menu)
+        .tooltip("Redragon Stream Deck")
+        .on_menu_event(move |app, event| on_menu(app, event.id().as_ref(), &config_path))
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                show_window(tray.app_handle());
+            }
+        });
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    match builder.build(app) {
+        Ok(icon) => {
+            TRAY.set(Tray { app: app.clone(), icon }).ok();
+            update();
+        }
+        Err(e) => warn!("Failed to create the tray icon: {}", e),
+    }
+}
+
+// Refresh the menu and tooltip (connection state, pages, brightness)
+pub fn update() {
+    let tray = match TRAY.get() {
+        Some(t) => t,
+        None => return,
+    };
+    let tooltip = if deck_connected() {
+        "Redragon Stream Deck - conectado"
+    } else {
+        "Redragon Stream Deck - desconectado"
+    };
+    tray.icon.set_tooltip(Some(tooltip)).ok();
+    match build_menu(&tray.app) {
+        Ok(menu) => {
+            tray.icon.set_menu(Some(menu)).ok();
+        }
+        Err(e) => warn!("Failed to rebuild the tray menu: {}", e),
+    }
+}
@@ -0,0 +1,143 @@
+// ============================================================================
+// Package Update Counter
+// ============================================================================
+//
+// Command format:
+//   __UPDATES__  - number of pending package updates; pressing opens a
+//                  terminal running the upgrade
+//
+// The checker follows the distro in os-release (ID and ID_LIKE):
+//   arch     - `checkupdates` (pacman-contrib, uses its own database copy)
+//   debian   - `apt list --upgradable` (as of the last `apt update`)
+//   fedora   - `dnf check-update`
+// Checking is slow and hits the mirrors, so it runs in the background once an
+// hour, and again after the upgrade terminal closes.
+
+use std::fs;
+use std::process::Stdio;
+use std::thread;
+
+use tracing::debug;
+
+use crate::{action_log, poller, request_refresh, sandbox, ssh};
+
+pub const COMMAND: &str = "__UPDATES__";
+
+const POLL_SECS: u64 = 3600;
+// dnf check-update exits with 100 when there are updates
+const DNF_UPDATES_EXIT: i32 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PackageManager {
+    Pacman,
+    Apt,
+    Dnf,
+}
+
+#[derive(Debug, Clone)]
+enum Updates {
+    Pending(usize),
+    Unsupported,
+    Error,
+}
+
+impl PackageManager {
+    fn upgrade_command(self) -> &'static str {
+        match self {
+            PackageManager::Pacman => "sudo pacman -Syu",
+            PackageManager::Apt => "sudo apt update && sudo apt upgrade",
+            PackageManager::Dnf => "sudo dnf upgrade",
+        }
+    }
+}
+
+// The host's os-release; Flatpak shows the runtime's in /etc
+fn os_release() -> String {
+    let paths = if sandbox::is_flatpak() {
+        ["/run/host/os-release", "/run/host/usr/lib/os-release"]
+    } else {
+        ["/etc/os-release", "/usr/lib/os-release"]
+    };
+    paths.iter().find_map(|p| fs::read_to_string(p).ok()).unwrap_or_default()
+}
+
+fn package_manager() -> Option<PackageManager> {
+    let release = os_release();
+    let ids: Vec<String> = release
+        .lines()
+        .filter_map(|line| line.strip_prefix("ID=").or_else(|| line.strip_prefix("ID_LIKE=")))
+        .flat_map(|value| value.trim_matches('"').split_whitespace().map(str::to_string).collect::<Vec<_>>())
+        .collect();
+    ids.iter().find_map(|id| match id.as_str() {
+        "arch" => Some(PackageManager::Pacman),
+        "debian" | "ubuntu" => Some(PackageManager::Apt),
+        "fedora" | "rhel" => Some(PackageManager::Dnf),
+        _ => None,
+    })
+}
+
+fn count_updates(manager: PackageManager) -> Result<usize, String> {
+    let (program, args): (&str, &[&str]) = match manager {
+        PackageManager::Pacman => ("checkupdates", &[]),
+        PackageManager::Apt => ("apt", &["list", "--upgradable"]),
+        PackageManager::Dnf => ("dnf", &["check-update", "-q"]),
+    };
+    let output = sandbox::host_command(program)
+        .args(args)
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let code = output.status.code();
+    match manager {
+        // 2 means no updates
+        PackageManager::Pacman if code == Some(2) => Ok(0),
+        PackageManager::Apt => Ok(stdout.lines().filter(|l| l.contains("[upgradable from")).count()),
+        PackageManager::Dnf if code == Some(DNF_UPDATES_EXIT) || code == Some(0) => Ok(stdout
+            .lines()
+            .take_while(|l| !l.starts_with("Obsoleting"))
+            .filter(|l| l.split_whitespace().count() == 3)
+            .count()),
+        PackageManager::Pacman if output.status.success() => Ok(stdout.lines().filter(|l| !l.trim().is_empty()).count()),
+        _ => Err(format!("{} failed ({})", program, output.status)),
+    }
+}
+
+fn check() -> Updates {
+    let Some(manager) = package_manager() else {
+        return Updates::Unsupported;
+    };
+    count_updates(manager).map(Updates::Pending).unwrap_or_else(|e| {
+        debug!("Update check: {}", e);
+        Updates::Error
+    })
+}
+
+pub fn get_widget_text() -> String {
+    match poller::poll(COMMAND, POLL_SECS, check) {
+        Some(Updates::Pending(count)) => format!("{} upd", count),
+        Some(Updates::Unsupported) => "N/A".to_string(),
+        Some(Updates::Error) => "ERR".to_string(),
+        None => "...".to_string(),
+    }
+}
+
+// Upgrade in a terminal, then count again once it is closed
+pub fn execute_command() -> Result<(), String> {
+    let manager = package_manager().ok_or("No supported package manager for this distro")?;
+    let terminal = ssh::find_terminal().ok_or("No terminal emulator found (set $TERMINAL)")?;
+    let script = format!("{}; printf '\\nPress Enter to close'; read -r _", manager.upgrade_command());
+    debug!("Opening the updater in {}", terminal);
+    let mut child = sandbox::host_command(&terminal)
+        .args(ssh::terminal_args(&terminal, &["sh", "-c", &script]))
+        .spawn()
+        .map_err(|e| format!("Failed to launch {}: {}", terminal, e))?;
+    thread::spawn(move || {
+        if let Err(e) = child.wait() {
+            action_log::record_error(COMMAND, &format!("Updater terminal: {}", e));
+        }
+        poller::store(COMMAND, check());
+        request_refresh();
+    });
+    Ok(())
+}
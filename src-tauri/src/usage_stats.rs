@@ -0,0 +1,160 @@
+// ============================================================================
+// Key Usage Statistics
+// ============================================================================
+//
+// Every press of a config page key is recorded (profile, page, key, command,
+// time) in <app data>/usage.db, a small SQLite database, so the UI can show
+// which keys are actually used and help reorganize pages. Presses on
+// generated pages (SSH picker, clipboard...) are not recorded.
+//
+// "usageStats": false in config.json (or set_usage_stats) stops recording;
+// clear_usage_stats deletes what was recorded. Presses older than
+// RETENTION_DAYS are dropped at startup.
+
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use tracing::{debug, warn};
+
+use crate::{config_store, virtual_page, Config};
+
+const RETENTION_DAYS: u64 = 365;
+const DAY_SECS: u64 = 86_400;
+
+static DB: OnceLock<Mutex<Connection>> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyUsage {
+    pub page: usize,
+    #[serde(rename = "pageName")]
+    pub page_name: String,
+    pub key: u8,
+    pub label: String,
+    pub command: String,
+    pub count: u64,
+    // Unix seconds of the last press
+    #[serde(rename = "lastPressed")]
+    pub last_pressed: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageStats {
+    pub enabled: bool,
+    pub total: u64,
+    // Most pressed first; keys never pressed are not listed
+    pub keys: Vec<KeyUsage>,
+}
+
+pub fn default_enabled() -> bool {
+    true
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+fn open(path: &Path) -> rusqlite::Result<Connection> {
+    let conn = Connection::open(path)?;
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS presses (
+             id INTEGER PRIMARY KEY,
+             profile TEXT NOT NULL,
+             page INTEGER NOT NULL,
+             key INTEGER NOT NULL,
+             command TEXT NOT NULL,
+             at INTEGER NOT NULL
+         );
+         CREATE INDEX IF NOT EXISTS presses_key ON presses (profile, page, key);",
+    )?;
+    let cutoff = now().saturating_sub(RETENTION_DAYS * DAY_SECS);
+    conn.execute("DELETE FROM presses WHERE at < ?1", params![cutoff as i64])?;
+    Ok(conn)
+}
+
+// Open <app data>/usage.db; called once at startup
+pub fn init(app_dir: &Path) {
+    let path = app_dir.join("usage.db");
+    match open(&path) {
+        Ok(conn) => {
+            DB.set(Mutex::new(conn)).ok();
+        }
+        Err(e) => warn!("Failed to open {}: {}", path.display(), e),
+    }
+}
+
+// Record a press of `key_id` on the current config page
+pub fn record(key_id: u8, command: &str) {
+    if virtual_page::active().is_some() {
+        return;
+    }
+    let (enabled, profile, page) = match config_store::read(|c| (c.usage_stats, c.profile.clone(), c.current_page)) {
+        Some(c) => c,
+        None => return,
+    };
+    if !enabled {
+        return;
+    }
+    let db = match DB.get().and_then(|db| db.lock().ok()) {
+        Some(db) => db,
+        None => return,
+    };
+    if let Err(e) = db.execute(
+        "INSERT INTO presses (profile, page, key, command, at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![profile, page as i64, key_id, command, now() as i64],
+    ) {
+        warn!("Failed to record key usage: {}", e);
+    }
+}
+
+// Press counts of the active profile, over the last `days` days (all when None)
+pub fn stats(days: Option<u64>) -> Result<UsageStats, String> {
+    let config = config_store::read(Config::clone).ok_or("Config unavailable")?;
+    let db = DB.get().ok_or("Usage statistics are unavailable")?.lock().map_err(|e| e.to_string())?;
+    let since = days.map(|d| now().saturating_sub(d * DAY_SECS)).unwrap_or(0);
+
+    let mut statement = db
+        .prepare(
+            "SELECT page, key, COUNT(*), MAX(at) FROM presses
+             WHERE profile = ?1 AND at >= ?2
+             GROUP BY page, key ORDER BY COUNT(*) DESC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = statement
+        .query_map(params![config.profile, since as i64], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, u8>(1)?, row.get::<_, i64>(2)?, row.get::<_, i64>(3)?))
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut keys = Vec::new();
+    for row in rows {
+        let (page, key, count, last) = row.map_err(|e| e.to_string())?;
+        let page = page as usize;
+        // Labels and commands as they are now; a deleted page keeps its number
+        let current = config.pages.get(page);
+        let button = current.and_then(|p| p.buttons.get(&key.to_string()));
+        keys.push(KeyUsage {
+            page,
+            page_name: current.map(|p| p.name.clone()).unwrap_or_default(),
+            key,
+            label: button.map(|b| b.label.clone()).unwrap_or_default(),
+            command: button.map(|b| b.command.clone()).unwrap_or_default(),
+            count: count as u64,
+            last_pressed: last as u64,
+        });
+    }
+    Ok(UsageStats {
+        enabled: config.usage_stats,
+        total: keys.iter().map(|k| k.count).sum(),
+        keys,
+    })
+}
+
+pub fn clear() -> Result<(), String> {
+    let db = DB.get().ok_or("Usage statistics are unavailable")?.lock().map_err(|e| e.to_string())?;
+    db.execute("DELETE FROM presses", []).map_err(|e| e.to_string())?;
+    debug!("Usage statistics cleared");
+    Ok(())
+}
@@ -0,0 +1,110 @@
+// ============================================================================
+// Global Variables
+// ============================================================================
+//
+// In-memory variables set with __SET_VAR_<name>=<value>__, the set_variable
+// Tauri command, plugins ({"event":"setVariable"}) or scripts (set_var).
+// `${name}` is replaced in labels, __TYPE_ text and shell commands; unknown
+// variables expand to an empty string.
+//
+// Keys are re-rendered when a variable used on the page on the deck changes.
+// Values are inserted into shell commands as-is, without quoting.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::RwLock;
+
+use crate::request_refresh;
+
+lazy_static::lazy_static! {
+    static ref VARIABLES: RwLock<HashMap<String, String>> = RwLock::new(HashMap::new());
+    // Variables referenced by the page currently on the deck
+    static ref DISPLAYED: RwLock<HashSet<String>> = RwLock::new(HashSet::new());
+}
+
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+// Names of the ${name} references in a text
+pub fn references(text: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        let after = &rest[start + 2..];
+        match after.find('}') {
+            Some(end) => {
+                let name = &after[..end];
+                if is_valid_name(name) {
+                    names.push(name.to_string());
+                }
+                rest = &after[end + 1..];
+            }
+            None => break,
+        }
+    }
+    names
+}
+
+pub fn interpolate(text: &str) -> String {
+    if !text.contains("${") {
+        return text.to_string();
+    }
+    let vars = match VARIABLES.read() {
+        Ok(v) => v,
+        Err(_) => return text.to_string(),
+    };
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        match after.find('}') {
+            Some(end) if is_valid_name(&after[..end]) => {
+                if let Some(value) = vars.get(&after[..end]) {
+                    result.push_str(value);
+                }
+                rest = &after[end + 1..];
+            }
+            _ => {
+                result.push_str("${");
+                rest = after;
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+pub fn get(name: &str) -> Option<String> {
+    VARIABLES.read().ok()?.get(name).cloned()
+}
+
+pub fn get_all() -> HashMap<String, String> {
+    VARIABLES.read().map(|v| v.clone()).unwrap_or_default()
+}
+
+// Set a variable, re-rendering the deck if the page shows it
+pub fn set(name: &str, value: &str) -> Result<(), String> {
+    if !is_valid_name(name) {
+        return Err(format!("Invalid variable name: {}", name));
+    }
+    let changed = VARIABLES
+        .write()
+        .map_err(|e| e.to_string())?
+        .insert(name.to_string(), value.to_string())
+        .as_deref()
+        != Some(value);
+
+    let displayed = DISPLAYED.read().map(|d| d.contains(name)).unwrap_or(false);
+    if changed && displayed {
+        request_refresh();
+    }
+    Ok(())
+}
+
+// Remember which variables the page being loaded uses
+pub fn set_displayed<'a>(texts: impl Iterator<Item = &'a str>) {
+    if let Ok(mut displayed) = DISPLAYED.write() {
+        *displayed = texts.flat_map(references).collect();
+    }
+}
@@ -0,0 +1,321 @@
+// ============================================================================
+// Virtual Pages
+// ============================================================================
+//
+// A virtual page is generated at runtime (e.g. the SSH host picker) and shown
+// on the device in place of the current config page. It is never written to
+// config.json. While one is open, key presses and widget updates use its
+// buttons; `__CLOSE_PAGE__` or any page change returns to the config page.
+//
+// Most generated pages are lists, built from a PageProvider: it returns the
+// entries (label, icon, color and optionally a command) and is told which
+// entry was pressed. This module lays them out with a close key, scrolls a
+// list longer than the keys (`__LIST_PREV__` / `__LIST_NEXT__` and a "2/5"
+// indicator) and asks the provider again on refresh() or every
+// refresh_every().
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::thread;
+use std::time::Duration;
+
+use tracing::debug;
+
+use crate::{request_key_refresh, request_refresh, ButtonConfig, Page};
+
+pub const CLOSE_COMMAND: &str = "__CLOSE_PAGE__";
+
+// Key used for the close button on generated pages
+pub const CLOSE_KEY: u8 = 15;
+
+// Page index for request_key_refresh on the generated page
+pub const PAGE_INDEX: usize = usize::MAX;
+
+pub const LIST_PREV_COMMAND: &str = "__LIST_PREV__";
+pub const LIST_NEXT_COMMAND: &str = "__LIST_NEXT__";
+
+// Layout of a list that needs scrolling: entries on keys 1..=11
+const SCROLL_ENTRIES: usize = 11;
+const INDICATOR_KEY: u8 = 12;
+const PREV_KEY: u8 = 13;
+const NEXT_KEY: u8 = 14;
+const NAV_COLOR: &str = "#2c3e50";
+const CLOSE_COLOR: &str = "#e94560";
+const ENTRY_COLOR: &str = "#2d4059";
+
+// One key of a list page
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ListEntry {
+    pub label: String,
+    // Icon file in the icons folder
+    pub icon: String,
+    // The provider's default color when empty
+    pub color: String,
+    // Run through the normal dispatcher; without one the press goes to the provider
+    pub command: Option<String>,
+}
+
+impl ListEntry {
+    pub fn new(label: &str) -> Self {
+        ListEntry {
+            label: label.to_string(),
+            ..ListEntry::default()
+        }
+    }
+}
+
+// What the list page does after a press reached the provider
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AfterPress {
+    Stay,
+    Refresh,
+    Close,
+}
+
+// A source of list entries. Called with the list locked: providers must not
+// call back into this module; they return AfterPress instead.
+pub trait PageProvider: Send {
+    fn title(&self) -> String;
+    fn entries(&mut self) -> Vec<ListEntry>;
+    // Color of entries without their own
+    fn color(&self) -> String {
+        ENTRY_COLOR.to_string()
+    }
+    // An entry without a command was pressed (index into entries())
+    fn pressed(&mut self, _index: usize, _entry: &ListEntry) -> Result<AfterPress, String> {
+        Ok(AfterPress::Stay)
+    }
+    // Ask for the entries again this often while the page is open
+    fn refresh_every(&self) -> Option<Duration> {
+        None
+    }
+}
+
+// The open list and which of its screens is shown
+struct List {
+    provider: Box<dyn PageProvider>,
+    entries: Vec<ListEntry>,
+    screen: usize,
+}
+
+lazy_static::lazy_static! {
+    static ref ACTIVE_PAGE: RwLock<Option<Page>> = RwLock::new(None);
+    static ref ACTIVE_LIST: Mutex<Option<List>> = Mutex::new(None);
+}
+
+// Bumped whenever a list opens or goes away; ends its refresh thread
+static LIST_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+fn show(page: Page) {
+    debug!("Opening virtual page '{}'", page.name);
+    if let Ok(mut active) = ACTIVE_PAGE.write() {
+        *active = Some(page);
+    }
+    request_refresh();
+}
+
+fn drop_list() {
+    if let Ok(mut list) = ACTIVE_LIST.lock() {
+        if list.take().is_some() {
+            LIST_GENERATION.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+}
+
+// Show a generated page on the device
+pub fn open(page: Page) {
+    drop_list();
+    show(page);
+}
+
+// Return to the config page (no-op if no virtual page is open)
+pub fn close() {
+    drop_list();
+    let was_open = match ACTIVE_PAGE.write() {
+        Ok(mut active) => active.take().is_some(),
+        Err(_) => false,
+    };
+    if was_open {
+        debug!("Closing virtual page");
+        request_refresh();
+    }
+}
+
+// Change one key of the open page and redraw just that key
+pub fn set_button(key_id: u8, button: ButtonConfig) {
+    let changed = match ACTIVE_PAGE.write() {
+        Ok(mut active) => active.as_mut().map(|page| page.buttons.insert(key_id.to_string(), button)).is_some(),
+        Err(_) => false,
+    };
+    if changed {
+        request_key_refresh(PAGE_INDEX, key_id);
+    }
+}
+
+pub fn active() -> Option<Page> {
+    ACTIVE_PAGE.read().ok()?.clone()
+}
+
+pub fn button(label: &str, command: &str, color: &str) -> ButtonConfig {
+    ButtonConfig {
+        label: label.to_string(),
+        command: command.to_string(),
+        color: color.to_string(),
+        icon: String::new(),
+        action: None,
+        hold_confirm: false,
+        confirm: false,
+        sound: None,
+    }
+}
+
+impl List {
+    fn screens(&self) -> usize {
+        if self.entries.len() < CLOSE_KEY as usize {
+            1
+        } else {
+            self.entries.len().div_ceil(SCROLL_ENTRIES)
+        }
+    }
+
+    fn per_screen(&self) -> usize {
+        if self.screens() == 1 {
+            CLOSE_KEY as usize - 1
+        } else {
+            SCROLL_ENTRIES
+        }
+    }
+
+    // Keys of the shown screen; a list that fits keeps keys 1..14 for entries
+    fn page(&self) -> Page {
+        let mut buttons = HashMap::new();
+        let screens = self.screens();
+        let per_screen = self.per_screen();
+        let default_color = self.provider.color();
+        let first = self.screen * per_screen;
+        for (i, entry) in self.entries.iter().enumerate().skip(first).take(per_screen) {
+            let command = entry.command.clone().unwrap_or_else(|| format!("__LIST_ITEM_{}__", i));
+            let color = if entry.color.is_empty() { &default_color } else { &entry.color };
+            let mut key = button(&entry.label, &command, color);
+            key.icon = entry.icon.clone();
+            buttons.insert((i - first + 1).to_string(), key);
+        }
+        if screens > 1 {
+            let indicator = format!("{}/{}", self.screen + 1, screens);
+            buttons.insert(INDICATOR_KEY.to_string(), button(&indicator, "", NAV_COLOR));
+            buttons.insert(PREV_KEY.to_string(), button("<", LIST_PREV_COMMAND, NAV_COLOR));
+            buttons.insert(NEXT_KEY.to_string(), button(">", LIST_NEXT_COMMAND, NAV_COLOR));
+        }
+        buttons.insert(CLOSE_KEY.to_string(), button("<<", CLOSE_COMMAND, CLOSE_COLOR));
+
+        Page {
+            name: self.provider.title(),
+            buttons,
+            brightness: None,
+            default_color: None,
+        }
+    }
+
+    // Ask the provider again; stays on the same screen when it still exists
+    fn reload(&mut self) {
+        self.entries = self.provider.entries();
+        self.screen = self.screen.min(self.screens() - 1);
+    }
+}
+
+fn refresh_periodically(generation: u64, interval: Duration) {
+    thread::spawn(move || loop {
+        thread::sleep(interval);
+        if LIST_GENERATION.load(Ordering::SeqCst) != generation {
+            return;
+        }
+        refresh();
+    });
+}
+
+// Open a list page filled by `provider`
+pub fn open_provider(mut provider: Box<dyn PageProvider>) {
+    let entries = provider.entries();
+    let interval = provider.refresh_every();
+    let list = List { provider, entries, screen: 0 };
+    let page = list.page();
+    let generation = match ACTIVE_LIST.lock() {
+        Ok(mut active) => {
+            *active = Some(list);
+            LIST_GENERATION.fetch_add(1, Ordering::SeqCst) + 1
+        }
+        Err(_) => return,
+    };
+    show(page);
+    if let Some(interval) = interval {
+        refresh_periodically(generation, interval);
+    }
+}
+
+// Ask the open list's provider for its entries again and redraw
+pub fn refresh() {
+    let page = match ACTIVE_LIST.lock() {
+        Ok(mut active) => match active.as_mut() {
+            Some(list) => {
+                let before = (list.entries.clone(), list.screen);
+                list.reload();
+                // Unchanged lists are not redrawn
+                Some(list.page()).filter(|_| (&list.entries, list.screen) != (&before.0, before.1))
+            }
+            None => None,
+        },
+        Err(_) => None,
+    };
+    if let Some(page) = page {
+        show(page);
+    }
+}
+
+fn parse_item(cmd: &str) -> Option<usize> {
+    cmd.strip_prefix("__LIST_ITEM_")?.strip_suffix("__")?.parse().ok()
+}
+
+pub fn is_list_command(cmd: &str) -> bool {
+    cmd == LIST_PREV_COMMAND || cmd == LIST_NEXT_COMMAND || parse_item(cmd).is_some()
+}
+
+// Scroll the open list (wrapping around at both ends) or pass a press to its provider
+pub fn execute_list_command(cmd: &str) -> Result<(), String> {
+    let mut active = ACTIVE_LIST.lock().map_err(|e| e.to_string())?;
+    let list = active.as_mut().ok_or("No list page is open")?;
+
+    let after = match parse_item(cmd) {
+        Some(index) => {
+            let entry = list.entries.get(index).cloned().ok_or_else(|| format!("No list entry {}", index))?;
+            list.provider.pressed(index, &entry)?
+        }
+        None => {
+            let screens = list.screens();
+            list.screen = if cmd == LIST_NEXT_COMMAND {
+                (list.screen + 1) % screens
+            } else {
+                (list.screen + screens - 1) % screens
+            };
+            AfterPress::Refresh
+        }
+    };
+
+    match after {
+        AfterPress::Stay => {}
+        AfterPress::Refresh => {
+            if parse_item(cmd).is_some() {
+                list.reload();
+            }
+            let page = list.page();
+            drop(active);
+            show(page);
+        }
+        AfterPress::Close => {
+            drop(active);
+            close();
+        }
+    }
+    Ok(())
+}
+
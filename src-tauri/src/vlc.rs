@@ -0,0 +1,101 @@
+// ============================================================================
+// VLC Remote Control (HTTP interface)
+// ============================================================================
+//
+// Commands: __VLC_PLAY__, __VLC_SEEK_+10__, __VLC_VOL_+5__, __VLC_POSITION__,
+// __VLC_TITLE__ (see player.rs).
+//
+// Enable VLC's web interface (Preferences > All > Interface > Main interfaces
+// > Web) and set a Lua HTTP password. Configuration via environment:
+//   VLC_HTTP_PASSWORD  - the Lua HTTP password
+//   VLC_HTTP_URL       - default http://localhost:8080
+
+use std::time::Duration;
+
+use crate::player::{self, PlayerCommand, PlayerStatus};
+
+pub const PREFIX: &str = "__VLC_";
+// VLC volume is 0-512, with 256 = 100%
+const VOLUME_PER_PERCENT: f64 = 2.56;
+
+pub fn is_vlc_command(cmd: &str) -> bool {
+    player::parse(cmd, PREFIX).is_some()
+}
+
+pub fn is_vlc_widget(cmd: &str) -> bool {
+    player::parse(cmd, PREFIX).map(|c| c.is_widget()).unwrap_or(false)
+}
+
+fn base_url() -> String {
+    std::env::var("VLC_HTTP_URL")
+        .unwrap_or_else(|_| "http://localhost:8080".to_string())
+        .trim_end_matches('/')
+        .to_string()
+}
+
+// GET /requests/status.json with an optional command, returning the status
+fn request(params: &[(&str, &str)]) -> Result<serde_json::Value, String> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(2))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let password = std::env::var("VLC_HTTP_PASSWORD").unwrap_or_default();
+    let response = client
+        .get(format!("{}/requests/status.json", base_url()))
+        .basic_auth("", Some(password))
+        .query(params)
+        .send()
+        .map_err(|e| format!("VLC not reachable: {}", e))?;
+    if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+        return Err("VLC rejected the password (set VLC_HTTP_PASSWORD)".to_string());
+    }
+    if !response.status().is_success() {
+        return Err(format!("VLC returned {}", response.status()));
+    }
+    response.json().map_err(|e| format!("Invalid VLC response: {}", e))
+}
+
+fn status() -> Result<PlayerStatus, String> {
+    let json = request(&[])?;
+    let meta = json
+        .get("information")
+        .and_then(|i| i.get("category"))
+        .and_then(|c| c.get("meta"));
+    let title = meta
+        .and_then(|m| m.get("title").or_else(|| m.get("filename")))
+        .and_then(|t| t.as_str())
+        .unwrap_or_default()
+        .to_string();
+    Ok(PlayerStatus {
+        playing: json.get("state").and_then(|s| s.as_str()) == Some("playing"),
+        position: json.get("time").and_then(|t| t.as_f64()).unwrap_or(0.0),
+        title,
+    })
+}
+
+pub fn execute_command(cmd: &str) -> Result<(), String> {
+    let command = player::parse(cmd, PREFIX).ok_or_else(|| format!("Invalid VLC command: {}", cmd))?;
+    match command {
+        PlayerCommand::PlayPause => {
+            request(&[("command", "pl_pause")])?;
+        }
+        PlayerCommand::Seek(secs) => {
+            request(&[("command", "seek"), ("val", &format!("{:+}s", secs))])?;
+        }
+        PlayerCommand::Volume(percent) => {
+            let steps = (percent as f64 * VOLUME_PER_PERCENT).round() as i64;
+            request(&[("command", "volume"), ("val", &format!("{:+}", steps))])?;
+        }
+        PlayerCommand::Position | PlayerCommand::Title => {}
+    }
+    player::action_done(PREFIX);
+    Ok(())
+}
+
+pub fn get_widget_text(cmd: &str) -> String {
+    match player::parse(cmd, PREFIX) {
+        // One status request serves every VLC widget
+        Some(command) => player::widget_text("__VLC_STATUS", command, status),
+        None => String::new(),
+    }
+}
@@ -0,0 +1,265 @@
+// ============================================================================
+// Widget Refresh Scheduling
+// ============================================================================
+//
+// Widgets are redrawn when their content can have changed, not every second:
+// a clock with seconds every second (also a __CLOCK_FMT_ whose format has
+// seconds), a clock on the minute boundary, the date and weekday at midnight,
+// CPU/RAM/temperature every 2 s, Twitch counters (cached for 30-60 s) every
+// 30 s. Widgets fed by the background poller use the poller's pace. A redrawn
+// key whose image did not change is not sent to the deck again.
+//
+// Rendering happens on a worker thread (CPU sampling sleeps, network calls),
+// never in the USB read loop. The worker wakes every TICK, and only when some
+// key is due does it look at the page and draw the due keys. The images go
+// through a bounded queue to the listener, which sends them between reads;
+// images of a page that was replaced meanwhile are dropped.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::{Local, Timelike};
+use image::RgbImage;
+
+use crate::{audio, ci, command_widget, deck_reader, docker, github, journal, kubernetes, network, process_monitor, render_widget_updates, systemd, updates, window, workspaces, CLOCK_FMT_PREFIX, OBS_HEALTH_COMMAND};
+
+const TICK: Duration = Duration::from_millis(100);
+// Images waiting for the listener; the worker waits when it is full
+const QUEUE_SIZE: usize = 15;
+
+// Bumped by every full page draw
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+enum Refresh {
+    Every(Duration),
+    MinuteBoundary,
+    DayBoundary,
+}
+
+struct Slot {
+    command: String,
+    next: Instant,
+    // Hash of the image last sent for the key
+    image: Option<u64>,
+}
+
+pub struct WidgetImage {
+    generation: u64,
+    pub key_id: u8,
+    pub image: RgbImage,
+}
+
+impl WidgetImage {
+    // False once the page it was drawn for is gone
+    pub fn is_current(&self) -> bool {
+        self.generation == GENERATION.load(Ordering::SeqCst)
+    }
+}
+
+lazy_static::lazy_static! {
+    // Key -> widget shown and when to draw it next
+    static ref SLOTS: Mutex<HashMap<u8, Slot>> = Mutex::new(HashMap::new());
+    // Earliest due time of any key; None means check the page now
+    static ref NEXT_CHECK: Mutex<Option<Instant>> = Mutex::new(None);
+}
+
+// strftime specifiers that change every second
+const SECOND_SPECIFIERS: [&str; 5] = ["%S", "%T", "%X", "%r", "%s"];
+
+fn policy(cmd: &str) -> Refresh {
+    let custom_seconds = cmd.starts_with(CLOCK_FMT_PREFIX) && SECOND_SPECIFIERS.iter().any(|s| cmd.contains(s));
+    if cmd == "__CLOCK_S__" || custom_seconds || cmd.starts_with("__TIMER") {
+        Refresh::Every(Duration::from_secs(1))
+    } else if cmd.starts_with("__CLOCK") {
+        Refresh::MinuteBoundary
+    } else if cmd.starts_with("__DATE") || cmd.starts_with("__WEEKDAY") {
+        Refresh::DayBoundary
+    } else if cmd.starts_with("__CPU") || cmd.starts_with("__RAM") || cmd.starts_with("__TEMP") || cmd == "__LOAD__" {
+        Refresh::Every(Duration::from_secs(2))
+    } else if cmd == "__UPTIME__" || cmd == updates::COMMAND {
+        // Updates are counted in the background once an hour
+        Refresh::Every(Duration::from_secs(60))
+    } else if cmd.starts_with("__TWITCH_") {
+        Refresh::Every(Duration::from_secs(30))
+    } else if window::is_window_widget(cmd) || workspaces::is_workspace_widget(cmd) {
+        // Focus and workspace events call refresh_now
+        Refresh::Every(Duration::from_secs(60))
+    } else if systemd::is_systemd_command(cmd)
+        || docker::is_docker_widget(cmd)
+        || ci::is_ci_command(cmd)
+        || github::is_github_command(cmd)
+        || kubernetes::is_kube_widget(cmd)
+        || command_widget::is_command_widget(cmd)
+        || network::is_network_widget(cmd)
+        || process_monitor::is_process_command(cmd)
+        || cmd == journal::COMMAND
+        || audio::is_audio_widget(cmd)
+        || cmd == OBS_HEALTH_COMMAND
+    {
+        // Polled in the background; this only picks up the new value
+        Refresh::Every(Duration::from_secs(2))
+    } else {
+        // Plugins, players, OBS, recording state: react to presses quickly
+        Refresh::Every(Duration::from_secs(1))
+    }
+}
+
+fn next_due(cmd: &str, now: Instant) -> Instant {
+    let local = Local::now();
+    let wait = match policy(cmd) {
+        Refresh::Every(interval) => interval,
+        Refresh::MinuteBoundary => {
+            Duration::from_secs(60 - local.second() as u64) - Duration::from_nanos(local.nanosecond().min(999_999_999) as u64)
+        }
+        Refresh::DayBoundary => Duration::from_secs(86_400 - local.num_seconds_from_midnight() as u64),
+    };
+    // A few ms late so the boundary has really passed
+    now + wait + Duration::from_millis(5)
+}
+
+// Forget every key (a page was just drawn from scratch)
+pub fn reset() {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    if let Ok(mut slots) = SLOTS.lock() {
+        slots.clear();
+    }
+    if let Ok(mut next) = NEXT_CHECK.lock() {
+        *next = None;
+    }
+}
+
+// Whether any widget key may need drawing now
+fn pending() -> bool {
+    match NEXT_CHECK.lock() {
+        Ok(next) => next.map(|at| Instant::now() >= at).unwrap_or(true),
+        Err(_) => true,
+    }
+}
+
+// Whether the widget on `key_id` is due; schedules its next turn. A key seen
+// for the first time was just drawn with the page, so it only gets scheduled.
+pub fn due(key_id: u8, cmd: &str) -> bool {
+    let now = Instant::now();
+    let mut slots = match SLOTS.lock() {
+        Ok(s) => s,
+        Err(_) => return true,
+    };
+    match slots.get_mut(&key_id) {
+        Some(slot) if slot.command == cmd => {
+            if now < slot.next {
+                return false;
+            }
+            slot.next = next_due(cmd, now);
+            true
+        }
+        _ => {
+            slots.insert(
+                key_id,
+                Slot {
+                    command: cmd.to_string(),
+                    next: next_due(cmd, now),
+                    image: None,
+                },
+            );
+            false
+        }
+    }
+}
+
+// Record the drawn image; false if the deck already shows exactly this
+pub fn changed(key_id: u8, image: &RgbImage) -> bool {
+    let mut hasher = DefaultHasher::new();
+    image.as_raw().hash(&mut hasher);
+    let hash = hasher.finish();
+    let mut slots = match SLOTS.lock() {
+        Ok(s) => s,
+        Err(_) => return true,
+    };
+    match slots.get_mut(&key_id) {
+        Some(slot) if slot.image == Some(hash) => false,
+        Some(slot) => {
+            slot.image = Some(hash);
+            true
+        }
+        None => true,
+    }
+}
+
+// The key was drawn by someone else; draw it again on its next turn
+pub fn forget(key_id: u8) {
+    if let Ok(mut slots) = SLOTS.lock() {
+        if let Some(slot) = slots.get_mut(&key_id) {
+            slot.image = None;
+        }
+    }
+    // It may now show a widget with a shorter interval
+    if let Ok(mut next) = NEXT_CHECK.lock() {
+        *next = None;
+    }
+}
+
+// Draw the widgets matching `is_command` on the next pass (an event changed them)
+pub fn refresh_now(is_command: impl Fn(&str) -> bool) {
+    let now = Instant::now();
+    if let Ok(mut slots) = SLOTS.lock() {
+        for slot in slots.values_mut().filter(|slot| is_command(&slot.command)) {
+            slot.next = now;
+        }
+    }
+    if let Ok(mut next) = NEXT_CHECK.lock() {
+        *next = None;
+    }
+}
+
+// Drop keys that no longer show a widget and note when the next one is due
+fn finish_pass(generation: u64, widget_keys: &[u8]) {
+    // The page was replaced during the pass; check the new one right away
+    if generation != GENERATION.load(Ordering::SeqCst) {
+        return;
+    }
+    let next = match SLOTS.lock() {
+        Ok(mut slots) => {
+            slots.retain(|key, _| widget_keys.contains(key));
+            slots.values().map(|s| s.next).min()
+        }
+        Err(_) => None,
+    };
+    if let Ok(mut check) = NEXT_CHECK.lock() {
+        // Nothing to schedule: look again in a second (a widget may be added)
+        *check = Some(next.unwrap_or_else(|| Instant::now() + Duration::from_secs(1)));
+    }
+}
+
+// Start the render thread; the listener drains the returned queue
+pub fn start_worker(icons_path: PathBuf) -> Receiver<WidgetImage> {
+    let (sender, receiver) = mpsc::sync_channel(QUEUE_SIZE);
+    thread::spawn(move || loop {
+        thread::sleep(TICK);
+        if !pending() {
+            continue;
+        }
+        let generation = GENERATION.load(Ordering::SeqCst);
+        let updates = render_widget_updates(&icons_path);
+        let keys: Vec<u8> = updates.iter().map(|(key_id, _)| *key_id).collect();
+        finish_pass(generation, &keys);
+
+        for (key_id, image) in updates {
+            let image = match image {
+                Some(i) => i,
+                None => continue,
+            };
+            if sender.send(WidgetImage { generation, key_id, image }).is_err() {
+                return;
+            }
+            deck_reader::wake();
+        }
+    });
+    receiver
+}
@@ -0,0 +1,327 @@
+// ============================================================================
+// Active Window Widget
+// ============================================================================
+//
+// Command formats:
+//   __WINDOW__      - title of the focused window (its app id if it has none)
+//   __WINDOW_APP__  - app id / window class of the focused window
+//
+// A watcher thread follows focus changes through the compositor's event IPC
+// instead of polling: Hyprland's event socket (.socket2.sock), `swaymsg -t
+// subscribe` on Sway, `xprop -spy` on the X11 root window otherwise. It starts
+// the first time the widget is drawn, and the keys are redrawn as soon as an
+// event arrives. On X11 a title change without a focus change is not seen; on
+// other desktops the widget shows "-".
+
+use std::io::{BufRead, BufReader};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::{Once, RwLock};
+use std::thread;
+use std::time::Duration;
+
+use tracing::{debug, warn};
+
+use crate::session::{self, Compositor};
+use crate::{sandbox, widget_schedule};
+
+pub const COMMAND: &str = "__WINDOW__";
+pub const APP_COMMAND: &str = "__WINDOW_APP__";
+
+const MAX_LABEL_CHARS: usize = 10;
+// Wait before following a compositor that went away (restart, crash)
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Focused {
+    app: String,
+    title: String,
+}
+
+lazy_static::lazy_static! {
+    static ref FOCUSED: RwLock<Focused> = RwLock::new(Focused::default());
+}
+
+// The watcher starts the first time a key shows the widget
+static WATCHER: Once = Once::new();
+
+pub fn is_window_widget(cmd: &str) -> bool {
+    cmd == COMMAND || cmd == APP_COMMAND
+}
+
+fn set_focused(app: &str, title: &str) {
+    let focused = Focused {
+        app: app.trim().to_string(),
+        title: title.trim().to_string(),
+    };
+    if let Ok(mut current) = FOCUSED.write() {
+        if *current == focused {
+            return;
+        }
+        *current = focused;
+    }
+    widget_schedule::refresh_now(is_window_widget);
+}
+
+// Shorten to what fits on a key
+pub(crate) fn truncate(text: &str) -> String {
+    if text.chars().count() > MAX_LABEL_CHARS {
+        let head: String = text.chars().take(MAX_LABEL_CHARS - 1).collect();
+        format!("{}…", head)
+    } else {
+        text.to_string()
+    }
+}
+
+pub fn get_widget_text(cmd: &str) -> String {
+    WATCHER.call_once(start_watcher);
+    let focused = FOCUSED.read().map(|f| f.clone()).unwrap_or_default();
+    let text = if cmd == APP_COMMAND || focused.title.is_empty() {
+        focused.app
+    } else {
+        focused.title
+    };
+    if text.is_empty() {
+        "-".to_string()
+    } else {
+        truncate(&text)
+    }
+}
+
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = sandbox::host_command(program).args(args).stderr(Stdio::null()).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+// ---------------------------------------------------------------------------
+// Hyprland
+// ---------------------------------------------------------------------------
+
+fn hyprland_socket() -> Option<PathBuf> {
+    let signature = std::env::var("HYPRLAND_INSTANCE_SIGNATURE").ok()?;
+    // $XDG_RUNTIME_DIR/hypr since Hyprland 0.40, /tmp/hypr before
+    let runtime = std::env::var("XDG_RUNTIME_DIR").map(|dir| PathBuf::from(dir).join("hypr"));
+    runtime
+        .into_iter()
+        .chain([PathBuf::from("/tmp/hypr")])
+        .map(|dir| dir.join(&signature).join(".socket2.sock"))
+        .find(|path| path.exists())
+}
+
+fn hyprland_current() {
+    let window = command_output("hyprctl", &["activewindow", "-j"])
+        .and_then(|out| serde_json::from_str::<serde_json::Value>(&out).ok());
+    let field = |name: &str| {
+        window
+            .as_ref()
+            .and_then(|w| w.get(name))
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string()
+    };
+    set_focused(&field("class"), &field("title"));
+}
+
+fn watch_hyprland(socket: PathBuf) {
+    loop {
+        match UnixStream::connect(&socket) {
+            Ok(stream) => {
+                debug!("Following Hyprland focus events");
+                hyprland_current();
+                for line in BufReader::new(stream).lines().map_while(Result::ok) {
+                    // activewindow>>class,title (both empty when nothing is focused)
+                    if let Some(event) = line.strip_prefix("activewindow>>") {
+                        let (class, title) = event.split_once(',').unwrap_or((event, ""));
+                        set_focused(class, title);
+                    }
+                }
+            }
+            Err(e) => warn!("Hyprland event socket {}: {}", socket.display(), e),
+        }
+        thread::sleep(RECONNECT_DELAY);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Sway
+// ---------------------------------------------------------------------------
+
+fn sway_app(node: &serde_json::Value) -> String {
+    node.get("app_id")
+        .and_then(|v| v.as_str())
+        .or_else(|| node.get("window_properties")?.get("class")?.as_str())
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn sway_title(node: &serde_json::Value) -> String {
+    node.get("name").and_then(|v| v.as_str()).unwrap_or_default().to_string()
+}
+
+// Depth-first search of the sway tree for the focused window
+fn find_sway_focused(node: &serde_json::Value) -> Option<&serde_json::Value> {
+    let focused = node.get("focused").and_then(|v| v.as_bool()).unwrap_or(false);
+    if focused && node.get("pid").is_some() {
+        return Some(node);
+    }
+    ["nodes", "floating_nodes"]
+        .iter()
+        .filter_map(|key| node.get(*key)?.as_array())
+        .flatten()
+        .find_map(find_sway_focused)
+}
+
+fn sway_current() {
+    let tree = command_output("swaymsg", &["-t", "get_tree", "-r"])
+        .and_then(|out| serde_json::from_str::<serde_json::Value>(&out).ok());
+    match tree.as_ref().and_then(find_sway_focused) {
+        Some(node) => set_focused(&sway_app(node), &sway_title(node)),
+        None => set_focused("", ""),
+    }
+}
+
+fn watch_sway() {
+    loop {
+        let child = sandbox::host_command("swaymsg")
+            .args(["-t", "subscribe", "-m", "-r", "[\"window\"]"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn();
+        match child {
+            Ok(mut child) => {
+                debug!("Following Sway focus events");
+                sway_current();
+                if let Some(stdout) = child.stdout.take() {
+                    for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                        let event: serde_json::Value = match serde_json::from_str(&line) {
+                            Ok(v) => v,
+                            Err(_) => continue,
+                        };
+                        let container = match event.get("container") {
+                            Some(c) => c,
+                            None => continue,
+                        };
+                        let focused = container.get("focused").and_then(|v| v.as_bool()).unwrap_or(false);
+                        match event.get("change").and_then(|v| v.as_str()) {
+                            Some("focus") => set_focused(&sway_app(container), &sway_title(container)),
+                            Some("title") if focused => set_focused(&sway_app(container), &sway_title(container)),
+                            Some("close") if focused => sway_current(),
+                            _ => {}
+                        }
+                    }
+                }
+                child.wait().ok();
+            }
+            Err(e) => warn!("Failed to run swaymsg: {}", e),
+        }
+        thread::sleep(RECONNECT_DELAY);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// X11
+// ---------------------------------------------------------------------------
+
+// Value of `NAME(TYPE) = "a", "b"` lines from xprop, first quoted string
+fn xprop_value<'a>(output: &'a str, name: &str) -> Option<&'a str> {
+    let line = output.lines().find(|l| l.starts_with(name))?;
+    let value = line.split_once(" = ")?.1;
+    let value = value.strip_prefix('"')?;
+    Some(value.split_once('"').map(|(v, _)| v).unwrap_or(value))
+}
+
+fn x11_window_changed(id: &str) {
+    let props = command_output("xprop", &["-id", id, "WM_CLASS", "_NET_WM_NAME", "WM_NAME"]).unwrap_or_default();
+    // WM_CLASS is "instance", "Class"; the class is the app
+    let class = props
+        .lines()
+        .find(|l| l.starts_with("WM_CLASS"))
+        .and_then(|l| l.rsplit('"').nth(1))
+        .unwrap_or_default();
+    let title = xprop_value(&props, "_NET_WM_NAME")
+        .or_else(|| xprop_value(&props, "WM_NAME"))
+        .unwrap_or_default();
+    set_focused(class, title);
+}
+
+fn watch_x11() {
+    loop {
+        let child = sandbox::host_command("xprop")
+            .args(["-spy", "-root", "_NET_ACTIVE_WINDOW"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn();
+        match child {
+            Ok(mut child) => {
+                debug!("Following X11 focus changes");
+                if let Some(stdout) = child.stdout.take() {
+                    // _NET_ACTIVE_WINDOW(WINDOW): window id # 0x3a00007
+                    for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                        match line.rsplit(' ').next().filter(|id| id.starts_with("0x") && *id != "0x0") {
+                            Some(id) => x11_window_changed(id),
+                            None => set_focused("", ""),
+                        }
+                    }
+                }
+                child.wait().ok();
+            }
+            Err(e) => {
+                warn!("Failed to run xprop: {}", e);
+                return;
+            }
+        }
+        thread::sleep(RECONNECT_DELAY);
+    }
+}
+
+pub enum Watcher {
+    Hyprland(PathBuf),
+    Sway,
+    X11,
+    None,
+}
+
+impl Watcher {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Watcher::Hyprland(_) => "hyprland",
+            Watcher::Sway => "sway",
+            Watcher::X11 => "x11",
+            Watcher::None => "none",
+        }
+    }
+}
+
+// How focus changes can be followed in this session
+pub fn watcher() -> Watcher {
+    match session::compositor() {
+        Compositor::Hyprland => match hyprland_socket() {
+            Some(socket) => Watcher::Hyprland(socket),
+            None => Watcher::None,
+        },
+        Compositor::Sway => Watcher::Sway,
+        // XWayland windows only on Wayland desktops without an IPC
+        _ if std::env::var("DISPLAY").is_ok() => Watcher::X11,
+        _ => Watcher::None,
+    }
+}
+
+// Follow the focused window in a background thread
+fn start_watcher() {
+    match watcher() {
+        Watcher::Hyprland(socket) => {
+            thread::spawn(move || watch_hyprland(socket));
+        }
+        Watcher::Sway => {
+            thread::spawn(watch_sway);
+        }
+        Watcher::X11 => {
+            thread::spawn(watch_x11);
+        }
+        Watcher::None => debug!("No compositor IPC for the active window widget"),
+    }
+}
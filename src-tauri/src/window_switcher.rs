@@ -0,0 +1,177 @@
+// ============================================================================
+// Window Switcher Page
+// ============================================================================
+//
+// __WINDOWS_PAGE__ opens a list page with one key per open window: the app's
+// icon (from its .desktop file, see apps.rs) and the shortened title. Pressing
+// a key focuses that window and closes the page, Alt-Tab on the deck.
+//
+// Windows come from the compositor: `hyprctl clients` on Hyprland, the
+// `swaymsg -t get_tree` tree on Sway, `wmctrl -lx` on X11. The page follows
+// windows opening and closing while it is shown.
+
+use std::process::Stdio;
+use std::time::Duration;
+
+use tracing::debug;
+
+use crate::session::{self, Compositor};
+use crate::virtual_page::{self, AfterPress, ListEntry, PageProvider};
+use crate::{apps, sandbox, window};
+
+pub const COMMAND: &str = "__WINDOWS_PAGE__";
+
+const WINDOW_COLOR: &str = "#1f4068";
+const REFRESH_EVERY: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone)]
+struct OpenWindow {
+    // Hyprland address, Sway con_id or X11 window id
+    id: String,
+    class: String,
+    title: String,
+}
+
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = sandbox::host_command(program).args(args).stderr(Stdio::null()).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn str_field(value: &serde_json::Value, name: &str) -> String {
+    value.get(name).and_then(|v| v.as_str()).unwrap_or_default().to_string()
+}
+
+fn hyprland_windows() -> Vec<OpenWindow> {
+    let clients: serde_json::Value = command_output("hyprctl", &["clients", "-j"])
+        .and_then(|out| serde_json::from_str(&out).ok())
+        .unwrap_or_default();
+    clients
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|client| client.get("mapped").and_then(|v| v.as_bool()).unwrap_or(true))
+        .map(|client| OpenWindow {
+            id: str_field(client, "address"),
+            class: str_field(client, "class"),
+            title: str_field(client, "title"),
+        })
+        .collect()
+}
+
+fn collect_sway_windows(node: &serde_json::Value, windows: &mut Vec<OpenWindow>) {
+    if node.get("pid").is_some() {
+        let class = match str_field(node, "app_id") {
+            id if !id.is_empty() => id,
+            _ => node.get("window_properties").map(|p| str_field(p, "class")).unwrap_or_default(),
+        };
+        windows.push(OpenWindow {
+            id: node.get("id").and_then(|v| v.as_i64()).unwrap_or_default().to_string(),
+            class,
+            title: str_field(node, "name"),
+        });
+    }
+    for key in ["nodes", "floating_nodes"] {
+        for child in node.get(key).and_then(|v| v.as_array()).into_iter().flatten() {
+            collect_sway_windows(child, windows);
+        }
+    }
+}
+
+fn sway_windows() -> Vec<OpenWindow> {
+    let mut windows = Vec::new();
+    if let Some(tree) = command_output("swaymsg", &["-t", "get_tree", "-r"]).and_then(|out| serde_json::from_str(&out).ok()) {
+        collect_sway_windows(&tree, &mut windows);
+    }
+    windows
+}
+
+// <id> <desktop> <instance>.<class> <host> <title...>; desktop -1 is a dock or panel
+fn x11_windows() -> Vec<OpenWindow> {
+    let output = command_output("wmctrl", &["-lx"]).unwrap_or_default();
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let id = fields.next()?.to_string();
+            if fields.next()? == "-1" {
+                return None;
+            }
+            let wm_class = fields.next()?;
+            let class = wm_class.split_once('.').map(|(_, c)| c).unwrap_or(wm_class).to_string();
+            fields.next()?;
+            let title = fields.collect::<Vec<_>>().join(" ");
+            Some(OpenWindow { id, class, title })
+        })
+        .collect()
+}
+
+fn list_windows() -> Vec<OpenWindow> {
+    match session::compositor() {
+        Compositor::Hyprland => hyprland_windows(),
+        Compositor::Sway => sway_windows(),
+        _ if std::env::var("DISPLAY").is_ok() => x11_windows(),
+        _ => {
+            debug!("No compositor IPC to list windows");
+            Vec::new()
+        }
+    }
+}
+
+fn focus(window: &OpenWindow) -> Result<(), String> {
+    let focused = match session::compositor() {
+        Compositor::Hyprland => {
+            command_output("hyprctl", &["dispatch", "focuswindow", &format!("address:{}", window.id)])
+        }
+        Compositor::Sway => command_output("swaymsg", &[&format!("[con_id={}] focus", window.id)]),
+        _ => command_output("wmctrl", &["-ia", &window.id]),
+    };
+    focused.map(|_| ()).ok_or_else(|| format!("Failed to focus {}", window.title))
+}
+
+struct WindowPage {
+    windows: Vec<OpenWindow>,
+}
+
+impl PageProvider for WindowPage {
+    fn title(&self) -> String {
+        "Windows".to_string()
+    }
+
+    fn entries(&mut self) -> Vec<ListEntry> {
+        self.windows = list_windows();
+        self.windows
+            .iter()
+            .map(|w| {
+                let label = if w.title.is_empty() { &w.class } else { &w.title };
+                ListEntry {
+                    icon: apps::window_icon(&w.class)
+                        .map(|p| p.to_string_lossy().to_string())
+                        .unwrap_or_default(),
+                    ..ListEntry::new(&window::truncate(label))
+                }
+            })
+            .collect()
+    }
+
+    fn color(&self) -> String {
+        WINDOW_COLOR.to_string()
+    }
+
+    fn pressed(&mut self, index: usize, _entry: &ListEntry) -> Result<AfterPress, String> {
+        let window = self.windows.get(index).ok_or("The window list changed")?;
+        debug!("Focusing {} ({})", window.title, window.class);
+        focus(window)?;
+        Ok(AfterPress::Close)
+    }
+
+    fn refresh_every(&self) -> Option<Duration> {
+        Some(REFRESH_EVERY)
+    }
+}
+
+pub fn open_page() {
+    virtual_page::open_provider(Box::new(WindowPage { windows: Vec::new() }));
+}
@@ -0,0 +1,305 @@
+// ============================================================================
+// Workspace Widgets
+// ============================================================================
+//
+// Command format:
+//   __WS_<n>__   - workspace n (1-10): "● n" when it has windows, "! n" when
+//                  a window on it asks for attention, highlighted while it is
+//                  the active one; pressing switches to it
+//
+// Like the active window widget, the state follows compositor events instead
+// of polling: Hyprland's event socket, `swaymsg -t subscribe` on Sway,
+// `xprop -spy` on the X11 root window (no urgency there). Each event re-reads
+// the workspace list and redraws the keys that changed. The watcher starts the
+// first time a key shows the widget.
+
+use std::collections::BTreeSet;
+use std::io::{BufRead, BufReader};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::{Once, RwLock};
+use std::thread;
+use std::time::Duration;
+
+use tracing::{debug, warn};
+
+use crate::session::{self, Compositor};
+use crate::{sandbox, widget_schedule, window};
+
+const MAX_WORKSPACE: u32 = 10;
+const ACTIVE_COLOR: &str = "#2980b9";
+const URGENT_COLOR: &str = "#e67e22";
+// Wait before following a compositor that went away (restart, crash)
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+// Hyprland events after which the workspace list is read again
+const HYPRLAND_EVENTS: [&str; 9] = [
+    "workspace>>",
+    "focusedmon>>",
+    "createworkspace>>",
+    "destroyworkspace>>",
+    "openwindow>>",
+    "closewindow>>",
+    "movewindow>>",
+    "moveworkspace>>",
+    "urgent>>",
+];
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Workspaces {
+    active: Option<u32>,
+    occupied: BTreeSet<u32>,
+    urgent: BTreeSet<u32>,
+}
+
+lazy_static::lazy_static! {
+    static ref STATE: RwLock<Workspaces> = RwLock::new(Workspaces::default());
+}
+
+static WATCHER: Once = Once::new();
+
+fn parse_command(cmd: &str) -> Option<u32> {
+    let n: u32 = cmd.strip_prefix("__WS_")?.strip_suffix("__")?.parse().ok()?;
+    (1..=MAX_WORKSPACE).contains(&n).then_some(n)
+}
+
+pub fn is_workspace_widget(cmd: &str) -> bool {
+    parse_command(cmd).is_some()
+}
+
+fn set_state(mut state: Workspaces) {
+    // Looking at a workspace answers its urgency
+    if let Some(active) = state.active {
+        state.urgent.remove(&active);
+    }
+    if let Ok(mut current) = STATE.write() {
+        if *current == state {
+            return;
+        }
+        *current = state;
+    }
+    widget_schedule::refresh_now(is_workspace_widget);
+}
+
+fn read_state() -> Workspaces {
+    STATE.read().map(|s| s.clone()).unwrap_or_default()
+}
+
+pub fn get_widget_text(cmd: &str) -> String {
+    WATCHER.call_once(start_watcher);
+    let n = parse_command(cmd).unwrap_or_default();
+    let state = read_state();
+    if state.urgent.contains(&n) {
+        format!("! {}", n)
+    } else if state.occupied.contains(&n) {
+        format!("● {}", n)
+    } else {
+        n.to_string()
+    }
+}
+
+pub fn get_widget_color(cmd: &str) -> Option<String> {
+    let n = parse_command(cmd)?;
+    let state = read_state();
+    if state.urgent.contains(&n) {
+        Some(URGENT_COLOR.to_string())
+    } else if state.active == Some(n) {
+        Some(ACTIVE_COLOR.to_string())
+    } else {
+        None
+    }
+}
+
+pub fn execute_command(cmd: &str) -> Result<(), String> {
+    let n = parse_command(cmd).ok_or_else(|| format!("Invalid workspace command: {}", cmd))?;
+    let target = n.to_string();
+    let switched = match session::compositor() {
+        Compositor::Hyprland => command_output("hyprctl", &["dispatch", "workspace", &target]),
+        Compositor::Sway => command_output("swaymsg", &["workspace", "number", &target]),
+        // X11 desktops count from 0
+        _ => command_output("wmctrl", &["-s", &(n - 1).to_string()]),
+    };
+    debug!("Switching to workspace {}", n);
+    switched.map(|_| ()).ok_or_else(|| format!("Failed to switch to workspace {}", n))
+}
+
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = sandbox::host_command(program).args(args).stderr(Stdio::null()).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn json_output(program: &str, args: &[&str]) -> Option<serde_json::Value> {
+    command_output(program, args).and_then(|out| serde_json::from_str(&out).ok())
+}
+
+fn workspace_id(value: &serde_json::Value, field: &str) -> Option<u32> {
+    value.get(field)?.as_i64().and_then(|id| u32::try_from(id).ok())
+}
+
+// ---------------------------------------------------------------------------
+// Hyprland
+// ---------------------------------------------------------------------------
+
+fn hyprland_current(urgent: BTreeSet<u32>) {
+    let workspaces = json_output("hyprctl", &["workspaces", "-j"]).unwrap_or_default();
+    let occupied = workspaces
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|w| w.get("windows").and_then(|v| v.as_u64()).unwrap_or(0) > 0)
+        .filter_map(|w| workspace_id(w, "id"))
+        .collect();
+    let active = json_output("hyprctl", &["activeworkspace", "-j"]).and_then(|w| workspace_id(&w, "id"));
+    set_state(Workspaces { active, occupied, urgent });
+}
+
+// urgent>>ADDRESS names a window; its workspace is in the client list
+fn hyprland_urgent_workspace(address: &str) -> Option<u32> {
+    let address = format!("0x{}", address.trim_start_matches("0x"));
+    let clients = json_output("hyprctl", &["clients", "-j"])?;
+    let client = clients
+        .as_array()?
+        .iter()
+        .find(|c| c.get("address").and_then(|v| v.as_str()) == Some(address.as_str()))?;
+    workspace_id(client.get("workspace")?, "id")
+}
+
+fn watch_hyprland(socket: PathBuf) {
+    loop {
+        match UnixStream::connect(&socket) {
+            Ok(stream) => {
+                debug!("Following Hyprland workspace events");
+                hyprland_current(BTreeSet::new());
+                for line in BufReader::new(stream).lines().map_while(Result::ok) {
+                    if !HYPRLAND_EVENTS.iter().any(|event| line.starts_with(event)) {
+                        continue;
+                    }
+                    let mut urgent = read_state().urgent;
+                    if let Some(workspace) = line.strip_prefix("urgent>>").and_then(hyprland_urgent_workspace) {
+                        urgent.insert(workspace);
+                    }
+                    hyprland_current(urgent);
+                }
+            }
+            Err(e) => warn!("Hyprland event socket {}: {}", socket.display(), e),
+        }
+        thread::sleep(RECONNECT_DELAY);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Sway
+// ---------------------------------------------------------------------------
+
+// Sway keeps a workspace only while it has windows or is focused; an empty
+// one has no representation
+fn sway_current() {
+    let workspaces = json_output("swaymsg", &["-t", "get_workspaces", "-r"]).unwrap_or_default();
+    let mut state = Workspaces::default();
+    for workspace in workspaces.as_array().into_iter().flatten() {
+        let Some(n) = workspace_id(workspace, "num") else { continue };
+        let flag = |name: &str| workspace.get(name).and_then(|v| v.as_bool()).unwrap_or(false);
+        if flag("focused") {
+            state.active = Some(n);
+        }
+        if flag("urgent") {
+            state.urgent.insert(n);
+        }
+        if workspace.get("representation").and_then(|v| v.as_str()).is_some_and(|r| !r.is_empty()) {
+            state.occupied.insert(n);
+        }
+    }
+    set_state(state);
+}
+
+fn watch_sway() {
+    loop {
+        let child = sandbox::host_command("swaymsg")
+            .args(["-t", "subscribe", "-m", "-r", "[\"workspace\",\"window\"]"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn();
+        match child {
+            Ok(mut child) => {
+                debug!("Following Sway workspace events");
+                sway_current();
+                if let Some(stdout) = child.stdout.take() {
+                    for _event in BufReader::new(stdout).lines().map_while(Result::ok) {
+                        sway_current();
+                    }
+                }
+                child.wait().ok();
+            }
+            Err(e) => warn!("Failed to run swaymsg: {}", e),
+        }
+        thread::sleep(RECONNECT_DELAY);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// X11
+// ---------------------------------------------------------------------------
+
+// `wmctrl -d` marks the current desktop with '*'; the second column of
+// `wmctrl -l` is each window's desktop (-1 for docks and panels)
+fn x11_current() {
+    let desktops = command_output("wmctrl", &["-d"]).unwrap_or_default();
+    let active = desktops.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let index: u32 = fields.next()?.parse().ok()?;
+        (fields.next()? == "*").then_some(index + 1)
+    });
+    let windows = command_output("wmctrl", &["-l"]).unwrap_or_default();
+    let occupied = windows
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(1)?.parse::<u32>().ok())
+        .map(|index| index + 1)
+        .collect();
+    set_state(Workspaces { active, occupied, urgent: BTreeSet::new() });
+}
+
+fn watch_x11() {
+    loop {
+        let child = sandbox::host_command("xprop")
+            .args(["-spy", "-root", "_NET_CURRENT_DESKTOP", "_NET_CLIENT_LIST"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn();
+        match child {
+            Ok(mut child) => {
+                debug!("Following X11 desktop changes");
+                if let Some(stdout) = child.stdout.take() {
+                    for _change in BufReader::new(stdout).lines().map_while(Result::ok) {
+                        x11_current();
+                    }
+                }
+                child.wait().ok();
+            }
+            Err(e) => {
+                warn!("Failed to run xprop: {}", e);
+                return;
+            }
+        }
+        thread::sleep(RECONNECT_DELAY);
+    }
+}
+
+// Same event sources as the active window widget
+fn start_watcher() {
+    match window::watcher() {
+        window::Watcher::Hyprland(socket) => {
+            thread::spawn(move || watch_hyprland(socket));
+        }
+        window::Watcher::Sway => {
+            thread::spawn(watch_sway);
+        }
+        window::Watcher::X11 => {
+            thread::spawn(watch_x11);
+        }
+        window::Watcher::None => debug!("No compositor IPC for the workspace widgets"),
+    }
+}